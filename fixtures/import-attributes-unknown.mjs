@@ -0,0 +1,3 @@
+import style from "./hello.txt" with { type: "css" };
+
+export default style;
@@ -0,0 +1,8 @@
+import text from "./hello.txt" with { type: "text" };
+import bytes from "./hello.txt" with { type: "bytes" };
+import pkg from "./package.json" with { type: "json" };
+
+export { text, bytes, pkg };
+
+export const importText = () =>
+  import("./hello.txt", { with: { type: "text" } });
@@ -0,0 +1,6 @@
+export const before = 1;
+
+export enum Color {
+  Red,
+  Green,
+}
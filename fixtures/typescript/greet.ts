@@ -0,0 +1,17 @@
+import type { Greeting, Person } from "./types.ts";
+import { shout } from "./shout.ts";
+
+type Counter = { count: number };
+
+const counter: Counter = { count: 0 };
+
+export function greet(person: Person, greeting: Greeting = "hello"): string {
+  counter.count++;
+  return shout(`${greeting} ${person.name}`);
+}
+
+export const add = <T extends number>(a: T, b: T): number => a + b;
+
+export function greetings(): number {
+  return counter.count as number;
+}
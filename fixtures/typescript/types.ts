@@ -0,0 +1,6 @@
+export type Greeting = "hello" | "hi";
+
+export interface Person {
+  name: string;
+  age?: number;
+}
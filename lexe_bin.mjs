@@ -15,7 +15,13 @@ if (arch === 'ia32') {
   throw new Error('32-bit architecture is not supported.');
 }
 
-const currentLLRT = `llrt-${platform}-${arch}`;
+// glibc reports its version, musl (Alpine) does not
+const libc =
+  platform === 'linux' && !process.report?.getReport().header.glibcVersionRuntime
+    ? '-musl'
+    : '';
+
+const currentLLRT = `llrt-${platform}-${arch}${libc}`;
 
 (async function () {
   try {
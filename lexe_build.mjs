@@ -16,9 +16,12 @@ const __dirname = path.dirname(__filename);
     const allPlatforms = [
       'linux-x64',
       'linux-arm64',
+      'linux-x64-musl',
+      'linux-arm64-musl',
       'darwin-x64',
       'darwin-arm64',
       'windows-x64',
+      'windows-arm64',
     ];
     
     // if platform is provided, only build the specified platform, otherwise build all platforms
@@ -1,10 +1,16 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
-use std::future::Future;
+use std::cell::RefCell;
+use std::future::{poll_fn, Future};
+use std::pin::Pin;
+use std::rc::Rc;
 use std::sync::OnceLock;
+use std::task::{Poll, Waker};
 
 use llrt_utils::primordials::{BasePrimordials, Primordial};
-use rquickjs::{atom::PredefinedAtom, CatchResultExt, CaughtError, Ctx, Object, Result};
+use rquickjs::{
+    atom::PredefinedAtom, CatchResultExt, CaughtError, Ctx, JsLifetime, Object, Result,
+};
 use tokio::sync::oneshot::{self, Receiver};
 use tracing::trace;
 
@@ -23,6 +29,14 @@ pub trait CtxExtension<'js> {
     fn spawn_exit_simple<F>(&self, future: F)
     where
         F: Future<Output = Result<()>> + 'js;
+
+    /// Spawns a future that does not keep the runtime alive, like an unref'd
+    /// handle in Node.js. It is only polled while futures spawned with
+    /// `spawn_exit` or `spawn_exit_simple` are running, and it is resumed
+    /// when new ones are spawned.
+    fn spawn_unref<F>(&self, future: F)
+    where
+        F: Future<Output = Result<()>> + 'js;
 }
 
 impl<'js> CtxExtension<'js> for Ctx<'js> {
@@ -39,7 +53,9 @@ impl<'js> CtxExtension<'js> for Ctx<'js> {
 
         let (join_channel_tx, join_channel_rx) = oneshot::channel();
 
+        let reference = Reference::acquire(self);
         self.spawn(async move {
+            let _reference = reference;
             match future.await.catch(&ctx) {
                 Ok(res) => {
                     //result here doesn't matter if receiver has dropped
@@ -57,12 +73,135 @@ impl<'js> CtxExtension<'js> for Ctx<'js> {
         F: Future<Output = Result<()>> + 'js,
     {
         let ctx = self.clone();
+        let reference = Reference::acquire(self);
         self.spawn(async move {
+            let _reference = reference;
             if let Err(err) = future.await.catch(&ctx) {
                 handle_spawn_error(&ctx, err, None)
             }
         });
     }
+
+    fn spawn_unref<F>(&self, future: F)
+    where
+        F: Future<Output = Result<()>> + 'js,
+    {
+        let Some(tasks) = UnrefTasks::get(self) else {
+            return;
+        };
+        let mut unref = tasks.0.borrow_mut();
+        unref.futures.push(Box::pin(future));
+        if unref.refs == 0 {
+            return;
+        }
+        if let Some(waker) = &unref.waker {
+            waker.wake_by_ref();
+        } else if !unref.driving {
+            drop(unref);
+            tasks.drive(self);
+        }
+    }
+}
+
+type UnrefFuture<'js> = Pin<Box<dyn Future<Output = Result<()>> + 'js>>;
+
+struct Unref<'js> {
+    // Running futures spawned with `spawn_exit` or `spawn_exit_simple`
+    refs: usize,
+    futures: Vec<UnrefFuture<'js>>,
+    driving: bool,
+    waker: Option<Waker>,
+}
+
+// The futures of a runtime spawned with `spawn_unref`
+#[derive(Clone)]
+struct UnrefTasks<'js>(Rc<RefCell<Unref<'js>>>);
+
+unsafe impl<'js> JsLifetime<'js> for UnrefTasks<'js> {
+    type Changed<'to> = UnrefTasks<'to>;
+}
+
+impl<'js> UnrefTasks<'js> {
+    fn get(ctx: &Ctx<'js>) -> Option<Self> {
+        if let Some(tasks) = ctx.userdata::<Self>() {
+            return Some(tasks.clone());
+        }
+        let tasks = Self(Rc::new(RefCell::new(Unref {
+            refs: 0,
+            futures: Vec::new(),
+            driving: false,
+            waker: None,
+        })));
+        ctx.store_userdata(tasks.clone()).ok()?;
+        Some(tasks)
+    }
+
+    // Polls the unref'd futures until none of them is left or no other
+    // future is running
+    fn drive(self, ctx: &Ctx<'js>) {
+        self.0.borrow_mut().driving = true;
+        let ctx2 = ctx.clone();
+        ctx.spawn(poll_fn(move |cx| {
+            let mut futures = std::mem::take(&mut self.0.borrow_mut().futures);
+            futures.retain_mut(|future| match future.as_mut().poll(cx) {
+                Poll::Ready(result) => {
+                    if let Err(err) = result.catch(&ctx2) {
+                        handle_spawn_error(&ctx2, err, None);
+                    }
+                    false
+                },
+                Poll::Pending => true,
+            });
+
+            let mut unref = self.0.borrow_mut();
+            // the futures spawned while polling are polled next
+            if !unref.futures.is_empty() {
+                cx.waker().wake_by_ref();
+            }
+            futures.append(&mut unref.futures);
+            unref.futures = futures;
+            if unref.refs == 0 || unref.futures.is_empty() {
+                unref.driving = false;
+                unref.waker = None;
+                return Poll::Ready(());
+            }
+            unref.waker = Some(cx.waker().clone());
+            Poll::Pending
+        }));
+    }
+}
+
+// Counts a running future as a reason to poll the unref'd futures
+struct Reference<'js>(Option<UnrefTasks<'js>>);
+
+impl<'js> Reference<'js> {
+    fn acquire(ctx: &Ctx<'js>) -> Self {
+        let tasks = UnrefTasks::get(ctx);
+        if let Some(tasks) = &tasks {
+            let mut unref = tasks.0.borrow_mut();
+            unref.refs += 1;
+            if unref.refs == 1 && !unref.driving && !unref.futures.is_empty() {
+                drop(unref);
+                tasks.clone().drive(ctx);
+            }
+        }
+        Self(tasks)
+    }
+}
+
+impl Drop for Reference<'_> {
+    fn drop(&mut self) {
+        let Some(tasks) = &self.0 else {
+            return;
+        };
+        let mut unref = tasks.0.borrow_mut();
+        unref.refs -= 1;
+        if unref.refs == 0 {
+            if let Some(waker) = unref.waker.take() {
+                waker.wake();
+            }
+        }
+    }
 }
 
 fn handle_spawn_error<'js>(ctx: &Ctx<'js>, err: CaughtError<'js>, stack: Option<String>) {
@@ -6,6 +6,7 @@ use std::{
     mem,
     ops::Deref,
     slice,
+    sync::OnceLock,
 };
 
 use llrt_json::stringify::json_stringify;
@@ -33,6 +34,8 @@ const SPACING: char = ' ';
 const CIRCULAR: &str = "[Circular]";
 pub const TIME_FORMAT: &str = "%Y-%m-%dT%H:%M:%S%.3fZ";
 
+static STACK_TRACE_MAPPER: OnceLock<fn(&str) -> Option<String>> = OnceLock::new();
+
 const MAX_INDENTATION_LEVEL: usize = 4;
 const MAX_EXPANSION_DEPTH: usize = 4;
 const INDENTATION_LOOKUP: [&str; MAX_INDENTATION_LEVEL + 1] =
@@ -175,6 +178,19 @@ impl<'js> FormatOptions<'js> {
     }
 }
 
+/// Sets the function used to rewrite error stack traces before they are
+/// printed, e.g. to map positions back through source maps.
+pub fn set_stack_trace_mapper(mapper: fn(&str) -> Option<String>) {
+    _ = STACK_TRACE_MAPPER.set(mapper);
+}
+
+pub fn map_stack_trace(stack: String) -> String {
+    STACK_TRACE_MAPPER
+        .get()
+        .and_then(|mapper| mapper(&stack))
+        .unwrap_or(stack)
+}
+
 pub fn format_plain<'js>(ctx: Ctx<'js>, newline: bool, args: Rest<Value<'js>>) -> Result<String> {
     format_values(&ctx, args, false, newline)
 }
@@ -471,7 +487,7 @@ fn format_raw_inner<'js>(
             if value.is_error() {
                 let name: String = obj.get(PredefinedAtom::Name)?;
                 let message: String = obj.get(PredefinedAtom::Message)?;
-                let stack: Result<String> = obj.get(PredefinedAtom::Stack);
+                let stack: Result<String> = obj.get(PredefinedAtom::Stack).map(map_stack_trace);
                 result.push_str(&name);
                 result.push_str(": ");
                 result.push_str(&message);
@@ -0,0 +1,225 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+//! Parser for `.env` files, used by `--env-file`, `process.loadEnvFile()` and
+//! `util.parseEnv()`.
+//!
+//! Every line is `KEY=value`, optionally prefixed by `export`. Values can be
+//! unquoted, where a ` #` starts a comment, or quoted with `'`, `"` or `` ` ``
+//! and span lines. Double quoted values expand `\n`, `\r`, `\t`, `\"`, `\\`
+//! and `\$`. `${VAR}` in unquoted and double quoted values is replaced with
+//! an earlier key of the file, or else the variable of the environment.
+use std::{env, fs, io, path::Path};
+
+/// Parses `source`, in order of appearance. A key defined twice keeps its
+/// last value.
+pub fn parse(source: &str) -> Vec<(String, String)> {
+    parse_with(source, |name| env::var(name).ok())
+}
+
+/// Like [`parse`], with `${VAR}` looking up variables that are not keys of
+/// the file in `variable` instead of the environment of the process.
+pub fn parse_with(
+    source: &str,
+    variable: impl Fn(&str) -> Option<String>,
+) -> Vec<(String, String)> {
+    let mut entries: Vec<(String, String)> = Vec::new();
+    let mut rest = source;
+
+    while !rest.is_empty() {
+        let (line, next) = split_line(rest);
+        rest = next;
+
+        let line = line.trim_start();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line
+            .strip_prefix("export ")
+            .map(str::trim_start)
+            .unwrap_or(line);
+        let Some((key, value)) = line.split_once('=') else {
+            continue;
+        };
+        let key = key.trim();
+        if key.is_empty() || key.contains(char::is_whitespace) {
+            continue;
+        }
+
+        let value = value.trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'' | '`')) => {
+                // the value continues past the end of the line until the
+                // closing quote, when there is one
+                let offset = value.as_ptr() as usize - source.as_ptr() as usize;
+                let after_quote = &source[offset + 1..];
+                match find_closing(after_quote, quote) {
+                    Some(end) => {
+                        let quoted = &after_quote[..end];
+                        let (_, next) = split_line(&after_quote[end + 1..]);
+                        rest = next;
+                        match quote {
+                            '"' => expand(quoted, true, &entries, &variable),
+                            _ => quoted.to_string(),
+                        }
+                    },
+                    None => expand(strip_comment(value).trim_end(), false, &entries, &variable),
+                }
+            },
+            _ => expand(strip_comment(value).trim_end(), false, &entries, &variable),
+        };
+
+        match entries.iter_mut().find(|(existing, _)| existing == key) {
+            Some(entry) => entry.1 = value,
+            None => entries.push((key.to_string(), value)),
+        }
+    }
+
+    entries
+}
+
+/// Sets the variables of the file at `path` that are not set already in the
+/// environment of the process. Only call it before other threads are
+/// started, as they may read the environment meanwhile.
+pub fn load(path: &Path) -> io::Result<Vec<(String, String)>> {
+    let entries = parse(&fs::read_to_string(path)?);
+    for (key, value) in &entries {
+        if env::var_os(key).is_none() {
+            env::set_var(key, value);
+        }
+    }
+    Ok(entries)
+}
+
+fn split_line(source: &str) -> (&str, &str) {
+    match source.find('\n') {
+        Some(end) => (source[..end].trim_end_matches('\r'), &source[end + 1..]),
+        None => (source, ""),
+    }
+}
+
+// Position of the quote ending a value, skipping escaped ones in double
+// quoted values
+fn find_closing(source: &str, quote: char) -> Option<usize> {
+    let mut escaped = false;
+    for (i, c) in source.char_indices() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' && quote == '"' {
+            escaped = true;
+        } else if c == quote {
+            return Some(i);
+        }
+    }
+    None
+}
+
+fn strip_comment(value: &str) -> &str {
+    if value.starts_with('#') {
+        return "";
+    }
+    match value.find(" #").or_else(|| value.find("\t#")) {
+        Some(start) => &value[..start],
+        None => value,
+    }
+}
+
+// Replaces `${VAR}`, and the escapes of double quoted values when `escapes`
+fn expand(
+    value: &str,
+    escapes: bool,
+    entries: &[(String, String)],
+    variable: &impl Fn(&str) -> Option<String>,
+) -> String {
+    let mut result = String::with_capacity(value.len());
+    let mut chars = value.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '\\' if escapes => match chars.next().map(|(_, c)| c) {
+                Some('n') => result.push('\n'),
+                Some('r') => result.push('\r'),
+                Some('t') => result.push('\t'),
+                Some(c @ ('"' | '\\' | '$')) => result.push(c),
+                Some(c) => {
+                    result.push('\\');
+                    result.push(c);
+                },
+                None => result.push('\\'),
+            },
+            '$' => {
+                let Some(len) = value[i..]
+                    .strip_prefix("${")
+                    .and_then(|after| after.find('}'))
+                else {
+                    result.push('$');
+                    continue;
+                };
+                let name = &value[i + 2..i + 2 + len];
+                let variable = entries
+                    .iter()
+                    .find(|(key, _)| key == name)
+                    .map(|(_, value)| value.clone())
+                    .or_else(|| variable(name))
+                    .unwrap_or_default();
+                result.push_str(&variable);
+                // skip the name and the closing brace
+                let end = i + 2 + len;
+                while chars.next().is_some_and(|(j, _)| j < end) {}
+            },
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse, parse_with};
+
+    fn get<'a>(entries: &'a [(String, String)], key: &str) -> Option<&'a str> {
+        entries
+            .iter()
+            .find(|(existing, _)| existing == key)
+            .map(|(_, value)| value.as_str())
+    }
+
+    #[test]
+    fn test_parse() {
+        let entries = parse(concat!(
+            "# comment\n",
+            "PLAIN=value # trailing comment\n",
+            "export EXPORTED = spaced \r\n",
+            "EMPTY=\n",
+            "SINGLE='no ${PLAIN} \\n'\n",
+            "DOUBLE=\"a\\nb \\\"q\\\" ${PLAIN} \\${PLAIN} \\\\${PLAIN}\"\n",
+            "MULTI=\"line 1\n",
+            "line 2\"\n",
+            "BACKTICK=`it's \"quoted\"`\n",
+            "HASH=\"#not a comment\"\n",
+            "EXPANDED=${PLAIN}-${MISSING_DOTENV_TEST_VAR}\n",
+            "PLAIN=again\n",
+            "not a pair\n",
+        ));
+
+        assert_eq!(get(&entries, "PLAIN"), Some("again"));
+        assert_eq!(get(&entries, "EXPORTED"), Some("spaced"));
+        assert_eq!(get(&entries, "EMPTY"), Some(""));
+        assert_eq!(get(&entries, "SINGLE"), Some("no ${PLAIN} \\n"));
+        assert_eq!(
+            get(&entries, "DOUBLE"),
+            Some("a\nb \"q\" value ${PLAIN} \\value")
+        );
+        assert_eq!(get(&entries, "MULTI"), Some("line 1\nline 2"));
+        assert_eq!(get(&entries, "BACKTICK"), Some("it's \"quoted\""));
+        assert_eq!(get(&entries, "HASH"), Some("#not a comment"));
+        assert_eq!(get(&entries, "EXPANDED"), Some("value-"));
+        assert_eq!(entries.len(), 9);
+    }
+
+    #[test]
+    fn test_parse_with() {
+        let entries = parse_with("A=${B}-${C}\nC=c\n", |name| {
+            (name == "B").then(|| "b".to_string())
+        });
+        assert_eq!(get(&entries, "A"), Some("b-"));
+    }
+}
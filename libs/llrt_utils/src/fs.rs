@@ -1,17 +1,125 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
-use std::{fs::Metadata, io, path::PathBuf};
+use std::{
+    fs::{Metadata, ReadDir},
+    io, mem,
+    path::PathBuf,
+};
 
-use tokio::fs::{self};
+use rquickjs::{Ctx, Error, Exception, Result};
+use tokio::task;
 
+/// Like [`ResultExt::or_throw_msg`](crate::result::ResultExt::or_throw_msg)
+/// for I/O errors. The thrown `Error` carries the `code`, `errno`, `syscall`
+/// and `path` properties of Node.js system errors.
+pub trait IoResultExt<T> {
+    fn or_throw_io(self, ctx: &Ctx, msg: &str, syscall: &str, path: &str) -> Result<T>;
+}
+
+impl<T> IoResultExt<T> for io::Result<T> {
+    fn or_throw_io(self, ctx: &Ctx, msg: &str, syscall: &str, path: &str) -> Result<T> {
+        self.map_err(|err| throw_io_error(ctx, &err, msg, Some(syscall), Some(path)))
+    }
+}
+
+/// Throws `err` prefixed by `msg`, see [`io_error`].
+pub fn throw_io_error(
+    ctx: &Ctx,
+    err: &io::Error,
+    msg: &str,
+    syscall: Option<&str>,
+    path: Option<&str>,
+) -> Error {
+    match io_error(ctx, err, msg, syscall, path) {
+        Ok(error) => ctx.throw(error.into_value()),
+        Err(err) => err,
+    }
+}
+
+/// Creates an `Error` for `err` prefixed by `msg`, with the `code` and
+/// `errno` of `err` and the `syscall` and `path` that failed.
+pub fn io_error<'js>(
+    ctx: &Ctx<'js>,
+    err: &io::Error,
+    msg: &str,
+    syscall: Option<&str>,
+    path: Option<&str>,
+) -> Result<Exception<'js>> {
+    let message = match msg {
+        "" => err.to_string(),
+        msg => [msg, ". ", &err.to_string()].concat(),
+    };
+    let error = Exception::from_message(ctx.clone(), &message)?;
+    if let Some(code) = io_error_code(err) {
+        error.set("code", code)?;
+    }
+    if let Some(errno) = err.raw_os_error() {
+        error.set("errno", -errno)?;
+    }
+    if let Some(syscall) = syscall {
+        error.set("syscall", syscall)?;
+    }
+    if let Some(path) = path {
+        error.set("path", path)?;
+    }
+    Ok(error)
+}
+
+/// The Node.js error code of `err`, such as `ENOENT`.
+pub fn io_error_code(err: &io::Error) -> Option<&'static str> {
+    use io::ErrorKind::*;
+
+    #[cfg(unix)]
+    match err.raw_os_error() {
+        Some(1) => return Some("EPERM"),
+        Some(9) => return Some("EBADF"),
+        _ => {},
+    }
+    // ERROR_INVALID_HANDLE
+    #[cfg(windows)]
+    if err.raw_os_error() == Some(6) {
+        return Some("EBADF");
+    }
+    Some(match err.kind() {
+        NotFound => "ENOENT",
+        PermissionDenied => "EACCES",
+        AlreadyExists => "EEXIST",
+        NotADirectory => "ENOTDIR",
+        IsADirectory => "EISDIR",
+        DirectoryNotEmpty => "ENOTEMPTY",
+        ReadOnlyFilesystem => "EROFS",
+        InvalidInput => "EINVAL",
+        InvalidFilename => "ENAMETOOLONG",
+        CrossesDevices => "EXDEV",
+        TooManyLinks => "EMLINK",
+        ResourceBusy => "EBUSY",
+        StorageFull => "ENOSPC",
+        FileTooLarge => "EFBIG",
+        BrokenPipe => "EPIPE",
+        Interrupted => "EINTR",
+        WouldBlock => "EAGAIN",
+        TimedOut => "ETIMEDOUT",
+        Unsupported => "ENOTSUP",
+        _ => return None,
+    })
+}
+
+// A directory of a walk, opened once its entries are read
+enum Level {
+    Unopened(PathBuf),
+    Open(ReadDir),
+}
+
+/// Walks the entries of a directory, and of its subdirectories when
+/// recursive, reading them from the file system one at a time. Only the
+/// handles of the directories being read are kept open.
 pub struct DirectoryWalker<T>
 where
     T: Fn(&str) -> bool,
 {
-    stack: Vec<(PathBuf, Option<Metadata>)>,
+    stack: Vec<Level>,
     filter: T,
     recursive: bool,
-    eat_root: bool,
 }
 
 impl<T> DirectoryWalker<T>
@@ -20,10 +128,9 @@ where
 {
     pub fn new(root: PathBuf, filter: T) -> Self {
         Self {
-            stack: vec![(root, None)],
+            stack: vec![Level::Unopened(root)],
             filter,
             recursive: false,
-            eat_root: true,
         }
     }
 
@@ -32,72 +139,69 @@ where
     }
 
     pub async fn walk(&mut self) -> io::Result<Option<(PathBuf, Metadata)>> {
-        if self.eat_root {
-            self.eat_root = false;
-            let (dir, _) = self.stack.pop().unwrap();
-            self.append_stack(&dir).await?;
-        }
-        if let Some((entry, metadata)) = self.stack.pop() {
-            let metadata = metadata.unwrap();
-            if self.recursive && metadata.is_dir() {
-                self.append_stack(&entry).await?;
-            }
+        loop {
+            let mut stack = mem::take(&mut self.stack);
+            let (stack, entry) = task::spawn_blocking(move || {
+                let entry = next_entry(&mut stack);
+                (stack, entry)
+            })
+            .await
+            .map_err(io::Error::other)?;
+            self.stack = stack;
 
-            Ok(Some((entry, metadata)))
-        } else {
-            Ok(None)
+            let Some((entry, metadata)) = entry? else {
+                return Ok(None);
+            };
+            if let Some(entry) = self.accept(entry, metadata) {
+                return Ok(Some(entry));
+            }
         }
     }
 
     pub fn walk_sync(&mut self) -> io::Result<Option<(PathBuf, Metadata)>> {
-        if self.eat_root {
-            self.eat_root = false;
-            let (dir, _) = self.stack.pop().unwrap();
-            self.append_stack_sync(&dir)?;
-        }
-        if let Some((entry, metadata)) = self.stack.pop() {
-            let metadata = metadata.unwrap();
-            if self.recursive && metadata.is_dir() {
-                self.append_stack_sync(&entry)?;
+        while let Some((entry, metadata)) = next_entry(&mut self.stack)? {
+            if let Some(entry) = self.accept(entry, metadata) {
+                return Ok(Some(entry));
             }
-
-            Ok(Some((entry, metadata)))
-        } else {
-            Ok(None)
         }
+        Ok(None)
     }
 
-    async fn append_stack(&mut self, dir: &PathBuf) -> io::Result<()> {
-        let mut stream = fs::read_dir(dir).await?;
-
-        while let Some(entry) = stream.next_entry().await? {
-            let name = entry.file_name();
-            let name = name.to_string_lossy();
-            if !(self.filter)(name.as_ref()) {
-                continue;
-            }
-            let entry_path = entry.path();
-            let metadata = fs::symlink_metadata(&entry_path).await?;
-
-            self.stack.push((entry_path, Some(metadata)));
+    // Returns the entry unless filtered out, a directory is read next when
+    // recursive
+    fn accept(&mut self, entry: PathBuf, metadata: Metadata) -> Option<(PathBuf, Metadata)> {
+        let name = entry.file_name().unwrap_or_default().to_string_lossy();
+        if !(self.filter)(name.as_ref()) {
+            return None;
+        }
+        if self.recursive && metadata.is_dir() {
+            self.stack.push(Level::Unopened(entry.clone()));
         }
-        Ok(())
+        Some((entry, metadata))
     }
+}
 
-    fn append_stack_sync(&mut self, dir: &PathBuf) -> io::Result<()> {
-        let dir = std::fs::read_dir(dir)?;
-
-        for entry in dir.flatten() {
-            let name = entry.file_name();
-            let name = name.to_string_lossy();
-            if !(self.filter)(name.as_ref()) {
+// Reads the next entry of the innermost directory of `stack`, and continues
+// with the directory containing it once it has no entries left
+fn next_entry(stack: &mut Vec<Level>) -> io::Result<Option<(PathBuf, Metadata)>> {
+    while let Some(level) = stack.last_mut() {
+        let dir = match level {
+            Level::Open(dir) => dir,
+            Level::Unopened(path) => {
+                *level = Level::Open(std::fs::read_dir(path)?);
                 continue;
-            }
-            let entry_path = entry.path();
-            let metadata = entry_path.symlink_metadata()?;
-            self.stack.push((entry_path, Some(metadata)))
+            },
+        };
+        match dir.next() {
+            Some(entry) => {
+                let entry_path = entry?.path();
+                let metadata = entry_path.symlink_metadata()?;
+                return Ok(Some((entry_path, metadata)));
+            },
+            None => {
+                stack.pop();
+            },
         }
-
-        Ok(())
     }
+    Ok(None)
 }
@@ -0,0 +1,14 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use rquickjs::{
+    prelude::{Rest, This},
+    Function, Result, Value,
+};
+
+/// Returns `func` with `this` and its leading arguments bound to `args`.
+/// Unlike values captured by a closure, bound values are visible to the
+/// garbage collector, so they do not keep the context alive.
+pub fn bind<'js>(func: &Function<'js>, args: Vec<Value<'js>>) -> Result<Function<'js>> {
+    let bind: Function = func.get("bind")?;
+    bind.call((This(func.clone()), Rest(args)))
+}
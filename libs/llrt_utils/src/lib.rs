@@ -5,10 +5,12 @@ pub mod bytearray_buffer;
 pub mod bytes;
 pub mod class;
 pub mod clone;
+pub mod dotenv;
 pub mod error;
 pub mod error_messages;
 #[cfg(feature = "fs")]
 pub mod fs;
+pub mod function;
 pub mod hash;
 pub mod macros;
 pub mod mc_oneshot;
@@ -20,5 +22,7 @@ pub mod result;
 pub mod reuse_list;
 pub mod sysinfo;
 pub mod time;
+#[cfg(feature = "watch")]
+pub mod watch;
 
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
@@ -0,0 +1,610 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+//! Blocking file system watcher. Uses inotify on Linux and compares
+//! modification times at an interval everywhere else.
+use std::{
+    io,
+    path::{Path, PathBuf},
+    sync::Arc,
+    time::Duration,
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    /// The contents or attributes of the path changed
+    Change,
+    /// The path was created, removed or renamed
+    Rename,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub kind: EventKind,
+    pub path: PathBuf,
+}
+
+/// Watches files and directories. Watching a directory reports changes of
+/// its entries, and of every directory below it when `recursive`.
+pub struct Watcher {
+    inner: imp::Watcher,
+}
+
+impl Watcher {
+    pub fn new() -> io::Result<Self> {
+        Ok(Self {
+            inner: imp::Watcher::new()?,
+        })
+    }
+
+    /// Watches `path`. A path can be watched several times, it is watched
+    /// recursively as long as one of them is recursive.
+    pub fn watch(&mut self, path: &Path, recursive: bool) -> io::Result<()> {
+        self.inner.watch(path, recursive)
+    }
+
+    /// Undoes one [`Watcher::watch`] of `path` with the same `recursive`.
+    pub fn unwatch(&mut self, path: &Path, recursive: bool) {
+        self.inner.unwatch(path, recursive)
+    }
+
+    pub fn is_watching(&self, path: &Path) -> bool {
+        self.inner.is_watching(path)
+    }
+
+    /// Returns a [`Waker`] that interrupts [`Watcher::wait`] from another thread.
+    pub fn waker(&self) -> Waker {
+        Waker {
+            inner: self.inner.waker(),
+        }
+    }
+
+    /// Blocks until there are events, `timeout` passed or it was woken by a
+    /// [`Waker`], in which case the returned list can be empty. Without a
+    /// timeout it waits for events or to be woken.
+    pub fn wait(&mut self, timeout: Option<Duration>) -> io::Result<Vec<Event>> {
+        self.inner.wait(timeout)
+    }
+}
+
+/// Wakes up a [`Watcher`] waiting for events, or its next wait when it is
+/// not waiting.
+#[derive(Clone)]
+pub struct Waker {
+    inner: Arc<imp::Waker>,
+}
+
+impl Waker {
+    pub fn wake(&self) {
+        self.inner.wake()
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod imp {
+    use std::{
+        collections::HashMap,
+        ffi::{CString, OsStr},
+        fs, io,
+        os::unix::ffi::OsStrExt,
+        path::{Path, PathBuf},
+        sync::Arc,
+        time::Duration,
+    };
+
+    use super::{Event, EventKind};
+
+    const CHANGE_MASK: u32 = libc::IN_MODIFY | libc::IN_ATTRIB;
+    const RENAME_MASK: u32 = libc::IN_CREATE
+        | libc::IN_DELETE
+        | libc::IN_MOVED_FROM
+        | libc::IN_MOVED_TO
+        | libc::IN_DELETE_SELF
+        | libc::IN_MOVE_SELF;
+    // Header of every event, followed by `len` bytes of NUL padded name
+    const EVENT_SIZE: usize = std::mem::size_of::<libc::inotify_event>();
+
+    // The watches of the directories below a recursively watched one are
+    // added along with it and have no references of their own
+    struct Watch {
+        path: PathBuf,
+        refs: usize,
+        recursive_refs: usize,
+    }
+
+    pub struct Watcher {
+        fd: libc::c_int,
+        watches: HashMap<libc::c_int, Watch>,
+        waker: Arc<Waker>,
+    }
+
+    // An eventfd polled along with the inotify descriptor
+    pub struct Waker {
+        fd: libc::c_int,
+    }
+
+    impl Waker {
+        fn new() -> io::Result<Self> {
+            let fd = unsafe { libc::eventfd(0, libc::EFD_CLOEXEC | libc::EFD_NONBLOCK) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self { fd })
+        }
+
+        pub fn wake(&self) {
+            let value: u64 = 1;
+            unsafe { libc::write(self.fd, (&value as *const u64).cast(), 8) };
+        }
+
+        fn reset(&self) {
+            let mut value: u64 = 0;
+            unsafe { libc::read(self.fd, (&mut value as *mut u64).cast(), 8) };
+        }
+    }
+
+    impl Drop for Waker {
+        fn drop(&mut self) {
+            unsafe { libc::close(self.fd) };
+        }
+    }
+
+    impl Watcher {
+        pub fn new() -> io::Result<Self> {
+            let waker = Arc::new(Waker::new()?);
+            let fd = unsafe { libc::inotify_init1(libc::IN_CLOEXEC | libc::IN_NONBLOCK) };
+            if fd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self {
+                fd,
+                watches: HashMap::new(),
+                waker,
+            })
+        }
+
+        pub fn waker(&self) -> Arc<Waker> {
+            self.waker.clone()
+        }
+
+        pub fn watch(&mut self, path: &Path, recursive: bool) -> io::Result<()> {
+            let wd = self.add_watch(path)?;
+            let watch = self.watches.get_mut(&wd).unwrap();
+            watch.refs += 1;
+            if recursive {
+                watch.recursive_refs += 1;
+                if watch.recursive_refs == 1 {
+                    self.watch_below(path);
+                }
+            }
+            Ok(())
+        }
+
+        pub fn unwatch(&mut self, path: &Path, recursive: bool) {
+            let Some(watch) = self
+                .watches
+                .values_mut()
+                .find(|watch| watch.path == path && watch.refs > 0)
+            else {
+                return;
+            };
+            watch.refs -= 1;
+            if recursive {
+                watch.recursive_refs = watch.recursive_refs.saturating_sub(1);
+            }
+
+            let removed: Vec<_> = self
+                .watches
+                .iter()
+                .filter(|(_, watch)| watch.refs == 0 && !self.is_below_recursive(&watch.path))
+                .map(|(&wd, _)| wd)
+                .collect();
+            for wd in removed {
+                unsafe { libc::inotify_rm_watch(self.fd, wd) };
+                self.watches.remove(&wd);
+            }
+        }
+
+        // Adds a watch for `path` without a reference, or returns the one
+        // that is watching it already
+        fn add_watch(&mut self, path: &Path) -> io::Result<libc::c_int> {
+            let path_c = CString::new(path.as_os_str().as_bytes())
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+            let wd = unsafe {
+                libc::inotify_add_watch(self.fd, path_c.as_ptr(), CHANGE_MASK | RENAME_MASK)
+            };
+            if wd < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // the same inode gives the same watch, which keeps its references
+            self.watches.entry(wd).or_insert_with(|| Watch {
+                path: path.to_path_buf(),
+                refs: 0,
+                recursive_refs: 0,
+            });
+            Ok(wd)
+        }
+
+        fn watch_below(&mut self, dir: &Path) {
+            let Ok(entries) = fs::read_dir(dir) else {
+                return;
+            };
+            for entry in entries.flatten() {
+                if entry.file_type().is_ok_and(|file_type| file_type.is_dir()) {
+                    // directories that cannot be read are skipped, like `find`
+                    let path = entry.path();
+                    if self.add_watch(&path).is_ok() {
+                        self.watch_below(&path);
+                    }
+                }
+            }
+        }
+
+        fn is_recursive(&self, path: &Path) -> bool {
+            self.watches
+                .values()
+                .any(|watch| watch.recursive_refs > 0 && path.starts_with(&watch.path))
+        }
+
+        // Whether `path` is watched because a directory above it is
+        // watched recursively
+        fn is_below_recursive(&self, path: &Path) -> bool {
+            self.watches.values().any(|watch| {
+                watch.recursive_refs > 0 && path != watch.path && path.starts_with(&watch.path)
+            })
+        }
+
+        pub fn is_watching(&self, path: &Path) -> bool {
+            self.watches.values().any(|watch| watch.path == path)
+        }
+
+        pub fn wait(&mut self, timeout: Option<Duration>) -> io::Result<Vec<Event>> {
+            let timeout = timeout.map_or(-1, |timeout| {
+                timeout.as_millis().min(i32::MAX as u128) as i32
+            });
+            let mut poll_fds = [self.fd, self.waker.fd].map(|fd| libc::pollfd {
+                fd,
+                events: libc::POLLIN,
+                revents: 0,
+            });
+            let ready = unsafe { libc::poll(poll_fds.as_mut_ptr(), 2, timeout) };
+            if ready < 0 {
+                let err = io::Error::last_os_error();
+                if err.kind() == io::ErrorKind::Interrupted {
+                    return Ok(Vec::new());
+                }
+                return Err(err);
+            }
+            if poll_fds[1].revents & libc::POLLIN != 0 {
+                self.waker.reset();
+            }
+
+            let mut events = Vec::new();
+            let mut buf = [0u8; 8192];
+            loop {
+                let len = unsafe { libc::read(self.fd, buf.as_mut_ptr().cast(), buf.len()) };
+                if len < 0 {
+                    let err = io::Error::last_os_error();
+                    if err.kind() == io::ErrorKind::WouldBlock {
+                        break;
+                    }
+                    return Err(err);
+                }
+                self.parse_events(&buf[..len as usize], &mut events);
+            }
+            Ok(events)
+        }
+
+        fn parse_events(&mut self, mut buf: &[u8], events: &mut Vec<Event>) {
+            while buf.len() >= EVENT_SIZE {
+                let event: libc::inotify_event =
+                    unsafe { std::ptr::read_unaligned(buf.as_ptr().cast()) };
+                let end = (EVENT_SIZE + event.len as usize).min(buf.len());
+                let name = &buf[EVENT_SIZE..end];
+                let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+                buf = &buf[end..];
+
+                if event.mask & libc::IN_IGNORED != 0 {
+                    self.watches.remove(&event.wd);
+                    continue;
+                }
+                let Some(watch) = self.watches.get(&event.wd) else {
+                    continue;
+                };
+                let path = if name.is_empty() {
+                    watch.path.clone()
+                } else {
+                    watch.path.join(OsStr::from_bytes(name))
+                };
+                let recursive = self.is_recursive(&watch.path);
+
+                let kind = if event.mask & RENAME_MASK != 0 {
+                    EventKind::Rename
+                } else {
+                    EventKind::Change
+                };
+                if recursive
+                    && event.mask & libc::IN_ISDIR != 0
+                    && event.mask & (libc::IN_CREATE | libc::IN_MOVED_TO) != 0
+                    && self.add_watch(&path).is_ok()
+                {
+                    self.watch_below(&path);
+                }
+                events.push(Event { kind, path });
+            }
+        }
+    }
+
+    impl Drop for Watcher {
+        fn drop(&mut self) {
+            unsafe { libc::close(self.fd) };
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+mod imp {
+    use std::{
+        collections::HashMap,
+        fs, io,
+        path::{Path, PathBuf},
+        sync::{Arc, Condvar, Mutex},
+        time::{Duration, Instant, SystemTime},
+    };
+
+    use super::{Event, EventKind};
+
+    const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+    // Modification time and size of the watched path and the entries below it
+    type Snapshot = HashMap<PathBuf, (Option<SystemTime>, u64)>;
+
+    struct Watch {
+        refs: usize,
+        recursive_refs: usize,
+        snapshot: Snapshot,
+    }
+
+    impl Watch {
+        fn recursive(&self) -> bool {
+            self.recursive_refs > 0
+        }
+    }
+
+    pub struct Watcher {
+        watches: HashMap<PathBuf, Watch>,
+        waker: Arc<Waker>,
+    }
+
+    #[derive(Default)]
+    pub struct Waker {
+        woken: Mutex<bool>,
+        condvar: Condvar,
+    }
+
+    impl Waker {
+        pub fn wake(&self) {
+            *self.woken.lock().unwrap() = true;
+            self.condvar.notify_one();
+        }
+
+        // Sleeps for `duration` unless woken, returns whether it was woken
+        fn sleep(&self, duration: Duration) -> bool {
+            let woken = self.woken.lock().unwrap();
+            let (mut woken, _) = self
+                .condvar
+                .wait_timeout_while(woken, duration, |woken| !*woken)
+                .unwrap();
+            std::mem::take(&mut *woken)
+        }
+    }
+
+    impl Watcher {
+        pub fn new() -> io::Result<Self> {
+            Ok(Self {
+                watches: HashMap::new(),
+                waker: Arc::default(),
+            })
+        }
+
+        pub fn waker(&self) -> Arc<Waker> {
+            self.waker.clone()
+        }
+
+        pub fn watch(&mut self, path: &Path, recursive: bool) -> io::Result<()> {
+            fs::metadata(path)?;
+            let watch = self
+                .watches
+                .entry(path.to_path_buf())
+                .or_insert_with(|| Watch {
+                    refs: 0,
+                    recursive_refs: 0,
+                    snapshot: Snapshot::new(),
+                });
+            watch.refs += 1;
+            if recursive {
+                watch.recursive_refs += 1;
+            }
+            if watch.refs == 1 || recursive && watch.recursive_refs == 1 {
+                watch.snapshot = snapshot(path, watch.recursive());
+            }
+            Ok(())
+        }
+
+        pub fn unwatch(&mut self, path: &Path, recursive: bool) {
+            let Some(watch) = self.watches.get_mut(path) else {
+                return;
+            };
+            watch.refs -= 1;
+            if recursive {
+                watch.recursive_refs = watch.recursive_refs.saturating_sub(1);
+            }
+            if watch.refs == 0 {
+                self.watches.remove(path);
+            }
+        }
+
+        pub fn is_watching(&self, path: &Path) -> bool {
+            self.watches.contains_key(path)
+        }
+
+        pub fn wait(&mut self, timeout: Option<Duration>) -> io::Result<Vec<Event>> {
+            let start = Instant::now();
+            loop {
+                let mut events = Vec::new();
+                for (path, watch) in &mut self.watches {
+                    let current = snapshot(path, watch.recursive());
+                    for (entry, state) in &current {
+                        match watch.snapshot.get(entry) {
+                            None => events.push(Event {
+                                kind: EventKind::Rename,
+                                path: entry.clone(),
+                            }),
+                            Some(previous) if previous != state => events.push(Event {
+                                kind: EventKind::Change,
+                                path: entry.clone(),
+                            }),
+                            _ => {},
+                        }
+                    }
+                    for entry in watch.snapshot.keys() {
+                        if !current.contains_key(entry) {
+                            events.push(Event {
+                                kind: EventKind::Rename,
+                                path: entry.clone(),
+                            });
+                        }
+                    }
+                    watch.snapshot = current;
+                }
+                if !events.is_empty() {
+                    return Ok(events);
+                }
+                let interval = match timeout {
+                    Some(timeout) => match timeout.checked_sub(start.elapsed()) {
+                        Some(remaining) if !remaining.is_zero() => remaining.min(POLL_INTERVAL),
+                        _ => return Ok(events),
+                    },
+                    None => POLL_INTERVAL,
+                };
+                if self.waker.sleep(interval) {
+                    return Ok(events);
+                }
+            }
+        }
+    }
+
+    fn snapshot(path: &Path, recursive: bool) -> Snapshot {
+        let mut snapshot = HashMap::new();
+        let Ok(metadata) = fs::metadata(path) else {
+            return snapshot;
+        };
+        snapshot.insert(
+            path.to_path_buf(),
+            (metadata.modified().ok(), metadata.len()),
+        );
+        if metadata.is_dir() {
+            add_entries(path, recursive, &mut snapshot);
+        }
+        snapshot
+    }
+
+    fn add_entries(dir: &Path, recursive: bool, snapshot: &mut Snapshot) {
+        let Ok(entries) = fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let Ok(metadata) = entry.metadata() else {
+                continue;
+            };
+            let path = entry.path();
+            snapshot.insert(path.clone(), (metadata.modified().ok(), metadata.len()));
+            if recursive && metadata.is_dir() {
+                add_entries(&path, true, snapshot);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, thread, time::Duration};
+
+    use super::{EventKind, Watcher};
+
+    #[test]
+    fn test_watch_directory() {
+        let dir = std::env::temp_dir().join("llrt-test-watch");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("nested")).unwrap();
+        fs::write(dir.join("a.js"), "1").unwrap();
+
+        let mut watcher = Watcher::new().unwrap();
+        watcher.watch(&dir, true).unwrap();
+        assert!(watcher.is_watching(&dir));
+        assert!(watcher
+            .wait(Some(Duration::from_millis(10)))
+            .unwrap()
+            .is_empty());
+
+        fs::write(dir.join("nested/b.js"), "2").unwrap();
+        let events = watcher.wait(Some(Duration::from_secs(5))).unwrap();
+        assert!(
+            events
+                .iter()
+                .any(|event| event.path == dir.join("nested/b.js")
+                    && event.kind == EventKind::Rename),
+            "{events:?}"
+        );
+
+        watcher.unwatch(&dir, true);
+        assert!(!watcher.is_watching(&dir));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_watch_twice() {
+        let dir = std::env::temp_dir().join("llrt-test-watch-twice");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("nested")).unwrap();
+
+        let mut watcher = Watcher::new().unwrap();
+        watcher.watch(&dir, true).unwrap();
+        watcher.watch(&dir, false).unwrap();
+        watcher.unwatch(&dir, false);
+        assert!(watcher.is_watching(&dir));
+
+        // still recursive after the other watch was added and removed
+        fs::write(dir.join("nested/a.js"), "1").unwrap();
+        let events = watcher.wait(Some(Duration::from_secs(5))).unwrap();
+        assert!(
+            events
+                .iter()
+                .any(|event| event.path == dir.join("nested/a.js")),
+            "{events:?}"
+        );
+
+        watcher.unwatch(&dir, true);
+        assert!(!watcher.is_watching(&dir));
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_waker() {
+        let dir = std::env::temp_dir().join("llrt-test-waker");
+        fs::create_dir_all(&dir).unwrap();
+
+        let mut watcher = Watcher::new().unwrap();
+        watcher.watch(&dir, false).unwrap();
+        let waker = watcher.waker();
+        let thread = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            waker.wake();
+        });
+        assert!(watcher.wait(None).unwrap().is_empty());
+        thread.join().unwrap();
+
+        watcher.waker().wake();
+        assert!(watcher.wait(None).unwrap().is_empty());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
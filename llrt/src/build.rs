@@ -1,15 +1,53 @@
 use libsui::{find_section, Elf, Macho, PortableExecutable};
-use llrt_core::compiler::compile_string;
+use llrt_core::bytecode::dictionary_id;
+use llrt_core::compiler::{compile_module_graph, AssetGlob, CompileOptions, Strip};
+use llrt_core::libs::encoding::bytes_to_hex_string;
+use llrt_core::signature::SigningKey;
+use llrt_core::vm::{bytecode_abi_hash, BYTECODE_ABI};
+use llrt_core::VERSION;
 use std::fs::File;
 use std::io::Write;
 use std::io::{Read, Seek, SeekFrom};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::env;
 
+use crate::build_config::{validate_flag_prefix, BuildConfig};
+use crate::VERSION_STRING;
+
 static MAGIC_NUMBER: &str = "1exe6und1e";
-static LIBSUI_MAGIC_NUMBER: u32 = 0x501e;
-static SECTION_NAME: &str = "1exec0de";
+pub(crate) static LIBSUI_MAGIC_NUMBER: u32 = 0x501e;
+pub(crate) static SECTION_NAME: &str = "1exec0de";
+
+// The version and QuickJS ABI of this runtime, between sentinels that appear
+// nowhere else in the executable so `llrt build` and `llrt inspect` can find
+// them in other runtimes. The sentinels are only read back from this static.
+const RUNTIME_MARKER_START: &str = "\0\x01LLRT_RUNTIME_MARKER\x02";
+const RUNTIME_MARKER_END: &str = "\x03\0";
+const RUNTIME_MARKER_START_LEN: usize = RUNTIME_MARKER_START.len();
+const RUNTIME_MARKER_END_LEN: usize = RUNTIME_MARKER_END.len();
+const RUNTIME_MARKER_STR: &str = constcat::concat!(
+    RUNTIME_MARKER_START,
+    VERSION_STRING,
+    "\x1f",
+    BYTECODE_ABI,
+    RUNTIME_MARKER_END
+);
+const RUNTIME_MARKER_LEN: usize = RUNTIME_MARKER_STR.len();
+
+#[used]
+pub(crate) static RUNTIME_MARKER: [u8; RUNTIME_MARKER_LEN] = marker_bytes(RUNTIME_MARKER_STR);
+
+const fn marker_bytes<const N: usize>(marker: &str) -> [u8; N] {
+    let bytes = marker.as_bytes();
+    let mut array = [0; N];
+    let mut i = 0;
+    while i < N {
+        array[i] = bytes[i];
+        i += 1;
+    }
+    array
+}
 
 /**
  * Build::validate_args(args)
@@ -19,9 +57,13 @@ static SECTION_NAME: &str = "1exec0de";
 pub enum Platform {
     LinuxX64,
     LinuxArm64,
+    /// Statically linked against musl, for Alpine and distroless images
+    LinuxX64Musl,
+    LinuxArm64Musl,
     DarwinX64,
     DarwinArm64,
     WindowsX64,
+    WindowsArm64,
 }
 
 impl FromStr for Platform {
@@ -31,9 +73,12 @@ impl FromStr for Platform {
         match s {
             "linux-x64" => Ok(Platform::LinuxX64),
             "linux-arm64" => Ok(Platform::LinuxArm64),
+            "linux-x64-musl" => Ok(Platform::LinuxX64Musl),
+            "linux-arm64-musl" => Ok(Platform::LinuxArm64Musl),
             "darwin-x64" => Ok(Platform::DarwinX64),
             "darwin-arm64" => Ok(Platform::DarwinArm64),
             "windows-x64" => Ok(Platform::WindowsX64),
+            "windows-arm64" => Ok(Platform::WindowsArm64),
             _ => Err(format!("Unknown platform: {}", s)),
         }
     }
@@ -52,15 +97,31 @@ struct BuildArgs {
 
     /// Target platform(s), comma-separated
     platform: Vec<Platform>,
+
+    /// Asset globs and bytecode options
+    compile: CompileOptions,
+
+    /// Environment variable defaults applied when the executable starts
+    env: Vec<(String, String)>,
+
+    /// Runtime settings as `(key, value, locked)`
+    runtime: Vec<(String, String, bool)>,
+
+    /// Ed25519 private key the payload is signed with (optional)
+    sign: Option<PathBuf>,
+
+    /// Prefix of the arguments taken as runtime flags (optional)
+    flag_prefix: Option<String>,
 }
 
 impl Platform {
     pub fn current() -> Self {
         if cfg!(target_os = "linux") {
-            if cfg!(target_arch = "x86_64") {
-                Platform::LinuxX64
-            } else {
-                Platform::LinuxArm64
+            match (cfg!(target_arch = "x86_64"), cfg!(target_env = "musl")) {
+                (true, false) => Platform::LinuxX64,
+                (false, false) => Platform::LinuxArm64,
+                (true, true) => Platform::LinuxX64Musl,
+                (false, true) => Platform::LinuxArm64Musl,
             }
         } else if cfg!(target_os = "macos") {
             if cfg!(target_arch = "x86_64") {
@@ -68,10 +129,27 @@ impl Platform {
             } else {
                 Platform::DarwinArm64
             }
+        } else if cfg!(target_arch = "aarch64") {
+            Platform::WindowsArm64
         } else {
             Platform::WindowsX64
         }
     }
+
+    pub fn is_linux(&self) -> bool {
+        matches!(
+            self,
+            Platform::LinuxX64 | Platform::LinuxArm64 | Platform::LinuxX64Musl | Platform::LinuxArm64Musl
+        )
+    }
+
+    pub fn is_darwin(&self) -> bool {
+        matches!(self, Platform::DarwinX64 | Platform::DarwinArm64)
+    }
+
+    pub fn is_windows(&self) -> bool {
+        matches!(self, Platform::WindowsX64 | Platform::WindowsArm64)
+    }
 }
 
 pub struct LexeBuild {
@@ -81,12 +159,19 @@ pub struct LexeBuild {
 type BuildErr = Box<dyn std::error::Error + Send + Sync>;
 
 impl LexeBuild {
-    /// Validate build arguments and return a Build instance
+    /// Validate build arguments and return a Build instance.
+    /// Flags override the settings of `lexe.toml` or the `lexe` section of `package.json`.
     pub fn validate_args(args: &[String]) -> Result<Self, String> {
         let mut input: Option<PathBuf> = None;
         let mut output = String::new();
         let mut directory: Option<PathBuf> = None;
         let mut platform = Vec::new();
+        let mut assets = Vec::new();
+        let mut config_path: Option<PathBuf> = None;
+        let mut strip: Option<Strip> = None;
+        let mut sign: Option<PathBuf> = None;
+        let mut dictionary: Option<PathBuf> = None;
+        let mut flag_prefix: Option<String> = None;
 
         for arg in args.iter().filter(|arg| arg.contains('=')) {
             let parts: Vec<&str> = arg.split('=').collect();
@@ -100,6 +185,15 @@ impl LexeBuild {
                 "-i" => input = Some(PathBuf::from(parts[1])),
                 "-o" => output = parts[1].to_string(),
                 "-d" => directory = Some(PathBuf::from(parts[1])),
+                "--asset" => assets.push(parts[1].to_string()),
+                "--config" => config_path = Some(PathBuf::from(parts[1])),
+                "--strip" => strip = Some(parts[1].parse()?),
+                "--sign" => sign = Some(PathBuf::from(parts[1])),
+                "--dict" => dictionary = Some(PathBuf::from(parts[1])),
+                "--flag-prefix" => {
+                    validate_flag_prefix(parts[1])?;
+                    flag_prefix = Some(parts[1].to_string());
+                },
                 "-p" => {
                     let parsed_platforms: Result<Vec<Platform>, _> = 
                         parts[1].split(',')
@@ -115,6 +209,80 @@ impl LexeBuild {
             }
         }
 
+        // the config file sits next to the entry point, or in the current directory
+        let config = match &config_path {
+            Some(path) => Some(BuildConfig::load(path)?),
+            None => {
+                let dir = input
+                    .as_deref()
+                    .and_then(Path::parent)
+                    .filter(|dir| !dir.as_os_str().is_empty())
+                    .unwrap_or(Path::new("."));
+                BuildConfig::find(dir)?
+            },
+        };
+
+        let cwd = env::current_dir().map_err(|e| format!("Failed to get current directory: {}", e))?;
+        let mut assets: Vec<AssetGlob> = assets
+            .into_iter()
+            .map(|pattern| AssetGlob { base: cwd.clone(), pattern })
+            .collect();
+        let mut compile = CompileOptions::default();
+        let mut env_defaults = Vec::new();
+        let mut runtime = Vec::new();
+
+        if let Some(config) = config {
+            if input.is_none() {
+                input = config.entry.clone();
+            }
+            if output.is_empty() {
+                output = config.output.clone().unwrap_or_default();
+            }
+            if directory.is_none() {
+                directory = config.directory.clone();
+            }
+            if platform.is_empty() {
+                platform = config.platforms.clone();
+            }
+            if assets.is_empty() {
+                let base = config.dir().to_path_buf();
+                assets = config
+                    .assets
+                    .iter()
+                    .map(|pattern| AssetGlob { base: base.clone(), pattern: pattern.clone() })
+                    .collect();
+            }
+            if let Some(compress) = config.compress {
+                compile.compress = compress;
+            }
+            if let Some(strip) = config.strip {
+                compile.strip = strip;
+            }
+            if sign.is_none() {
+                sign = config.sign.clone();
+            }
+            if dictionary.is_none() {
+                dictionary = config.dictionary.clone();
+            }
+            if flag_prefix.is_none() {
+                flag_prefix = config.flag_prefix.clone();
+            }
+            env_defaults = config.env;
+            runtime = config.runtime;
+        }
+        compile.assets = assets;
+        if let Some(strip) = strip {
+            compile.strip = strip;
+        }
+        if let Some(path) = dictionary {
+            let bytes = std::fs::read(&path)
+                .map_err(|e| format!("Failed to read dictionary {}: {}", path.display(), e))?;
+            if dictionary_id(&bytes).is_none() {
+                return Err(format!("Not a zstd dictionary: {}", path.display()));
+            }
+            compile.dictionary = Some(bytes);
+        }
+
         // validate input
         if input.is_none() {
             return Err("Input file is required".to_string());
@@ -138,7 +306,7 @@ impl LexeBuild {
             directory = Some(PathBuf::from("./dist"));
         }
 
-        let args = BuildArgs { input: input.unwrap(), output, directory: directory.unwrap(), platform };
+        let args = BuildArgs { input: input.unwrap(), output, directory: directory.unwrap(), platform, compile, env: env_defaults, runtime, sign, flag_prefix };
         Ok(LexeBuild { args })
     }
 
@@ -146,16 +314,29 @@ impl LexeBuild {
     pub async fn run_build(self) -> Result<(), BuildErr> {
         println!("\x1b[33mCompile input file: {}\x1b[0m", self.args.input.display());
 
-        // read input file
-        let input_str = match std::fs::read_to_string(&self.args.input) {
-            Ok(content) => content,
-            Err(e) => return Err(format!("Failed to read input file: {}", e).into()),
-        };
-        // compile input file
-        let compiled = match compile_string(&input_str).await {
-            Ok(bytes) => bytes,
+        if !self.args.input.is_file() {
+            return Err(format!("Failed to read input file: {}", self.args.input.display()).into());
+        }
+        // compile the input file, every module it imports and the assets into one archive
+        let mut archive = match compile_module_graph(&self.args.input, &self.args.compile).await {
+            Ok(archive) => archive,
             Err(e) => return Err(format!("Failed to compile input file: {}", e).into()),
         };
+        for (key, value) in &self.args.env {
+            archive.add_env(key.clone(), value.clone());
+        }
+        for (key, value, locked) in &self.args.runtime {
+            archive.add_runtime_setting(key.clone(), value.clone(), *locked);
+        }
+        if let Some(prefix) = &self.args.flag_prefix {
+            archive.set_flag_prefix(prefix.clone());
+        }
+        let mut compiled = archive.encode();
+        if let Some(key_path) = &self.args.sign {
+            let key = SigningKey::load(key_path)?;
+            println!("\x1b[33mSigning payload with key: {}\x1b[0m", bytes_to_hex_string(key.public_key()));
+            compiled = key.sign(&compiled);
+        }
 
         // get current executable path
         // use this path to find other platform's llrt binary
@@ -187,6 +368,8 @@ impl LexeBuild {
                 Ok(bytes) => bytes,
                 Err(e) => return Err(format!("Failed to read llrt binary: {}", e).into()),
             };
+            // the embedded bytecode only runs on the runtime that compiled it
+            check_runtime_compatibility(&llrt_binary, &cache_path)?;
 
             // Ensure the output directory exists before creating the file
             if let Some(parent_dir) = output_path.parent() {
@@ -204,18 +387,18 @@ impl LexeBuild {
                 }
             }
             let mut output = File::create(&output_path)?;
-            if platform == &Platform::WindowsX64 {
+            if platform.is_windows() {
                 PortableExecutable::from(&llrt_binary)?
                     .write_resource(SECTION_NAME, compiled.clone())?
                     .build(&mut output)?;
                 output.write_all(MAGIC_NUMBER.as_bytes())?;
-            } else if platform == &Platform::LinuxX64 || platform == &Platform::LinuxArm64 {
+            } else if platform.is_linux() {
                 Elf::new(&llrt_binary)
                     .append(SECTION_NAME, &compiled, &mut output)?;
                 // Linux does not need to write magic number
                 // libsui already appends magic number to the output file
                 // 1exe6und1e will affect libsui find section
-            } else if platform == &Platform::DarwinX64 || platform == &Platform::DarwinArm64 {
+            } else if platform.is_darwin() {
                 Macho::from(llrt_binary)?
                     .write_section(SECTION_NAME, compiled.clone())?
                     .build_and_sign(&mut output)?;
@@ -230,27 +413,76 @@ impl LexeBuild {
 
     // output name for platform
     fn output_path_for_platform(&self, platform: &Platform) -> PathBuf {
-        let file_name = match platform {
-            Platform::WindowsX64 => format!("{}-{}.exe", self.args.output, platform_to_str(platform)),
-            _ => format!("{}-{}", self.args.output, platform_to_str(platform)),
+        let file_name = if platform.is_windows() {
+            format!("{}-{}.exe", self.args.output, platform_to_str(platform))
+        } else {
+            format!("{}-{}", self.args.output, platform_to_str(platform))
         };
 
         self.args.directory.join(file_name)
     }
 }
 
-fn platform_to_str(platform: &Platform) -> &str {
+fn check_runtime_compatibility(llrt_binary: &[u8], path: &Path) -> Result<(), BuildErr> {
+    let marker = runtime_marker(llrt_binary);
+    let expected_version = ["LLRT v", VERSION, " ("].concat();
+    if let Some((version, abi)) = marker {
+        if version.starts_with(&expected_version) && abi == bytecode_abi_hash() {
+            return Ok(());
+        }
+    }
+    Err(format!(
+        "LLRT binary in cache is incompatible with this runtime: {} is {} (QuickJS ABI {}) but this is LLRT v{} (QuickJS ABI {}). Update the cached binaries to the same release",
+        path.display(),
+        marker.map_or("an unknown version", |(version, _)| version),
+        marker.map_or("unknown", |(_, abi)| abi),
+        VERSION,
+        bytecode_abi_hash()
+    )
+    .into())
+}
+
+/// The version string and QuickJS ABI hash of an llrt executable, e.g.
+/// `LLRT v0.5.1-beta (linux, x64)` and the hash, read from its runtime marker.
+fn runtime_marker(data: &[u8]) -> Option<(&str, &str)> {
+    let start = &RUNTIME_MARKER[..RUNTIME_MARKER_START_LEN];
+    let end = &RUNTIME_MARKER[RUNTIME_MARKER_LEN - RUNTIME_MARKER_END_LEN..];
+    let from = find_bytes(data, start)? + start.len();
+    let rest = &data[from..data.len().min(from + 256)];
+    let marker = std::str::from_utf8(&rest[..find_bytes(rest, end)?]).ok()?;
+    marker.split_once('\x1f')
+}
+
+/// The version string of an llrt executable, e.g. `LLRT v0.5.1-beta (linux, x64)`.
+pub(crate) fn runtime_version(data: &[u8]) -> Option<String> {
+    runtime_marker(data).map(|(version, _)| version.to_string())
+}
+
+/// The QuickJS ABI hash an llrt executable was built with.
+pub(crate) fn runtime_bytecode_abi(data: &[u8]) -> Option<String> {
+    runtime_marker(data).map(|(_, abi)| abi.to_string())
+}
+
+fn find_bytes(data: &[u8], needle: &[u8]) -> Option<usize> {
+    data.windows(needle.len())
+        .position(|window| window == needle)
+}
+
+pub(crate) fn platform_to_str(platform: &Platform) -> &str {
     match platform {
         Platform::LinuxX64 => "linux-x64",
         Platform::LinuxArm64 => "linux-arm64",
+        Platform::LinuxX64Musl => "linux-x64-musl",
+        Platform::LinuxArm64Musl => "linux-arm64-musl",
         Platform::DarwinX64 => "darwin-x64",
         Platform::DarwinArm64 => "darwin-arm64",
         Platform::WindowsX64 => "windows-x64",
+        Platform::WindowsArm64 => "windows-arm64",
     }
 }
 
 fn platform_to_binary_name(platform: &Platform) -> &str {
-    if platform == &Platform::WindowsX64 {
+    if platform.is_windows() {
         "llrt.exe"
     } else {
         "llrt"
@@ -267,7 +499,7 @@ pub fn has_magic_number(platform: &Platform) -> std::io::Result<bool> {
     }
 
     match platform {
-        Platform::LinuxX64 | Platform::LinuxArm64 => {
+        platform if platform.is_linux() => {
             // for Linux platform, need to detect libsui's magic number
             // libsui appends 16 bytes trailer to the file: magic number(4 bytes) + hash(4 bytes) + size(8 bytes)
             const TRAILER_LEN: i64 = 16;
@@ -0,0 +1,580 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::build::Platform;
+use crate::core::compiler::Strip;
+use crate::core::environment::{
+    ENV_LLRT_GC_THRESHOLD_MB, ENV_LLRT_HTTP_VERSION, ENV_LLRT_NET_POOL_IDLE_TIMEOUT,
+    ENV_LLRT_PLATFORM, ENV_LLRT_TLS_VERSION, RUNTIME_CONFIG_KEYS,
+};
+
+const TOML_FILE: &str = "lexe.toml";
+const PACKAGE_JSON_FILE: &str = "package.json";
+const PACKAGE_JSON_SECTION: &str = "lexe";
+
+/// Build settings read from `lexe.toml` or the `lexe` section of
+/// `package.json`. Relative paths are resolved against the directory of the
+/// file they were read from.
+#[derive(Debug, Default)]
+pub struct BuildConfig {
+    /// The file the settings were read from
+    pub path: PathBuf,
+    pub entry: Option<PathBuf>,
+    pub output: Option<String>,
+    pub directory: Option<PathBuf>,
+    pub platforms: Vec<Platform>,
+    pub assets: Vec<String>,
+    pub env: Vec<(String, String)>,
+    /// Runtime settings as `(key, value, locked)`
+    pub runtime: Vec<(String, String, bool)>,
+    pub compress: Option<bool>,
+    pub strip: Option<Strip>,
+    /// zstd dictionary module bytecode is compressed with
+    pub dictionary: Option<PathBuf>,
+    /// Ed25519 private key the payload is signed with
+    pub sign: Option<PathBuf>,
+    /// Prefix of the arguments taken as runtime flags by the executable
+    pub flag_prefix: Option<String>,
+}
+
+// Common shape of TOML and JSON values so both files share one validator
+enum ConfigValue {
+    String(String),
+    Bool(bool),
+    Array(Vec<ConfigValue>),
+    Table(Vec<(String, ConfigValue)>),
+    Number(String),
+    Other(&'static str),
+}
+
+impl BuildConfig {
+    /// Looks for a config file in `dir`. `lexe.toml` takes precedence over
+    /// `package.json`, and a `package.json` without a `lexe` section is ignored.
+    pub fn find(dir: &Path) -> Result<Option<Self>, String> {
+        let toml_path = dir.join(TOML_FILE);
+        if toml_path.is_file() {
+            return Self::load(&toml_path).map(Some);
+        }
+
+        let package_path = dir.join(PACKAGE_JSON_FILE);
+        if package_path.is_file() {
+            let table = parse_json(&package_path)?;
+            return match section(table, PACKAGE_JSON_SECTION) {
+                Some(value) => {
+                    Self::from_value(&package_path, PACKAGE_JSON_SECTION, value).map(Some)
+                },
+                None => Ok(None),
+            };
+        }
+
+        Ok(None)
+    }
+
+    /// Reads an explicitly selected config file.
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let is_json = path.extension().is_some_and(|ext| ext == "json");
+        if !is_json {
+            let table = parse_toml(path)?;
+            return Self::from_value(path, "", table);
+        }
+
+        let value = parse_json(path)?;
+        if path
+            .file_name()
+            .is_some_and(|name| name == PACKAGE_JSON_FILE)
+        {
+            return match section(value, PACKAGE_JSON_SECTION) {
+                Some(value) => Self::from_value(path, PACKAGE_JSON_SECTION, value),
+                None => Err(format!(
+                    "{}: missing \"{}\" section",
+                    path.display(),
+                    PACKAGE_JSON_SECTION
+                )),
+            };
+        }
+        Self::from_value(path, "", value)
+    }
+
+    /// Directory that relative paths and asset globs are resolved against.
+    pub fn dir(&self) -> &Path {
+        match self.path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        }
+    }
+
+    fn from_value(path: &Path, prefix: &str, value: ConfigValue) -> Result<Self, String> {
+        let mut config = BuildConfig {
+            path: path.to_path_buf(),
+            ..Default::default()
+        };
+        let field = Field {
+            path,
+            name: prefix.to_string(),
+        };
+        let base = config.dir().to_path_buf();
+
+        for (key, value) in field.table(value)? {
+            let field = field.child(&key);
+            match key.as_str() {
+                "entry" => config.entry = Some(base.join(field.string(value)?)),
+                "output" => config.output = Some(field.string(value)?),
+                "directory" => config.directory = Some(base.join(field.string(value)?)),
+                "platforms" => {
+                    for (index, value) in field.array(value)?.into_iter().enumerate() {
+                        let field = field.index(index);
+                        let platform = field.string(value)?;
+                        config
+                            .platforms
+                            .push(platform.parse().map_err(|err| field.error(err))?);
+                    }
+                },
+                "assets" => {
+                    for (index, value) in field.array(value)?.into_iter().enumerate() {
+                        config.assets.push(field.index(index).string(value)?);
+                    }
+                },
+                "env" => {
+                    for (name, value) in field.table(value)? {
+                        let field = field.child(&name);
+                        if name.is_empty() || name.contains(['=', '\0']) {
+                            return Err(field.error("invalid environment variable name"));
+                        }
+                        config.env.push((name, field.string(value)?));
+                    }
+                },
+                "sign" => config.sign = Some(base.join(field.string(value)?)),
+                "flag_prefix" => {
+                    let prefix = field.string(value)?;
+                    validate_flag_prefix(&prefix).map_err(|err| field.error(err))?;
+                    config.flag_prefix = Some(prefix);
+                },
+                "runtime" => config.runtime = runtime_settings(&field, value)?,
+                "compile" => {
+                    for (name, value) in field.table(value)? {
+                        let field = field.child(&name);
+                        match name.as_str() {
+                            "compress" => config.compress = Some(field.bool(value)?),
+                            "strip" => {
+                                let strip = field.string(value)?;
+                                config.strip = Some(strip.parse().map_err(|err| field.error(err))?);
+                            },
+                            "dictionary" => {
+                                config.dictionary = Some(base.join(field.string(value)?))
+                            },
+                            _ => return Err(field.error("unknown key")),
+                        }
+                    }
+                },
+                _ => return Err(field.error("unknown key")),
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+// Location of a value, used to point validation errors at the file and field
+struct Field<'a> {
+    path: &'a Path,
+    name: String,
+}
+
+impl<'a> Field<'a> {
+    fn child(&self, key: &str) -> Field<'a> {
+        let name = if self.name.is_empty() {
+            key.to_string()
+        } else {
+            [self.name.as_str(), ".", key].concat()
+        };
+        Field {
+            path: self.path,
+            name,
+        }
+    }
+
+    fn index(&self, index: usize) -> Field<'a> {
+        Field {
+            path: self.path,
+            name: format!("{}[{}]", self.name, index),
+        }
+    }
+
+    fn error(&self, message: impl std::fmt::Display) -> String {
+        if self.name.is_empty() {
+            format!("{}: {}", self.path.display(), message)
+        } else {
+            format!("{}: {}: {}", self.path.display(), self.name, message)
+        }
+    }
+
+    fn expected(&self, expected: &str, value: &ConfigValue) -> String {
+        self.error(format!(
+            "expected {}, found {}",
+            expected,
+            value.type_name()
+        ))
+    }
+
+    fn string(&self, value: ConfigValue) -> Result<String, String> {
+        match value {
+            ConfigValue::String(value) => Ok(value),
+            value => Err(self.expected("a string", &value)),
+        }
+    }
+
+    fn bool(&self, value: ConfigValue) -> Result<bool, String> {
+        match value {
+            ConfigValue::Bool(value) => Ok(value),
+            value => Err(self.expected("a boolean", &value)),
+        }
+    }
+
+    fn array(&self, value: ConfigValue) -> Result<Vec<ConfigValue>, String> {
+        match value {
+            ConfigValue::Array(values) => Ok(values),
+            value => Err(self.expected("an array", &value)),
+        }
+    }
+
+    fn table(&self, value: ConfigValue) -> Result<Vec<(String, ConfigValue)>, String> {
+        match value {
+            ConfigValue::Table(entries) => Ok(entries),
+            value => Err(self.expected("a table", &value)),
+        }
+    }
+}
+
+impl ConfigValue {
+    fn type_name(&self) -> &'static str {
+        match self {
+            ConfigValue::String(_) => "a string",
+            ConfigValue::Bool(_) => "a boolean",
+            ConfigValue::Array(_) => "an array",
+            ConfigValue::Table(_) => "a table",
+            ConfigValue::Number(_) => "a number",
+            ConfigValue::Other(name) => name,
+        }
+    }
+
+    fn from_json(value: serde_json::Value) -> Self {
+        use serde_json::Value;
+        match value {
+            Value::String(value) => ConfigValue::String(value),
+            Value::Bool(value) => ConfigValue::Bool(value),
+            Value::Array(values) => {
+                ConfigValue::Array(values.into_iter().map(Self::from_json).collect())
+            },
+            Value::Object(entries) => ConfigValue::Table(
+                entries
+                    .into_iter()
+                    .map(|(key, value)| (key, Self::from_json(value)))
+                    .collect(),
+            ),
+            Value::Number(value) => ConfigValue::Number(value.to_string()),
+            Value::Null => ConfigValue::Other("null"),
+        }
+    }
+
+    fn from_toml_item(item: &toml_edit::Item) -> Self {
+        use toml_edit::Item;
+        match item {
+            Item::Value(value) => Self::from_toml_value(value),
+            Item::Table(table) => ConfigValue::Table(
+                table
+                    .iter()
+                    .map(|(key, item)| (key.to_string(), Self::from_toml_item(item)))
+                    .collect(),
+            ),
+            Item::ArrayOfTables(tables) => ConfigValue::Array(
+                tables
+                    .iter()
+                    .map(|table| Self::from_toml_item(&toml_edit::Item::Table(table.clone())))
+                    .collect(),
+            ),
+            Item::None => ConfigValue::Other("nothing"),
+        }
+    }
+
+    fn from_toml_value(value: &toml_edit::Value) -> Self {
+        use toml_edit::Value;
+        match value {
+            Value::String(value) => ConfigValue::String(value.value().clone()),
+            Value::Boolean(value) => ConfigValue::Bool(*value.value()),
+            Value::Array(values) => {
+                ConfigValue::Array(values.iter().map(Self::from_toml_value).collect())
+            },
+            Value::InlineTable(table) => ConfigValue::Table(
+                table
+                    .iter()
+                    .map(|(key, value)| (key.to_string(), Self::from_toml_value(value)))
+                    .collect(),
+            ),
+            Value::Integer(value) => ConfigValue::Number(value.value().to_string()),
+            Value::Float(value) => ConfigValue::Number(value.value().to_string()),
+            Value::Datetime(_) => ConfigValue::Other("a datetime"),
+        }
+    }
+}
+
+// `locked` is either `true` to lock every setting or a list of locked keys
+fn runtime_settings(
+    field: &Field,
+    value: ConfigValue,
+) -> Result<Vec<(String, String, bool)>, String> {
+    let mut settings = Vec::new();
+    let mut locked_keys = Vec::new();
+    let mut lock_all = false;
+
+    for (key, value) in field.table(value)? {
+        let field = field.child(&key);
+        if key == "locked" {
+            match value {
+                ConfigValue::Bool(locked) => lock_all = locked,
+                ConfigValue::Array(values) => {
+                    for (index, value) in values.into_iter().enumerate() {
+                        let field = field.index(index);
+                        let key = field.string(value)?;
+                        if !RUNTIME_CONFIG_KEYS.contains(&key.as_str()) {
+                            return Err(field.error("unknown runtime setting"));
+                        }
+                        locked_keys.push(key);
+                    }
+                },
+                value => return Err(field.expected("a boolean or an array", &value)),
+            }
+            continue;
+        }
+        if !RUNTIME_CONFIG_KEYS.contains(&key.as_str()) {
+            return Err(field.error("unknown runtime setting"));
+        }
+        let value = match value {
+            ConfigValue::String(value) | ConfigValue::Number(value) => value,
+            value => return Err(field.expected("a string or a number", &value)),
+        };
+        validate_runtime_setting(&key, &value).map_err(|err| field.error(err))?;
+        settings.push((key, value, false));
+    }
+
+    for key in &locked_keys {
+        if !settings.iter().any(|(name, _, _)| name == key) {
+            return Err(field
+                .child("locked")
+                .error(format!("{} is locked but has no value", key)));
+        }
+    }
+    for (key, _, locked) in &mut settings {
+        *locked = lock_all || locked_keys.contains(key);
+    }
+
+    Ok(settings)
+}
+
+fn validate_runtime_setting(key: &str, value: &str) -> Result<(), String> {
+    let valid = match key {
+        ENV_LLRT_GC_THRESHOLD_MB => value.parse::<usize>().is_ok(),
+        ENV_LLRT_NET_POOL_IDLE_TIMEOUT => value.parse::<u64>().is_ok(),
+        ENV_LLRT_HTTP_VERSION => matches!(value, "1.1" | "2"),
+        ENV_LLRT_TLS_VERSION => matches!(value, "1.2" | "1.3"),
+        ENV_LLRT_PLATFORM => matches!(value, "node" | "browser"),
+        _ => true,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("invalid value \"{}\"", value))
+    }
+}
+
+/// A prefix has to look like an option, so that it cannot take away
+/// positional arguments of the script, and must not be `--` alone.
+pub fn validate_flag_prefix(prefix: &str) -> Result<(), String> {
+    if !prefix.starts_with('-') || prefix == "-" || prefix == "--" || prefix.contains('=') {
+        return Err(format!("invalid flag prefix \"{}\"", prefix));
+    }
+    Ok(())
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+fn parse_toml(path: &Path) -> Result<ConfigValue, String> {
+    let document = read(path)?
+        .parse::<toml_edit::DocumentMut>()
+        .map_err(|err| format!("{}: {}", path.display(), err))?;
+    Ok(ConfigValue::from_toml_item(document.as_item()))
+}
+
+fn parse_json(path: &Path) -> Result<ConfigValue, String> {
+    let value =
+        serde_json::from_str(&read(path)?).map_err(|err| format!("{}: {}", path.display(), err))?;
+    Ok(ConfigValue::from_json(value))
+}
+
+fn section(value: ConfigValue, name: &str) -> Option<ConfigValue> {
+    match value {
+        ConfigValue::Table(entries) => entries
+            .into_iter()
+            .find_map(|(key, value)| (key == name).then_some(value)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{env, fs, path::PathBuf};
+
+    use super::BuildConfig;
+    use crate::build::Platform;
+    use crate::core::compiler::Strip;
+    use crate::core::environment::{
+        ENV_LLRT_GC_THRESHOLD_MB, ENV_LLRT_HTTP_VERSION, ENV_LLRT_NET_POOL_IDLE_TIMEOUT,
+        ENV_LLRT_PLATFORM, ENV_LLRT_TLS_VERSION, RUNTIME_CONFIG_KEYS,
+    };
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = env::temp_dir().join(format!("lexe_config_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_load_toml() {
+        let dir = temp_dir("toml");
+        let path = dir.join("lexe.toml");
+        fs::write(
+            &path,
+            r#"
+entry = "src/index.js"
+output = "tool"
+platforms = ["linux-x64", "darwin-arm64"]
+assets = ["templates/**"]
+sign = "keys/release.pem"
+flag_prefix = "--tool-"
+
+[env]
+LLRT_LOG = "info"
+
+[runtime]
+LLRT_NET_ALLOW = "api.example.com"
+LLRT_GC_THRESHOLD_MB = 64
+locked = ["LLRT_NET_ALLOW"]
+
+[compile]
+compress = false
+strip = "source"
+dictionary = "app.dict"
+"#,
+        )
+        .unwrap();
+
+        let config = BuildConfig::find(&dir).unwrap().unwrap();
+        assert_eq!(config.entry, Some(dir.join("src/index.js")));
+        assert_eq!(config.output.as_deref(), Some("tool"));
+        assert_eq!(
+            config.platforms,
+            vec![Platform::LinuxX64, Platform::DarwinArm64]
+        );
+        assert_eq!(config.assets, vec!["templates/**"]);
+        assert_eq!(config.sign, Some(dir.join("keys/release.pem")));
+        assert_eq!(config.flag_prefix.as_deref(), Some("--tool-"));
+        assert_eq!(
+            config.env,
+            vec![("LLRT_LOG".to_string(), "info".to_string())]
+        );
+        assert_eq!(
+            config.runtime,
+            vec![
+                (
+                    "LLRT_NET_ALLOW".to_string(),
+                    "api.example.com".to_string(),
+                    true
+                ),
+                ("LLRT_GC_THRESHOLD_MB".to_string(), "64".to_string(), false),
+            ]
+        );
+        assert_eq!(config.compress, Some(false));
+        assert_eq!(config.strip, Some(Strip::Source));
+        assert_eq!(config.dictionary, Some(dir.join("app.dict")));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_package_json_section() {
+        let dir = temp_dir("package");
+        let path = dir.join("package.json");
+
+        fs::write(&path, r#"{ "name": "tool" }"#).unwrap();
+        assert!(BuildConfig::find(&dir).unwrap().is_none());
+
+        fs::write(
+            &path,
+            r#"{ "lexe": { "entry": "index.js", "assets": ["a/*"] } }"#,
+        )
+        .unwrap();
+        let config = BuildConfig::find(&dir).unwrap().unwrap();
+        assert_eq!(config.entry, Some(dir.join("index.js")));
+        assert_eq!(config.assets, vec!["a/*"]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_validation_errors_name_file_and_field() {
+        let dir = temp_dir("errors");
+        let path = dir.join("package.json");
+
+        fs::write(&path, r#"{ "lexe": { "entyr": "index.js" } }"#).unwrap();
+        let err = BuildConfig::find(&dir).unwrap_err();
+        assert!(
+            err.ends_with("package.json: lexe.entyr: unknown key"),
+            "{err}"
+        );
+
+        fs::write(
+            &path,
+            r#"{ "lexe": { "platforms": ["linux-x64", "beos"] } }"#,
+        )
+        .unwrap();
+        let err = BuildConfig::find(&dir).unwrap_err();
+        assert!(
+            err.ends_with("lexe.platforms[1]: Unknown platform: beos"),
+            "{err}"
+        );
+
+        fs::write(&path, r#"{ "lexe": { "compile": { "compress": "yes" } } }"#).unwrap();
+        let err = BuildConfig::find(&dir).unwrap_err();
+        assert!(
+            err.ends_with("lexe.compile.compress: expected a boolean, found a string"),
+            "{err}"
+        );
+
+        fs::write(&path, r#"{ "lexe": { "compile": { "strip": "lines" } } }"#).unwrap();
+        let err = BuildConfig::find(&dir).unwrap_err();
+        assert!(
+            err.ends_with(
+                "lexe.compile.strip: Unknown strip mode: lines, expected source, debug or all"
+            ),
+            "{err}"
+        );
+
+        fs::write(
+            &path,
+            r#"{ "lexe": { "runtime": { "LLRT_TLS_VERSION": "1.0" } } }"#,
+        )
+        .unwrap();
+        let err = BuildConfig::find(&dir).unwrap_err();
+        assert!(
+            err.ends_with("lexe.runtime.LLRT_TLS_VERSION: invalid value \"1.0\""),
+            "{err}"
+        );
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,328 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+//! Splits the command line into the runtime flags and the command or script
+//! with its arguments.
+use std::{env, path::PathBuf};
+
+use crate::core::{
+    environment::{ENV_LLRT_GC_THRESHOLD_MB, ENV_LLRT_NET_ALLOW},
+    libs::utils::dotenv,
+};
+
+// Options handled by `start_cli` as commands rather than flags
+const COMMAND_OPTIONS: &[&str] = &[
+    "-v",
+    "--version",
+    "-h",
+    "--help",
+    "-e",
+    "--eval",
+    "--clear-cache",
+];
+
+/// Flags that configure the runtime, given before the command or script.
+#[derive(Debug, Default, PartialEq)]
+pub struct RuntimeFlags {
+    pub env_files: Vec<PathBuf>,
+    pub gc_threshold_mb: Option<usize>,
+    /// Space-delimited hosts, like `LLRT_NET_ALLOW`
+    pub net_allow: Option<String>,
+    pub stack_size_kb: Option<usize>,
+    pub import_map: Option<PathBuf>,
+    /// `Some(None)` uses the default directory
+    pub compile_cache: Option<Option<PathBuf>>,
+    pub watch: bool,
+    pub watch_paths: Vec<String>,
+}
+
+impl RuntimeFlags {
+    /// Loads the env files and sets the variables of the flags that have one,
+    /// which makes processes started by the script inherit them. Locked
+    /// settings of a built executable keep their value. Called before the
+    /// async runtime starts its threads.
+    pub fn apply_env(&self) -> Result<(), String> {
+        for file in &self.env_files {
+            dotenv::load(file).map_err(|err| format!("{}: {}", file.display(), err))?;
+        }
+        if let Some(threshold) = self.gc_threshold_mb {
+            env::set_var(ENV_LLRT_GC_THRESHOLD_MB, threshold.to_string());
+        }
+        if let Some(hosts) = &self.net_allow {
+            env::set_var(ENV_LLRT_NET_ALLOW, hosts);
+        }
+        Ok(())
+    }
+
+    /// The flags for the runs started by `--watch`.
+    pub fn forwarded(&self) -> Vec<String> {
+        let mut flags = Vec::new();
+        for file in &self.env_files {
+            flags.push(["--env-file=", &file.to_string_lossy()].concat());
+        }
+        if let Some(threshold) = self.gc_threshold_mb {
+            flags.push(format!("--gc-threshold={}", threshold));
+        }
+        if let Some(hosts) = &self.net_allow {
+            flags.push(["--net-allow=", hosts].concat());
+        }
+        if let Some(size) = self.stack_size_kb {
+            flags.push(format!("--stack-size={}", size));
+        }
+        if let Some(file) = &self.import_map {
+            flags.push(["--import-map=", &file.to_string_lossy()].concat());
+        }
+        match &self.compile_cache {
+            Some(Some(dir)) => flags.push(["--compile-cache=", &dir.to_string_lossy()].concat()),
+            Some(None) => flags.push("--compile-cache".into()),
+            None => {},
+        }
+        flags
+    }
+
+    // Applies the flag `name` (without leading dashes), taking its value from
+    // `value` or else the next argument
+    fn set(
+        &mut self,
+        name: &str,
+        value: Option<&str>,
+        next: &mut impl Iterator<Item = String>,
+        raw: &mut Vec<String>,
+    ) -> Result<bool, String> {
+        let mut required = || match value {
+            Some(value) => Ok(value.to_string()),
+            None => match next.next() {
+                Some(value) => {
+                    raw.push(value.clone());
+                    Ok(value)
+                },
+                None => Err(format!("--{} requires a value", name)),
+            },
+        };
+        match name {
+            "env-file" => self.env_files.push(PathBuf::from(required()?)),
+            "gc-threshold" => self.gc_threshold_mb = Some(number(name, &required()?)?),
+            "net-allow" => {
+                let hosts = required()?;
+                self.net_allow = Some(match self.net_allow.take() {
+                    Some(previous) => [previous.as_str(), " ", &hosts].concat(),
+                    None => hosts,
+                });
+            },
+            "stack-size" => self.stack_size_kb = Some(number(name, &required()?)?),
+            "import-map" => self.import_map = Some(PathBuf::from(required()?)),
+            "compile-cache" => self.compile_cache = Some(value.map(PathBuf::from)),
+            "watch" if value.is_none() => self.watch = true,
+            "watch-path" => {
+                self.watch = true;
+                self.watch_paths.push(required()?);
+            },
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+}
+
+/// The parsed command line.
+#[derive(Debug, Default, PartialEq)]
+pub struct CommandLine {
+    /// The name the runtime was started with
+    pub exe: String,
+    pub flags: RuntimeFlags,
+    /// The runtime flags as given, for `process.execArgv`
+    pub exec_argv: Vec<String>,
+    /// The command or script, followed by its arguments
+    pub args: Vec<String>,
+}
+
+impl CommandLine {
+    /// Parses the command line of `llrt`. Flags end at the first argument
+    /// that is not one, or at `--`.
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut args = args.into_iter();
+        let mut command_line = CommandLine {
+            exe: args.next().unwrap_or_default(),
+            ..Default::default()
+        };
+
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                break;
+            }
+            if !arg.starts_with('-') || arg == "-" || COMMAND_OPTIONS.contains(&arg.as_str()) {
+                command_line.args.push(arg);
+                break;
+            }
+            let Some(flag) = arg.strip_prefix("--") else {
+                return Err(format!("Unknown option: {}", arg));
+            };
+            let (name, value) = split_flag(flag);
+            let mut raw = vec![arg.clone()];
+            if !command_line.flags.set(name, value, &mut args, &mut raw)? {
+                return Err(format!("Unknown option: {}", arg));
+            }
+            command_line.exec_argv.extend(raw);
+        }
+
+        command_line.args.extend(args);
+        Ok(command_line)
+    }
+
+    /// Parses the command line of a built executable, where every argument
+    /// belongs to the script. With a `prefix`, arguments starting with it up
+    /// to `--` are runtime flags instead: `<prefix>gc-threshold=64` is
+    /// `--gc-threshold=64`.
+    pub fn parse_embedded(
+        args: impl IntoIterator<Item = String>,
+        prefix: Option<&str>,
+    ) -> Result<Self, String> {
+        let mut args = args.into_iter();
+        let mut command_line = CommandLine {
+            exe: args.next().unwrap_or_default(),
+            ..Default::default()
+        };
+        let Some(prefix) = prefix else {
+            command_line.args.extend(args);
+            return Ok(command_line);
+        };
+
+        while let Some(arg) = args.next() {
+            if arg == "--" {
+                break;
+            }
+            let Some(flag) = arg.strip_prefix(prefix) else {
+                command_line.args.push(arg);
+                continue;
+            };
+            let (name, value) = split_flag(flag);
+            // flags of the development workflow do not apply to built executables
+            let allowed = matches!(
+                name,
+                "env-file" | "gc-threshold" | "net-allow" | "stack-size"
+            );
+            let mut raw = vec![arg.clone()];
+            if !allowed || !command_line.flags.set(name, value, &mut args, &mut raw)? {
+                return Err(format!("Unknown option: {}", arg));
+            }
+            command_line.exec_argv.extend(raw);
+        }
+
+        command_line.args.extend(args);
+        Ok(command_line)
+    }
+
+    /// `process.argv`: the name of the runtime and the arguments of the
+    /// command or script. The source of `--eval` is left out.
+    pub fn argv(&self) -> Vec<String> {
+        let args = match self.args.first().map(String::as_str) {
+            Some("-e" | "--eval") => self.args.get(2..).unwrap_or_default(),
+            _ => &self.args,
+        };
+        let mut argv = Vec::with_capacity(args.len() + 1);
+        argv.push(self.exe.clone());
+        argv.extend(args.iter().cloned());
+        argv
+    }
+}
+
+fn split_flag(flag: &str) -> (&str, Option<&str>) {
+    match flag.split_once('=') {
+        Some((name, value)) => (name, Some(value)),
+        None => (flag, None),
+    }
+}
+
+fn number(name: &str, value: &str) -> Result<usize, String> {
+    value
+        .parse()
+        .map_err(|_| format!("--{}: invalid number \"{}\"", name, value))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::{CommandLine, RuntimeFlags};
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_flags_and_script() {
+        let command_line = CommandLine::parse(args(&[
+            "llrt",
+            "--gc-threshold=64",
+            "--stack-size",
+            "1024",
+            "--env-file=.env",
+            "--net-allow=a.com",
+            "--net-allow=b.com",
+            "app.js",
+            "--gc-threshold=1",
+            "--",
+        ]))
+        .unwrap();
+
+        assert_eq!(
+            command_line.flags,
+            RuntimeFlags {
+                env_files: vec![PathBuf::from(".env")],
+                gc_threshold_mb: Some(64),
+                net_allow: Some("a.com b.com".into()),
+                stack_size_kb: Some(1024),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            command_line.exec_argv,
+            args(&[
+                "--gc-threshold=64",
+                "--stack-size",
+                "1024",
+                "--env-file=.env",
+                "--net-allow=a.com",
+                "--net-allow=b.com",
+            ])
+        );
+        assert_eq!(
+            command_line.argv(),
+            args(&["llrt", "app.js", "--gc-threshold=1", "--"])
+        );
+    }
+
+    #[test]
+    fn test_parse_double_dash_and_commands() {
+        let command_line =
+            CommandLine::parse(args(&["llrt", "--watch", "--", "--odd.js"])).unwrap();
+        assert!(command_line.flags.watch);
+        assert_eq!(command_line.args, args(&["--odd.js"]));
+
+        let command_line =
+            CommandLine::parse(args(&["llrt", "--compile-cache", "-e", "1", "x"])).unwrap();
+        assert_eq!(command_line.flags.compile_cache, Some(None));
+        assert_eq!(command_line.args, args(&["-e", "1", "x"]));
+        assert_eq!(command_line.argv(), args(&["llrt", "x"]));
+
+        assert!(CommandLine::parse(args(&["llrt", "--unknown", "app.js"])).is_err());
+        assert!(CommandLine::parse(args(&["llrt", "--gc-threshold=lots"])).is_err());
+        assert!(CommandLine::parse(args(&["llrt", "--env-file"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_embedded() {
+        let all = args(&["app", "--app-gc-threshold=8", "--verbose", "--", "--app-x"]);
+
+        let command_line = CommandLine::parse_embedded(all.clone(), None).unwrap();
+        assert_eq!(command_line.flags, RuntimeFlags::default());
+        assert_eq!(command_line.argv(), all);
+
+        let command_line = CommandLine::parse_embedded(all, Some("--app-")).unwrap();
+        assert_eq!(command_line.flags.gc_threshold_mb, Some(8));
+        assert_eq!(command_line.exec_argv, args(&["--app-gc-threshold=8"]));
+        assert_eq!(command_line.argv(), args(&["app", "--verbose", "--app-x"]));
+
+        assert!(
+            CommandLine::parse_embedded(args(&["app", "--app-watch"]), Some("--app-")).is_err()
+        );
+    }
+}
@@ -4,14 +4,21 @@ pub use self::core::*;
 
 #[allow(clippy::module_inception)]
 mod core {
+    pub use llrt_core::archive;
     pub use llrt_core::bytecode;
+    pub use llrt_core::compile_cache;
     #[cfg(not(feature = "lambda"))]
     pub use llrt_core::compiler;
     pub use llrt_core::environment;
     pub use llrt_core::libs;
+    #[cfg(not(feature = "lambda"))]
+    pub use llrt_core::module_graph;
     pub use llrt_core::modules;
+    pub use llrt_core::signature;
+    pub use llrt_core::source_map;
     pub use llrt_core::utils;
     pub use llrt_core::vm;
+    pub use llrt_core::watch_report;
 }
 pub use llrt_core::VERSION;
 
@@ -0,0 +1,662 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use crate::build::{
+    platform_to_str, runtime_bytecode_abi, runtime_version, Platform, LIBSUI_MAGIC_NUMBER,
+    SECTION_NAME,
+};
+use crate::core::{
+    archive::{Archive, ARCHIVE_VERSION, VIRTUAL_ROOT},
+    bytecode::{dictionary_id, read_bytecode_header, BytecodeHeader, BYTECODE_FILE_EXT},
+    libs::encoding::bytes_to_hex_string,
+    modules::require::CJS_IMPORT_PREFIX,
+    signature::{self, pinned_public_key},
+    source_map::SOURCE_MAP_EXT,
+};
+
+// ELF e_machine
+const EM_X86_64: u16 = 0x3e;
+const EM_AARCH64: u16 = 0xb7;
+// Mach-O cputype
+const CPU_TYPE_X86_64: u32 = 0x0100_0007;
+const CPU_TYPE_ARM64: u32 = 0x0100_000c;
+// PE machine
+const IMAGE_FILE_MACHINE_AMD64: u16 = 0x8664;
+const IMAGE_FILE_MACHINE_ARM64: u16 = 0xaa64;
+
+const PT_INTERP: u32 = 3;
+const MH_MAGIC_64: u32 = 0xfeed_facf;
+const LC_SEGMENT_64: u32 = 0x19;
+const RT_RCDATA: u32 = 10;
+
+/// Where the payload of a built executable lives and what it targets.
+struct Executable<'a> {
+    format: &'static str,
+    platform: Option<Platform>,
+    machine: String,
+    payload: &'a [u8],
+}
+
+/// `llrt inspect <binary> [--extract=<dir>]`
+pub fn run_inspect(args: &[String]) -> Result<(), String> {
+    let mut binary: Option<PathBuf> = None;
+    let mut extract_dir: Option<PathBuf> = None;
+
+    for arg in args {
+        if let Some(dir) = arg.strip_prefix("--extract=") {
+            extract_dir = Some(PathBuf::from(dir));
+        } else if arg.starts_with('-') {
+            return Err(format!("Unknown argument: {}", arg));
+        } else if binary.is_none() {
+            binary = Some(PathBuf::from(arg));
+        } else {
+            return Err(format!("Unexpected argument: {}", arg));
+        }
+    }
+
+    let binary = binary.ok_or("inspect: binary path is required")?;
+    let data =
+        fs::read(&binary).map_err(|e| format!("Failed to read {}: {}", binary.display(), e))?;
+    let executable = locate_payload(&data).map_err(|e| format!("{}: {}", binary.display(), e))?;
+
+    let payload = print_summary(&binary, &data, &executable)?;
+
+    if let Some(dir) = extract_dir {
+        let written = extract(&binary, payload, &dir)?;
+        println!("\nExtracted {} file(s) to {}", written, dir.display());
+    }
+
+    Ok(())
+}
+
+/// Prints what the executable contains and returns the payload without its
+/// signature.
+fn print_summary<'a>(
+    binary: &Path,
+    data: &[u8],
+    executable: &Executable<'a>,
+) -> Result<&'a [u8], String> {
+    let mut payload = executable.payload;
+    let platform = executable
+        .platform
+        .as_ref()
+        .map(|platform| platform_to_str(platform).to_string())
+        .unwrap_or_else(|| ["unknown (", &executable.machine, ")"].concat());
+
+    println!("File:      {}", binary.display());
+    println!("Format:    {} ({})", executable.format, executable.machine);
+    println!("Platform:  {}", platform);
+    println!(
+        "Runtime:   {}",
+        runtime_version(data).unwrap_or_else(|| "unknown".into())
+    );
+    println!(
+        "ABI:       QuickJS {}",
+        runtime_bytecode_abi(data).unwrap_or_else(|| "unknown".into())
+    );
+
+    if signature::is_signed(payload) {
+        // checked against the key stored with the payload, the pin is reported separately
+        match signature::verify_with(payload, None) {
+            Ok(verified) => {
+                let key = verified.public_key.unwrap_or_default();
+                let pinned = match pinned_public_key() {
+                    Some(pinned) if pinned == key => ", pinned by this runtime",
+                    Some(_) => ", NOT the key pinned by this runtime",
+                    None => "",
+                };
+                println!(
+                    "Signature: Ed25519, valid, key {}{}",
+                    bytes_to_hex_string(&key),
+                    pinned
+                );
+                payload = verified.payload;
+            },
+            Err(err) => return Err(format!("Invalid payload signature: {}", err)),
+        }
+    } else {
+        println!("Signature: none");
+    }
+
+    if !Archive::is_archive(payload) {
+        println!("Payload:   {} bytes, single module", payload.len());
+        println!("Bytecode:  {}", describe_bytecode(payload));
+        return Ok(payload);
+    }
+
+    let archive = Archive::decode(payload).map_err(|e| format!("Invalid archive: {}", e))?;
+    println!(
+        "Payload:   {} bytes, archive {}",
+        payload.len(),
+        ARCHIVE_VERSION
+    );
+    println!("Entry:     {}", archive.entry());
+    if let Some(dictionary) = archive.dictionary() {
+        println!(
+            "Dict:      {} bytes, id {}",
+            dictionary.len(),
+            dictionary_id(dictionary).unwrap_or_default()
+        );
+    }
+    if let Some(prefix) = archive.flag_prefix() {
+        println!("Flags:     {}<flag>", prefix);
+    }
+
+    let mut modules: Vec<&str> = archive.module_names().collect();
+    modules.sort_unstable();
+    println!("\nModules ({}):", modules.len());
+    for name in modules {
+        let bytecode = archive.module(name).unwrap_or_default();
+        println!("  {}  {}", name, describe_bytecode(bytecode));
+    }
+
+    let mut files: Vec<&str> = archive.file_names().collect();
+    files.sort_unstable();
+    if !files.is_empty() {
+        println!("\nFiles ({}):", files.len());
+        for name in files {
+            let size = archive.file(name).map(|bytes| bytes.len()).unwrap_or(0);
+            println!("  {}  {} bytes", name, size);
+        }
+    }
+
+    let mut source_maps: Vec<&str> = archive.source_map_names().collect();
+    source_maps.sort_unstable();
+    if !source_maps.is_empty() {
+        println!("\nSource maps ({}):", source_maps.len());
+        for name in source_maps {
+            let size = archive.source_map(name).map(|json| json.len()).unwrap_or(0);
+            println!("  {}  {} bytes", name, size);
+        }
+    }
+
+    let mut env: Vec<(&str, &str)> = archive.env().collect();
+    env.sort_unstable();
+    if !env.is_empty() {
+        println!("\nEnvironment defaults:");
+        for (key, value) in env {
+            println!("  {}={}", key, value);
+        }
+    }
+
+    let runtime = archive.runtime_config();
+    if !runtime.is_empty() {
+        println!("\nRuntime settings:");
+        for (key, value, locked) in runtime.iter() {
+            let lock = if locked { " (locked)" } else { "" };
+            println!("  {}={}{}", key, value, lock);
+        }
+    }
+
+    Ok(payload)
+}
+
+fn describe_bytecode(bytes: &[u8]) -> String {
+    let Some((header, _)) = read_bytecode_header(bytes) else {
+        return format!("unknown header, {} bytes", bytes.len());
+    };
+    let BytecodeHeader {
+        version,
+        runtime_version,
+        abi_hash,
+        uncompressed_size,
+        dictionary_id,
+    } = header;
+    let compiled_by = match (runtime_version, abi_hash) {
+        (Some(runtime_version), Some(abi_hash)) => {
+            format!(" (LLRT v{}, QuickJS {})", runtime_version, abi_hash)
+        },
+        _ => String::new(),
+    };
+    let dictionary = match dictionary_id {
+        Some(id) => format!(" with dictionary {}", id),
+        None => String::new(),
+    };
+    match uncompressed_size {
+        Some(size) => format!(
+            "{}{}, compressed{}, {} -> {} bytes",
+            version,
+            compiled_by,
+            dictionary,
+            bytes.len(),
+            size
+        ),
+        None => format!(
+            "{}{}, uncompressed, {} bytes",
+            version,
+            compiled_by,
+            bytes.len()
+        ),
+    }
+}
+
+/// Writes every module as a `.lrt` file, its source map as `.lrt.map` and
+/// every embedded file as is. Returns the number of files written.
+fn extract(binary: &Path, payload: &[u8], dir: &Path) -> Result<usize, String> {
+    let write = |path: PathBuf, bytes: &[u8]| -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+        }
+        fs::write(&path, bytes).map_err(|e| format!("Failed to write {}: {}", path.display(), e))
+    };
+
+    if !Archive::is_archive(payload) {
+        let name = binary
+            .file_stem()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "module".into());
+        write(
+            dir.join([name.as_str(), BYTECODE_FILE_EXT].concat()),
+            payload,
+        )?;
+        return Ok(1);
+    }
+
+    let archive = Archive::decode(payload).map_err(|e| format!("Invalid archive: {}", e))?;
+    let mut written = 0;
+    for name in archive.module_names() {
+        let bytes = archive.module(name).unwrap_or_default();
+        write(dir.join(module_file_name(name)), bytes)?;
+        written += 1;
+    }
+    for name in archive.source_map_names() {
+        let json = archive.source_map(name).unwrap_or_default();
+        write(
+            dir.join([module_file_name(name).as_str(), SOURCE_MAP_EXT].concat()),
+            json,
+        )?;
+        written += 1;
+    }
+    for name in archive.file_names() {
+        let bytes = archive.file(name).unwrap_or_default();
+        write(dir.join(relative_path(name)), bytes)?;
+        written += 1;
+    }
+    Ok(written)
+}
+
+// "/__lexe__/src/a.js" -> "src/a.js.lrt", "__cjs:/__lexe__/src/a.js" -> "src/a.js.cjs.lrt"
+fn module_file_name(name: &str) -> String {
+    let (path, is_cjs) = match name.strip_prefix(CJS_IMPORT_PREFIX) {
+        Some(path) => (path, true),
+        None => (name, false),
+    };
+    let mut file_name = relative_path(path);
+    if is_cjs && !file_name.ends_with(".cjs") {
+        file_name.push_str(".cjs");
+    }
+    if !file_name.ends_with(BYTECODE_FILE_EXT) {
+        file_name.push_str(BYTECODE_FILE_EXT);
+    }
+    file_name
+}
+
+fn relative_path(name: &str) -> String {
+    name.strip_prefix(VIRTUAL_ROOT)
+        .unwrap_or(name)
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != "." && *part != "..")
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+fn locate_payload(data: &[u8]) -> Result<Executable<'_>, String> {
+    if data.starts_with(b"\x7fELF") {
+        locate_elf(data)
+    } else if data.starts_with(b"MZ") {
+        locate_pe(data)
+    } else if read_u32(data, 0) == Some(MH_MAGIC_64) {
+        locate_macho(data)
+    } else {
+        Err("Not an ELF, PE or 64-bit Mach-O executable".into())
+    }
+}
+
+// libsui appends the section data to ELF files, followed by a 16 byte
+// trailer: magic number (4 bytes), hash (4 bytes) and data size (8 bytes)
+fn locate_elf(data: &[u8]) -> Result<Executable<'_>, String> {
+    const TRAILER_LEN: usize = 16;
+
+    let machine = read_u16(data, 18).ok_or("Truncated ELF header")?;
+    // glibc builds are dynamically linked, musl builds are static or use the musl loader
+    let musl = elf_interpreter(data).is_none_or(|interpreter| interpreter.contains("musl"));
+    let platform = match (machine, musl) {
+        (EM_X86_64, false) => Some(Platform::LinuxX64),
+        (EM_AARCH64, false) => Some(Platform::LinuxArm64),
+        (EM_X86_64, true) => Some(Platform::LinuxX64Musl),
+        (EM_AARCH64, true) => Some(Platform::LinuxArm64Musl),
+        _ => None,
+    };
+
+    let trailer_start = data
+        .len()
+        .checked_sub(TRAILER_LEN)
+        .ok_or("No embedded payload")?;
+    if read_u32(data, trailer_start) != Some(LIBSUI_MAGIC_NUMBER) {
+        return Err("No embedded payload".into());
+    }
+    let size = read_u64(data, trailer_start + 8).ok_or("No embedded payload")? as usize;
+    let start = trailer_start
+        .checked_sub(size)
+        .ok_or("Invalid embedded payload size")?;
+
+    Ok(Executable {
+        format: "ELF",
+        platform,
+        machine: format!("e_machine 0x{:x}", machine),
+        payload: &data[start..trailer_start],
+    })
+}
+
+// Program interpreter (dynamic loader) of a 64-bit ELF file
+fn elf_interpreter(data: &[u8]) -> Option<&str> {
+    let header_offset = read_u64(data, 32)? as usize;
+    let header_size = read_u16(data, 54)? as usize;
+    let header_count = read_u16(data, 56)? as usize;
+
+    (0..header_count).find_map(|index| {
+        let header = header_offset + index * header_size;
+        if read_u32(data, header)? != PT_INTERP {
+            return None;
+        }
+        let offset = read_u64(data, header + 8)? as usize;
+        let size = read_u64(data, header + 32)? as usize;
+        Some(c_str(data.get(offset..offset + size)?))
+    })
+}
+
+fn locate_macho(data: &[u8]) -> Result<Executable<'_>, String> {
+    const HEADER_LEN: usize = 32;
+    const SEGMENT_LEN: usize = 72;
+    const SECTION_LEN: usize = 80;
+
+    let truncated = || "Truncated Mach-O header".to_string();
+    let cpu_type = read_u32(data, 4).ok_or_else(truncated)?;
+    let platform = match cpu_type {
+        CPU_TYPE_X86_64 => Some(Platform::DarwinX64),
+        CPU_TYPE_ARM64 => Some(Platform::DarwinArm64),
+        _ => None,
+    };
+    let commands = read_u32(data, 16).ok_or_else(truncated)?;
+
+    let mut offset = HEADER_LEN;
+    for _ in 0..commands {
+        let cmd = read_u32(data, offset).ok_or_else(truncated)?;
+        let cmd_size = read_u32(data, offset + 4).ok_or_else(truncated)? as usize;
+        if cmd == LC_SEGMENT_64 {
+            let sections = read_u32(data, offset + 64).ok_or_else(truncated)? as usize;
+            for index in 0..sections {
+                let section = offset + SEGMENT_LEN + index * SECTION_LEN;
+                let name = data.get(section..section + 16).ok_or_else(truncated)?;
+                if c_str(name).trim_start_matches('_') != SECTION_NAME {
+                    continue;
+                }
+                let size = read_u64(data, section + 40).ok_or_else(truncated)? as usize;
+                let start = read_u32(data, section + 48).ok_or_else(truncated)? as usize;
+                let payload = data
+                    .get(start..start + size)
+                    .ok_or("Section extends beyond end of file")?;
+                return Ok(Executable {
+                    format: "Mach-O",
+                    platform,
+                    machine: format!("cputype 0x{:x}", cpu_type),
+                    payload,
+                });
+            }
+        }
+        if cmd_size == 0 {
+            break;
+        }
+        offset += cmd_size;
+    }
+
+    Err("No embedded payload".into())
+}
+
+// libsui stores the payload as a named RT_RCDATA resource
+fn locate_pe(data: &[u8]) -> Result<Executable<'_>, String> {
+    let truncated = || "Truncated PE header".to_string();
+    let pe = read_u32(data, 0x3c).ok_or_else(truncated)? as usize;
+    if data.get(pe..pe + 4) != Some(b"PE\0\0") {
+        return Err("Invalid PE signature".into());
+    }
+    let machine = read_u16(data, pe + 4).ok_or_else(truncated)?;
+    let platform = match machine {
+        IMAGE_FILE_MACHINE_AMD64 => Some(Platform::WindowsX64),
+        IMAGE_FILE_MACHINE_ARM64 => Some(Platform::WindowsArm64),
+        _ => None,
+    };
+    let machine_name = match machine {
+        IMAGE_FILE_MACHINE_ARM64 => "machine 0xaa64 (arm64)".to_string(),
+        _ => format!("machine 0x{:x}", machine),
+    };
+    let section_count = read_u16(data, pe + 6).ok_or_else(truncated)? as usize;
+    let optional_len = read_u16(data, pe + 20).ok_or_else(truncated)? as usize;
+    let optional = pe + 24;
+    let data_directories = match read_u16(data, optional).ok_or_else(truncated)? {
+        0x20b => optional + 112,
+        0x10b => optional + 96,
+        _ => return Err("Unknown PE optional header".into()),
+    };
+    // resource table is the third data directory
+    let resource_rva = read_u32(data, data_directories + 16).ok_or_else(truncated)?;
+
+    let sections: Vec<(u32, u32, u32)> = (0..section_count)
+        .filter_map(|index| {
+            let section = optional + optional_len + index * 40;
+            Some((
+                read_u32(data, section + 12)?,
+                read_u32(data, section + 8)?.max(read_u32(data, section + 16)?),
+                read_u32(data, section + 20)?,
+            ))
+        })
+        .collect();
+    let rva_to_offset = |rva: u32| -> Option<usize> {
+        sections
+            .iter()
+            .find(|(address, size, _)| rva >= *address && rva < address + size)
+            .map(|(address, _, raw)| (rva - address + raw) as usize)
+    };
+
+    let root = rva_to_offset(resource_rva).ok_or("No resources")?;
+    let rcdata = resource_entries(data, root, root)
+        .into_iter()
+        .find(|(name, _)| matches!(name, ResourceName::Id(RT_RCDATA)))
+        .ok_or("No embedded payload")?;
+    let named = resource_entries(data, root, root + rcdata.1)
+        .into_iter()
+        .find(|(name, _)| match name {
+            ResourceName::Name(name) => name.eq_ignore_ascii_case(SECTION_NAME),
+            ResourceName::Id(_) => false,
+        })
+        .ok_or("No embedded payload")?;
+    let language = resource_entries(data, root, root + named.1)
+        .into_iter()
+        .next()
+        .ok_or("No embedded payload")?;
+
+    let entry = root + language.1;
+    let rva = read_u32(data, entry).ok_or_else(truncated)?;
+    let size = read_u32(data, entry + 4).ok_or_else(truncated)? as usize;
+    let start = rva_to_offset(rva).ok_or("Invalid resource address")?;
+    let payload = data
+        .get(start..start + size)
+        .ok_or("Resource extends beyond end of file")?;
+
+    Ok(Executable {
+        format: "PE",
+        platform,
+        machine: machine_name,
+        payload,
+    })
+}
+
+enum ResourceName {
+    Id(u32),
+    Name(String),
+}
+
+// Entries of a resource directory as (name, offset relative to the resource root)
+fn resource_entries(data: &[u8], root: usize, directory: usize) -> Vec<(ResourceName, usize)> {
+    let named = read_u16(data, directory + 12).unwrap_or(0) as usize;
+    let ids = read_u16(data, directory + 14).unwrap_or(0) as usize;
+
+    (0..named + ids)
+        .filter_map(|index| {
+            let entry = directory + 16 + index * 8;
+            let name = read_u32(data, entry)?;
+            let offset = (read_u32(data, entry + 4)? & 0x7fff_ffff) as usize;
+            let name = if name & 0x8000_0000 != 0 {
+                let string = root + (name & 0x7fff_ffff) as usize;
+                let len = read_u16(data, string)? as usize;
+                let units: Vec<u16> = (0..len)
+                    .map(|i| read_u16(data, string + 2 + i * 2))
+                    .collect::<Option<_>>()?;
+                ResourceName::Name(String::from_utf16_lossy(&units))
+            } else {
+                ResourceName::Id(name)
+            };
+            Some((name, offset))
+        })
+        .collect()
+}
+
+fn c_str(bytes: &[u8]) -> &str {
+    let end = bytes.iter().position(|&c| c == 0).unwrap_or(bytes.len());
+    std::str::from_utf8(&bytes[..end]).unwrap_or_default()
+}
+
+fn read_u16(data: &[u8], offset: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(
+        data.get(offset..offset + 2)?.try_into().ok()?,
+    ))
+}
+
+fn read_u32(data: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        data.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn read_u64(data: &[u8], offset: usize) -> Option<u64> {
+    Some(u64::from_le_bytes(
+        data.get(offset..offset + 8)?.try_into().ok()?,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{build::RUNTIME_MARKER, core::vm::bytecode_abi_hash, VERSION_STRING};
+
+    #[test]
+    fn test_locate_elf_payload() {
+        let mut data = vec![0u8; 64];
+        data[..4].copy_from_slice(b"\x7fELF");
+        data[18..20].copy_from_slice(&EM_AARCH64.to_le_bytes());
+        data.extend_from_slice(b"payload");
+        data.extend_from_slice(&LIBSUI_MAGIC_NUMBER.to_le_bytes());
+        data.extend_from_slice(&0u32.to_le_bytes());
+        data.extend_from_slice(&7u64.to_le_bytes());
+
+        let executable = locate_payload(&data).unwrap();
+        assert_eq!(executable.format, "ELF");
+        // no program interpreter, so statically linked
+        assert_eq!(executable.platform, Some(Platform::LinuxArm64Musl));
+        assert_eq!(executable.payload, b"payload");
+    }
+
+    #[test]
+    fn test_elf_libc() {
+        const INTERPRETER: &[u8] = b"/lib64/ld-linux-x86-64.so.2\0";
+
+        // one PT_INTERP program header right after the ELF header
+        let mut data = vec![0u8; 64 + 56];
+        data[..4].copy_from_slice(b"\x7fELF");
+        data[18..20].copy_from_slice(&EM_X86_64.to_le_bytes());
+        data[32..40].copy_from_slice(&64u64.to_le_bytes());
+        data[54..56].copy_from_slice(&56u16.to_le_bytes());
+        data[56..58].copy_from_slice(&1u16.to_le_bytes());
+        data[64..68].copy_from_slice(&PT_INTERP.to_le_bytes());
+        data[72..80].copy_from_slice(&(data.len() as u64).to_le_bytes());
+        data[96..104].copy_from_slice(&(INTERPRETER.len() as u64).to_le_bytes());
+        data.extend_from_slice(INTERPRETER);
+        assert_eq!(elf_interpreter(&data), Some("/lib64/ld-linux-x86-64.so.2"));
+
+        let mut payload = data.clone();
+        payload.extend_from_slice(&LIBSUI_MAGIC_NUMBER.to_le_bytes());
+        payload.extend_from_slice(&[0u8; 12]);
+        let executable = locate_payload(&payload).unwrap();
+        assert_eq!(executable.platform, Some(Platform::LinuxX64));
+
+        // statically linked
+        data[56..58].copy_from_slice(&0u16.to_le_bytes());
+        assert_eq!(elf_interpreter(&data), None);
+    }
+
+    #[test]
+    fn test_describe_bytecode() {
+        let mut compressed = b"lrt01c".to_vec();
+        compressed.extend_from_slice(&1234u32.to_le_bytes());
+        assert_eq!(
+            describe_bytecode(&compressed),
+            "lrt01, compressed, 10 -> 1234 bytes"
+        );
+
+        let mut current = b"lrt02u".to_vec();
+        for field in ["0.5.1-beta", "abc123"] {
+            current.push(field.len() as u8);
+            current.extend_from_slice(field.as_bytes());
+        }
+        assert_eq!(
+            describe_bytecode(&current),
+            "lrt02 (LLRT v0.5.1-beta, QuickJS abc123), uncompressed, 24 bytes"
+        );
+
+        let mut dictionary = b"lrt03c".to_vec();
+        for field in ["0.5.1-beta", "abc123"] {
+            dictionary.push(field.len() as u8);
+            dictionary.extend_from_slice(field.as_bytes());
+        }
+        dictionary.extend_from_slice(&1234u32.to_le_bytes());
+        dictionary.extend_from_slice(&42u32.to_le_bytes());
+        assert_eq!(
+            describe_bytecode(&dictionary),
+            "lrt03 (LLRT v0.5.1-beta, QuickJS abc123), compressed with dictionary 42, 32 -> 1234 bytes"
+        );
+        assert_eq!(describe_bytecode(b"garbage"), "unknown header, 7 bytes");
+    }
+
+    #[test]
+    fn test_runtime_markers() {
+        // version-like strings outside the marker, e.g. in error messages, are ignored
+        let mut data = b"....LLRT v1.2.3 (linux, x64)...LLRT_BYTECODE_ABI:deadbeef;..".to_vec();
+        data.extend_from_slice(&RUNTIME_MARKER);
+        data.extend_from_slice(b"LLRT v1.2.3 (linux, x64)..");
+        assert_eq!(runtime_version(&data).as_deref(), Some(VERSION_STRING));
+        assert_eq!(
+            runtime_bytecode_abi(&data).as_deref(),
+            Some(bytecode_abi_hash())
+        );
+
+        let truncated = &RUNTIME_MARKER[..RUNTIME_MARKER.len() - 1];
+        assert_eq!(runtime_version(truncated), None);
+        assert_eq!(runtime_bytecode_abi(b"LLRT_BYTECODE_ABI:deadbeef;"), None);
+    }
+
+    #[test]
+    fn test_module_file_name() {
+        assert_eq!(
+            module_file_name("/__lexe__/src/index.js"),
+            "src/index.js.lrt"
+        );
+        assert_eq!(module_file_name("__cjs:/__lexe__/lib.js"), "lib.js.cjs.lrt");
+        assert_eq!(module_file_name("__cjs:/__lexe__/lib.cjs"), "lib.cjs.lrt");
+        assert_eq!(module_file_name("/__lexe__/prebuilt.lrt"), "prebuilt.lrt");
+    }
+}
@@ -9,30 +9,49 @@ use std::{
 };
 
 mod build;
+mod build_config;
+mod cli;
 mod core;
+#[cfg(not(feature = "lambda"))]
+mod inspect;
 mod minimal_tracer;
 #[cfg(not(feature = "lambda"))]
 mod repl;
+#[cfg(not(feature = "lambda"))]
+mod watch;
 
 use build::{extract_code_binary, has_magic_number, LexeBuild, Platform};
+use cli::CommandLine;
 use constcat::concat;
 use minimal_tracer::MinimalTracer;
 use tracing::trace;
 
-#[cfg(not(feature = "lambda"))]
-use crate::core::compiler::compile_file;
 use crate::core::{
+    archive::{self, Archive},
     bytecode::BYTECODE_EXT,
+    compile_cache, environment,
     libs::utils::{
         fs::DirectoryWalker,
         sysinfo::{ARCH, PLATFORM},
     },
-    modules::path::name_extname,
-    runtime_client,
-    utils::io::{is_supported_ext, SUPPORTED_EXTENSIONS},
-    vm::Vm,
+    modules::{
+        path::name_extname,
+        process,
+        require::{
+            import_map::{self, ImportMap},
+            loader::CustomLoader,
+        },
+    },
+    runtime_client, signature,
+    utils::io::{is_commonjs, is_supported_ext, SUPPORTED_EXTENSIONS},
+    vm::{Vm, VmOptions},
     VERSION,
 };
+#[cfg(not(feature = "lambda"))]
+use crate::core::{
+    bytecode::dictionary_id,
+    compiler::{compile_directory, compile_file, train_dictionary, Strip, DEFAULT_DICTIONARY_SIZE},
+};
 
 // rquickjs components
 use crate::core::{async_with, CatchResultExt};
@@ -41,20 +60,53 @@ use crate::core::{async_with, CatchResultExt};
 #[global_allocator]
 static ALLOC: snmalloc_rs::SnMalloc = snmalloc_rs::SnMalloc;
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
+fn main() -> Result<(), Box<dyn Error + Send + Sync>> {
     let now = Instant::now();
 
+    // settings baked into a built executable and the variables of the flags
+    // must be in place before the tracer and VM read them, and are set before
+    // the threads of the async runtime start
+    let embedded = load_embedded();
+    let command_line = match embedded {
+        Some(_) => CommandLine::parse_embedded(
+            env::args(),
+            archive::embedded().and_then(Archive::flag_prefix),
+        ),
+        None => CommandLine::parse(env::args()),
+    };
+    let command_line = command_line
+        .and_then(|command_line| command_line.flags.apply_env().map(|_| command_line))
+        .unwrap_or_else(|err| {
+            eprintln!("{err}");
+            exit(1);
+        });
+    process::set_args(command_line.argv(), command_line.exec_argv.clone());
+
+    tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?
+        .block_on(start(now, embedded, command_line))
+}
+
+async fn start(
+    now: Instant,
+    embedded: Option<Embedded>,
+    command_line: CommandLine,
+) -> Result<(), Box<dyn Error + Send + Sync>> {
     MinimalTracer::register()?;
     trace!("Started runtime");
 
-    let vm = Vm::new().await?;
+    let mut vm_options = VmOptions::default();
+    if let Some(stack_size) = command_line.flags.stack_size_kb {
+        vm_options.max_stack_size = stack_size * 1024;
+    }
+    let vm = Vm::from_options(vm_options).await?;
     trace!("Initialized VM in {}ms", now.elapsed().as_millis());
 
     if env::var("AWS_LAMBDA_RUNTIME_API").is_ok() && env::var("_HANDLER").is_ok() {
         start_runtime(&vm).await
     } else {
-        start_cli(&vm).await;
+        start_cli(&vm, embedded, command_line).await;
     }
 
     vm.idle().await?;
@@ -74,34 +126,99 @@ fn usage() {
         r#"
 
 Usage:
-  llrt <filename>
+  llrt [runtime flags] [--] <filename|command> [args...]
+  llrt [runtime flags] --watch [--watch-path=<glob>] <filename|test <test_args>>
+  llrt --clear-cache
   llrt -v | --version
   llrt -h | --help
   llrt -e | --eval <source>
-  llrt compile input.js [output.lrt]
+  llrt compile input.js [output.lrt] [--strip=source|debug|all]
+  llrt compile <dir> <outdir> [--strip=source|debug|all]
+  llrt compile --train-dict=<file> [--dict-size=<bytes>] <inputs...>
   llrt test <test_args>
   llrt build <build_args>
+  llrt inspect <binary> [--extract=<directory>]
+
+Runtime flags, before the filename or command (`--` ends them, later
+arguments are passed to the script as they are):
+  --gc-threshold    Memory in MB allocated before the garbage collector runs
+                      (same as $LLRT_GC_THRESHOLD_MB)
+  --net-allow       Space-delimited hosts allowed for network connections,
+                      repeatable (same as $LLRT_NET_ALLOW)
+  --stack-size      Maximum stack size of the VM in KB (default: 512)
+  --env-file        Set the variables of a .env file that are not set already,
+                      before the runtime starts (repeatable)
+  --import-map      Import map applied before Node module resolution, also when
+                      build bundles modules (default: the "importMap" field of
+                      ./package.json, a file path or the map itself)
+  --compile-cache   Keep the bytecode of loaded modules in <dir> and reuse it
+                      while their source is unchanged (default:
+                      $LLRT_COMPILE_CACHE, or ~/.cache/llrt without it)
+  --watch           Run the file, or test, again when a file it loaded changes.
+                      Tests only run again for test files that load the
+                      changed file
+  --watch-path      Glob of more files to watch, relative to the current
+                      directory (repeatable, implies --watch)
+  Flags take their value as --flag=<value> or --flag <value>, --compile-cache
+  only as --compile-cache=<dir>.
 
 Options:
   -v, --version     Print version information
   -h, --help        Print this help message
   -e, --eval        Evaluate the provided source code
+  --clear-cache     Remove the modules kept by --compile-cache
   compile           Compile JS to bytecode and compress it with zstd:
                       if [output.lrt] is omitted, <input>.lrt is used.
-                      lrt file is expected to be executed by the llrt version
-                      that created it
+                      lrt file can only be executed by the llrt version
+                      that created it, other versions refuse to load it.
+                      A source map of the input, given with
+                      --source-map=<file> or found through its
+                      sourceMappingURL comment, is written to <output.lrt>.map
+                      and used to show original positions in stack traces.
+                      --strip removes function source text (source), file
+                      names and line numbers (debug) or both (all),
+                      debug and all can't be used with --source-map
+                      TypeScript input (.ts, .mts, .cts) has its types stripped
+                      A directory is compiled file by file into <outdir>,
+                      keeping its layout: .lrt files for JS and TypeScript,
+                      .json files copied, hidden directories skipped. Files
+                      that fail are listed at the end.
+                      --train-dict trains a zstd dictionary on the bytecode of
+                      the inputs (.lrt, JS and TypeScript files or directories)
+                      and writes it to <file>, for build --dict. --dict-size
+                      sets its maximum size (default: 112640 bytes)
   test              Run tests with provided arguments:
                       <test_args> -d <directory> <test-filter>
+                      --watch and --watch-path are accepted here as well
   build             This command will create a standalone executable from the source code.
                     build_args:
-                      -i      input file(required)
+                      -i      input file(required), modules it imports are bundled as well
                       -o      output file(optional, default: <input file name>-<platform>)
                       -d      output directory(optional, default: ./dist)
                       -p      target platform, use "," to separate multiple platforms
-                                options: linux-x64,linux-arm64,darwin-x64,darwin-arm64,windows-x64
+                                options: linux-x64,linux-arm64,linux-x64-musl,linux-arm64-musl,
+                                darwin-x64,darwin-arm64,windows-x64,windows-arm64
                                 (optional, default: current platform)
+                      --asset glob of files to embed, read-only via fs relative to import.meta.dirname
+                                (optional, repeatable, e.g. --asset=templates/**)
+                      --sign    sign the embedded payload with an Ed25519 private key (PKCS#8 PEM
+                                or DER file), executables refuse to start if it was modified (optional)
+                      --strip   remove function source text and/or debug info from the
+                                bytecode: source, debug or all (optional)
+                      --dict    compress the bytecode with a zstd dictionary trained by
+                                compile --train-dict, embedded in the executable (optional)
+                      --flag-prefix  arguments of the executable starting with this prefix
+                                are runtime flags instead of script arguments, e.g. with
+                                --flag-prefix=--app- `--app-gc-threshold=64` (optional)
+                      --config  build config file (optional, default: lexe.toml or the "lexe"
+                                section of package.json next to the input file or in the current directory)
+                    flags override the values of the config file
                     
                     like: llrt build -i=input.js -o=binary -d=dist -p=linux-x64,windows-x64
+  inspect           Print the target platform, runtime version and embedded modules of an
+                    executable created by build (any platform):
+                      --extract  write the embedded modules as .lrt files and the embedded
+                                 files to <directory>
 "#
     );
 }
@@ -115,8 +232,49 @@ async fn start_runtime(vm: &Vm) {
     .await;
 }
 
-async fn start_cli(vm: &Vm) {
-    let args: Vec<String> = env::args().collect();
+async fn start_cli(vm: &Vm, embedded: Option<Embedded>, command_line: CommandLine) {
+    if let Some(embedded) = embedded {
+        return run_embedded(vm, embedded).await;
+    }
+
+    let CommandLine {
+        exe,
+        flags,
+        args: command_args,
+        ..
+    } = command_line;
+    let mut args = vec![exe];
+    args.extend(command_args);
+
+    match &flags.compile_cache {
+        Some(Some(dir)) => compile_cache::enable(dir.clone()),
+        Some(None) => compile_cache::enable(compile_cache::default_dir()),
+        None => {},
+    }
+    let import_map = flags.import_map.as_deref();
+
+    let mut watch = flags.watch;
+    let mut watch_paths = flags.watch_paths.clone();
+
+    // `llrt test --watch` is the same as `llrt --watch test`
+    if args.get(1).is_some_and(|arg| arg == "test") {
+        args.retain(|arg| {
+            if arg == "--watch" {
+                watch = true;
+            } else if let Some(glob) = arg.strip_prefix("--watch-path=") {
+                watch = true;
+                watch_paths.push(glob.to_string());
+            } else {
+                return true;
+            }
+            false
+        });
+    }
+    if watch {
+        // the files loaded by the runs are found with the import map as well
+        install_import_map(import_map);
+        return run_watch(&flags.forwarded(), &watch_paths, &args[1..]).await;
+    }
 
     if args.len() > 1 {
         for (i, arg) in args.iter().enumerate() {
@@ -132,11 +290,31 @@ async fn start_cli(vm: &Vm) {
                         return;
                     },
                     "-e" | "--eval" => {
+                        install_import_map(import_map);
                         if let Some(source) = args.get(i + 1) {
                             vm.run(source.as_bytes(), false, false).await;
                         }
                         return;
                     },
+                    "--clear-cache" => {
+                        let dir = compile_cache::dir()
+                            .map(Path::to_path_buf)
+                            .unwrap_or_else(compile_cache::default_dir);
+                        match compile_cache::clear(&dir) {
+                            Ok(removed) => {
+                                println!(
+                                    "Removed {} cached modules from {}",
+                                    removed,
+                                    dir.display()
+                                );
+                            },
+                            Err(err) => {
+                                eprintln!("Failed to clear {}: {}", dir.display(), err);
+                                exit(1);
+                            },
+                        }
+                        return;
+                    },
                     "test" => {
                         if let Err(error) = run_tests(vm, &args[i + 1..]).await {
                             eprintln!("{error}");
@@ -147,8 +325,101 @@ async fn start_cli(vm: &Vm) {
                     "compile" => {
                         #[cfg(not(feature = "lambda"))]
                         {
-                            if let Some(filename) = args.get(i + 1) {
-                                let output_filename = if let Some(arg) = args.get(i + 2) {
+                            let mut source_map = None;
+                            let mut strip = Strip::None;
+                            let mut train_dict = None;
+                            let mut dict_size = DEFAULT_DICTIONARY_SIZE;
+                            let mut positional = Vec::with_capacity(2);
+                            for arg in &args[i + 1..] {
+                                if let Some(path) = arg.strip_prefix("--source-map=") {
+                                    source_map = Some(PathBuf::from(path));
+                                } else if let Some(path) = arg.strip_prefix("--train-dict=") {
+                                    train_dict = Some(PathBuf::from(path));
+                                } else if let Some(size) = arg.strip_prefix("--dict-size=") {
+                                    dict_size = match size.parse() {
+                                        Ok(size) => size,
+                                        Err(_) => {
+                                            eprintln!("compile: invalid dictionary size: {size}");
+                                            exit(1);
+                                        },
+                                    };
+                                } else if let Some(mode) = arg.strip_prefix("--strip=") {
+                                    strip = match mode.parse() {
+                                        Ok(strip) => strip,
+                                        Err(error) => {
+                                            eprintln!("compile: {error}");
+                                            exit(1);
+                                        },
+                                    };
+                                } else {
+                                    positional.push(arg);
+                                }
+                            }
+                            if let Some(output) = train_dict {
+                                if positional.is_empty() {
+                                    eprintln!(
+                                        "compile: modules to train the dictionary on are required."
+                                    );
+                                    exit(1);
+                                }
+                                let inputs: Vec<PathBuf> =
+                                    positional.iter().map(PathBuf::from).collect();
+                                let trained = train_dictionary(&inputs, dict_size, strip).await;
+                                let written = trained.and_then(|(dictionary, samples)| {
+                                    std::fs::write(&output, &dictionary)?;
+                                    Ok((dictionary, samples))
+                                });
+                                match written {
+                                    Ok((dictionary, samples)) => println!(
+                                        "Trained dictionary {} ({} bytes) on {} modules: {}",
+                                        dictionary_id(&dictionary).unwrap_or_default(),
+                                        dictionary.len(),
+                                        samples,
+                                        output.display()
+                                    ),
+                                    Err(error) => {
+                                        eprintln!("{error}");
+                                        exit(1);
+                                    },
+                                }
+                                return;
+                            }
+                            if let Some(dir) =
+                                positional.first().filter(|dir| Path::new(dir).is_dir())
+                            {
+                                let Some(output_dir) = positional.get(1) else {
+                                    eprintln!("compile: output directory is required.");
+                                    exit(1);
+                                };
+                                match compile_directory(
+                                    Path::new(dir),
+                                    Path::new(output_dir),
+                                    strip,
+                                )
+                                .await
+                                {
+                                    Ok(summary) => {
+                                        for (file, error) in &summary.errors {
+                                            eprintln!("{}: {}", file.display(), error);
+                                        }
+                                        println!("{summary}");
+                                        if !summary.errors.is_empty() {
+                                            eprintln!(
+                                                "compile: {} files failed to compile",
+                                                summary.errors.len()
+                                            );
+                                            exit(1);
+                                        }
+                                    },
+                                    Err(error) => {
+                                        eprintln!("{error}");
+                                        exit(1);
+                                    },
+                                }
+                                return;
+                            }
+                            if let Some(filename) = positional.first() {
+                                let output_filename = if let Some(arg) = positional.get(1) {
                                     arg.to_string()
                                 } else {
                                     let mut buf = PathBuf::from(filename);
@@ -158,7 +429,14 @@ async fn start_cli(vm: &Vm) {
 
                                 let filename = Path::new(filename);
                                 let output_filename = Path::new(&output_filename);
-                                if let Err(error) = compile_file(filename, output_filename).await {
+                                if let Err(error) = compile_file(
+                                    filename,
+                                    output_filename,
+                                    source_map.as_deref(),
+                                    strip,
+                                )
+                                .await
+                                {
                                     eprintln!("{error}");
                                     exit(1);
                                 }
@@ -174,9 +452,25 @@ async fn start_cli(vm: &Vm) {
                             exit(1);
                         }
                     },
+                    "inspect" => {
+                        #[cfg(not(feature = "lambda"))]
+                        {
+                            if let Err(err) = inspect::run_inspect(&args[i + 1..]) {
+                                eprintln!("{}", err);
+                                exit(1);
+                            }
+                            return;
+                        }
+                        #[cfg(feature = "lambda")]
+                        {
+                            eprintln!("Not supported in \"lambda\" version.");
+                            exit(1);
+                        }
+                    },
                     "build" => {
                         #[cfg(not(feature = "lambda"))]
                         {
+                            install_import_map(import_map);
                             let build_args = args[i + 1..].to_vec();
                             match LexeBuild::validate_args(&build_args) {
                                 Ok(args) => {
@@ -206,10 +500,11 @@ async fn start_cli(vm: &Vm) {
                 let filename = Path::new(arg);
                 let file_exists = filename.exists();
 
-                let global = ext == ".cjs";
+                let global = is_commonjs(ext);
 
                 if is_supported_ext(ext) {
                     if file_exists {
+                        install_import_map(import_map);
                         return vm.run_file(arg, true, global).await;
                     } else {
                         eprintln!("No such file: {}", arg);
@@ -217,6 +512,7 @@ async fn start_cli(vm: &Vm) {
                     }
                 } else {
                     if file_exists {
+                        install_import_map(import_map);
                         return vm.run_file(arg, true, false).await;
                     }
                     eprintln!("Unknown command: {}", arg);
@@ -228,23 +524,8 @@ async fn start_cli(vm: &Vm) {
     } else {
         #[cfg(not(feature = "lambda"))]
         {
-            let has_magic_number = match has_magic_number(&Platform::current()) {
-                Ok(has_magic_number) => has_magic_number,
-                Err(_) => false,
-            };
-            if has_magic_number {
-                // extract code binary by libsui
-                let code_binary = extract_code_binary();
-                if let Some(code_binary) = code_binary {
-                    vm.run_with(|ctx| {
-                        let module = llrt_core::modules::require::loader::CustomLoader::load_bytecode_module(ctx.clone(), &code_binary)?;
-                        module.eval()?;
-                        Ok(())
-                    }).await;
-                }
-            } else {
-                repl::run_repl(&vm.ctx).await;
-            }
+            install_import_map(import_map);
+            repl::run_repl(&vm.ctx).await;
         }
 
         #[cfg(feature = "lambda")]
@@ -255,7 +536,114 @@ async fn start_cli(vm: &Vm) {
     }
 }
 
-async fn run_tests(vm: &Vm, args: &[std::string::String]) -> Result<(), String> {
+// Runs a script or `test` in child runtimes that start again on changes
+#[cfg(not(feature = "lambda"))]
+async fn run_watch(flags: &[String], paths: &[String], args: &[String]) {
+    let watch_args = watch::WatchArgs { flags, paths };
+    let result = match args.first().map(String::as_str) {
+        None => {
+            eprintln!("--watch: a script or test is required.");
+            exit(1);
+        },
+        Some("test") => watch::run_tests(&watch_args, &args[1..]).await,
+        Some(_) => watch::run_script(&watch_args, args),
+    };
+    if let Err(err) = result {
+        eprintln!("--watch: {err}");
+        exit(1);
+    }
+}
+
+#[cfg(feature = "lambda")]
+async fn run_watch(_flags: &[String], _paths: &[String], _args: &[String]) {
+    eprintln!("Not supported in \"lambda\" version.");
+    exit(1);
+}
+
+// An explicit --import-map wins over the "importMap" field of ./package.json.
+// Only installed for the commands that load modules, which exit when the map
+// is invalid.
+fn install_import_map(file: Option<&Path>) {
+    let import_map = match file {
+        Some(file) => ImportMap::load(file).map(Some),
+        None => ImportMap::from_package_json(Path::new(".")),
+    };
+    match import_map {
+        Ok(Some(map)) => import_map::install(map),
+        Ok(None) => {},
+        Err(err) => {
+            eprintln!("Invalid import map: {err}");
+            exit(1);
+        },
+    }
+}
+
+enum Embedded {
+    /// Executables built before module graphs were bundled embed a single module
+    Module(Vec<u8>),
+    /// Entry point of an installed archive
+    Archive(String),
+}
+
+fn load_embedded() -> Option<Embedded> {
+    if cfg!(feature = "lambda") {
+        return None;
+    }
+    let has_magic_number = has_magic_number(&Platform::current()).unwrap_or(false);
+    if !has_magic_number {
+        return None;
+    }
+    // extract code binary by libsui
+    let code_binary = extract_code_binary()?;
+    let code_binary = match signature::verify(&code_binary) {
+        Ok(verified) => verified.payload.to_vec(),
+        Err(error) => {
+            eprintln!("Embedded payload failed the integrity check: {error}");
+            exit(signature::INTEGRITY_EXIT_CODE);
+        },
+    };
+    if !Archive::is_archive(&code_binary) {
+        return Some(Embedded::Module(code_binary));
+    }
+
+    let archive = match Archive::decode(&code_binary) {
+        Ok(archive) => archive,
+        Err(error) => {
+            eprintln!("Invalid embedded archive: {error}");
+            exit(1);
+        },
+    };
+    for (key, value) in archive.env() {
+        if env::var_os(key).is_none() {
+            env::set_var(key, value);
+        }
+    }
+    environment::set_runtime_config(archive.runtime_config().clone());
+    let entry = archive.entry().to_string();
+    archive::install(archive);
+    Some(Embedded::Archive(entry))
+}
+
+async fn run_embedded(vm: &Vm, embedded: Embedded) {
+    match embedded {
+        Embedded::Module(code_binary) => {
+            vm.run_with(|ctx| {
+                let module = CustomLoader::load_bytecode_module(ctx.clone(), &code_binary)?;
+                module.eval()?;
+                Ok(())
+            })
+            .await;
+        },
+        Embedded::Archive(entry) => {
+            let global = is_commonjs(&entry);
+            vm.run_file(&entry, true, global).await;
+        },
+    }
+}
+
+// Splits the arguments of `llrt test` into the directory to search and the
+// filters
+fn parse_test_args(args: &[String]) -> Result<(&str, Vec<&str>), String> {
     let mut filters: Vec<&str> = Vec::with_capacity(args.len());
 
     let mut root = ".";
@@ -280,6 +668,12 @@ async fn run_tests(vm: &Vm, args: &[std::string::String]) -> Result<(), String>
         }
     }
 
+    Ok((root, filters))
+}
+
+/// Test files below `root` as absolute paths, sorted. A filter selects the
+/// files whose name contains it, or the file at its absolute path.
+async fn find_test_files(root: &str, filters: &[&str]) -> Result<Vec<String>, String> {
     let now = Instant::now();
 
     let mut entries: Vec<String> = Vec::with_capacity(100);
@@ -302,17 +696,17 @@ async fn run_tests(vm: &Vm, args: &[std::string::String]) -> Result<(), String>
         .map(|ext| [".test", ext].concat())
         .collect();
 
-    let pwd = env::current_dir().map_err(|e| e.to_string())?;
-    let pwd = pwd.to_string_lossy();
     while let Some((entry, _)) = directory_walker.walk().await.map_err(|e| e.to_string())? {
         if let Some(name) = entry.file_name() {
             let name = name.to_string_lossy();
             let name = name.as_ref();
+            let path = std::path::absolute(&entry).map_err(|e| e.to_string())?;
+            let path = path.to_string_lossy();
             for ext_name in &test_js_extensions {
                 if name.ends_with(ext_name)
-                    && (!has_filters || filters.iter().any(|&f| name.contains(f)))
+                    && (!has_filters || filters.iter().any(|&f| name.contains(f) || path == f))
                 {
-                    entries.push([pwd.as_ref(), "/", entry.to_string_lossy().as_ref()].concat());
+                    entries.push(path.to_string());
                 }
             }
         };
@@ -322,6 +716,13 @@ async fn run_tests(vm: &Vm, args: &[std::string::String]) -> Result<(), String>
 
     trace!("Found tests in {}ms", now.elapsed().as_millis());
 
+    Ok(entries)
+}
+
+async fn run_tests(vm: &Vm, args: &[std::string::String]) -> Result<(), String> {
+    let (root, filters) = parse_test_args(args)?;
+    let entries = find_test_files(root, &filters).await?;
+
     vm.run_with(|ctx| {
         ctx.globals().set("__testEntries", entries)?;
         Ok(())
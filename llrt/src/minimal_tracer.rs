@@ -1,7 +1,6 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 use std::{
-    env,
     fmt::{self, Write},
     sync::atomic::{AtomicUsize, Ordering},
     write,
@@ -57,7 +56,7 @@ impl MinimalTracer {
     pub fn register() -> Result<(), tracing::subscriber::SetGlobalDefaultError> {
         let mut enabled = false;
         let mut filters: Vec<LogFilter> = Vec::with_capacity(10);
-        if let Ok(env_value) = env::var(environment::ENV_LLRT_LOG) {
+        if let Ok(env_value) = environment::var(environment::ENV_LLRT_LOG) {
             enabled = true;
             for filter in env_value.split(',') {
                 let mut target = Some(filter);
@@ -0,0 +1,304 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+//! `--watch`: runs a script or the tests in a child runtime and starts it
+//! again when a file it loaded, or a file matching `--watch-path`, changes.
+use std::{
+    collections::{HashMap, HashSet},
+    env, fs, io,
+    path::{Path, PathBuf},
+    process::{self, Child, Command, ExitStatus},
+    time::Duration,
+};
+
+use crate::core::{
+    bytecode::BYTECODE_EXT,
+    environment::ENV_LLRT_WATCH_REPORT,
+    libs::utils::watch::Watcher,
+    module_graph::{dependencies, expand_glob},
+    utils::io::SUPPORTED_EXTENSIONS,
+    watch_report,
+};
+use crate::{find_test_files, parse_test_args};
+
+// Changes closer together than this are handled as one
+const DEBOUNCE: Duration = Duration::from_millis(100);
+// How often the files reported by the child and its state are checked
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Arguments of a watched run.
+pub struct WatchArgs<'a> {
+    /// Runtime flags passed on to every run
+    pub flags: &'a [String],
+    /// `--watch-path` globs, relative to the current directory
+    pub paths: &'a [String],
+}
+
+/// Runs `args` (a script and its arguments) until interrupted, restarting
+/// it whenever one of its files changes.
+pub fn run_script(watch: &WatchArgs, args: &[String]) -> io::Result<()> {
+    let mut files = WatchedFiles::new(watch.paths)?;
+    let report = report_path();
+    if let Some(script) = args.first() {
+        files.add(Path::new(script));
+    }
+
+    loop {
+        let mut child = spawn(watch.flags, args, &report)?;
+        let changed = wait_for_changes(&mut files, &mut child, &report, |status| {
+            print_status(["Exited with ", &describe(status), ", waiting for changes"].concat())
+        })?;
+        stop(&mut child);
+        print_status(["Restarting after changes to ", &list(&changed)].concat());
+    }
+}
+
+/// Runs the tests selected by `args` until interrupted. After a change only
+/// the test files that load a changed file run again, or all of them when
+/// the changed file was only loaded at runtime or matched `--watch-path`.
+pub async fn run_tests(watch: &WatchArgs<'_>, args: &[String]) -> io::Result<()> {
+    let (root, filters) = parse_test_args(args).map_err(io::Error::other)?;
+    let mut files = WatchedFiles::new(watch.paths)?;
+    files.new_tests = true;
+    let report = report_path();
+
+    let mut test_files = find_tests(root, &filters).await?;
+    let mut graphs = test_graphs(&test_files)?;
+    let mut selected: Option<Vec<String>> = None;
+
+    loop {
+        for (test_file, graph) in &graphs {
+            files.add(Path::new(test_file));
+            for file in graph.iter().flatten() {
+                files.add(Path::new(file));
+            }
+        }
+
+        let run_args = match &selected {
+            None => args.to_vec(),
+            Some(selected) => ["-d", root]
+                .into_iter()
+                .map(String::from)
+                .chain(selected.iter().cloned())
+                .collect(),
+        };
+        let mut test_args = vec!["test".to_string()];
+        test_args.extend(run_args);
+        let mut child = spawn(watch.flags, &test_args, &report)?;
+        let changed = wait_for_changes(&mut files, &mut child, &report, |status| {
+            print_status(format!(
+                "Tests finished with {}, waiting for changes",
+                describe(status)
+            ))
+        })?;
+        stop(&mut child);
+
+        let found = find_tests(root, &filters).await?;
+        let mut rerun: Vec<String> = found
+            .iter()
+            .filter(|test_file| {
+                let path = Path::new(test_file.as_str());
+                !test_files.contains(test_file) || changed.iter().any(|file| file == path)
+            })
+            .cloned()
+            .collect();
+        let mut everything = false;
+        for file in &changed {
+            let file = file.to_string_lossy();
+            let dependents: Vec<&String> = graphs
+                .iter()
+                .filter(|(_, graph)| {
+                    graph
+                        .as_ref()
+                        .is_none_or(|graph| graph.iter().any(|dependency| *dependency == file))
+                })
+                .map(|(test_file, _)| test_file)
+                .collect();
+            if dependents.is_empty() && !found.iter().any(|test_file| *test_file == file) {
+                everything = true;
+            }
+            rerun.extend(dependents.into_iter().cloned());
+        }
+        rerun.retain(|test_file| found.contains(test_file));
+        rerun.sort_unstable();
+        rerun.dedup();
+
+        test_files = found;
+        graphs = test_graphs(&test_files)?;
+        selected = if everything || rerun.len() == test_files.len() {
+            print_status(["Re-running all tests after changes to ", &list(&changed)].concat());
+            None
+        } else {
+            print_status(format!(
+                "Re-running {} of {} test files after changes to {}",
+                rerun.len(),
+                test_files.len(),
+                list(&changed)
+            ));
+            Some(rerun)
+        };
+    }
+}
+
+// The files whose changes restart a run
+struct WatchedFiles<'a> {
+    watcher: Watcher,
+    files: HashSet<PathBuf>,
+    globs: &'a [String],
+    cwd: PathBuf,
+    // Test files created in a watched directory count as changes
+    new_tests: bool,
+}
+
+impl<'a> WatchedFiles<'a> {
+    fn new(globs: &'a [String]) -> io::Result<Self> {
+        let mut files = Self {
+            watcher: Watcher::new()?,
+            files: HashSet::new(),
+            globs,
+            cwd: env::current_dir()?,
+            new_tests: false,
+        };
+        files.add_globs();
+        Ok(files)
+    }
+
+    // Files are watched through their directory, which keeps working when
+    // an editor saves by replacing the file
+    fn add(&mut self, file: &Path) {
+        let file = self.cwd.join(file);
+        let Some(dir) = file.parent() else {
+            return;
+        };
+        if !self.watcher.is_watching(dir) {
+            if let Err(err) = self.watcher.watch(dir, false) {
+                print_status(format!("Cannot watch {}: {}", dir.display(), err));
+            }
+        }
+        self.files.insert(file);
+    }
+
+    fn add_globs(&mut self) {
+        for glob in self.globs {
+            for file in expand_glob(&self.cwd, glob).unwrap_or_default() {
+                self.add(&file);
+            }
+        }
+    }
+
+    // Waits up to `timeout` for changes of watched files, then collects
+    // further changes until none arrive for `DEBOUNCE`
+    fn changed(&mut self, timeout: Duration) -> io::Result<Vec<PathBuf>> {
+        let mut changed = Vec::new();
+        let mut timeout = timeout;
+        loop {
+            let events = self.watcher.wait(Some(timeout))?;
+            if !events.is_empty() {
+                // new files can match a glob now
+                self.add_globs();
+            }
+            let count = changed.len();
+            for event in events {
+                let new_test = self.new_tests && is_test_file(&event.path);
+                if new_test || self.files.contains(&event.path) {
+                    changed.push(event.path);
+                }
+            }
+            if changed.len() == count {
+                break;
+            }
+            timeout = DEBOUNCE;
+        }
+        changed.sort_unstable();
+        changed.dedup();
+        Ok(changed)
+    }
+}
+
+fn wait_for_changes(
+    files: &mut WatchedFiles,
+    child: &mut Child,
+    report: &Path,
+    on_exit: impl Fn(ExitStatus),
+) -> io::Result<Vec<PathBuf>> {
+    let mut running = true;
+    loop {
+        for file in watch_report::read(report) {
+            files.add(&file);
+        }
+        if running {
+            if let Some(status) = child.try_wait()? {
+                running = false;
+                on_exit(status);
+            }
+        }
+        let changed = files.changed(POLL_INTERVAL)?;
+        if !changed.is_empty() {
+            return Ok(changed);
+        }
+    }
+}
+
+fn spawn(flags: &[String], args: &[String], report: &Path) -> io::Result<Child> {
+    let _ = fs::remove_file(report);
+    Command::new(env::current_exe()?)
+        .args(flags)
+        .args(args)
+        .env(ENV_LLRT_WATCH_REPORT, report)
+        .spawn()
+}
+
+fn stop(child: &mut Child) {
+    let _ = child.kill();
+    let _ = child.wait();
+}
+
+fn report_path() -> PathBuf {
+    env::temp_dir().join(format!("llrt-watch-{}.txt", process::id()))
+}
+
+async fn find_tests(root: &str, filters: &[&str]) -> io::Result<Vec<String>> {
+    find_test_files(root, filters)
+        .await
+        .map_err(io::Error::other)
+}
+
+// Files loaded by every test file, `None` when they could not be determined
+fn test_graphs(test_files: &[String]) -> io::Result<HashMap<String, Option<Vec<String>>>> {
+    let paths: Vec<PathBuf> = test_files.iter().map(PathBuf::from).collect();
+    let graphs = dependencies(&paths).map_err(|err| io::Error::other(err.to_string()))?;
+    Ok(test_files.iter().cloned().zip(graphs).collect())
+}
+
+fn is_test_file(path: &Path) -> bool {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    SUPPORTED_EXTENSIONS
+        .iter()
+        .filter(|&ext| *ext != BYTECODE_EXT)
+        .any(|ext| name.ends_with(&[".test", ext].concat()))
+}
+
+fn describe(status: ExitStatus) -> String {
+    match status.code() {
+        Some(code) => format!("code {}", code),
+        None => "a signal".to_string(),
+    }
+}
+
+// Paths relative to the current directory when below it
+fn list(files: &[PathBuf]) -> String {
+    let cwd = env::current_dir().unwrap_or_default();
+    files
+        .iter()
+        .map(|file| {
+            file.strip_prefix(&cwd)
+                .unwrap_or(file)
+                .display()
+                .to_string()
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn print_status(message: impl AsRef<str>) {
+    println!("\x1b[33m[watch] {}\x1b[0m", message.as_ref());
+}
@@ -11,6 +11,8 @@ mod build_test {
     enum Platform {
         LinuxX64,
         LinuxArm64,
+        LinuxX64Musl,
+        LinuxArm64Musl,
         DarwinX64,
         DarwinArm64,
         WindowsX64,
@@ -24,6 +26,8 @@ mod build_test {
             match s {
                 "linux-x64" => Ok(Platform::LinuxX64),
                 "linux-arm64" => Ok(Platform::LinuxArm64),
+                "linux-x64-musl" => Ok(Platform::LinuxX64Musl),
+                "linux-arm64-musl" => Ok(Platform::LinuxArm64Musl),
                 "darwin-x64" => Ok(Platform::DarwinX64),
                 "darwin-arm64" => Ok(Platform::DarwinArm64),
                 "windows-x64" => Ok(Platform::WindowsX64),
@@ -113,6 +117,15 @@ mod build_test {
         assert_eq!(platforms[1], Platform::LinuxX64);
         assert_eq!(platforms[2], Platform::WindowsX64);
         
+        assert_eq!(
+            Platform::from_str("linux-arm64-musl").unwrap(),
+            Platform::LinuxArm64Musl
+        );
+        assert_eq!(
+            Platform::from_str("windows-arm64").unwrap(),
+            Platform::WindowsArm64
+        );
+
         let invalid_platform = Platform::from_str("invalid-platform");
         assert!(invalid_platform.is_err());
     }
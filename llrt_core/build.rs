@@ -17,7 +17,7 @@ use rquickjs::{CatchResultExt, CaughtError, Context, Module, Runtime, WriteOptio
 
 const BUNDLE_JS_DIR: &str = "../bundle/js";
 
-include!("src/bytecode.rs");
+include!("src/bytecode_common.rs");
 
 macro_rules! info {
     ($($tokens: tt)*) => {
@@ -41,13 +41,60 @@ fn main() -> StdResult<(), Box<dyn Error>> {
 
     let out_dir = env::var("OUT_DIR").unwrap();
 
+    let abi_hash = bytecode_abi_hash();
+    println!("cargo:rustc-env=LLRT_BYTECODE_ABI={}", abi_hash);
+
+    check_pinned_public_key()?;
+
     // #[cfg(feature = "lambda")]
     // {
     generate_sdk_client_endpoint_map(&out_dir)?;
     //}
 
-    generate_bytecode_cache(&out_dir)?;
+    generate_bytecode_cache(&out_dir, &abi_hash)?;
+
+    Ok(())
+}
+
+// Bytecode is only portable between runtimes built from the same QuickJS
+// sources, which are identified by the locked rquickjs-sys package
+fn bytecode_abi_hash() -> String {
+    const LOCK_FILE: &str = "../Cargo.lock";
+    rerun_if_changed!(LOCK_FILE);
+
+    let identity = fs::read_to_string(LOCK_FILE)
+        .ok()
+        .and_then(|lock| {
+            lock.split("[[package]]")
+                .find(|package| package.contains("name = \"rquickjs-sys\""))
+                .map(|package| package.trim().to_string())
+        })
+        .unwrap_or_else(|| env::var("CARGO_PKG_VERSION").unwrap());
+
+    // FNV-1a
+    let hash = identity.bytes().fold(0x811c_9dc5_u32, |hash, byte| {
+        (hash ^ byte as u32).wrapping_mul(0x0100_0193)
+    });
+    format!("{:08x}", hash)
+}
 
+// Executables of a runtime built with a pinned key only run payloads signed
+// with it, see src/signature.rs
+fn check_pinned_public_key() -> StdResult<(), Box<dyn Error>> {
+    const PINNED_PUBLIC_KEY: &str = "LLRT_PINNED_PUBLIC_KEY";
+    println!("cargo:rerun-if-env-changed={}", PINNED_PUBLIC_KEY);
+
+    let key = env::var(PINNED_PUBLIC_KEY).unwrap_or_default();
+    if !key.is_empty() && (key.len() != 64 || !key.bytes().all(|c| c.is_ascii_hexdigit())) {
+        return Err(format!(
+            "{} must be a hex encoded 32 byte Ed25519 public key",
+            PINNED_PUBLIC_KEY
+        )
+        .into());
+    }
+    if !key.is_empty() {
+        info!("Pinned payload signing key: {}", key);
+    }
     Ok(())
 }
 
@@ -90,7 +137,7 @@ fn generate_sdk_client_endpoint_map(out_dir: &str) -> StdResult<(), Box<dyn Erro
     Ok(())
 }
 
-fn generate_bytecode_cache(out_dir: &str) -> StdResult<(), Box<dyn Error>> {
+fn generate_bytecode_cache(out_dir: &str, abi_hash: &str) -> StdResult<(), Box<dyn Error>> {
     let resolver = (DummyResolver,);
     let loader = (DummyLoader,);
 
@@ -173,7 +220,7 @@ fn generate_bytecode_cache(out_dir: &str) -> StdResult<(), Box<dyn Error>> {
 
             fs::create_dir_all(lrt_path.parent().unwrap())?;
             if cfg!(feature = "uncompressed") {
-                let uncompressed = add_bytecode_header(bytes, None);
+                let uncompressed = add_bytecode_header(bytes, None, abi_hash);
                 fs::write(&lrt_path, uncompressed)?;
             } else {
                 fs::write(&lrt_path, bytes)?;
@@ -208,7 +255,7 @@ fn generate_bytecode_cache(out_dir: &str) -> StdResult<(), Box<dyn Error>> {
     if cfg!(feature = "uncompressed") {
         // use prebuild dict
     } else {
-        total_bytes = compress_bytecode(dictionary_path.to_string(), lrt_filenames)?;
+        total_bytes = compress_bytecode(dictionary_path.to_string(), lrt_filenames, abi_hash)?;
 
         info!(
             "\n===============================\nCompressed bytecode size: {}\n===============================",
@@ -218,8 +265,15 @@ fn generate_bytecode_cache(out_dir: &str) -> StdResult<(), Box<dyn Error>> {
     Ok(())
 }
 
-fn compress_bytecode(_: String, source_files: Vec<String>) -> io::Result<usize> {
+fn compress_bytecode(
+    _: String,
+    source_files: Vec<String>,
+    abi_hash: &str,
+) -> io::Result<usize> {
     let prebuild_dict_path = "../prebuild/compression.dict";
+    let dictionary_id = dictionary_id(&fs::read(prebuild_dict_path)?).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, "Invalid compression dictionary")
+    })?;
     
     let mut total_size = 0;
     let tmp_dir = env::temp_dir();
@@ -257,7 +311,11 @@ fn compress_bytecode(_: String, source_files: Vec<String>) -> io::Result<usize>
         }
 
         let bytes = fs::read(&filename)?;
-        let compressed = add_bytecode_header(bytes, Some(uncompressed_file_size));
+        let compression = Compression {
+            uncompressed_size: uncompressed_file_size,
+            dictionary_id,
+        };
+        let compressed = add_bytecode_header(bytes, Some(compression), abi_hash);
         fs::write(&filename, compressed)?;
 
         let compressed_file_size = PathBuf::from(&filename).metadata().unwrap().len() as usize;
@@ -0,0 +1,413 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use std::{collections::HashMap, io, result::Result as StdResult};
+
+use once_cell::sync::OnceCell;
+
+use crate::environment::RuntimeConfig;
+use crate::modules::fs::vfs;
+use crate::source_map;
+
+pub const ARCHIVE_VERSION: &str = "lxa01";
+
+// Embedded modules are addressed relative to this root so that names never
+// collide with files on the machine running the executable
+pub const VIRTUAL_ROOT: &str = "/__lexe__";
+
+const TAG_ENTRY: u8 = b'e';
+const TAG_MODULE: u8 = b'm';
+const TAG_FILE: u8 = b'f';
+const TAG_RESOLUTION: u8 = b'r';
+const TAG_ENV: u8 = b'v';
+const TAG_RUNTIME: u8 = b'c';
+const TAG_SOURCE_MAP: u8 = b's';
+const TAG_DICTIONARY: u8 = b'd';
+const TAG_FLAG_PREFIX: u8 = b'p';
+
+const RESOLVE_IMPORT: u8 = b'i';
+const RESOLVE_REQUIRE: u8 = b'r';
+
+static EMBEDDED_ARCHIVE: OnceCell<Archive> = OnceCell::new();
+
+/// Multi-module payload stored in the executable section by `llrt build`.
+///
+/// The archive is a version signature followed by tagged records
+/// (`tag: u8, length: u32, payload`). Readers skip tags they do not know.
+#[derive(Debug, Default)]
+pub struct Archive {
+    entry: String,
+    modules: HashMap<String, Vec<u8>>,
+    files: HashMap<String, Vec<u8>>,
+    resolutions: HashMap<String, String>,
+    env: HashMap<String, String>,
+    runtime: RuntimeConfig,
+    source_maps: HashMap<String, Vec<u8>>,
+    dictionary: Option<Vec<u8>>,
+    flag_prefix: Option<String>,
+}
+
+impl Archive {
+    pub fn new(entry: String) -> Self {
+        Self {
+            entry,
+            ..Default::default()
+        }
+    }
+
+    pub fn is_archive(bytes: &[u8]) -> bool {
+        bytes.starts_with(ARCHIVE_VERSION.as_bytes())
+    }
+
+    pub fn entry(&self) -> &str {
+        &self.entry
+    }
+
+    pub fn add_module(&mut self, name: String, bytecode: Vec<u8>) {
+        self.modules.insert(name, bytecode);
+    }
+
+    pub fn add_file(&mut self, path: String, bytes: Vec<u8>) {
+        self.files.insert(path, bytes);
+    }
+
+    pub fn add_resolution(&mut self, base: &str, specifier: &str, is_esm: bool, target: String) {
+        self.resolutions
+            .insert(resolution_key(base, specifier, is_esm), target);
+    }
+
+    /// Adds an environment variable default applied before the runtime starts.
+    pub fn add_env(&mut self, key: String, value: String) {
+        self.env.insert(key, value);
+    }
+
+    /// Adds a runtime setting default. Locked settings ignore the environment.
+    pub fn add_runtime_setting(&mut self, key: String, value: String, locked: bool) {
+        self.runtime.set(key, value, locked);
+    }
+
+    /// Adds the source map used to remap stack frames of module `name`.
+    pub fn add_source_map(&mut self, name: String, json: Vec<u8>) {
+        self.source_maps.insert(name, json);
+    }
+
+    /// Sets the zstd dictionary the module bytecode is compressed with.
+    pub fn set_dictionary(&mut self, dictionary: Vec<u8>) {
+        self.dictionary = Some(dictionary);
+    }
+
+    /// Sets the prefix of the arguments the executable takes as runtime flags
+    /// instead of handing them to the script.
+    pub fn set_flag_prefix(&mut self, prefix: String) {
+        self.flag_prefix = Some(prefix);
+    }
+
+    pub fn module(&self, name: &str) -> Option<&[u8]> {
+        self.modules.get(name).map(|bytes| bytes.as_slice())
+    }
+
+    pub fn file(&self, path: &str) -> Option<&[u8]> {
+        self.files.get(path).map(|bytes| bytes.as_slice())
+    }
+
+    pub fn resolve(&self, base: &str, specifier: &str, is_esm: bool) -> Option<&str> {
+        self.resolutions
+            .get(&resolution_key(base, specifier, is_esm))
+            .map(|target| target.as_str())
+    }
+
+    pub fn module_names(&self) -> impl Iterator<Item = &str> {
+        self.modules.keys().map(|name| name.as_str())
+    }
+
+    pub fn file_names(&self) -> impl Iterator<Item = &str> {
+        self.files.keys().map(|name| name.as_str())
+    }
+
+    pub fn source_map(&self, name: &str) -> Option<&[u8]> {
+        self.source_maps.get(name).map(|json| json.as_slice())
+    }
+
+    pub fn source_map_names(&self) -> impl Iterator<Item = &str> {
+        self.source_maps.keys().map(|name| name.as_str())
+    }
+
+    pub fn dictionary(&self) -> Option<&[u8]> {
+        self.dictionary.as_deref()
+    }
+
+    pub fn flag_prefix(&self) -> Option<&str> {
+        self.flag_prefix.as_deref()
+    }
+
+    pub fn runtime_config(&self) -> &RuntimeConfig {
+        &self.runtime
+    }
+
+    pub fn env(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.env
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str()))
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(
+            ARCHIVE_VERSION.len() + self.modules.values().map(|m| m.len() + 64).sum::<usize>(),
+        );
+        buf.extend_from_slice(ARCHIVE_VERSION.as_bytes());
+
+        write_record(&mut buf, TAG_ENTRY, &[self.entry.as_bytes()]);
+
+        let mut modules: Vec<_> = self.modules.iter().collect();
+        modules.sort_unstable();
+        for (name, bytecode) in modules {
+            write_record(&mut buf, TAG_MODULE, &[name.as_bytes(), bytecode]);
+        }
+
+        let mut files: Vec<_> = self.files.iter().collect();
+        files.sort_unstable();
+        for (path, bytes) in files {
+            write_record(&mut buf, TAG_FILE, &[path.as_bytes(), bytes]);
+        }
+
+        let mut resolutions: Vec<_> = self.resolutions.iter().collect();
+        resolutions.sort_unstable();
+        for (key, target) in resolutions {
+            write_record(
+                &mut buf,
+                TAG_RESOLUTION,
+                &[key.as_bytes(), target.as_bytes()],
+            );
+        }
+
+        let mut env: Vec<_> = self.env.iter().collect();
+        env.sort_unstable();
+        for (key, value) in env {
+            write_record(&mut buf, TAG_ENV, &[key.as_bytes(), value.as_bytes()]);
+        }
+
+        for (key, value, locked) in self.runtime.iter() {
+            write_record(
+                &mut buf,
+                TAG_RUNTIME,
+                &[key.as_bytes(), &[locked as u8], value.as_bytes()],
+            );
+        }
+
+        let mut source_maps: Vec<_> = self.source_maps.iter().collect();
+        source_maps.sort_unstable();
+        for (name, json) in source_maps {
+            write_record(&mut buf, TAG_SOURCE_MAP, &[name.as_bytes(), json]);
+        }
+
+        if let Some(dictionary) = &self.dictionary {
+            write_record(&mut buf, TAG_DICTIONARY, &[dictionary]);
+        }
+
+        if let Some(prefix) = &self.flag_prefix {
+            write_record(&mut buf, TAG_FLAG_PREFIX, &[prefix.as_bytes()]);
+        }
+
+        buf
+    }
+
+    pub fn decode(bytes: &[u8]) -> StdResult<Self, io::Error> {
+        let mut input = bytes
+            .strip_prefix(ARCHIVE_VERSION.as_bytes())
+            .ok_or_else(|| invalid_data("Invalid archive version"))?;
+
+        let mut archive = Archive::default();
+
+        while let Some((&tag, rest)) = input.split_first() {
+            let (payload, rest) = read_chunk(rest)?;
+            input = rest;
+
+            match tag {
+                TAG_ENTRY => {
+                    archive.entry = read_string(payload)?;
+                },
+                TAG_MODULE => {
+                    let (name, bytecode) = read_chunk(payload)?;
+                    archive
+                        .modules
+                        .insert(read_string(name)?, bytecode.to_vec());
+                },
+                TAG_FILE => {
+                    let (path, bytes) = read_chunk(payload)?;
+                    archive.files.insert(read_string(path)?, bytes.to_vec());
+                },
+                TAG_RESOLUTION => {
+                    let (key, target) = read_chunk(payload)?;
+                    archive
+                        .resolutions
+                        .insert(read_string(key)?, read_string(target)?);
+                },
+                TAG_ENV => {
+                    let (key, value) = read_chunk(payload)?;
+                    archive.env.insert(read_string(key)?, read_string(value)?);
+                },
+                TAG_RUNTIME => {
+                    let (key, rest) = read_chunk(payload)?;
+                    let (locked, value) = read_chunk(rest)?;
+                    archive
+                        .runtime
+                        .set(read_string(key)?, read_string(value)?, locked == [1]);
+                },
+                TAG_SOURCE_MAP => {
+                    let (name, json) = read_chunk(payload)?;
+                    archive
+                        .source_maps
+                        .insert(read_string(name)?, json.to_vec());
+                },
+                TAG_DICTIONARY => {
+                    archive.dictionary = Some(payload.to_vec());
+                },
+                TAG_FLAG_PREFIX => {
+                    archive.flag_prefix = Some(read_string(payload)?);
+                },
+                _ => {},
+            }
+        }
+
+        if archive.entry.is_empty() {
+            return Err(invalid_data("Archive has no entry module"));
+        }
+
+        Ok(archive)
+    }
+}
+
+/// Makes `archive` visible to the module resolver and loader, mounts its
+/// files for the fs module and registers its source maps. Only the first
+/// installed archive is kept.
+pub fn install(archive: Archive) {
+    if EMBEDDED_ARCHIVE.set(archive).is_err() {
+        return;
+    }
+    let archive = EMBEDDED_ARCHIVE.get().unwrap();
+    for (name, json) in &archive.source_maps {
+        source_map::register(name.clone(), json.clone());
+    }
+    vfs::mount(
+        VIRTUAL_ROOT,
+        archive
+            .files
+            .iter()
+            .map(|(path, bytes)| (path.as_str(), bytes.as_slice())),
+    );
+}
+
+pub fn embedded() -> Option<&'static Archive> {
+    EMBEDDED_ARCHIVE.get()
+}
+
+fn resolution_key(base: &str, specifier: &str, is_esm: bool) -> String {
+    let kind = if is_esm {
+        RESOLVE_IMPORT
+    } else {
+        RESOLVE_REQUIRE
+    };
+    let mut key = String::with_capacity(base.len() + specifier.len() + 3);
+    key.push_str(base);
+    key.push('\0');
+    key.push_str(specifier);
+    key.push('\0');
+    key.push(kind as char);
+    key
+}
+
+// Records with more than one part store every part but the last with its
+// own length prefix so they can be split again on read
+fn write_record(buf: &mut Vec<u8>, tag: u8, parts: &[&[u8]]) {
+    let (last, init) = parts.split_last().unwrap();
+    let len = init.iter().map(|part| part.len() + 4).sum::<usize>() + last.len();
+
+    buf.push(tag);
+    buf.extend_from_slice(&(len as u32).to_le_bytes());
+    for part in init {
+        buf.extend_from_slice(&(part.len() as u32).to_le_bytes());
+        buf.extend_from_slice(part);
+    }
+    buf.extend_from_slice(last);
+}
+
+fn read_chunk(input: &[u8]) -> StdResult<(&[u8], &[u8]), io::Error> {
+    let len = input
+        .get(..4)
+        .ok_or_else(|| invalid_data("Truncated archive record"))?;
+    let len = u32::from_le_bytes(len.try_into().unwrap()) as usize;
+    let rest = &input[4..];
+    if rest.len() < len {
+        return Err(invalid_data("Truncated archive record"));
+    }
+    Ok(rest.split_at(len))
+}
+
+fn read_string(bytes: &[u8]) -> StdResult<String, io::Error> {
+    String::from_utf8(bytes.to_vec()).map_err(|_| invalid_data("Invalid archive string"))
+}
+
+fn invalid_data(msg: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_archive_roundtrip() {
+        let mut archive = Archive::new("/__lexe__/index.js".into());
+        archive.add_module("/__lexe__/index.js".into(), vec![1, 2, 3]);
+        archive.add_module("__cjs:/__lexe__/lib.cjs".into(), vec![4, 5]);
+        archive.add_file("/__lexe__/data.json".into(), b"{}".to_vec());
+        archive.add_resolution(
+            "/__lexe__/index.js",
+            "./lib.cjs",
+            true,
+            "/__lexe__/lib.cjs".into(),
+        );
+        archive.add_env("LLRT_LOG".into(), "info".into());
+        archive.add_runtime_setting("LLRT_NET_ALLOW".into(), "example.com".into(), true);
+        archive.add_source_map("/__lexe__/index.js".into(), b"{}".to_vec());
+        archive.set_dictionary(vec![0x37, 0xa4, 0x30, 0xec, 1, 0, 0, 0]);
+        archive.set_flag_prefix("--app-".into());
+
+        let decoded = Archive::decode(&archive.encode()).unwrap();
+
+        assert_eq!(decoded.entry(), "/__lexe__/index.js");
+        assert_eq!(decoded.module("/__lexe__/index.js"), Some(&[1, 2, 3][..]));
+        assert_eq!(decoded.module("__cjs:/__lexe__/lib.cjs"), Some(&[4, 5][..]));
+        assert_eq!(decoded.file("/__lexe__/data.json"), Some(&b"{}"[..]));
+        assert_eq!(
+            decoded.resolve("/__lexe__/index.js", "./lib.cjs", true),
+            Some("/__lexe__/lib.cjs")
+        );
+        assert_eq!(
+            decoded.resolve("/__lexe__/index.js", "./lib.cjs", false),
+            None
+        );
+        assert_eq!(
+            decoded.env().collect::<Vec<_>>(),
+            vec![("LLRT_LOG", "info")]
+        );
+        assert_eq!(
+            decoded.runtime_config().iter().collect::<Vec<_>>(),
+            vec![("LLRT_NET_ALLOW", "example.com", true)]
+        );
+        assert_eq!(decoded.source_map("/__lexe__/index.js"), Some(&b"{}"[..]));
+        assert_eq!(
+            decoded.dictionary(),
+            Some(&[0x37, 0xa4, 0x30, 0xec, 1, 0, 0, 0][..])
+        );
+        assert_eq!(decoded.flag_prefix(), Some("--app-"));
+    }
+
+    #[test]
+    fn test_archive_rejects_truncated_input() {
+        let archive = Archive::new("/__lexe__/index.js".into());
+        let bytes = archive.encode();
+
+        assert!(Archive::decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(Archive::decode(b"lrt01c").is_err());
+    }
+}
@@ -1,32 +1,184 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 
-pub const BYTECODE_VERSION: &str = "lrt01";
-pub const BYTECODE_COMPRESSED: u8 = b'c';
-pub const BYTECODE_UNCOMPRESSED: u8 = b'u';
-
-macro_rules! define_extension {
-    ($base:ident, $file:ident, $ext:expr) => {
-        #[allow(dead_code)]
-        pub const $base: &str = $ext;
-        #[allow(dead_code)]
-        pub const $file: &str = concat!(".", $ext);
+include!("bytecode_common.rs");
+
+// Written before the header recorded the runtime that produced the bytecode
+pub const BYTECODE_VERSION_V1: &str = "lrt01";
+// Written before the header recorded the compression dictionary
+pub const BYTECODE_VERSION_V2: &str = "lrt02";
+pub const BYTECODE_FILE_EXT: &str = ".lrt";
+pub const SIGNATURE_LENGTH: usize = BYTECODE_VERSION.len() + 1;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct BytecodeHeader<'a> {
+    pub version: &'a str,
+    /// Not recorded by `lrt01` bytecode
+    pub runtime_version: Option<&'a str>,
+    /// Not recorded by `lrt01` bytecode
+    pub abi_hash: Option<&'a str>,
+    /// Set for compressed bytecode
+    pub uncompressed_size: Option<u32>,
+    /// Set for compressed bytecode, not recorded before `lrt03`
+    pub dictionary_id: Option<u32>,
+}
+
+/// Splits a bytecode file into its header and the (possibly compressed)
+/// bytecode. Headers of unknown versions only carry the version.
+pub fn read_bytecode_header(input: &[u8]) -> Option<(BytecodeHeader<'_>, &[u8])> {
+    let version = std::str::from_utf8(input.get(..BYTECODE_VERSION.len())?).ok()?;
+    if !version.starts_with(BYTECODE_EXT) {
+        return None;
+    }
+    let compressed = match *input.get(BYTECODE_VERSION.len())? {
+        BYTECODE_COMPRESSED => true,
+        BYTECODE_UNCOMPRESSED => false,
+        _ => return None,
+    };
+    let mut rest = &input[SIGNATURE_LENGTH..];
+    let mut header = BytecodeHeader {
+        version,
+        runtime_version: None,
+        abi_hash: None,
+        uncompressed_size: None,
+        dictionary_id: None,
     };
+
+    if version == BYTECODE_VERSION || version == BYTECODE_VERSION_V2 {
+        for field in [&mut header.runtime_version, &mut header.abi_hash] {
+            let (&len, tail) = rest.split_first()?;
+            let value = tail.get(..len as usize)?;
+            *field = Some(std::str::from_utf8(value).ok()?);
+            rest = &tail[len as usize..];
+        }
+    } else if version != BYTECODE_VERSION_V1 {
+        return Some((header, rest));
+    }
+
+    if compressed {
+        let size = rest.get(..4)?;
+        header.uncompressed_size = Some(u32::from_le_bytes(size.try_into().ok()?));
+        rest = &rest[4..];
+        if version == BYTECODE_VERSION {
+            let id = rest.get(..4)?;
+            header.dictionary_id = Some(u32::from_le_bytes(id.try_into().ok()?));
+            rest = &rest[4..];
+        }
+    }
+
+    Some((header, rest))
 }
 
-define_extension!(BYTECODE_EXT, BYTECODE_FILE_EXT, "lrt");
-pub const SIGNATURE_LENGTH: usize = BYTECODE_VERSION.len() + 1;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-#[allow(dead_code)]
-pub fn add_bytecode_header(bytes: Vec<u8>, file_size: Option<u32>) -> Vec<u8> {
-    let mut compressed_bytes = Vec::with_capacity(bytes.len());
-    compressed_bytes.extend_from_slice(BYTECODE_VERSION.as_bytes());
-    if let Some(file_size) = file_size {
-        compressed_bytes.push(BYTECODE_COMPRESSED);
-        compressed_bytes.extend_from_slice(&file_size.to_le_bytes());
-    } else {
-        compressed_bytes.push(BYTECODE_UNCOMPRESSED)
-    }
-    compressed_bytes.extend_from_slice(&bytes);
-    compressed_bytes
+    // A legacy header: signature, compression flag and the given fields
+    fn legacy_header(version: &str, compressed: bool, fields: &[&[u8]]) -> Vec<u8> {
+        let mut bytes = version.as_bytes().to_vec();
+        bytes.push(if compressed {
+            BYTECODE_COMPRESSED
+        } else {
+            BYTECODE_UNCOMPRESSED
+        });
+        for field in fields {
+            bytes.extend_from_slice(field);
+        }
+        bytes.extend_from_slice(b"code");
+        bytes
+    }
+
+    #[test]
+    fn test_header_roundtrip() {
+        let bytes = add_bytecode_header(b"code".to_vec(), None, "abc123");
+        let (header, rest) = read_bytecode_header(&bytes).unwrap();
+        assert_eq!(
+            header,
+            BytecodeHeader {
+                version: BYTECODE_VERSION,
+                runtime_version: Some(BYTECODE_RUNTIME_VERSION),
+                abi_hash: Some("abc123"),
+                uncompressed_size: None,
+                dictionary_id: None,
+            }
+        );
+        assert_eq!(rest, b"code");
+
+        let compression = Compression {
+            uncompressed_size: 1234,
+            dictionary_id: 42,
+        };
+        let bytes = add_bytecode_header(b"code".to_vec(), Some(compression), "abc123");
+        let (header, rest) = read_bytecode_header(&bytes).unwrap();
+        assert_eq!(header.version, BYTECODE_VERSION);
+        assert_eq!(header.runtime_version, Some(BYTECODE_RUNTIME_VERSION));
+        assert_eq!(header.abi_hash, Some("abc123"));
+        assert_eq!(header.uncompressed_size, Some(1234));
+        assert_eq!(header.dictionary_id, Some(42));
+        assert_eq!(rest, b"code");
+    }
+
+    #[test]
+    fn test_legacy_headers() {
+        let bytes = legacy_header(BYTECODE_VERSION_V1, true, &[&1234u32.to_le_bytes()]);
+        let (header, rest) = read_bytecode_header(&bytes).unwrap();
+        assert_eq!(
+            header,
+            BytecodeHeader {
+                version: BYTECODE_VERSION_V1,
+                runtime_version: None,
+                abi_hash: None,
+                uncompressed_size: Some(1234),
+                dictionary_id: None,
+            }
+        );
+        assert_eq!(rest, b"code");
+
+        let bytes = legacy_header(
+            BYTECODE_VERSION_V2,
+            true,
+            &[b"\x050.5.1", b"\x03abc", &1234u32.to_le_bytes()],
+        );
+        let (header, rest) = read_bytecode_header(&bytes).unwrap();
+        assert_eq!(
+            header,
+            BytecodeHeader {
+                version: BYTECODE_VERSION_V2,
+                runtime_version: Some("0.5.1"),
+                abi_hash: Some("abc"),
+                uncompressed_size: Some(1234),
+                dictionary_id: None,
+            }
+        );
+        assert_eq!(rest, b"code");
+
+        let bytes = legacy_header(BYTECODE_VERSION_V2, false, &[b"\x050.5.1", b"\x03abc"]);
+        let (header, rest) = read_bytecode_header(&bytes).unwrap();
+        assert_eq!(header.uncompressed_size, None);
+        assert_eq!(rest, b"code");
+    }
+
+    #[test]
+    fn test_invalid_headers() {
+        // unknown versions only carry the version
+        let bytes = legacy_header("lrt99", false, &[]);
+        let (header, rest) = read_bytecode_header(&bytes).unwrap();
+        assert_eq!(header.version, "lrt99");
+        assert_eq!(header.runtime_version, None);
+        assert_eq!(rest, b"code");
+
+        assert_eq!(read_bytecode_header(b"abcdeu"), None);
+        assert_eq!(read_bytecode_header(b"lrt03x"), None);
+        assert_eq!(read_bytecode_header(b"lrt03u\x05abc"), None);
+        assert_eq!(read_bytecode_header(b"lrt"), None);
+    }
+
+    #[test]
+    fn test_dictionary_id() {
+        let mut dictionary = ZSTD_DICTIONARY_MAGIC.to_vec();
+        dictionary.extend_from_slice(&42u32.to_le_bytes());
+        dictionary.extend_from_slice(b"content");
+        assert_eq!(dictionary_id(&dictionary), Some(42));
+        assert_eq!(dictionary_id(b"raw content dictionary"), None);
+    }
 }
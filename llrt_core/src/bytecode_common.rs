@@ -0,0 +1,54 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+// Shared with build.rs and bytecode.rs
+
+pub const BYTECODE_VERSION: &str = "lrt03";
+pub const BYTECODE_COMPRESSED: u8 = b'c';
+pub const BYTECODE_UNCOMPRESSED: u8 = b'u';
+pub const BYTECODE_RUNTIME_VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const BYTECODE_EXT: &str = "lrt";
+
+// zstd dictionaries start with this magic number followed by their ID
+const ZSTD_DICTIONARY_MAGIC: [u8; 4] = [0x37, 0xa4, 0x30, 0xec];
+
+/// How compressed bytecode was compressed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compression {
+    pub uncompressed_size: u32,
+    /// ID of the zstd dictionary, see [`dictionary_id`]
+    pub dictionary_id: u32,
+}
+
+/// The ID recorded in a zstd dictionary, `None` when `dictionary` is not one.
+pub fn dictionary_id(dictionary: &[u8]) -> Option<u32> {
+    let id = dictionary.strip_prefix(&ZSTD_DICTIONARY_MAGIC)?.get(..4)?;
+    Some(u32::from_le_bytes(id.try_into().ok()?))
+}
+
+/// Prepends the header: version signature, compression flag, the runtime
+/// version and QuickJS ABI hash of the compiling runtime (each prefixed with
+/// a length byte) and, for compressed bytecode, the uncompressed size and
+/// the dictionary ID.
+pub fn add_bytecode_header(
+    bytes: Vec<u8>,
+    compression: Option<Compression>,
+    abi_hash: &str,
+) -> Vec<u8> {
+    let mut compressed_bytes = Vec::with_capacity(bytes.len() + 36);
+    compressed_bytes.extend_from_slice(BYTECODE_VERSION.as_bytes());
+    if compression.is_some() {
+        compressed_bytes.push(BYTECODE_COMPRESSED);
+    } else {
+        compressed_bytes.push(BYTECODE_UNCOMPRESSED)
+    }
+    for field in [BYTECODE_RUNTIME_VERSION, abi_hash] {
+        compressed_bytes.push(field.len() as u8);
+        compressed_bytes.extend_from_slice(field.as_bytes());
+    }
+    if let Some(compression) = compression {
+        compressed_bytes.extend_from_slice(&compression.uncompressed_size.to_le_bytes());
+        compressed_bytes.extend_from_slice(&compression.dictionary_id.to_le_bytes());
+    }
+    compressed_bytes.extend_from_slice(&bytes);
+    compressed_bytes
+}
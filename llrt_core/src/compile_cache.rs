@@ -0,0 +1,184 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+//! Opt-in cache of compiled modules for `llrt file.js` runs, enabled with
+//! `LLRT_COMPILE_CACHE=<dir>` or `--compile-cache[=<dir>]`.
+//!
+//! Each module is stored as `<key>.lrt`, where the key hashes the runtime
+//! version, the QuickJS ABI, the module name and the source. A changed
+//! source or another runtime build therefore never finds an old entry, and
+//! entries that fail to load are compiled again and replaced.
+use std::{
+    env, fs, io,
+    path::{Path, PathBuf},
+    process,
+};
+
+use once_cell::sync::OnceCell;
+use ring::digest::{Context as DigestContext, SHA256};
+use rquickjs::{Ctx, Module, WriteOptions};
+use tracing::trace;
+
+use crate::bytecode::{add_bytecode_header, BYTECODE_FILE_EXT, BYTECODE_RUNTIME_VERSION};
+use crate::environment;
+use crate::libs::encoding::bytes_to_hex_string;
+use crate::modules::require::loader::CustomLoader;
+use crate::vm::bytecode_abi_hash;
+
+static CACHE_DIR: OnceCell<Option<PathBuf>> = OnceCell::new();
+
+/// Caches modules in `dir`, unless a cache directory is already in use.
+pub fn enable(dir: PathBuf) {
+    let _ = CACHE_DIR.set(Some(dir));
+}
+
+/// The directory modules are cached in, `None` when the cache is disabled.
+pub fn dir() -> Option<&'static Path> {
+    CACHE_DIR
+        .get_or_init(|| {
+            environment::var(environment::ENV_LLRT_COMPILE_CACHE)
+                .ok()
+                .filter(|dir| !dir.is_empty())
+                .map(PathBuf::from)
+        })
+        .as_deref()
+}
+
+/// `$XDG_CACHE_HOME/llrt`, or `~/.cache/llrt`.
+pub fn default_dir() -> PathBuf {
+    env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| home::home_dir().map(|home| home.join(".cache")))
+        .unwrap_or_else(env::temp_dir)
+        .join("llrt")
+}
+
+/// Removes the cached modules from `dir`, other files are kept. Returns the
+/// number of removed modules.
+pub fn clear(dir: &Path) -> io::Result<usize> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(0),
+        Err(err) => return Err(err),
+    };
+    let mut removed = 0;
+    for entry in entries {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if is_cache_file_name(&name) {
+            fs::remove_file(&path)?;
+            removed += 1;
+        } else if name
+            .strip_suffix(".tmp")
+            .and_then(|name| name.rsplit_once('.'))
+            .is_some_and(|(name, _)| is_cache_file_name(name))
+        {
+            // left behind by a process that was killed while writing
+            fs::remove_file(&path)?;
+        }
+    }
+    Ok(removed)
+}
+
+/// Loads the cached compilation of the module `name` with `source`.
+pub fn load<'js>(ctx: &Ctx<'js>, name: &str, source: &[u8]) -> Option<Module<'js>> {
+    let file = dir()?.join(cache_file_name(name, source));
+    let bytes = fs::read(&file).ok()?;
+    match CustomLoader::load_bytecode_module(ctx.clone(), &bytes) {
+        Ok(module) => {
+            trace!("Loading cached module: {} ({})", name, file.display());
+            Some(module)
+        },
+        Err(err) => {
+            trace!("Ignoring cached module {}: {}", file.display(), err);
+            None
+        },
+    }
+}
+
+/// Stores the compilation of the module `name` with `source`. Failing to
+/// write the cache is not an error, the module just is compiled again on
+/// the next run.
+pub fn store(name: &str, source: &[u8], module: &Module<'_>) {
+    let Some(dir) = dir() else {
+        return;
+    };
+    let file = dir.join(cache_file_name(name, source));
+    if let Err(err) = write(dir, &file, module) {
+        trace!("Failed to cache module {}: {}", name, err);
+    }
+}
+
+// Written to a temporary file first, so a run that reads the cache at the
+// same time never sees a partial module
+fn write(dir: &Path, file: &Path, module: &Module<'_>) -> io::Result<()> {
+    let bytes = module
+        .write(WriteOptions::default())
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err.to_string()))?;
+    let bytes = add_bytecode_header(bytes, None, bytecode_abi_hash());
+
+    fs::create_dir_all(dir)?;
+    let mut temp_file = file.as_os_str().to_owned();
+    temp_file.push(format!(".{}.tmp", process::id()));
+    fs::write(&temp_file, bytes)?;
+    fs::rename(&temp_file, file).inspect_err(|_| {
+        let _ = fs::remove_file(&temp_file);
+    })
+}
+
+fn cache_file_name(name: &str, source: &[u8]) -> String {
+    let mut digest = DigestContext::new(&SHA256);
+    for field in [
+        BYTECODE_RUNTIME_VERSION.as_bytes(),
+        bytecode_abi_hash().as_bytes(),
+        name.as_bytes(),
+    ] {
+        digest.update(&(field.len() as u64).to_le_bytes());
+        digest.update(field);
+    }
+    digest.update(source);
+    bytes_to_hex_string(digest.finish().as_ref()) + BYTECODE_FILE_EXT
+}
+
+fn is_cache_file_name(name: &str) -> bool {
+    name.strip_suffix(BYTECODE_FILE_EXT)
+        .is_some_and(|key| key.len() == 64 && key.bytes().all(|byte| byte.is_ascii_hexdigit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{cache_file_name, clear, is_cache_file_name};
+
+    #[test]
+    fn test_cache_file_name() {
+        let name = cache_file_name("/app/a.js", b"export default 1");
+        assert!(is_cache_file_name(&name));
+        assert_eq!(name, cache_file_name("/app/a.js", b"export default 1"));
+        assert_ne!(name, cache_file_name("/app/a.js", b"export default 2"));
+        assert_ne!(name, cache_file_name("/app/b.js", b"export default 1"));
+        assert!(!is_cache_file_name("main.lrt"));
+    }
+
+    #[test]
+    fn test_clear() {
+        let dir = std::env::temp_dir().join("llrt-test-compile-cache");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let cached = cache_file_name("/app/a.js", b"");
+        fs::write(dir.join(&cached), b"").unwrap();
+        fs::write(dir.join([cached.as_str(), ".42.tmp"].concat()), b"").unwrap();
+        fs::write(dir.join("notes.txt"), b"").unwrap();
+
+        assert_eq!(clear(&dir).unwrap(), 1);
+        let left: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(left, ["notes.txt"]);
+        assert_eq!(clear(&dir.join("missing")).unwrap(), 0);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
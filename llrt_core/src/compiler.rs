@@ -1,29 +1,156 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
-use std::{fs, io, path::Path};
+use std::{
+    fmt, fs, io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
 
-use rquickjs::{CatchResultExt, Context, Module, Runtime, WriteOptions};
+use rquickjs::{CatchResultExt, Context, Ctx, Module, Runtime, WriteOptions};
 use tracing::trace;
 use zstd::bulk::Compressor;
 
-use crate::bytecode::add_bytecode_header;
+use crate::archive::Archive;
+use crate::bytecode::{
+    add_bytecode_header, dictionary_id, Compression, BYTECODE_EXT, BYTECODE_FILE_EXT,
+};
 use crate::compiler_common::{human_file_size, DummyLoader, DummyResolver};
+use crate::import_attributes::rewrite_import_attributes_or_throw;
 use crate::libs::utils::result::ResultExt;
-use crate::vm::{Vm, COMPRESSION_DICT};
+use crate::module_builder::ModuleBuilder;
+use crate::module_graph::{expand_glob, ModuleFormat, ModuleGraph};
+use crate::modules::{
+    path::name_extname,
+    require::{loader::CustomLoader, CJS_IMPORT_PREFIX},
+};
+use crate::source_map::{self, SOURCE_MAP_EXT};
+use crate::typescript::{is_typescript, strip_types_or_throw};
+use crate::utils::io::JS_EXTENSIONS;
+use crate::vm::{bytecode_abi_hash, Vm, COMPRESSION_DICT};
 
-fn compress_module(bytes: &[u8]) -> io::Result<Vec<u8>> {
-    let mut compressor = Compressor::with_dictionary(22, COMPRESSION_DICT)?;
+/// Size of dictionaries trained by [`train_dictionary`] unless another is
+/// requested, the default of the zstd CLI.
+pub const DEFAULT_DICTIONARY_SIZE: usize = 110 * 1024;
+
+fn compress_module(bytes: &[u8], dictionary: &[u8]) -> io::Result<Vec<u8>> {
+    let dictionary_id = dictionary_id(dictionary)
+        .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Invalid zstd dictionary"))?;
+    let mut compressor = Compressor::with_dictionary(22, dictionary)?;
     let compressed_bytes = compressor.compress(bytes)?;
-    let uncompressed_len = bytes.len() as u32;
+    let compression = Compression {
+        uncompressed_size: bytes.len() as u32,
+        dictionary_id,
+    };
 
-    let compressed = add_bytecode_header(compressed_bytes, Some(uncompressed_len));
+    let compressed = add_bytecode_header(compressed_bytes, Some(compression), bytecode_abi_hash());
     Ok(compressed)
 }
 
+/// What to remove from compiled bytecode.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Strip {
+    #[default]
+    None,
+    /// Function source text, `Function.prototype.toString` no longer shows it
+    Source,
+    /// File names and line numbers, stack frames no longer show positions
+    Debug,
+    All,
+}
+
+impl Strip {
+    fn write_options(self) -> WriteOptions {
+        WriteOptions {
+            strip_source: matches!(self, Strip::Source | Strip::All),
+            strip_debug: self.strips_debug(),
+            ..WriteOptions::default()
+        }
+    }
+
+    pub fn strips_debug(self) -> bool {
+        matches!(self, Strip::Debug | Strip::All)
+    }
+}
+
+impl FromStr for Strip {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Strip::None),
+            "source" => Ok(Strip::Source),
+            "debug" => Ok(Strip::Debug),
+            "all" => Ok(Strip::All),
+            _ => Err(format!(
+                "Unknown strip mode: {}, expected source, debug or all",
+                s
+            )),
+        }
+    }
+}
+
+impl fmt::Display for Strip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Strip::None => "none",
+            Strip::Source => "source",
+            Strip::Debug => "debug",
+            Strip::All => "all",
+        })
+    }
+}
+
+// Writes `module` with the options of `strip`. Returns the bytecode and the
+// size it would have without stripping.
+fn write_module(module: &Module<'_>, strip: Strip) -> rquickjs::Result<(Vec<u8>, usize)> {
+    let bytes = module.write(strip.write_options())?;
+    let unstripped_len = match strip {
+        Strip::None => bytes.len(),
+        _ => module.write(WriteOptions::default())?.len(),
+    };
+    Ok((bytes, unstripped_len))
+}
+
+fn trace_sizes(
+    js_bytes: usize,
+    total_bytes: usize,
+    unstripped_bytes: usize,
+    compressed: usize,
+    strip: Strip,
+) {
+    trace!("JS size: {}", human_file_size(js_bytes));
+    trace!("Bytecode size: {}", human_file_size(total_bytes));
+    if strip != Strip::None {
+        trace!(
+            "Stripped {}: {} -> {} (saved {})",
+            strip,
+            human_file_size(unstripped_bytes),
+            human_file_size(total_bytes),
+            human_file_size(unstripped_bytes.saturating_sub(total_bytes))
+        );
+    }
+    trace!("Compressed bytecode size: {}", human_file_size(compressed));
+}
+
+/// Compiles `input_filename` to bytecode. When the input has a source map,
+/// either given as `source_map` or referenced by a `sourceMappingURL`
+/// comment, it is written next to the output as `<output>.map`, unless
+/// `strip` removes the positions it maps. Giving `source_map` together with
+/// such a `strip` is an error.
 pub async fn compile_file(
     input_filename: &Path,
     output_filename: &Path,
+    source_map: Option<&Path>,
+    strip: Strip,
 ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    if source_map.is_some() && strip.strips_debug() {
+        return Err(format!(
+            "--source-map can't be used with --strip={}, which removes the positions it maps",
+            strip
+        )
+        .into());
+    }
+
     let resolver = (DummyResolver,);
     let loader = (DummyLoader,);
 
@@ -32,6 +159,7 @@ pub async fn compile_file(
     let ctx = Context::full(&rt)?;
 
     let mut total_bytes: usize = 0;
+    let mut unstripped_bytes: usize = 0;
     let mut compressed_bytes: usize = 0;
     let mut js_bytes: usize = 0;
 
@@ -43,40 +171,305 @@ pub async fn compile_file(
             )?;
             js_bytes = source.len();
 
-            let module_name = input_filename
-                .with_extension("")
-                .to_string_lossy()
-                .to_string();
+            let module_name = input_filename.to_string_lossy().to_string();
+
+            // without debug info there are no positions to map
+            let source_map = match source_map {
+                Some(path) => Some(source_map::load_file(path)),
+                None if strip.strips_debug() => None,
+                None => source_map::load_for_source(source.as_bytes(), input_filename).transpose(),
+            }
+            .transpose()
+            .or_throw(&ctx)?;
+
+            let mut source = if is_typescript(&module_name) {
+                strip_types_or_throw(&ctx, source.as_bytes(), &module_name)?
+            } else {
+                source.into_bytes()
+            };
+            if let Some(rewritten) =
+                rewrite_import_attributes_or_throw(&ctx, &source, &module_name)?
+            {
+                source = rewritten;
+            }
 
             trace!("Compiling module: {}", module_name);
 
-            let module = Module::declare(ctx.clone(), module_name, source)?;
-            let bytes = module.write(WriteOptions::default())?;
-            let compressed = compress_module(&bytes)?;
+            let module = Module::declare(ctx.clone(), module_name.as_str(), source)?;
+            let (bytes, unstripped_len) = write_module(&module, strip)?;
+            let compressed = compress_module(&bytes, COMPRESSION_DICT)?;
 
             total_bytes += bytes.len();
+            unstripped_bytes += unstripped_len;
             compressed_bytes += compressed.len();
 
             fs::write(output_filename, &compressed)?;
 
+            if let Some(mut source_map) = source_map {
+                source_map.relativize_sources(Path::new(""));
+                let mut map_filename = output_filename.as_os_str().to_owned();
+                map_filename.push(SOURCE_MAP_EXT);
+                fs::write(map_filename, source_map.to_json(&module_name))?;
+            }
+
             Ok(())
         })()
         .catch(&ctx)
         .unwrap_or_else(|err| Vm::print_error_and_exit(&ctx, err))
     });
 
-    trace!("JS size: {}", human_file_size(js_bytes));
-    trace!("Bytecode size: {}", human_file_size(total_bytes));
-    trace!(
-        "Compressed bytecode size: {}",
-        human_file_size(compressed_bytes)
+    trace_sizes(
+        js_bytes,
+        total_bytes,
+        unstripped_bytes,
+        compressed_bytes,
+        strip,
     );
 
     Ok(())
 }
 
+/// Result of [`compile_directory`].
+#[derive(Debug, Default)]
+pub struct DirectorySummary {
+    pub modules: usize,
+    /// `.json` files copied as they are
+    pub copied: usize,
+    pub js_bytes: usize,
+    pub bytecode_bytes: usize,
+    pub compressed_bytes: usize,
+    /// Files that failed to compile, with the error
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+impl fmt::Display for DirectorySummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(
+            f,
+            "Compiled {} modules, copied {} JSON files",
+            self.modules, self.copied
+        )?;
+        writeln!(f, "JS size: {}", human_file_size(self.js_bytes))?;
+        writeln!(f, "Bytecode size: {}", human_file_size(self.bytecode_bytes))?;
+        write!(
+            f,
+            "Compressed bytecode size: {}",
+            human_file_size(self.compressed_bytes)
+        )
+    }
+}
+
+/// Compiles every JS and TypeScript file below `input_dir` to a `.lrt` file
+/// at the same place below `output_dir`, and copies `.json` files so package
+/// scopes and JSON imports keep working. Modules are named after their
+/// output path, imports of `./a.js` load the compiled `./a.lrt`. Hidden
+/// directories and `output_dir` are skipped. A file that fails to compile
+/// does not stop the others, its error is returned in the summary.
+pub async fn compile_directory(
+    input_dir: &Path,
+    output_dir: &Path,
+    strip: Strip,
+) -> Result<DirectorySummary, Box<dyn std::error::Error + Send + Sync>> {
+    let input_dir = std::path::absolute(input_dir)?;
+    let output_dir = std::path::absolute(output_dir)?;
+    let mut files = Vec::new();
+    collect_files(&input_dir, &output_dir, &mut files)?;
+
+    let resolver = (DummyResolver,);
+    let loader = (DummyLoader,);
+
+    let rt = Runtime::new()?;
+    rt.set_loader(resolver, loader);
+    let ctx = Context::full(&rt)?;
+
+    let mut summary = DirectorySummary::default();
+
+    for input in files {
+        let relative = input.strip_prefix(&input_dir)?;
+        let name = relative.to_string_lossy();
+        let (_, ext) = name_extname(&name);
+        if ext == ".json" {
+            let output = output_dir.join(relative);
+            if let Some(parent) = output.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(&input, &output)?;
+            summary.copied += 1;
+            continue;
+        }
+        if !JS_EXTENSIONS.contains(&ext) {
+            continue;
+        }
+
+        let output = output_dir.join(relative).with_extension(BYTECODE_EXT);
+        if let Some(parent) = output.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let compiled = ctx.with(|ctx| {
+            compile_tree_module(&ctx, &input, &output, strip)
+                .catch(&ctx)
+                .map_err(|err| err.to_string())
+        });
+        match compiled {
+            Ok((js_bytes, bytecode_bytes, compressed_bytes)) => {
+                summary.modules += 1;
+                summary.js_bytes += js_bytes;
+                summary.bytecode_bytes += bytecode_bytes;
+                summary.compressed_bytes += compressed_bytes;
+            },
+            Err(err) => summary.errors.push((input, err)),
+        }
+    }
+
+    Ok(summary)
+}
+
+// Returns the JS, bytecode and compressed sizes
+fn compile_tree_module(
+    ctx: &Ctx<'_>,
+    input: &Path,
+    output: &Path,
+    strip: Strip,
+) -> rquickjs::Result<(usize, usize, usize)> {
+    let input_name = input.to_string_lossy();
+    let module_name = output.to_string_lossy();
+    let source = fs::read(input).or_throw_msg(ctx, &["Unable to load: ", &*input_name].concat())?;
+    let js_bytes = source.len();
+
+    // without debug info there are no positions to map
+    let source_map = match strip.strips_debug() {
+        true => None,
+        false => source_map::load_for_source(&source, input)?,
+    };
+
+    let module = declare_file_module(ctx, source, &input_name, &module_name)?;
+    let (bytes, _) = write_module(&module, strip)?;
+    let compressed = compress_module(&bytes, COMPRESSION_DICT)?;
+    fs::write(output, &compressed)?;
+
+    if let Some(mut source_map) = source_map {
+        source_map.relativize_sources(output.parent().unwrap_or(Path::new("")));
+        let mut map_filename = output.as_os_str().to_owned();
+        map_filename.push(SOURCE_MAP_EXT);
+        fs::write(map_filename, source_map.to_json(&module_name))?;
+    }
+
+    Ok((js_bytes, bytes.len(), compressed.len()))
+}
+
+// Declares the module `module_name` from the `source` of the file `input_name`
+fn declare_file_module<'js>(
+    ctx: &Ctx<'js>,
+    source: Vec<u8>,
+    input_name: &str,
+    module_name: &str,
+) -> rquickjs::Result<Module<'js>> {
+    let mut source = if is_typescript(input_name) {
+        strip_types_or_throw(ctx, &source, input_name)?
+    } else {
+        source
+    };
+    if let Some(rewritten) = rewrite_import_attributes_or_throw(ctx, &source, input_name)? {
+        source = rewritten;
+    }
+    let mut source: &[u8] = &source;
+    // Blank out the shebang but keep the line so positions still match
+    if source.starts_with(b"#!") {
+        let end = source.iter().position(|&c| c == b'\n');
+        source = &source[end.unwrap_or(source.len())..];
+    }
+
+    trace!("Compiling module: {}", module_name);
+
+    Module::declare(ctx.clone(), module_name, source)
+}
+
+/// Trains a zstd dictionary of at most `max_size` bytes on the bytecode of
+/// `inputs`. Inputs are `.lrt` files, JS and TypeScript files, which are
+/// compiled first, or directories searched for both. Returns the dictionary
+/// and the number of modules it was trained on.
+pub async fn train_dictionary(
+    inputs: &[PathBuf],
+    max_size: usize,
+    strip: Strip,
+) -> Result<(Vec<u8>, usize), Box<dyn std::error::Error + Send + Sync>> {
+    let mut files = Vec::new();
+    for input in inputs {
+        if input.is_dir() {
+            collect_files(input, Path::new(""), &mut files)?;
+        } else {
+            files.push(input.clone());
+        }
+    }
+
+    let resolver = (DummyResolver,);
+    let loader = (DummyLoader,);
+
+    let rt = Runtime::new()?;
+    rt.set_loader(resolver, loader);
+    let ctx = Context::full(&rt)?;
+
+    let mut samples = Vec::new();
+    for file in files {
+        let name = file.to_string_lossy();
+        let (_, ext) = name_extname(&name);
+        if ext == BYTECODE_FILE_EXT {
+            let bytes = fs::read(&file)?;
+            let bytecode = CustomLoader::get_module_bytecode(&bytes)
+                .map_err(|err| format!("{}: {}", name, err))?;
+            samples.push(bytecode);
+        } else if JS_EXTENSIONS.contains(&ext) {
+            let bytecode = ctx.with(|ctx| {
+                compile_sample(&ctx, &name, strip)
+                    .catch(&ctx)
+                    .map_err(|err| err.to_string())
+            })?;
+            samples.push(bytecode);
+        }
+    }
+
+    if samples.is_empty() {
+        return Err("No modules to train the dictionary on".into());
+    }
+    trace!("Training dictionary on {} modules", samples.len());
+    let dictionary = zstd::dict::from_samples(&samples, max_size)?;
+    Ok((dictionary, samples.len()))
+}
+
+fn compile_sample(ctx: &Ctx<'_>, input_name: &str, strip: Strip) -> rquickjs::Result<Vec<u8>> {
+    let source =
+        fs::read(input_name).or_throw_msg(ctx, &["Unable to load: ", input_name].concat())?;
+    let module = declare_file_module(ctx, source, input_name, input_name)?;
+    let (bytes, _) = write_module(&module, strip)?;
+    Ok(bytes)
+}
+
+fn collect_files(dir: &Path, skip: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let mut entries = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    entries.sort_unstable();
+    for path in entries {
+        if path == skip
+            || path
+                .file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+        {
+            continue;
+        }
+        if path.is_dir() {
+            collect_files(&path, skip, files)?;
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
+
 pub async fn compile_string(
     input_str: &str,
+    module_name: &str,
+    strip: Strip,
 ) -> Result<Vec<u8>, Box<dyn std::error::Error + Send + Sync>> {
     let resolver = (DummyResolver,);
     let loader = (DummyLoader,);
@@ -86,6 +479,7 @@ pub async fn compile_string(
     let ctx = Context::full(&rt)?;
 
     let mut total_bytes: usize = 0;
+    let mut unstripped_bytes: usize = 0;
     let mut compressed_bytes: usize = 0;
     let js_bytes: usize = input_str.len();
     let mut result_bytes = Vec::new();
@@ -93,15 +487,15 @@ pub async fn compile_string(
     ctx.with(|ctx| {
         (|| {
             let source = input_str;
-            let module_name = "string_module";
 
             trace!("Compiling module: {}", module_name);
 
             let module = Module::declare(ctx.clone(), module_name, source)?;
-            let bytes = module.write(WriteOptions::default())?;
-            let compressed = compress_module(&bytes)?;
+            let (bytes, unstripped_len) = write_module(&module, strip)?;
+            let compressed = compress_module(&bytes, COMPRESSION_DICT)?;
 
             total_bytes += bytes.len();
+            unstripped_bytes += unstripped_len;
             compressed_bytes += compressed.len();
 
             result_bytes = compressed;
@@ -111,12 +505,443 @@ pub async fn compile_string(
         .unwrap_or_else(|err| Vm::print_error_and_exit(&ctx, err))
     });
 
-    trace!("JS size: {}", human_file_size(js_bytes));
-    trace!("Bytecode size: {}", human_file_size(total_bytes));
-    trace!(
-        "Compressed bytecode size: {}",
-        human_file_size(compressed_bytes)
+    trace_sizes(
+        js_bytes,
+        total_bytes,
+        unstripped_bytes,
+        compressed_bytes,
+        strip,
     );
 
     Ok(result_bytes)
 }
+
+/// An asset glob together with the directory it is relative to.
+#[derive(Debug, Clone)]
+pub struct AssetGlob {
+    pub base: PathBuf,
+    pub pattern: String,
+}
+
+/// Options for [`compile_module_graph`].
+#[derive(Debug, Clone)]
+pub struct CompileOptions {
+    /// Files embedded next to the modules, readable through `fs`
+    pub assets: Vec<AssetGlob>,
+    /// Compress module bytecode with zstd
+    pub compress: bool,
+    /// What to remove from module bytecode
+    pub strip: Strip,
+    /// zstd dictionary to compress module bytecode with instead of the
+    /// built-in one, embedded in the archive
+    pub dictionary: Option<Vec<u8>>,
+}
+
+impl Default for CompileOptions {
+    fn default() -> Self {
+        Self {
+            assets: Vec::new(),
+            compress: true,
+            strip: Strip::None,
+            dictionary: None,
+        }
+    }
+}
+
+/// Compiles `entry` and every module it imports or requires into an
+/// [`Archive`] that can be embedded in an executable.
+pub async fn compile_module_graph(
+    entry: &Path,
+    options: &CompileOptions,
+) -> Result<Archive, Box<dyn std::error::Error + Send + Sync>> {
+    let mut assets = Vec::new();
+    for asset in &options.assets {
+        let matched = expand_glob(&asset.base, &asset.pattern)?;
+        if matched.is_empty() {
+            return Err(["Asset pattern matched no files: ", &asset.pattern]
+                .concat()
+                .into());
+        }
+        assets.extend(matched);
+    }
+
+    let resolver = (DummyResolver,);
+    let loader = (DummyLoader,);
+
+    let rt = Runtime::new()?;
+    rt.set_loader(resolver, loader);
+    let ctx = Context::full(&rt)?;
+
+    let (_, _, builtins, _) = ModuleBuilder::default().build();
+    let strip = options.strip;
+    let dictionary = options.dictionary.as_deref().unwrap_or(COMPRESSION_DICT);
+
+    let mut total_bytes: usize = 0;
+    let mut unstripped_bytes: usize = 0;
+    let mut compressed_bytes: usize = 0;
+    let mut js_bytes: usize = 0;
+    let mut result = None;
+
+    ctx.with(|ctx| {
+        (|| {
+            let graph = ModuleGraph::walk(&ctx, entry, &assets, &builtins)?;
+            if !graph.unresolved.is_empty() {
+                eprintln!(
+                    "Warning: {} imports are left to the runtime, their modules are not embedded:",
+                    graph.unresolved.len()
+                );
+                for unresolved in &graph.unresolved {
+                    eprintln!("  {}", unresolved);
+                }
+            }
+            let mut archive = Archive::new(graph.virtual_path(&graph.entry));
+            if let Some(dictionary) = &options.dictionary {
+                archive.set_dictionary(dictionary.clone());
+            }
+
+            for module in &graph.modules {
+                let virtual_path = graph.virtual_path(&module.path);
+
+                let module_name = match module.format {
+                    ModuleFormat::Json => {
+                        archive.add_file(virtual_path, fs::read(&module.path)?);
+                        continue;
+                    },
+                    ModuleFormat::Bytecode => {
+                        archive.add_module(virtual_path, fs::read(&module.path)?);
+                        continue;
+                    },
+                    ModuleFormat::Esm => virtual_path,
+                    ModuleFormat::Cjs => [CJS_IMPORT_PREFIX, &virtual_path].concat(),
+                };
+
+                let source = fs::read(&module.path)
+                    .or_throw_msg(&ctx, &["Unable to load: ", &module.path].concat())?;
+                js_bytes += source.len();
+
+                // without debug info there are no positions to map
+                let source_map = match strip.strips_debug() {
+                    true => None,
+                    false => source_map::load_for_source(&source, Path::new(&module.path))?,
+                };
+                if let Some(mut source_map) = source_map {
+                    source_map.relativize_sources(graph.root());
+                    archive.add_source_map(
+                        module_name.clone(),
+                        source_map.to_json(&module_name).into_bytes(),
+                    );
+                }
+
+                let mut source = if is_typescript(&module.path) {
+                    strip_types_or_throw(&ctx, &source, &module.path)?
+                } else {
+                    source
+                };
+                if let Some(rewritten) =
+                    rewrite_import_attributes_or_throw(&ctx, &source, &module.path)?
+                {
+                    source = rewritten;
+                }
+
+                let mut source: &[u8] = &source;
+                // Blank out the shebang but keep the line so positions still match
+                if module.format == ModuleFormat::Esm && source.starts_with(b"#!") {
+                    let end = source.iter().position(|&c| c == b'\n');
+                    source = &source[end.unwrap_or(source.len())..];
+                }
+
+                trace!("Compiling module: {}", module_name);
+
+                let declared = Module::declare(ctx.clone(), module_name.as_str(), source)?;
+                let (bytes, unstripped_len) = write_module(&declared, strip)?;
+                let bytes_len = bytes.len();
+                let bytecode = if options.compress {
+                    compress_module(&bytes, dictionary)?
+                } else {
+                    add_bytecode_header(bytes, None, bytecode_abi_hash())
+                };
+
+                total_bytes += bytes_len;
+                unstripped_bytes += unstripped_len;
+                compressed_bytes += bytecode.len();
+
+                archive.add_module(module_name, bytecode);
+            }
+
+            for resolution in &graph.resolutions {
+                archive.add_resolution(
+                    &graph.virtual_path(&resolution.base),
+                    &resolution.specifier,
+                    resolution.is_esm,
+                    graph.virtual_path(&resolution.target),
+                );
+            }
+
+            for asset in &graph.assets {
+                let bytes = fs::read(asset)
+                    .or_throw_msg(&ctx, &["Unable to read asset: ", asset].concat())?;
+                archive.add_file(graph.virtual_path(asset), bytes);
+            }
+
+            trace!(
+                "Bundled {} modules and {} assets",
+                graph.modules.len(),
+                graph.assets.len()
+            );
+
+            result = Some(archive);
+            Ok(())
+        })()
+        .catch(&ctx)
+        .unwrap_or_else(|err| Vm::print_error_and_exit(&ctx, err))
+    });
+
+    trace_sizes(
+        js_bytes,
+        total_bytes,
+        unstripped_bytes,
+        compressed_bytes,
+        strip,
+    );
+
+    Ok(result.unwrap())
+}
+
+#[cfg(test)]
+mod tests {
+    use llrt_test::test_sync_with;
+    use rquickjs::{prelude::This, Error, Function};
+
+    use super::*;
+    use crate::{bytecode::read_bytecode_header, source_map::remap_stack_trace};
+
+    const SOURCE: &str = r#"export function hello() {
+  return "hello from the source";
+}
+export function fail() {
+  throw new Error("failed");
+}
+"#;
+
+    fn load_export<'js>(
+        ctx: &Ctx<'js>,
+        bytecode: &[u8],
+        name: &str,
+    ) -> rquickjs::Result<Function<'js>> {
+        let module = CustomLoader::load_bytecode_module(ctx.clone(), bytecode)?;
+        let (module, promise) = module.eval()?;
+        promise.finish::<()>()?;
+        module.get(name)
+    }
+
+    #[test]
+    fn test_strip_from_str() {
+        for strip in [Strip::None, Strip::Source, Strip::Debug, Strip::All] {
+            assert_eq!(strip.to_string().parse::<Strip>(), Ok(strip));
+        }
+        assert_eq!(
+            "lines".parse::<Strip>(),
+            Err("Unknown strip mode: lines, expected source, debug or all".into())
+        );
+    }
+
+    #[tokio::test]
+    async fn test_strip_source() {
+        let kept = compile_string(SOURCE, "keep_source.js", Strip::None)
+            .await
+            .unwrap();
+        let stripped = compile_string(SOURCE, "strip_source.js", Strip::Source)
+            .await
+            .unwrap();
+        test_sync_with(move |ctx| {
+            let to_string = |bytecode: &[u8]| -> rquickjs::Result<String> {
+                let hello = load_export(&ctx, bytecode, "hello")?;
+                let to_string: Function = hello.get("toString")?;
+                to_string.call((This(hello),))
+            };
+            assert!(to_string(&kept)?.contains("hello from the source"));
+            assert!(!to_string(&stripped)?.contains("hello from the source"));
+            Ok(())
+        })
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_strip_debug() {
+        let kept = compile_string(SOURCE, "keep_debug.js", Strip::None)
+            .await
+            .unwrap();
+        let stripped = compile_string(SOURCE, "strip_debug.js", Strip::Debug)
+            .await
+            .unwrap();
+        test_sync_with(move |ctx| {
+            let stack = |bytecode: &[u8]| -> rquickjs::Result<String> {
+                let fail = load_export(&ctx, bytecode, "fail")?;
+                let err = fail.call::<_, ()>(()).unwrap_err();
+                assert!(matches!(err, Error::Exception));
+                let exception = ctx.catch().into_object().unwrap();
+                exception.get("stack")
+            };
+            assert!(stack(&kept)?.contains("keep_debug.js:5"));
+
+            let stripped_stack = stack(&stripped)?;
+            assert!(!stripped_stack.contains("strip_debug.js:"));
+            assert!(stripped_stack.contains("(missing)"));
+            let explained = remap_stack_trace(&stripped_stack).unwrap();
+            assert!(explained.contains("at fail (no debug info)"));
+            Ok(())
+        })
+        .await;
+    }
+
+    #[test]
+    fn test_write_module_sizes() {
+        let rt = Runtime::new().unwrap();
+        let ctx = Context::full(&rt).unwrap();
+        ctx.with(|ctx| {
+            let module = Module::declare(ctx, "sizes.js", SOURCE).unwrap();
+            let (bytes, unstripped_len) = write_module(&module, Strip::None).unwrap();
+            assert_eq!(unstripped_len, bytes.len());
+            for strip in [Strip::Source, Strip::Debug, Strip::All] {
+                let (stripped, unstripped_len) = write_module(&module, strip).unwrap();
+                assert_eq!(unstripped_len, bytes.len());
+                assert!(unstripped_len >= stripped.len());
+            }
+            let (stripped, _) = write_module(&module, Strip::All).unwrap();
+            assert!(stripped.len() < bytes.len());
+        });
+    }
+
+    #[tokio::test]
+    async fn test_compile_file_source_map_without_debug_info() {
+        let dir = std::env::temp_dir().join("llrt-test-compile-source-map");
+        for strip in [Strip::Debug, Strip::All] {
+            let err = compile_file(
+                &dir.join("a.js"),
+                &dir.join("a.lrt"),
+                Some(&dir.join("a.js.map")),
+                strip,
+            )
+            .await
+            .unwrap_err();
+            assert_eq!(
+                err.to_string(),
+                [
+                    "--source-map can't be used with --strip=",
+                    &strip.to_string(),
+                    ", which removes the positions it maps"
+                ]
+                .concat()
+            );
+        }
+        assert!(!dir.exists());
+    }
+
+    #[tokio::test]
+    async fn test_compile_directory() {
+        let dir = std::env::temp_dir().join("llrt-test-compile-directory");
+        let _ = fs::remove_dir_all(&dir);
+        let input = dir.join("src");
+        let output = dir.join("out");
+        fs::create_dir_all(input.join("lib/nested")).unwrap();
+        fs::create_dir_all(input.join(".cache")).unwrap();
+        fs::write(
+            input.join("index.js"),
+            "import { a } from \"./lib/a.js\";\nexport default a;\n",
+        )
+        .unwrap();
+        fs::write(input.join("lib/a.js"), "export const a = 1;\n").unwrap();
+        fs::write(
+            input.join("lib/nested/b.ts"),
+            "export const b: number = 2;\n",
+        )
+        .unwrap();
+        fs::write(input.join("lib/data.json"), r#"{ "a": 1 }"#).unwrap();
+        fs::write(input.join("lib/broken.js"), "export const = ;\n").unwrap();
+        fs::write(input.join("README.md"), "# readme\n").unwrap();
+        fs::write(input.join(".cache/c.js"), "export const c = 3;\n").unwrap();
+
+        let summary = compile_directory(&input, &output, Strip::None)
+            .await
+            .unwrap();
+        assert_eq!(summary.modules, 3);
+        assert_eq!(summary.copied, 1);
+        assert_eq!(summary.errors.len(), 1);
+        let (failed, err) = &summary.errors[0];
+        assert_eq!(failed, &input.join("lib/broken.js"));
+        assert!(!err.is_empty());
+
+        let mut files = Vec::new();
+        collect_files(&output, Path::new(""), &mut files).unwrap();
+        let files: Vec<_> = files
+            .iter()
+            .map(|file| file.strip_prefix(&output).unwrap().to_string_lossy())
+            .collect();
+        assert_eq!(
+            files,
+            [
+                "index.lrt",
+                "lib/a.lrt",
+                "lib/data.json",
+                "lib/nested/b.lrt"
+            ]
+        );
+        assert_eq!(
+            fs::read(output.join("lib/data.json")).unwrap(),
+            br#"{ "a": 1 }"#
+        );
+        let bytecode = fs::read(output.join("lib/nested/b.lrt")).unwrap();
+        assert!(CustomLoader::get_module_bytecode(&bytecode).is_ok());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_train_dictionary() {
+        let dir = std::env::temp_dir().join("llrt-test-train-dictionary");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for i in 0..32 {
+            fs::write(
+                dir.join(format!("handler{i}.js")),
+                format!(
+                    "export function handler{i}(event) {{\n  \
+                     const items = event.items.map((item) => item.value * {i});\n  \
+                     return {{ statusCode: 200, body: JSON.stringify(items) }};\n}}\n"
+                ),
+            )
+            .unwrap();
+        }
+        fs::write(dir.join("notes.txt"), "not a module\n").unwrap();
+
+        let (dictionary, modules) = train_dictionary(&[dir.clone()], 4096, Strip::None)
+            .await
+            .unwrap();
+        assert_eq!(modules, 32);
+        assert!(dictionary.len() <= 4096);
+        let id = dictionary_id(&dictionary).unwrap();
+        assert_ne!(dictionary_id(COMPRESSION_DICT), Some(id));
+
+        let source = fs::read_to_string(dir.join("handler7.js")).unwrap();
+        let compiled = compile_string(&source, "handler7.js", Strip::None)
+            .await
+            .unwrap();
+        let bytecode = CustomLoader::get_module_bytecode(&compiled).unwrap();
+
+        let compressed = compress_module(&bytecode, &dictionary).unwrap();
+        let (header, rest) = read_bytecode_header(&compressed).unwrap();
+        assert_eq!(header.dictionary_id, Some(id));
+        assert_eq!(header.uncompressed_size, Some(bytecode.len() as u32));
+        let mut decompressor = zstd::bulk::Decompressor::with_dictionary(&dictionary).unwrap();
+        let decompressed = decompressor.decompress(rest, bytecode.len()).unwrap();
+        assert_eq!(decompressed, bytecode);
+
+        // this executable has neither the trained dictionary built in nor embedded
+        let err = CustomLoader::get_module_bytecode(&compressed).unwrap_err();
+        assert!(err.to_string().contains(&format!(
+            "Bytecode was compressed with zstd dictionary {id}, "
+        )));
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,5 +1,11 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
+use std::{
+    collections::{BTreeMap, HashSet},
+    env,
+};
+
+use once_cell::sync::OnceCell;
 
 //network
 pub const ENV_LLRT_NET_ALLOW: &str = "LLRT_NET_ALLOW";
@@ -14,6 +20,94 @@ pub const ENV_LLRT_LOG: &str = "LLRT_LOG";
 
 //module
 pub const ENV_LLRT_PLATFORM: &str = "LLRT_PLATFORM";
+pub const ENV_LLRT_COMPILE_CACHE: &str = "LLRT_COMPILE_CACHE";
+pub const ENV_LLRT_RESOLVE_CACHE: &str = "LLRT_RESOLVE_CACHE";
+pub const ENV_LLRT_WATCH_REPORT: &str = "LLRT_WATCH_REPORT";
 
 //vm
 pub const ENV_LLRT_GC_THRESHOLD_MB: &str = "LLRT_GC_THRESHOLD_MB";
+
+/// Settings that can be baked into a built executable.
+pub const RUNTIME_CONFIG_KEYS: &[&str] = &[
+    ENV_LLRT_NET_ALLOW,
+    ENV_LLRT_NET_DENY,
+    ENV_LLRT_NET_POOL_IDLE_TIMEOUT,
+    ENV_LLRT_HTTP_VERSION,
+    ENV_LLRT_TLS_VERSION,
+    ENV_LLRT_EXTRA_CA_CERTS,
+    ENV_LLRT_LOG,
+    ENV_LLRT_PLATFORM,
+    ENV_LLRT_GC_THRESHOLD_MB,
+];
+
+static RUNTIME_CONFIG: OnceCell<RuntimeConfig> = OnceCell::new();
+
+/// Runtime settings baked into a built executable. They act as defaults for
+/// the environment, or replace it for locked settings.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct RuntimeConfig {
+    values: BTreeMap<String, String>,
+    locked: HashSet<String>,
+}
+
+impl RuntimeConfig {
+    pub fn set(&mut self, key: String, value: String, locked: bool) {
+        if locked {
+            self.locked.insert(key.clone());
+        } else {
+            self.locked.remove(&key);
+        }
+        self.values.insert(key, value);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+
+    /// Settings as `(key, value, locked)`, sorted by key.
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &str, bool)> {
+        self.values
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.as_str(), self.locked.contains(key)))
+    }
+
+    fn get(&self, key: &str) -> Option<(&str, bool)> {
+        self.values
+            .get(key)
+            .map(|value| (value.as_str(), self.locked.contains(key)))
+    }
+}
+
+/// Applies the baked-in settings. Must be called before the VM is created;
+/// only the first call has an effect.
+pub fn set_runtime_config(config: RuntimeConfig) {
+    let _ = RUNTIME_CONFIG.set(config);
+}
+
+/// Reads a runtime setting, taking baked-in defaults and locks into account.
+pub fn var(key: &str) -> Result<String, env::VarError> {
+    match RUNTIME_CONFIG.get().and_then(|config| config.get(key)) {
+        Some((value, true)) => Ok(value.to_string()),
+        Some((value, false)) => env::var(key).or_else(|_| Ok(value.to_string())),
+        None => env::var(key),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RuntimeConfig;
+
+    #[test]
+    fn test_runtime_config_lock() {
+        let mut config = RuntimeConfig::default();
+        config.set("LLRT_NET_ALLOW".into(), "a.com".into(), true);
+        config.set("LLRT_LOG".into(), "warn".into(), false);
+
+        assert_eq!(config.get("LLRT_NET_ALLOW"), Some(("a.com", true)));
+        assert_eq!(config.get("LLRT_LOG"), Some(("warn", false)));
+        assert_eq!(config.get("LLRT_NET_DENY"), None);
+
+        config.set("LLRT_NET_ALLOW".into(), "b.com".into(), false);
+        assert_eq!(config.get("LLRT_NET_ALLOW"), Some(("b.com", false)));
+    }
+}
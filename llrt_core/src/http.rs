@@ -1,6 +1,6 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
-use std::{env, fs::File, io, result::Result as StdResult};
+use std::{fs::File, io, result::Result as StdResult};
 
 use rustls::{pki_types::CertificateDer, version, SupportedProtocolVersion};
 use tracing::warn;
@@ -28,7 +28,7 @@ pub fn init() -> StdResult<(), Box<dyn std::error::Error + Send + Sync>> {
 }
 
 fn build_pool_idle_timeout() -> Option<u64> {
-    let Ok(env_value) = env::var(environment::ENV_LLRT_NET_POOL_IDLE_TIMEOUT) else {
+    let Ok(env_value) = environment::var(environment::ENV_LLRT_NET_POOL_IDLE_TIMEOUT) else {
         return None;
     };
     let Ok(pool_idle_timeout) = env_value.parse::<u64>() else {
@@ -45,7 +45,7 @@ fn build_pool_idle_timeout() -> Option<u64> {
 }
 
 fn buid_extra_ca_certs() -> StdResult<Option<Vec<CertificateDer<'static>>>, io::Error> {
-    if let Ok(extra_ca_certs) = env::var(environment::ENV_LLRT_EXTRA_CA_CERTS) {
+    if let Ok(extra_ca_certs) = environment::var(environment::ENV_LLRT_EXTRA_CA_CERTS) {
         if !extra_ca_certs.is_empty() {
             let file = File::open(extra_ca_certs) // This can be sync since we do this once when the VM starts
                 .map_err(|_| io::Error::other("Failed to open extra CA certificates file"))?;
@@ -61,14 +61,14 @@ fn buid_extra_ca_certs() -> StdResult<Option<Vec<CertificateDer<'static>>>, io::
 }
 
 fn build_tls_versions() -> Vec<&'static SupportedProtocolVersion> {
-    match env::var(environment::ENV_LLRT_TLS_VERSION).as_deref() {
+    match environment::var(environment::ENV_LLRT_TLS_VERSION).as_deref() {
         Ok("1.3") => vec![&version::TLS13, &version::TLS12],
         _ => vec![&version::TLS12], //Use TLS 1.2 by default to increase compat and keep latency low
     }
 }
 
 fn build_http_version() -> HttpVersion {
-    match env::var(environment::ENV_LLRT_HTTP_VERSION).as_deref() {
+    match environment::var(environment::ENV_LLRT_HTTP_VERSION).as_deref() {
         Ok("2") => HttpVersion::Http2,
         _ => HttpVersion::Http1_1,
     }
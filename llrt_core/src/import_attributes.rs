@@ -0,0 +1,265 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+//! Import attributes, `import data from "./data.json" with { type: "json" }`.
+//!
+//! The module loader only ever sees a resolved name, so the `type` attribute
+//! is moved into the specifier before a module is declared: the specifier
+//! gets a prefix naming the type and the `with` clause is blanked out. Lines
+//! are preserved so stack traces still match the source.
+use std::fmt;
+
+use rquickjs::{Ctx, Exception, Result};
+
+use crate::module_graph::{scan_import_sites, ImportAttributes, ImportKind};
+use crate::modules::require::{BYTES_IMPORT_PREFIX, JSON_IMPORT_PREFIX, TEXT_IMPORT_PREFIX};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportType {
+    /// The parsed file, only `.json` files can be imported this way
+    Json,
+    /// The file decoded as UTF-8, as a string
+    Text,
+    /// The raw file, as a `Uint8Array`
+    Bytes,
+}
+
+impl ImportType {
+    fn from_attribute(value: &str) -> Option<Self> {
+        match value {
+            "json" => Some(Self::Json),
+            "text" => Some(Self::Text),
+            "bytes" => Some(Self::Bytes),
+            _ => None,
+        }
+    }
+
+    pub fn prefix(self) -> &'static str {
+        match self {
+            Self::Json => JSON_IMPORT_PREFIX,
+            Self::Text => TEXT_IMPORT_PREFIX,
+            Self::Bytes => BYTES_IMPORT_PREFIX,
+        }
+    }
+
+    /// Splits a prefixed specifier or module name into its type and the rest.
+    pub fn split(name: &str) -> Option<(Self, &str)> {
+        if !name.starts_with("__") {
+            return None;
+        }
+        [Self::Json, Self::Text, Self::Bytes]
+            .into_iter()
+            .find_map(|import_type| {
+                name.strip_prefix(import_type.prefix())
+                    .map(|rest| (import_type, rest))
+            })
+    }
+}
+
+impl fmt::Display for ImportType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Json => "json",
+            Self::Text => "text",
+            Self::Bytes => "bytes",
+        })
+    }
+}
+
+/// An attribute that is not supported, 1-based position.
+#[derive(Debug, PartialEq, Eq)]
+pub struct ImportAttributeError {
+    pub message: String,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for ImportAttributeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+/// Returns the `type` of an import. `type` is the only supported attribute
+/// and `json`, `text` and `bytes` the only supported types.
+pub fn import_type(
+    source: &str,
+    attributes: &ImportAttributes,
+) -> std::result::Result<Option<ImportType>, ImportAttributeError> {
+    let mut import_type = None;
+    for (key, value) in &attributes.entries {
+        let message = match key.as_str() {
+            "type" => match ImportType::from_attribute(value) {
+                Some(value) => {
+                    import_type = Some(value);
+                    continue;
+                },
+                None => ["Unsupported import type '", value, "'"].concat(),
+            },
+            _ => ["Unsupported import attribute '", key, "'"].concat(),
+        };
+        return Err(error_at(source, attributes.clause.start, message));
+    }
+    Ok(import_type)
+}
+
+/// Moves the `type` attribute of every import into its specifier. Returns
+/// `None` when `source` has no import attributes.
+pub fn rewrite_import_attributes(
+    source: &[u8],
+) -> std::result::Result<Option<Vec<u8>>, ImportAttributeError> {
+    if !source.windows(4).any(|window| window == b"with") {
+        return Ok(None);
+    }
+    let Ok(text) = std::str::from_utf8(source) else {
+        return Ok(None);
+    };
+
+    let mut output = Vec::new();
+    let mut copied = 0;
+    for site in scan_import_sites(text) {
+        let (Some(attributes), ImportKind::Import) = (&site.attributes, site.kind) else {
+            continue;
+        };
+        let prefix = import_type(text, attributes)?.map_or("", ImportType::prefix);
+
+        let literal = &source[site.literal.clone()];
+        output.extend_from_slice(&source[copied..site.literal.start]);
+        output.push(literal[0]);
+        output.extend_from_slice(prefix.as_bytes());
+        output.extend_from_slice(&literal[1..]);
+
+        // The prefix takes the room of the clause so columns after it still match
+        let mut excess = prefix.len();
+        for &byte in &source[site.literal.end..attributes.clause.end] {
+            match byte {
+                b'\n' | b'\r' => output.push(byte),
+                _ if excess > 0 => excess -= 1,
+                _ => output.push(b' '),
+            }
+        }
+        copied = attributes.clause.end;
+    }
+
+    if copied == 0 {
+        return Ok(None);
+    }
+    output.extend_from_slice(&source[copied..]);
+    Ok(Some(output))
+}
+
+/// Like [`rewrite_import_attributes`], throwing a `SyntaxError` that names
+/// `path`.
+pub fn rewrite_import_attributes_or_throw(
+    ctx: &Ctx<'_>,
+    source: &[u8],
+    path: &str,
+) -> Result<Option<Vec<u8>>> {
+    rewrite_import_attributes(source)
+        .map_err(|err| Exception::throw_syntax(ctx, &[path, ":", &err.to_string()].concat()))
+}
+
+fn error_at(source: &str, offset: usize, message: String) -> ImportAttributeError {
+    let before = &source[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    ImportAttributeError {
+        message,
+        line,
+        column: before[line_start..].chars().count() + 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{rewrite_import_attributes, ImportType};
+
+    fn rewrite(source: &str) -> String {
+        let output = rewrite_import_attributes(source.as_bytes())
+            .unwrap()
+            .unwrap_or_else(|| source.as_bytes().to_vec());
+        assert_eq!(output.len(), source.len());
+        String::from_utf8(output).unwrap()
+    }
+
+    fn error(source: &str) -> String {
+        rewrite_import_attributes(source.as_bytes())
+            .unwrap_err()
+            .to_string()
+    }
+
+    #[test]
+    fn test_rewrite_static_imports() {
+        assert_eq!(
+            rewrite(r#"import data from "./data.json" with { type: "json" };"#),
+            r#"import data from "__json:./data.json"               ;"#
+        );
+        assert_eq!(
+            rewrite(r#"import page from './page.html' with { 'type': 'text' }"#),
+            r#"import page from '__text:./page.html'                 "#
+        );
+        assert_eq!(
+            rewrite(r#"export { default } from "./a.bin" with {type:"bytes"}"#),
+            r#"export { default } from "__bytes:./a.bin"            "#
+        );
+        assert_eq!(
+            rewrite("import \"./a.txt\"\n  with { type: \"text\" };\nlet x;"),
+            "import \"__text:./a.txt\"\n                ;\nlet x;"
+        );
+    }
+
+    #[test]
+    fn test_rewrite_dynamic_imports() {
+        assert_eq!(
+            rewrite(r#"await import("./a.txt", { with: { type: "text" } });"#),
+            r#"await import("__text:./a.txt"                     );"#
+        );
+        assert_eq!(
+            rewrite(r#"import(`./a.bin`, { with: { type: "bytes" }, },)"#),
+            r#"import(`__bytes:./a.bin`                       )"#
+        );
+        // options that are not a literal are left to the runtime
+        let source = r#"import("./a.json", options); require("./b.json")"#;
+        assert_eq!(rewrite(source), source);
+    }
+
+    #[test]
+    fn test_rewrite_keeps_other_code() {
+        let source = r#"
+            import a from "./a.js";
+            // import b from "./b.txt" with { type: "text" };
+            const s = 'import c from "./c.txt" with { type: "text" }';
+            const without = { with: 1 };
+        "#;
+        assert_eq!(rewrite(source), source);
+        assert_eq!(
+            rewrite(r#"import a from "./a.js" with {}; a.with;"#),
+            r#"import a from "./a.js"        ; a.with;"#
+        );
+    }
+
+    #[test]
+    fn test_rejected_attributes() {
+        assert_eq!(
+            error("let a;\nimport s from './s.css' with { type: 'css' };"),
+            "2:25: Unsupported import type 'css'"
+        );
+        assert_eq!(
+            error(r#"import d from "./d.json" with { type: "json", integrity: "x" }"#),
+            "1:26: Unsupported import attribute 'integrity'"
+        );
+    }
+
+    #[test]
+    fn test_split() {
+        assert_eq!(
+            ImportType::split("__text:/app/page.html"),
+            Some((ImportType::Text, "/app/page.html"))
+        );
+        assert_eq!(
+            ImportType::split("__bytes:./a.bin"),
+            Some((ImportType::Bytes, "./a.bin"))
+        );
+        assert_eq!(ImportType::split("__cjs:/app/a.cjs"), None);
+        assert_eq!(ImportType::split("./a.txt"), None);
+    }
+}
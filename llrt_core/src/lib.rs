@@ -5,18 +5,26 @@
 #![allow(clippy::inherent_to_string)]
 #![cfg_attr(rust_nightly, feature(portable_simd))]
 
+pub mod archive;
 pub mod bytecode;
+pub mod compile_cache;
 pub mod compiler;
 mod compiler_common;
 pub mod environment;
 mod http;
+mod import_attributes;
 pub mod libs;
 mod module_builder;
+pub mod module_graph;
 pub mod modules;
 pub mod runtime_client;
 mod security;
+pub mod signature;
+pub mod source_map;
+pub mod typescript;
 pub mod utils;
 pub mod vm;
+pub mod watch_report;
 
 pub use llrt_modules::VERSION;
 
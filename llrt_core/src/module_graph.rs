@@ -0,0 +1,1138 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    collections::{HashSet, VecDeque},
+    fmt, fs, io,
+    ops::Range,
+    path::{Component, Path, PathBuf},
+};
+
+use rquickjs::{CatchResultExt, Context, Ctx, Exception, Result, Runtime};
+use tracing::trace;
+
+use crate::archive::VIRTUAL_ROOT;
+use crate::bytecode::BYTECODE_FILE_EXT;
+use crate::compiler_common::{DummyLoader, DummyResolver};
+use crate::import_attributes::{import_type, ImportType};
+use crate::module_builder::ModuleBuilder;
+use crate::modules::{
+    path::resolve_path,
+    require::{clear_resolver_caches, resolver::require_resolve, CJS_LOADER_PREFIX},
+};
+use crate::typescript::{is_typescript, strip_types_or_throw};
+use crate::utils::io::is_commonjs;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportKind {
+    Import,
+    Require,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ModuleFormat {
+    Esm,
+    Cjs,
+    Json,
+    Bytecode,
+}
+
+#[derive(Debug)]
+pub struct GraphModule {
+    pub path: String,
+    pub format: ModuleFormat,
+}
+
+/// An import or require the graph does not follow, left for the runtime to
+/// handle. The modules it loads are not bundled.
+#[derive(Debug)]
+pub struct Unresolved {
+    pub base: String,
+    /// `None` for an `import()` or `require()` call whose argument is not a
+    /// string literal
+    pub specifier: Option<String>,
+    pub kind: ImportKind,
+    /// 1-based line of the import in `base`
+    pub line: usize,
+}
+
+impl fmt::Display for Unresolved {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let call = match self.kind {
+            ImportKind::Import => "import",
+            ImportKind::Require => "require",
+        };
+        match &self.specifier {
+            Some(specifier) => write!(f, "{} '{}'", call, specifier)?,
+            None => write!(f, "{}() with a computed specifier", call)?,
+        }
+        write!(f, " in {}:{}", self.base, self.line)
+    }
+}
+
+#[derive(Debug)]
+pub struct Resolution {
+    pub base: String,
+    pub specifier: String,
+    pub is_esm: bool,
+    pub target: String,
+}
+
+/// Every module reachable from an entry point, in discovery order, together
+/// with the resolver decisions that connect them, the imports it could not
+/// follow and any extra asset files, including the files imported as text or
+/// bytes.
+#[derive(Debug)]
+pub struct ModuleGraph {
+    pub entry: String,
+    pub modules: Vec<GraphModule>,
+    pub resolutions: Vec<Resolution>,
+    pub unresolved: Vec<Unresolved>,
+    pub assets: Vec<String>,
+    root: PathBuf,
+}
+
+impl ModuleGraph {
+    /// Walks the import/require graph of `entry` with the same resolution
+    /// rules as the runtime. Builtin modules are left for the runtime to
+    /// handle, and so are specifiers that cannot be resolved and computed
+    /// `import()` and `require()` calls, which are listed in `unresolved`.
+    pub fn walk(
+        ctx: &Ctx<'_>,
+        entry: &Path,
+        assets: &[PathBuf],
+        builtins: &HashSet<&str>,
+    ) -> Result<Self> {
+        let entry = resolve_path([entry.to_string_lossy()])?;
+        let entry_format = format_for(&entry, ImportKind::Import, false);
+
+        let mut modules = Vec::new();
+        let mut resolutions = Vec::new();
+        let mut unresolved = Vec::new();
+        let mut seen = HashSet::new();
+        let mut imported_files = Vec::new();
+        let mut queue = VecDeque::from([(entry.clone(), entry_format)]);
+
+        while let Some((path, format)) = queue.pop_front() {
+            if !seen.insert((path.clone(), format)) {
+                continue;
+            }
+
+            if matches!(format, ModuleFormat::Esm | ModuleFormat::Cjs) {
+                let mut source = fs::read_to_string(&path)?;
+                if is_typescript(&path) {
+                    // type-only imports are not dependencies
+                    let stripped = strip_types_or_throw(ctx, source.as_bytes(), &path)?;
+                    source = String::from_utf8_lossy(&stripped).into_owned();
+                }
+
+                let (sites, computed) = scan(&source);
+                let first_unresolved = unresolved.len();
+                for (kind, position) in computed {
+                    unresolved.push(Unresolved {
+                        base: path.clone(),
+                        specifier: None,
+                        kind,
+                        line: line_at(&source, position),
+                    });
+                }
+
+                for site in sites {
+                    let import_type = match &site.attributes {
+                        Some(attributes) => import_type(&source, attributes).map_err(|err| {
+                            Exception::throw_syntax(ctx, &[&path, ":", &err.to_string()].concat())
+                        })?,
+                        None => None,
+                    };
+                    let line = line_at(&source, site.literal.start);
+                    let (specifier, kind) = (site.specifier, site.kind);
+                    if is_builtin(&specifier, builtins) {
+                        continue;
+                    }
+                    let is_esm = kind == ImportKind::Import;
+                    let target = match require_resolve(ctx, &specifier, &path, is_esm) {
+                        Ok(target) => target.into_owned(),
+                        Err(_) => {
+                            unresolved.push(Unresolved {
+                                base: path.clone(),
+                                specifier: Some(specifier),
+                                kind,
+                                line,
+                            });
+                            continue;
+                        },
+                    };
+                    let (target_path, forced_cjs) = match target.strip_prefix(CJS_LOADER_PREFIX) {
+                        Some(target_path) => (target_path.to_string(), true),
+                        None => (target.clone(), false),
+                    };
+                    // Modules embedded in the runtime itself resolve to their bare name
+                    if !Path::new(&target_path).is_absolute() {
+                        continue;
+                    }
+
+                    resolutions.push(Resolution {
+                        base: path.clone(),
+                        specifier,
+                        is_esm,
+                        target: target.clone(),
+                    });
+                    match import_type {
+                        Some(ImportType::Json) if !target_path.ends_with(".json") => {
+                            return Err(Exception::throw_type(
+                                ctx,
+                                &[
+                                    "Module '",
+                                    &target_path,
+                                    "' imported from '",
+                                    &path,
+                                    "' is not a JSON module and cannot be imported with type 'json'",
+                                ]
+                                .concat(),
+                            ));
+                        },
+                        // embedded as files and read by the loader
+                        Some(ImportType::Text | ImportType::Bytes) => {
+                            imported_files.push(target_path);
+                        },
+                        _ => {
+                            let target_format = format_for(&target_path, kind, forced_cjs);
+                            queue.push_back((target_path, target_format));
+                        },
+                    }
+                }
+                unresolved[first_unresolved..].sort_by_key(|unresolved| unresolved.line);
+            }
+
+            modules.push(GraphModule { path, format });
+        }
+
+        let mut assets = assets
+            .iter()
+            .map(|asset| resolve_path([asset.to_string_lossy()]))
+            .collect::<Result<Vec<_>>>()?;
+        for file in imported_files {
+            if !assets.contains(&file) {
+                assets.push(file);
+            }
+        }
+
+        let root = common_root(
+            modules
+                .iter()
+                .map(|module| Path::new(&module.path))
+                .chain(assets.iter().map(Path::new)),
+        );
+
+        Ok(Self {
+            entry,
+            modules,
+            resolutions,
+            unresolved,
+            assets,
+            root,
+        })
+    }
+
+    /// Directory on the build machine that maps to the archive root.
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Maps a path on the build machine to its name inside the archive.
+    pub fn virtual_path(&self, path: &str) -> String {
+        if let Some(path) = path.strip_prefix(CJS_LOADER_PREFIX) {
+            return [CJS_LOADER_PREFIX, &self.virtual_path(path)].concat();
+        }
+        let relative = Path::new(path)
+            .strip_prefix(&self.root)
+            .unwrap_or(Path::new(path));
+
+        let mut virtual_path = String::from(VIRTUAL_ROOT);
+        for component in relative.components() {
+            if let Component::Normal(name) = component {
+                virtual_path.push('/');
+                virtual_path.push_str(&name.to_string_lossy());
+            }
+        }
+        virtual_path
+    }
+}
+
+/// The files each of `entries` loads, itself included, found by walking its
+/// module graph. Resolver caches are cleared first, so files created since
+/// an earlier call are found. Entries whose graph cannot be walked, for
+/// example because of a syntax error, map to `None`.
+pub fn dependencies(entries: &[PathBuf]) -> Result<Vec<Option<Vec<String>>>> {
+    clear_resolver_caches();
+
+    let rt = Runtime::new()?;
+    rt.set_loader((DummyResolver,), (DummyLoader,));
+    let ctx = Context::full(&rt)?;
+    let (_, _, builtins, _) = ModuleBuilder::default().build();
+
+    Ok(ctx.with(|ctx| {
+        let mut dependencies = Vec::with_capacity(entries.len());
+        for entry in entries {
+            match ModuleGraph::walk(&ctx, entry, &[], &builtins).catch(&ctx) {
+                Ok(graph) => {
+                    let files = graph.modules.into_iter().map(|module| module.path);
+                    dependencies.push(Some(files.chain(graph.assets).collect()));
+                },
+                Err(err) => {
+                    trace!("Failed to walk {}: {}", entry.display(), err);
+                    dependencies.push(None);
+                },
+            }
+        }
+        dependencies
+    }))
+}
+
+fn format_for(path: &str, kind: ImportKind, forced_cjs: bool) -> ModuleFormat {
+    if path.ends_with(".json") {
+        ModuleFormat::Json
+    } else if path.ends_with(BYTECODE_FILE_EXT) {
+        ModuleFormat::Bytecode
+    } else if forced_cjs || kind == ImportKind::Require || is_commonjs(path) {
+        ModuleFormat::Cjs
+    } else {
+        ModuleFormat::Esm
+    }
+}
+
+fn is_builtin(specifier: &str, builtins: &HashSet<&str>) -> bool {
+    specifier.starts_with("node:") || specifier.starts_with("llrt:") || builtins.contains(specifier)
+}
+
+fn common_root<'a>(mut paths: impl Iterator<Item = &'a Path>) -> PathBuf {
+    let Some(first) = paths.next() else {
+        return PathBuf::from("/");
+    };
+    let mut root = first.parent().map(Path::to_path_buf).unwrap_or_default();
+    for path in paths {
+        while !path.starts_with(&root) {
+            if !root.pop() {
+                break;
+            }
+        }
+    }
+    root
+}
+
+/// Expands an asset glob relative to `base` into the files it matches, sorted.
+///
+/// `*` and `?` match within a single path segment and `**` matches any number
+/// of directories. A pattern that ends at a directory includes every file
+/// below it.
+pub fn expand_glob(base: &Path, pattern: &str) -> io::Result<Vec<PathBuf>> {
+    let (start, pattern) = match pattern.strip_prefix('/') {
+        Some(pattern) => (PathBuf::from("/"), pattern),
+        None => (base.to_path_buf(), pattern),
+    };
+    let segments: Vec<&str> = pattern
+        .split(['/', '\\'])
+        .filter(|segment| !segment.is_empty() && *segment != ".")
+        .collect();
+
+    let mut files = Vec::new();
+    match_segments(&start, &segments, &mut files)?;
+    files.sort_unstable();
+    files.dedup();
+    Ok(files)
+}
+
+fn match_segments(dir: &Path, segments: &[&str], files: &mut Vec<PathBuf>) -> io::Result<()> {
+    let Some((segment, rest)) = segments.split_first() else {
+        if dir.is_file() {
+            files.push(dir.to_path_buf());
+        } else if dir.is_dir() {
+            for child in read_dir_sorted(dir)? {
+                match_segments(&child, segments, files)?;
+            }
+        }
+        return Ok(());
+    };
+
+    if *segment == "**" {
+        match_segments(dir, rest, files)?;
+        if dir.is_dir() {
+            for child in read_dir_sorted(dir)? {
+                if child.is_dir() {
+                    match_segments(&child, segments, files)?;
+                }
+            }
+        }
+        return Ok(());
+    }
+
+    if !segment.contains(['*', '?']) {
+        let child = dir.join(segment);
+        if child.exists() {
+            match_segments(&child, rest, files)?;
+        }
+        return Ok(());
+    }
+
+    if dir.is_dir() {
+        for child in read_dir_sorted(dir)? {
+            let name = child.file_name().unwrap_or_default().to_string_lossy();
+            if wildcard_match(segment.as_bytes(), name.as_bytes()) {
+                match_segments(&child, rest, files)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_dir_sorted(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut children = fs::read_dir(dir)?
+        .map(|entry| entry.map(|entry| entry.path()))
+        .collect::<io::Result<Vec<_>>>()?;
+    children.sort_unstable();
+    Ok(children)
+}
+
+fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+    let (mut p, mut n) = (0, 0);
+    let mut backtrack = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some(b'*') => {
+                backtrack = Some((p, n));
+                p += 1;
+            },
+            Some(&c) if c == b'?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            },
+            _ => match backtrack {
+                Some((star, matched)) => {
+                    p = star + 1;
+                    n = matched + 1;
+                    backtrack = Some((star, matched + 1));
+                },
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == b'*')
+}
+
+/// An import found by [`scan_import_sites`] and where it is in the source.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportSite {
+    pub specifier: String,
+    pub kind: ImportKind,
+    /// Byte range of the specifier literal, quotes included
+    pub literal: Range<usize>,
+    pub attributes: Option<ImportAttributes>,
+}
+
+/// The import attributes of a static import or of an `import()` call with a
+/// literal options object.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ImportAttributes {
+    pub entries: Vec<(String, String)>,
+    /// Byte range of the `with { .. }` clause or of the `, { with: { .. } }`
+    /// options argument
+    pub clause: Range<usize>,
+}
+
+/// Finds the string specifiers of static imports, re-exports, dynamic
+/// `import()` and `require()` calls in a module, with their positions and
+/// import attributes.
+///
+/// This is a lexer, not a parser: it skips comments, strings, template
+/// literals and regular expressions so that their contents are never
+/// mistaken for imports, but computed specifiers are ignored.
+pub fn scan_import_sites(source: &str) -> Vec<ImportSite> {
+    scan(source).0
+}
+
+// The import sites of a module and the kind and position of the `import()`
+// and `require()` calls whose specifier is computed
+fn scan(source: &str) -> (Vec<ImportSite>, Vec<(ImportKind, usize)>) {
+    let mut scanner = Scanner {
+        src: source.as_bytes(),
+        pos: 0,
+        brace_depth: 0,
+        template_depths: Vec::new(),
+        last: Token::None,
+        imports: Vec::new(),
+        computed: Vec::new(),
+    };
+    scanner.run();
+    (scanner.imports, scanner.computed)
+}
+
+fn line_at(source: &str, position: usize) -> usize {
+    source.as_bytes()[..position]
+        .iter()
+        .filter(|&&c| c == b'\n')
+        .count()
+        + 1
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Token {
+    None,
+    Punct(u8),
+    Keyword,
+    Value,
+}
+
+struct Scanner<'a> {
+    src: &'a [u8],
+    pos: usize,
+    brace_depth: usize,
+    template_depths: Vec<usize>,
+    last: Token,
+    imports: Vec<ImportSite>,
+    computed: Vec<(ImportKind, usize)>,
+}
+
+// Keywords after which a `/` starts a regular expression instead of a division
+const REGEX_KEYWORDS: &[&str] = &[
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+];
+
+impl<'a> Scanner<'a> {
+    fn run(&mut self) {
+        if self.src.starts_with(b"#!") {
+            self.skip_line();
+        }
+
+        while let Some(&c) = self.src.get(self.pos) {
+            match c {
+                b' ' | b'\t' | b'\n' | b'\r' => self.pos += 1,
+                b'/' => match self.src.get(self.pos + 1) {
+                    Some(b'/') => self.skip_line(),
+                    Some(b'*') => self.skip_block_comment(),
+                    _ if self.regex_allowed() => {
+                        self.skip_regex();
+                        self.last = Token::Value;
+                    },
+                    _ => {
+                        self.pos += 1;
+                        self.last = Token::Punct(c);
+                    },
+                },
+                b'\'' | b'"' => {
+                    self.read_string();
+                    self.last = Token::Value;
+                },
+                b'`' => {
+                    self.pos += 1;
+                    self.skip_template();
+                },
+                b'{' => {
+                    self.brace_depth += 1;
+                    self.pos += 1;
+                    self.last = Token::Punct(c);
+                },
+                b'}' => {
+                    self.pos += 1;
+                    if self.template_depths.last() == Some(&self.brace_depth) {
+                        self.template_depths.pop();
+                        self.brace_depth -= 1;
+                        self.skip_template();
+                    } else {
+                        self.brace_depth = self.brace_depth.saturating_sub(1);
+                        self.last = Token::Punct(c);
+                    }
+                },
+                c if is_ident_start(c) => {
+                    let after_dot = self.last == Token::Punct(b'.');
+                    let ident = self.read_ident();
+                    self.last = if REGEX_KEYWORDS.contains(&ident) {
+                        Token::Keyword
+                    } else {
+                        Token::Value
+                    };
+                    if !after_dot {
+                        match ident {
+                            "import" => self.scan_import(),
+                            "export" => self.scan_export(),
+                            "require" => self.scan_require(),
+                            _ => {},
+                        }
+                    }
+                },
+                c if c.is_ascii_digit() => {
+                    while self
+                        .src
+                        .get(self.pos)
+                        .is_some_and(|c| c.is_ascii_alphanumeric() || *c == b'.' || *c == b'_')
+                    {
+                        self.pos += 1;
+                    }
+                    self.last = Token::Value;
+                },
+                _ => {
+                    self.pos += 1;
+                    self.last = Token::Punct(c);
+                },
+            }
+        }
+    }
+
+    fn regex_allowed(&self) -> bool {
+        match self.last {
+            Token::None | Token::Keyword => true,
+            Token::Value => false,
+            Token::Punct(c) => !matches!(c, b')' | b']' | b'}'),
+        }
+    }
+
+    fn scan_import(&mut self) {
+        let start = self.pos;
+        self.skip_trivia();
+        match self.src.get(self.pos) {
+            Some(b'(') => {
+                self.pos += 1;
+                if let Some((specifier, literal, attributes)) = self.read_call_argument() {
+                    self.push(specifier, ImportKind::Import, literal, attributes);
+                    return;
+                }
+                self.computed.push((ImportKind::Import, start));
+            },
+            Some(b'\'' | b'"') => {
+                let literal_start = self.pos;
+                if let Some(specifier) = self.read_string() {
+                    let literal = literal_start..self.pos;
+                    let attributes = self.read_with_clause();
+                    self.push(specifier, ImportKind::Import, literal, attributes);
+                    return;
+                }
+            },
+            Some(b'.') => {},
+            Some(_) => {
+                if let Some((specifier, literal)) = self.read_from_clause() {
+                    let attributes = self.read_with_clause();
+                    self.push(specifier, ImportKind::Import, literal, attributes);
+                    return;
+                }
+            },
+            None => {},
+        }
+        self.pos = start;
+    }
+
+    fn scan_export(&mut self) {
+        let start = self.pos;
+        self.skip_trivia();
+        if matches!(self.src.get(self.pos), Some(b'*' | b'{')) {
+            if let Some((specifier, literal)) = self.read_from_clause() {
+                let attributes = self.read_with_clause();
+                self.push(specifier, ImportKind::Import, literal, attributes);
+                return;
+            }
+        }
+        self.pos = start;
+    }
+
+    fn scan_require(&mut self) {
+        let start = self.pos;
+        self.skip_trivia();
+        if self.src.get(self.pos) == Some(&b'(') {
+            self.pos += 1;
+            if let Some((specifier, literal, _)) = self.read_call_argument() {
+                self.push(specifier, ImportKind::Require, literal, None);
+                return;
+            }
+            if !self.declares_function(start - "require".len()) {
+                self.computed.push((ImportKind::Require, start));
+            }
+        }
+        self.pos = start;
+    }
+
+    // Whether the identifier at `ident_start` names a function declaration,
+    // like `function require(id) {`
+    fn declares_function(&self, ident_start: usize) -> bool {
+        self.src[..ident_start]
+            .trim_ascii_end()
+            .strip_suffix(b"function")
+            .is_some_and(|before| {
+                !before
+                    .last()
+                    .is_some_and(|c| is_ident_start(*c) || c.is_ascii_digit())
+            })
+    }
+
+    fn push(
+        &mut self,
+        specifier: String,
+        kind: ImportKind,
+        literal: Range<usize>,
+        attributes: Option<ImportAttributes>,
+    ) {
+        self.imports.push(ImportSite {
+            specifier,
+            kind,
+            literal,
+            attributes,
+        });
+    }
+
+    // Reads `"specifier")` or `"specifier", options)` after an opening paren
+    fn read_call_argument(&mut self) -> Option<(String, Range<usize>, Option<ImportAttributes>)> {
+        self.skip_trivia();
+        let literal_start = self.pos;
+        let specifier = match self.src.get(self.pos)? {
+            b'\'' | b'"' => self.read_string()?,
+            b'`' => self.read_plain_template()?,
+            _ => return None,
+        };
+        let literal = literal_start..self.pos;
+        self.skip_trivia();
+        let attributes = match self.src.get(self.pos)? {
+            b')' => None,
+            b',' => {
+                let comma = self.pos;
+                let attributes = self.read_import_options();
+                if attributes.is_none() {
+                    self.pos = comma;
+                }
+                attributes
+            },
+            _ => return None,
+        };
+        self.last = Token::Value;
+        Some((specifier, literal, attributes))
+    }
+
+    // Reads the bindings of an import or re-export up to `from "specifier"`
+    fn read_from_clause(&mut self) -> Option<(String, Range<usize>)> {
+        let mut in_braces = false;
+        loop {
+            self.skip_trivia();
+            let c = *self.src.get(self.pos)?;
+            match c {
+                b'{' if !in_braces => {
+                    in_braces = true;
+                    self.pos += 1;
+                },
+                b'}' if in_braces => {
+                    in_braces = false;
+                    self.pos += 1;
+                },
+                b'*' | b',' => self.pos += 1,
+                b'\'' | b'"' if in_braces => {
+                    self.read_string()?;
+                },
+                c if is_ident_start(c) => {
+                    let ident = self.read_ident();
+                    if ident == "from" && !in_braces {
+                        self.skip_trivia();
+                        if !matches!(self.src.get(self.pos), Some(b'\'' | b'"')) {
+                            return None;
+                        }
+                        let literal_start = self.pos;
+                        let specifier = self.read_string()?;
+                        self.last = Token::Value;
+                        return Some((specifier, literal_start..self.pos));
+                    }
+                },
+                _ => return None,
+            }
+        }
+    }
+
+    // Reads `with { type: "json" }` after the specifier of a static import,
+    // leaving the position untouched when there is none
+    fn read_with_clause(&mut self) -> Option<ImportAttributes> {
+        let start = self.pos;
+        self.skip_trivia();
+        let clause_start = self.pos;
+        if self.src.get(self.pos).is_some_and(|&c| is_ident_start(c)) && self.read_ident() == "with"
+        {
+            if let Some(entries) = self.read_attribute_entries() {
+                return Some(ImportAttributes {
+                    entries,
+                    clause: clause_start..self.pos,
+                });
+            }
+        }
+        self.pos = start;
+        None
+    }
+
+    // Reads `, { with: { type: "json" } })` up to the closing paren of an
+    // `import()` call
+    fn read_import_options(&mut self) -> Option<ImportAttributes> {
+        let start = self.pos;
+        self.pos += 1;
+        self.skip_trivia();
+        if self.src.get(self.pos) != Some(&b'{') {
+            return None;
+        }
+        self.pos += 1;
+        self.skip_trivia();
+        let key = match *self.src.get(self.pos)? {
+            b'\'' | b'"' => self.read_string()?,
+            c if is_ident_start(c) => self.read_ident().to_string(),
+            _ => return None,
+        };
+        self.skip_trivia();
+        if key != "with" || self.src.get(self.pos) != Some(&b':') {
+            return None;
+        }
+        self.pos += 1;
+        let entries = self.read_attribute_entries()?;
+        for close in [b'}', b')'] {
+            self.skip_trivia();
+            if self.src.get(self.pos) == Some(&b',') {
+                self.pos += 1;
+                self.skip_trivia();
+            }
+            if self.src.get(self.pos) != Some(&close) {
+                return None;
+            }
+            if close == b'}' {
+                self.pos += 1;
+            }
+        }
+        Some(ImportAttributes {
+            entries,
+            clause: start..self.pos,
+        })
+    }
+
+    // Reads `{ key: "value", .. }`
+    fn read_attribute_entries(&mut self) -> Option<Vec<(String, String)>> {
+        self.skip_trivia();
+        if self.src.get(self.pos) != Some(&b'{') {
+            return None;
+        }
+        self.pos += 1;
+        let mut entries = Vec::new();
+        loop {
+            self.skip_trivia();
+            let key = match *self.src.get(self.pos)? {
+                b'}' => {
+                    self.pos += 1;
+                    return Some(entries);
+                },
+                b'\'' | b'"' => self.read_string()?,
+                c if is_ident_start(c) => self.read_ident().to_string(),
+                _ => return None,
+            };
+            self.skip_trivia();
+            if self.src.get(self.pos) != Some(&b':') {
+                return None;
+            }
+            self.pos += 1;
+            self.skip_trivia();
+            if !matches!(self.src.get(self.pos), Some(b'\'' | b'"')) {
+                return None;
+            }
+            let value = self.read_string()?;
+            entries.push((key, value));
+            self.skip_trivia();
+            match *self.src.get(self.pos)? {
+                b',' => self.pos += 1,
+                b'}' => {},
+                _ => return None,
+            }
+        }
+    }
+
+    fn read_ident(&mut self) -> &'a str {
+        let start = self.pos;
+        while self
+            .src
+            .get(self.pos)
+            .is_some_and(|c| is_ident_start(*c) || c.is_ascii_digit())
+        {
+            self.pos += 1;
+        }
+        std::str::from_utf8(&self.src[start..self.pos]).unwrap_or_default()
+    }
+
+    // Reads a quoted string starting at the current quote and returns its
+    // contents, or `None` if it is unterminated
+    fn read_string(&mut self) -> Option<String> {
+        let quote = self.src[self.pos];
+        self.pos += 1;
+        let mut value = Vec::new();
+        while let Some(&c) = self.src.get(self.pos) {
+            self.pos += 1;
+            match c {
+                b'\\' => {
+                    if let Some(&escaped) = self.src.get(self.pos) {
+                        value.push(escaped);
+                        self.pos += 1;
+                    }
+                },
+                b'\n' => return None,
+                c if c == quote => return String::from_utf8(value).ok(),
+                c => value.push(c),
+            }
+        }
+        None
+    }
+
+    // Reads a template literal without substitutions, used as a specifier
+    fn read_plain_template(&mut self) -> Option<String> {
+        let start = self.pos + 1;
+        let end = start + self.src[start..].iter().position(|&c| c == b'`')?;
+        let value = &self.src[start..end];
+        if value.contains(&b'$') || value.contains(&b'\\') {
+            return None;
+        }
+        self.pos = end + 1;
+        String::from_utf8(value.to_vec()).ok()
+    }
+
+    // Skips template characters until the closing backtick or the start of a
+    // substitution, which is then scanned as regular code
+    fn skip_template(&mut self) {
+        while let Some(&c) = self.src.get(self.pos) {
+            self.pos += 1;
+            match c {
+                b'\\' => self.pos += 1,
+                b'`' => {
+                    self.last = Token::Value;
+                    return;
+                },
+                b'$' if self.src.get(self.pos) == Some(&b'{') => {
+                    self.pos += 1;
+                    self.brace_depth += 1;
+                    self.template_depths.push(self.brace_depth);
+                    self.last = Token::Punct(b'{');
+                    return;
+                },
+                _ => {},
+            }
+        }
+    }
+
+    fn skip_regex(&mut self) {
+        self.pos += 1;
+        let mut in_class = false;
+        while let Some(&c) = self.src.get(self.pos) {
+            self.pos += 1;
+            match c {
+                b'\\' => self.pos += 1,
+                b'[' => in_class = true,
+                b']' => in_class = false,
+                b'/' if !in_class => break,
+                b'\n' => return,
+                _ => {},
+            }
+        }
+        // flags
+        while self
+            .src
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_alphabetic())
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn skip_line(&mut self) {
+        while let Some(&c) = self.src.get(self.pos) {
+            if c == b'\n' {
+                break;
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn skip_block_comment(&mut self) {
+        self.pos += 2;
+        while self.pos < self.src.len() {
+            if self.src[self.pos..].starts_with(b"*/") {
+                self.pos += 2;
+                return;
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn skip_trivia(&mut self) {
+        while let Some(&c) = self.src.get(self.pos) {
+            match c {
+                b' ' | b'\t' | b'\n' | b'\r' => self.pos += 1,
+                b'/' if self.src.get(self.pos + 1) == Some(&b'/') => self.skip_line(),
+                b'/' if self.src.get(self.pos + 1) == Some(&b'*') => self.skip_block_comment(),
+                _ => break,
+            }
+        }
+    }
+}
+
+fn is_ident_start(c: u8) -> bool {
+    c.is_ascii_alphabetic() || c == b'_' || c == b'$' || c >= 0x80
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{expand_glob, line_at, scan, scan_import_sites, wildcard_match, ImportKind};
+
+    fn specifiers(source: &str) -> Vec<(String, ImportKind)> {
+        scan_import_sites(source)
+            .into_iter()
+            .map(|site| (site.specifier, site.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_scan_static_imports() {
+        let source = r#"
+            import a from "./a.js";
+            import { b, c as d } from './b.js'
+            import * as e from "e"
+            import f, { g } from "./f.mjs";
+            import "./side-effect.js";
+            export * from "./reexport.js";
+            export { h } from "./h.js";
+            export { local };
+            export const value = 1;
+        "#;
+        let found: Vec<String> = specifiers(source).into_iter().map(|(s, _)| s).collect();
+        assert_eq!(
+            found,
+            vec![
+                "./a.js",
+                "./b.js",
+                "e",
+                "./f.mjs",
+                "./side-effect.js",
+                "./reexport.js",
+                "./h.js"
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_dynamic_imports_and_requires() {
+        let source = r#"
+            const a = require("./a.cjs");
+            const b = await import('./b.js');
+            const c = import(`./c.js`);
+            const d = require(name);
+            const e = obj.require("./not-this.js");
+            const meta = import.meta.url;
+        "#;
+        assert_eq!(
+            specifiers(source),
+            vec![
+                ("./a.cjs".to_string(), ImportKind::Require),
+                ("./b.js".to_string(), ImportKind::Import),
+                ("./c.js".to_string(), ImportKind::Import),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_computed_specifiers() {
+        let source = r#"
+            const a = require(name);
+            const b = await import(`./locale/${lang}.js`);
+            const c = import("./" + file);
+            function require(id) {}
+            const d = require("./d.cjs");
+        "#;
+        let (sites, computed) = scan(source);
+        assert_eq!(sites.len(), 1);
+        let computed: Vec<_> = computed
+            .into_iter()
+            .map(|(kind, position)| (kind, line_at(source, position)))
+            .collect();
+        assert_eq!(
+            computed,
+            vec![
+                (ImportKind::Require, 2),
+                (ImportKind::Import, 3),
+                (ImportKind::Import, 4),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_scan_skips_comments_strings_and_regex() {
+        let source = r#"#!/usr/bin/env llrt
+            // import a from "./comment.js";
+            /* require("./block.js") */
+            const s = "import b from './string.js'";
+            const t = `${require("./template.js")} import c from "./tpl.js"`;
+            const r = /import d from "\.\/regex.js"/g;
+            const ratio = total / count / 2;
+            import e from "./real.js";
+        "#;
+        assert_eq!(
+            specifiers(source),
+            vec![
+                ("./template.js".to_string(), ImportKind::Require),
+                ("./real.js".to_string(), ImportKind::Import),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_wildcard_match() {
+        assert!(wildcard_match(b"*.json", b"schema.json"));
+        assert!(wildcard_match(b"a?c", b"abc"));
+        assert!(wildcard_match(b"*", b""));
+        assert!(wildcard_match(b"*b*d", b"abcbd"));
+        assert!(!wildcard_match(b"*.json", b"schema.yaml"));
+        assert!(!wildcard_match(b"a?c", b"ac"));
+    }
+
+    #[test]
+    fn test_expand_glob() {
+        let base = std::env::temp_dir().join(format!("lexe_glob_{}", std::process::id()));
+        fs::create_dir_all(base.join("templates/email")).unwrap();
+        fs::write(base.join("templates/index.html"), "").unwrap();
+        fs::write(base.join("templates/email/welcome.html"), "").unwrap();
+        fs::write(base.join("templates/email/welcome.txt"), "").unwrap();
+        fs::write(base.join("config.json"), "").unwrap();
+
+        let relative = |pattern: &str| -> Vec<String> {
+            expand_glob(&base, pattern)
+                .unwrap()
+                .iter()
+                .map(|path| {
+                    let path = path.strip_prefix(&base).unwrap();
+                    path.to_string_lossy().replace('\\', "/")
+                })
+                .collect()
+        };
+
+        assert_eq!(
+            relative("templates/**"),
+            vec![
+                "templates/email/welcome.html",
+                "templates/email/welcome.txt",
+                "templates/index.html"
+            ]
+        );
+        assert_eq!(
+            relative("templates/**/*.html"),
+            vec!["templates/email/welcome.html", "templates/index.html"]
+        );
+        assert_eq!(relative("*.json"), vec!["config.json"]);
+        assert_eq!(relative("./templates/email"), relative("templates/email/*"));
+        assert!(relative("missing/**").is_empty());
+
+        fs::remove_dir_all(&base).unwrap();
+    }
+}
@@ -17,8 +17,8 @@ use rquickjs::{
 use crate::libs::{
     json::{escape::escape_json, stringify::json_stringify},
     logging::{
-        build_formatted_string, replace_newline_with_carriage_return, FormatOptions, LogLevel,
-        NEWLINE, TIME_FORMAT,
+        build_formatted_string, map_stack_trace, replace_newline_with_carriage_return,
+        FormatOptions, LogLevel, NEWLINE, TIME_FORMAT,
     },
     utils::{
         class::get_class_name,
@@ -239,7 +239,7 @@ fn write_lambda_log<'js>(
                     if let Some(message) = exception.message() {
                         obj.set("errorMessage", message)?;
                     }
-                    if let Some(stack) = exception.stack() {
+                    if let Some(stack) = exception.stack().map(map_stack_trace) {
                         let stack_object = Array::new(ctx.clone())?;
 
                         for (i, trace) in stack.split('\n').enumerate() {
@@ -294,7 +294,7 @@ fn write_lambda_log<'js>(
                 //stack trace
                 result.push_str("\"stackTrace\":[");
                 let mut write_comma = false;
-                if let Some(stack) = exception.stack() {
+                if let Some(stack) = exception.stack().map(map_stack_trace) {
                     if !stack.is_empty() {
                         for trace in stack.split('\n') {
                             if write_comma {
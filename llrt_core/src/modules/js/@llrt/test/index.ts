@@ -233,7 +233,11 @@ class TestServer {
     delete env.LLRT_LOG;
     const proc = spawn(
       process.argv0,
-      ["-e", `import("llrt:test/worker").catch(console.error)`],
+      [
+        ...process.execArgv,
+        "-e",
+        `import("llrt:test/worker").catch(console.error)`,
+      ],
       {
         env,
       }
@@ -18,6 +18,7 @@ use rquickjs::{
 use tokio::time::Instant;
 use tracing::trace;
 
+use crate::archive;
 use crate::bytecode::BYTECODE_FILE_EXT;
 use crate::libs::{
     json::parse::json_parse,
@@ -175,7 +176,12 @@ pub fn init(ctx: &Ctx, module_names: HashSet<&'static str>) -> Result<()> {
             }
 
             if is_json {
-                let json = fs::read_to_string(import_name.as_ref())?;
+                let json = match archive::embedded()
+                    .and_then(|archive| archive.file(import_name.as_ref()))
+                {
+                    Some(bytes) => String::from_utf8_lossy(bytes).into_owned(),
+                    None => fs::read_to_string(import_name.as_ref())?,
+                };
                 let json = json_parse(&ctx, json)?;
                 state.cache.insert(import_name, json.clone());
                 return Ok(json);
@@ -0,0 +1,517 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+//! Caches used by the resolver: parsed `package.json` files, package scopes,
+//! paths found missing during a resolution and the opt-in persistent
+//! resolution cache set by `LLRT_RESOLVE_CACHE`.
+use std::{
+    cell::RefCell,
+    collections::{HashMap, HashSet},
+    fmt,
+    fs::{self, File, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    time::UNIX_EPOCH,
+};
+
+use once_cell::sync::Lazy;
+use rquickjs::{Ctx, Result};
+use simd_json::BorrowedValue;
+use tracing::trace;
+
+use crate::environment;
+use crate::libs::utils::result::ResultExt;
+use crate::modules::path;
+
+use super::{
+    tsconfig::CONFIG_FILES, BYTES_IMPORT_PREFIX, CJS_LOADER_PREFIX, JSON_IMPORT_PREFIX,
+    TEXT_IMPORT_PREFIX,
+};
+
+static PACKAGE_JSON_CACHE: Lazy<Cache<Arc<BorrowedValue<'static>>>> =
+    Lazy::new(|| Cache::new("PACKAGE_JSON_CACHE"));
+
+//None entry means that neither the path nor its parents contain a package.json
+pub static PACKAGE_SCOPE_CACHE: Lazy<Cache<Option<Box<str>>>> =
+    Lazy::new(|| Cache::new("PACKAGE_SCOPE_CACHE"));
+
+thread_local! {
+    // Paths found missing during the current resolution pass, `None` outside of one
+    static MISSING_PATHS: RefCell<Option<HashSet<Box<str>>>> = const { RefCell::new(None) };
+}
+
+static MISSING_PATHS_STATS: Lazy<Stats> = Lazy::new(|| Stats::new("MISSING_PATHS_CACHE"));
+
+static RESOLUTION_CACHE: Lazy<Option<ResolutionCache>> = Lazy::new(|| {
+    let file = environment::var(environment::ENV_LLRT_RESOLVE_CACHE).ok()?;
+    if file.is_empty() {
+        return None;
+    }
+    match ResolutionCache::open(Path::new(&file)) {
+        Ok(cache) => Some(cache),
+        Err(err) => {
+            trace!("Ignoring resolution cache {}: {}", file, err);
+            None
+        },
+    }
+});
+
+#[derive(Debug)]
+struct Stats {
+    name: &'static str,
+    hits: AtomicUsize,
+    misses: AtomicUsize,
+}
+
+impl Stats {
+    fn new(name: &'static str) -> Self {
+        Self {
+            name,
+            hits: AtomicUsize::new(0),
+            misses: AtomicUsize::new(0),
+        }
+    }
+
+    fn hit(&self, key: impl fmt::Display) {
+        let hits = self.hits.fetch_add(1, Ordering::Relaxed) + 1;
+        trace!(
+            "|  {} hit: {} (hits: {}, misses: {})",
+            self.name,
+            key,
+            hits,
+            self.misses.load(Ordering::Relaxed)
+        );
+    }
+
+    fn miss(&self, key: impl fmt::Display) {
+        let misses = self.misses.fetch_add(1, Ordering::Relaxed) + 1;
+        trace!(
+            "|  {} miss: {} (hits: {}, misses: {})",
+            self.name,
+            key,
+            self.hits.load(Ordering::Relaxed),
+            misses
+        );
+    }
+}
+
+/// A map from paths to values computed from the file system, kept for the
+/// lifetime of the runtime. Hits and misses are counted in the trace output.
+#[derive(Debug)]
+pub struct Cache<V> {
+    entries: Mutex<HashMap<Box<str>, V>>,
+    stats: Stats,
+}
+
+impl<V: Clone> Cache<V> {
+    fn new(name: &'static str) -> Self {
+        Self {
+            entries: Mutex::new(HashMap::new()),
+            stats: Stats::new(name),
+        }
+    }
+
+    fn get(&self, key: &str) -> Option<V> {
+        let value = self.entries.lock().unwrap().get(key).cloned();
+        match value {
+            Some(_) => self.stats.hit(key),
+            None => self.stats.miss(key),
+        }
+        value
+    }
+
+    /// Returns the cached value of `key`, or computes and caches it.
+    pub fn get_or_insert_with(&self, key: &str, f: impl FnOnce() -> V) -> V {
+        if let Some(value) = self.get(key) {
+            return value;
+        }
+        let value = f();
+        self.insert(key, value.clone());
+        value
+    }
+
+    /// Like [`Cache::get_or_insert_with`], errors are returned without being
+    /// cached.
+    pub fn get_or_try_insert_with<E>(
+        &self,
+        key: &str,
+        f: impl FnOnce() -> std::result::Result<V, E>,
+    ) -> std::result::Result<V, E> {
+        if let Some(value) = self.get(key) {
+            return Ok(value);
+        }
+        let value = f()?;
+        self.insert(key, value.clone());
+        Ok(value)
+    }
+
+    fn insert(&self, key: &str, value: V) {
+        self.entries.lock().unwrap().insert(key.into(), value);
+    }
+
+    fn clear(&self) {
+        self.entries.lock().unwrap().clear();
+    }
+}
+
+/// Empties the in-memory caches. The persistent resolution cache checks its
+/// entries itself and is kept.
+pub fn clear() {
+    PACKAGE_JSON_CACHE.clear();
+    PACKAGE_SCOPE_CACHE.clear();
+}
+
+/// Runs `f` as one resolution pass. Paths found missing are not probed again
+/// until the pass ends, so files created afterwards are found by the next
+/// one.
+pub fn resolution_pass<R>(f: impl FnOnce() -> R) -> R {
+    struct Pass;
+
+    impl Drop for Pass {
+        fn drop(&mut self) {
+            MISSING_PATHS.with(|missing| missing.borrow_mut().take());
+        }
+    }
+
+    if MISSING_PATHS.with(|missing| missing.borrow().is_some()) {
+        return f();
+    }
+    MISSING_PATHS.with(|missing| *missing.borrow_mut() = Some(HashSet::new()));
+    let _pass = Pass;
+    f()
+}
+
+/// Reads and parses a `package.json` once per runtime.
+pub fn package_json(ctx: &Ctx<'_>, path: &str) -> Result<Arc<BorrowedValue<'static>>> {
+    PACKAGE_JSON_CACHE.get_or_try_insert_with(path, || {
+        let mut json = fs::read(path).or_throw(ctx)?;
+        let value = simd_json::to_borrowed_value(&mut json).or_throw(ctx)?;
+        Ok(Arc::new(value.into_static()))
+    })
+}
+
+/// The metadata of `path`, or `None` when it does not exist. Paths that do
+/// not exist are remembered until the end of the [`resolution_pass`].
+pub fn metadata(path: &str) -> Option<fs::Metadata> {
+    let is_missing = MISSING_PATHS.with(|missing| {
+        let missing = missing.borrow();
+        missing
+            .as_ref()
+            .is_some_and(|missing| missing.contains(path))
+    });
+    if is_missing {
+        MISSING_PATHS_STATS.hit(path);
+        return None;
+    }
+    match fs::metadata(path) {
+        Ok(metadata) => Some(metadata),
+        Err(err) => {
+            if err.kind() == io::ErrorKind::NotFound {
+                MISSING_PATHS.with(|missing| {
+                    if let Some(missing) = missing.borrow_mut().as_mut() {
+                        MISSING_PATHS_STATS.miss(path);
+                        missing.insert(path.into());
+                    }
+                });
+            }
+            None
+        },
+    }
+}
+
+pub fn is_file(path: &str) -> bool {
+    metadata(path).is_some_and(|metadata| metadata.is_file())
+}
+
+pub fn is_dir(path: &str) -> bool {
+    metadata(path).is_some_and(|metadata| metadata.is_dir())
+}
+
+pub fn exists(path: &str) -> bool {
+    metadata(path).is_some()
+}
+
+/// Returns the path a previous run resolved `specifier` to from `base`, when
+/// `LLRT_RESOLVE_CACHE` is set, that path still exists and the files the
+/// resolution depends on are unchanged.
+pub fn cached_resolution(base: &str, specifier: &str, is_esm: bool) -> Option<String> {
+    RESOLUTION_CACHE.as_ref()?.get(base, specifier, is_esm)
+}
+
+/// Records a resolution in the `LLRT_RESOLVE_CACHE` file, if there is one.
+pub fn cache_resolution(base: &str, specifier: &str, is_esm: bool, path: &str) {
+    if let Some(cache) = RESOLUTION_CACHE.as_ref() {
+        cache.insert(base, specifier, is_esm, path);
+    }
+}
+
+/// Resolutions of earlier runs, stored one per line as NUL separated
+/// `kind`, `base`, `specifier`, `stamp` and `path` fields. The stamp holds
+/// the modification times of the files the resolution depends on, see
+/// [`stamp`]. New resolutions are appended as they are made, so the file is
+/// complete even when the process exits early, and the file is rewritten
+/// without superseded lines when it is opened.
+#[derive(Debug)]
+struct ResolutionCache {
+    entries: Mutex<HashMap<String, Entry>>,
+    file: Mutex<File>,
+    stats: Stats,
+}
+
+#[derive(Debug, PartialEq)]
+struct Entry {
+    stamp: String,
+    path: String,
+}
+
+impl ResolutionCache {
+    fn open(path: &Path) -> io::Result<Self> {
+        let contents = match fs::read(path) {
+            Ok(contents) => contents,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Vec::new(),
+            Err(err) => return Err(err),
+        };
+        let entries = parse_entries(&String::from_utf8_lossy(&contents));
+        compact(path, &entries)?;
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            entries: Mutex::new(entries),
+            file: Mutex::new(file),
+            stats: Stats::new("RESOLUTION_CACHE"),
+        })
+    }
+
+    fn get(&self, base: &str, specifier: &str, is_esm: bool) -> Option<String> {
+        let key = entry_key(base, specifier, is_esm);
+        let mut entries = self.entries.lock().unwrap();
+        if let Some(entry) = entries.get(&key) {
+            // The target may have been removed since, or the files deciding
+            // the resolution changed, resolve it again then
+            if Path::new(module_file(&entry.path)).is_file()
+                && entry.stamp == stamp(base, &entry.path)
+            {
+                self.stats.hit(format_args!("{} from {}", specifier, base));
+                return Some(entry.path.clone());
+            }
+            entries.remove(&key);
+        }
+        self.stats.miss(format_args!("{} from {}", specifier, base));
+        None
+    }
+
+    fn insert(&self, base: &str, specifier: &str, is_esm: bool, path: &str) {
+        // Only files are worth caching, not names of embedded modules
+        if !path::is_absolute(module_file(path)) {
+            return;
+        }
+        let key = entry_key(base, specifier, is_esm);
+        if [key.as_str(), path]
+            .iter()
+            .any(|field| field.contains('\n'))
+        {
+            return;
+        }
+        let entry = Entry {
+            stamp: stamp(base, path),
+            path: path.into(),
+        };
+        let mut entries = self.entries.lock().unwrap();
+        if entries.get(&key) == Some(&entry) {
+            return;
+        }
+        if let Err(err) = self
+            .file
+            .lock()
+            .unwrap()
+            .write_all(line(&key, &entry).as_bytes())
+        {
+            trace!("Failed to write resolution cache: {}", err);
+        }
+        entries.insert(key, entry);
+    }
+}
+
+fn entry_key(base: &str, specifier: &str, is_esm: bool) -> String {
+    let kind = if is_esm { "esm" } else { "cjs" };
+    [kind, "\0", base, "\0", specifier].concat()
+}
+
+fn line(key: &str, entry: &Entry) -> String {
+    [key, "\0", &entry.stamp, "\0", &entry.path, "\n"].concat()
+}
+
+// Later lines win, so a target that moved is recorded again without
+// rewriting the file
+fn parse_entries(contents: &str) -> HashMap<String, Entry> {
+    contents
+        .lines()
+        .filter_map(|line| {
+            let (rest, path) = line.rsplit_once('\0')?;
+            let (key, stamp) = rest.rsplit_once('\0')?;
+            (key.split('\0').count() == 3).then(|| {
+                let entry = Entry {
+                    stamp: stamp.into(),
+                    path: path.into(),
+                };
+                (key.to_string(), entry)
+            })
+        })
+        .collect()
+}
+
+// Rewrites the file with one line per entry
+fn compact(path: &Path, entries: &HashMap<String, Entry>) -> io::Result<()> {
+    let contents: String = entries
+        .iter()
+        .map(|(key, entry)| line(key, entry))
+        .collect();
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    fs::write(&temp, contents)?;
+    fs::rename(&temp, path)
+}
+
+// The modification times of the files that decide how `base` resolves to
+// `path`: the nearest tsconfig.json or jsconfig.json and package.json of
+// `base` and the package.json of `path`
+fn stamp(base: &str, path: &str) -> String {
+    let base = Path::new(base);
+    let base_dir = if base.is_dir() {
+        Some(base)
+    } else {
+        base.parent()
+    };
+    let target_dir = Path::new(module_file(path)).parent();
+    let inputs = [
+        base_dir.and_then(|dir| nearest(dir, &CONFIG_FILES)),
+        base_dir.and_then(|dir| nearest(dir, &["package.json"])),
+        target_dir.and_then(|dir| nearest(dir, &["package.json"])),
+    ];
+    inputs
+        .iter()
+        .map(|input| {
+            input
+                .as_ref()
+                .and_then(|input| fs::metadata(input).ok()?.modified().ok())
+                .and_then(|modified| modified.duration_since(UNIX_EPOCH).ok())
+                .map(|modified| modified.as_nanos().to_string())
+                .unwrap_or_default()
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+// The first of `names` found in `dir` or its closest parent
+fn nearest(dir: &Path, names: &[&str]) -> Option<PathBuf> {
+    dir.ancestors()
+        .flat_map(|dir| names.iter().map(move |name| dir.join(name)))
+        .find(|file| file.is_file())
+}
+
+// The file behind a resolved module name
+fn module_file(path: &str) -> &str {
+    [
+        CJS_LOADER_PREFIX,
+        JSON_IMPORT_PREFIX,
+        TEXT_IMPORT_PREFIX,
+        BYTES_IMPORT_PREFIX,
+    ]
+    .iter()
+    .find_map(|prefix| path.strip_prefix(prefix))
+    .unwrap_or(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::{self, File},
+        time::{Duration, SystemTime},
+    };
+
+    use super::{entry_key, parse_entries, Cache, Entry, ResolutionCache};
+
+    #[test]
+    fn test_cache() {
+        let cache = Cache::new("TEST_CACHE");
+        let mut calls = 0;
+        for _ in 0..2 {
+            let value = cache.get_or_try_insert_with("/app/package.json", || {
+                calls += 1;
+                Ok::<_, ()>(1)
+            });
+            assert_eq!(value, Ok(1));
+        }
+        assert_eq!(calls, 1);
+        assert_eq!(
+            cache.get_or_try_insert_with("/app/a", || Err("failed")),
+            Err("failed")
+        );
+        assert_eq!(cache.get("/app/a"), None);
+    }
+
+    #[test]
+    fn test_parse_entries() {
+        let entries = parse_entries(
+            "esm\0/app/main.js\0lodash\0,1\0/app/node_modules/lodash/lodash.js\n\
+             invalid line\n\
+             cjs\0/app/main.js\0./a\0/app/a.js\n\
+             cjs\0/app/main.js\0./a\0,1\0/app/a.js\n\
+             cjs\0/app/main.js\0./a\0,3\0/app/a/index.js\n",
+        );
+        assert_eq!(entries.len(), 2);
+        assert_eq!(
+            entries[&entry_key("/app/main.js", "lodash", true)].path,
+            "/app/node_modules/lodash/lodash.js"
+        );
+        assert_eq!(
+            entries[&entry_key("/app/main.js", "./a", false)],
+            Entry {
+                stamp: ",3".into(),
+                path: "/app/a/index.js".into()
+            }
+        );
+    }
+
+    #[test]
+    fn test_resolution_cache() {
+        let dir = std::env::temp_dir().join("llrt-test-resolution-cache");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("lib")).unwrap();
+        let dir = dir.canonicalize().unwrap().to_string_lossy().to_string();
+        let (main, lib, package_json, file) = (
+            [&dir, "/main.js"].concat(),
+            [&dir, "/lib/index.js"].concat(),
+            [&dir, "/package.json"].concat(),
+            [&dir, "/cache"].concat(),
+        );
+        fs::write(&main, "").unwrap();
+        fs::write(&lib, "").unwrap();
+        fs::write(&package_json, "{}").unwrap();
+        let old_line = "cjs\0/app/main.js\0./a\0/app/a.js\n";
+        fs::write(&file, [old_line, old_line].concat()).unwrap();
+
+        let cache = ResolutionCache::open(file.as_ref()).unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "");
+        cache.insert(&main, "./lib", true, &lib);
+        assert_eq!(cache.get(&main, "./lib", true), Some(lib.clone()));
+        assert_eq!(cache.get(&main, "./lib", false), None);
+
+        let cache = ResolutionCache::open(file.as_ref()).unwrap();
+        assert_eq!(cache.get(&main, "./lib", true), Some(lib.clone()));
+
+        // A changed package.json may resolve differently
+        let modified = SystemTime::now() + Duration::from_secs(10);
+        File::options()
+            .write(true)
+            .open(&package_json)
+            .unwrap()
+            .set_modified(modified)
+            .unwrap();
+        assert_eq!(cache.get(&main, "./lib", true), None);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
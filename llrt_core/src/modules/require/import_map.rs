@@ -0,0 +1,295 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+//! [Import maps](https://html.spec.whatwg.org/multipage/webappapis.html#import-maps),
+//! applied by the resolver before the Node resolution algorithm.
+//!
+//! Addresses are file paths rather than URLs: they must start with `/`, `./`,
+//! `../` or `file://` and are resolved against the directory of the map. The
+//! mapped path then goes through the Node algorithm, so extensions and
+//! `index` files are found as usual.
+use std::{fs, io, path::Path, result::Result as StdResult};
+
+use once_cell::sync::OnceCell;
+use simd_json::BorrowedValue;
+use tracing::trace;
+
+use crate::modules::path::{self, resolve_path};
+
+static IMPORT_MAP: OnceCell<ImportMap> = OnceCell::new();
+
+// Sorted by key in descending order, so a longer key sharing a prefix with a
+// shorter one comes first. `None` is an invalid address that blocks the key.
+type SpecifierMap = Vec<(String, Option<String>)>;
+
+#[derive(Debug, Default)]
+pub struct ImportMap {
+    imports: SpecifierMap,
+    scopes: Vec<(String, SpecifierMap)>,
+}
+
+impl ImportMap {
+    /// Reads an import map file, resolving addresses against its directory.
+    pub fn load(file: &Path) -> io::Result<Self> {
+        let mut json = fs::read(file)?;
+        let base_dir = file_dir(file)?;
+        Self::parse(&mut json, &base_dir).map_err(|message| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                [&file.to_string_lossy(), ": ", &message].concat(),
+            )
+        })
+    }
+
+    /// Reads the `importMap` field of `dir/package.json`, the path of an
+    /// import map file relative to it or the import map itself.
+    pub fn from_package_json(dir: &Path) -> io::Result<Option<Self>> {
+        let file = dir.join("package.json");
+        let mut json = match fs::read(&file) {
+            Ok(json) => json,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let invalid = |message: &str| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                [&file.to_string_lossy(), ": ", message].concat(),
+            )
+        };
+        let package_json =
+            simd_json::to_borrowed_value(&mut json).map_err(|err| invalid(&err.to_string()))?;
+        let BorrowedValue::Object(package_json) = package_json else {
+            return Ok(None);
+        };
+        match package_json.get("importMap") {
+            None => Ok(None),
+            Some(BorrowedValue::String(path)) => Self::load(&dir.join(&**path)).map(Some),
+            Some(value) => Self::from_value(value, &file_dir(&file)?)
+                .map(Some)
+                .map_err(|message| invalid(&["importMap: ", &message].concat())),
+        }
+    }
+
+    pub fn parse(json: &mut [u8], base_dir: &str) -> StdResult<Self, String> {
+        let value = simd_json::to_borrowed_value(json).map_err(|err| err.to_string())?;
+        Self::from_value(&value, base_dir)
+    }
+
+    fn from_value(value: &BorrowedValue, base_dir: &str) -> StdResult<Self, String> {
+        let BorrowedValue::Object(map) = value else {
+            return Err("an import map must be a JSON object".into());
+        };
+
+        let imports = match map.get("imports") {
+            None => Vec::new(),
+            Some(BorrowedValue::Object(imports)) => specifier_map(imports, base_dir),
+            Some(_) => return Err("\"imports\" must be a JSON object".into()),
+        };
+
+        let mut scopes = Vec::new();
+        match map.get("scopes") {
+            None => {},
+            Some(BorrowedValue::Object(entries)) => {
+                for (prefix, imports) in entries.iter() {
+                    let BorrowedValue::Object(imports) = imports else {
+                        return Err(["scope \"", &**prefix, "\" must be a JSON object"].concat());
+                    };
+                    match parse_address(prefix, base_dir) {
+                        Some(prefix) => scopes.push((prefix, specifier_map(imports, base_dir))),
+                        None => trace!("Ignoring import map scope '{}'", prefix),
+                    }
+                }
+            },
+            Some(_) => return Err("\"scopes\" must be a JSON object".into()),
+        }
+        scopes.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+        Ok(Self { imports, scopes })
+    }
+
+    /// Returns the path `specifier` maps to when imported from the module at
+    /// `base`, or an error when its mapping is invalid.
+    pub fn resolve(&self, specifier: &str, base: &str) -> StdResult<Option<String>, String> {
+        let as_path = parse_address(specifier, &path::dirname(base));
+        let normalized = as_path.as_deref().unwrap_or(specifier);
+
+        for (prefix, imports) in &self.scopes {
+            if base == prefix.as_str()
+                || (prefix.ends_with('/') && base.starts_with(prefix.as_str()))
+            {
+                if let Some(path) = resolve_imports_match(normalized, imports)? {
+                    return Ok(Some(path));
+                }
+            }
+        }
+        resolve_imports_match(normalized, &self.imports)
+    }
+}
+
+/// Makes `import_map` apply to every module resolved from now on. Only the
+/// first installed import map is kept.
+pub fn install(import_map: ImportMap) {
+    let _ = IMPORT_MAP.set(import_map);
+}
+
+pub fn installed() -> Option<&'static ImportMap> {
+    IMPORT_MAP.get()
+}
+
+fn file_dir(file: &Path) -> io::Result<String> {
+    Ok(path::dirname(std::path::absolute(file)?.to_string_lossy()))
+}
+
+fn specifier_map(entries: &simd_json::borrowed::Object<'_>, base_dir: &str) -> SpecifierMap {
+    let mut map: SpecifierMap = entries
+        .iter()
+        .filter(|(key, _)| !key.is_empty())
+        .map(|(key, value)| {
+            let key = parse_address(key, base_dir).unwrap_or_else(|| key.to_string());
+            let address = match value {
+                BorrowedValue::String(address) => parse_address(address, base_dir),
+                _ => None,
+            };
+            // a prefix can only map to another prefix
+            let address = address.filter(|address| !key.ends_with('/') || address.ends_with('/'));
+            if address.is_none() {
+                trace!("Import map entry '{}' has an invalid address", key);
+            }
+            (key, address)
+        })
+        .collect();
+    map.sort_by(|(a, _), (b, _)| b.cmp(a));
+    map
+}
+
+// Resolves a path-like specifier or address against `base_dir`, keeping a
+// trailing slash. Bare specifiers and URLs other than file: are not paths.
+fn parse_address(address: &str, base_dir: &str) -> Option<String> {
+    let address = address.strip_prefix("file://").unwrap_or(address);
+    if !(address.starts_with('/') || address.starts_with("./") || address.starts_with("../")) {
+        return None;
+    }
+    let mut path = resolve_path([base_dir, address]).ok()?;
+    if address.ends_with('/') && !path.ends_with('/') {
+        path.push('/');
+    }
+    Some(path)
+}
+
+fn resolve_imports_match(
+    normalized: &str,
+    imports: &SpecifierMap,
+) -> StdResult<Option<String>, String> {
+    for (key, address) in imports {
+        let rest = if key == normalized {
+            ""
+        } else if key.ends_with('/') {
+            match normalized.strip_prefix(key.as_str()) {
+                Some(rest) => rest,
+                None => continue,
+            }
+        } else {
+            continue;
+        };
+
+        let Some(address) = address else {
+            return Err([
+                "'",
+                normalized,
+                "' is blocked by an invalid import map entry",
+            ]
+            .concat());
+        };
+        if rest.is_empty() {
+            return Ok(Some(address.clone()));
+        }
+        let path = path::normalize([address, rest].concat());
+        if !path.starts_with(address.as_str()) {
+            return Err(["'", normalized, "' backtracks above its import map prefix"].concat());
+        }
+        return Ok(Some(path));
+    }
+    Ok(None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ImportMap;
+
+    fn import_map(json: &str) -> ImportMap {
+        ImportMap::parse(&mut json.as_bytes().to_vec(), "/app").unwrap()
+    }
+
+    #[test]
+    fn test_resolve_imports() {
+        let map = import_map(
+            r#"{
+                "imports": {
+                    "lodash": "./vendor/lodash.js",
+                    "@app/": "./src/",
+                    "@app/config": "./config/index.js",
+                    "/app/old/": "/app/new/",
+                    "bare": "lodash"
+                }
+            }"#,
+        );
+        let resolve = |specifier| map.resolve(specifier, "/app/main.js");
+
+        assert_eq!(resolve("lodash"), Ok(Some("/app/vendor/lodash.js".into())));
+        assert_eq!(
+            resolve("@app/util/a.js"),
+            Ok(Some("/app/src/util/a.js".into()))
+        );
+        assert_eq!(
+            resolve("@app/config"),
+            Ok(Some("/app/config/index.js".into()))
+        );
+        assert_eq!(resolve("./old/a.js"), Ok(Some("/app/new/a.js".into())));
+        assert_eq!(resolve("react"), Ok(None));
+        assert_eq!(resolve("./main.js"), Ok(None));
+        assert!(resolve("bare").is_err());
+        assert!(resolve("@app/../../etc/passwd").is_err());
+    }
+
+    #[test]
+    fn test_resolve_scopes() {
+        let map = import_map(
+            r#"{
+                "imports": { "dep": "./dep/v2.js" },
+                "scopes": {
+                    "./legacy/": { "dep": "./dep/v1.js" },
+                    "./legacy/inner/": { "other": "./other.js" }
+                }
+            }"#,
+        );
+
+        assert_eq!(
+            map.resolve("dep", "/app/main.js"),
+            Ok(Some("/app/dep/v2.js".into()))
+        );
+        assert_eq!(
+            map.resolve("dep", "/app/legacy/a.js"),
+            Ok(Some("/app/dep/v1.js".into()))
+        );
+        // falls back from the most specific scope to the enclosing ones
+        assert_eq!(
+            map.resolve("dep", "/app/legacy/inner/a.js"),
+            Ok(Some("/app/dep/v1.js".into()))
+        );
+        assert_eq!(
+            map.resolve("other", "/app/legacy/inner/a.js"),
+            Ok(Some("/app/other.js".into()))
+        );
+        assert_eq!(map.resolve("other", "/app/legacy/a.js"), Ok(None));
+    }
+
+    #[test]
+    fn test_invalid_import_maps() {
+        for json in [
+            r#"[]"#,
+            r#"{ "imports": [] }"#,
+            r#"{ "scopes": { "/a/": 1 } }"#,
+        ] {
+            assert!(ImportMap::parse(&mut json.as_bytes().to_vec(), "/app").is_err());
+        }
+    }
+}
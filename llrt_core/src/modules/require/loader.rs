@@ -1,27 +1,43 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
-use std::{
-    fs::File,
-    io::{self, Read},
-    result::Result as StdResult,
-};
+use std::{io, result::Result as StdResult};
 
 use once_cell::sync::Lazy;
-use rquickjs::{loader::Loader, Ctx, Function, Module, Object, Result, Value};
+use rquickjs::{loader::Loader, Ctx, Function, Module, Object, Result, TypedArray, Value};
 use tracing::trace;
 use zstd::{bulk::Decompressor, dict::DecoderDictionary};
 
+use crate::archive;
 use crate::bytecode::{
-    BYTECODE_COMPRESSED, BYTECODE_FILE_EXT, BYTECODE_UNCOMPRESSED, BYTECODE_VERSION,
-    SIGNATURE_LENGTH,
+    dictionary_id, read_bytecode_header, Compression, BYTECODE_FILE_EXT, BYTECODE_RUNTIME_VERSION,
+    BYTECODE_VERSION,
 };
-use crate::vm::COMPRESSION_DICT;
+use crate::compile_cache;
+use crate::import_attributes::{rewrite_import_attributes_or_throw, ImportType};
+use crate::libs::json::parse::json_parse;
+use crate::modules::path::dirname;
+use crate::source_map;
+use crate::typescript::{is_typescript, strip_types_or_throw};
+use crate::utils::io::is_commonjs;
+use crate::vm::{bytecode_abi_hash, COMPRESSION_DICT};
+use crate::watch_report;
 
 use super::{CJS_IMPORT_PREFIX, CJS_LOADER_PREFIX};
 
 static DECOMPRESSOR_DICT: Lazy<DecoderDictionary> =
     Lazy::new(|| DecoderDictionary::copy(COMPRESSION_DICT));
 
+static BUILTIN_DICTIONARY_ID: Lazy<Option<u32>> = Lazy::new(|| dictionary_id(COMPRESSION_DICT));
+
+// The dictionary `llrt build --dict` embedded next to the modules
+static EMBEDDED_DECOMPRESSOR_DICT: Lazy<Option<(u32, DecoderDictionary)>> = Lazy::new(|| {
+    let dictionary = archive::embedded()?.dictionary()?;
+    Some((
+        dictionary_id(dictionary)?,
+        DecoderDictionary::copy(dictionary),
+    ))
+});
+
 include!(concat!(env!("OUT_DIR"), "/bytecode_cache.rs"));
 #[cfg(feature = "lambda")]
 include!(concat!(env!("OUT_DIR"), "/sdk_client_endpoints.rs"));
@@ -43,13 +59,14 @@ impl CustomLoader {
         Ok((uncompressed_size, rest))
     }
 
-    fn get_module_bytecode(input: &[u8]) -> Result<Vec<u8>> {
-        let (_, compressed, input) = Self::get_bytecode_signature(input)?;
+    /// Checks the header of a `.lrt` file and returns its uncompressed bytecode.
+    pub fn get_module_bytecode(input: &[u8]) -> Result<Vec<u8>> {
+        let (compression, input) = Self::get_bytecode_signature(input)?;
 
-        if compressed {
-            let (size, input) = Self::uncompressed_size(input)?;
-            let mut buf = Vec::with_capacity(size);
-            let mut decompressor = Decompressor::with_prepared_dictionary(&DECOMPRESSOR_DICT)?;
+        if let Some(compression) = compression {
+            let dictionary = Self::decoder_dictionary(compression.dictionary_id)?;
+            let mut buf = Vec::with_capacity(compression.uncompressed_size as usize);
+            let mut decompressor = Decompressor::with_prepared_dictionary(dictionary)?;
             decompressor.decompress_to_buffer(input, &mut buf)?;
             return Ok(buf);
         }
@@ -57,39 +74,79 @@ impl CustomLoader {
         Ok(input.to_vec())
     }
 
-    fn get_bytecode_signature(input: &[u8]) -> StdResult<(&[u8], bool, &[u8]), io::Error> {
-        let raw_signature = input
-            .get(..SIGNATURE_LENGTH)
-            .ok_or(io::Error::new::<String>(
-                io::ErrorKind::InvalidInput,
-                "Invalid bytecode signature length".into(),
-            ))?;
-
-        let (last, signature) = raw_signature.split_last().unwrap();
-
-        if signature != BYTECODE_VERSION.as_bytes() {
-            return Err(io::Error::new::<String>(
-                io::ErrorKind::InvalidInput,
-                "Invalid bytecode version".into(),
-            ));
+    // Bytecode is compressed with the built-in dictionary, or with the one
+    // embedded by `llrt build --dict`
+    fn decoder_dictionary(id: u32) -> StdResult<&'static DecoderDictionary<'static>, io::Error> {
+        if Some(id) == *BUILTIN_DICTIONARY_ID {
+            return Ok(&DECOMPRESSOR_DICT);
         }
+        let builtin_id = BUILTIN_DICTIONARY_ID.unwrap_or_default();
+        let message = match &*EMBEDDED_DECOMPRESSOR_DICT {
+            Some((embedded_id, dictionary)) if *embedded_id == id => return Ok(dictionary),
+            Some((embedded_id, _)) => format!(
+                "Bytecode was compressed with zstd dictionary {}, which is neither the built-in \
+                 dictionary ({}) nor the dictionary embedded in this executable ({}). \
+                 Compile it again with the dictionary the executable is built with.",
+                id, builtin_id, embedded_id
+            ),
+            None => format!(
+                "Bytecode was compressed with zstd dictionary {}, which is not the built-in \
+                 dictionary ({}) and this executable embeds no dictionary. \
+                 Build it with `llrt build --dict=<file>`.",
+                id, builtin_id
+            ),
+        };
+        Err(io::Error::new(io::ErrorKind::InvalidData, message))
+    }
 
-        let mut compressed = None;
-        if *last == BYTECODE_COMPRESSED {
-            compressed = Some(true)
-        } else if *last == BYTECODE_UNCOMPRESSED {
-            compressed = Some(false)
+    // Refuses bytecode that was not produced by this exact runtime build,
+    // returns how compressed bytecode was compressed and the bytecode
+    fn get_bytecode_signature(input: &[u8]) -> StdResult<(Option<Compression>, &[u8]), io::Error> {
+        let (header, rest) = read_bytecode_header(input).ok_or(io::Error::new::<String>(
+            io::ErrorKind::InvalidInput,
+            "Invalid bytecode signature".into(),
+        ))?;
+
+        let abi_hash = bytecode_abi_hash();
+        if header.version != BYTECODE_VERSION
+            || header.runtime_version != Some(BYTECODE_RUNTIME_VERSION)
+            || header.abi_hash != Some(abi_hash)
+        {
+            let compiled_by = match (header.runtime_version, header.abi_hash) {
+                (Some(runtime_version), Some(compiled_abi_hash)) => [
+                    "LLRT v",
+                    runtime_version,
+                    " (bytecode ",
+                    header.version,
+                    ", QuickJS ABI ",
+                    compiled_abi_hash,
+                    ")",
+                ]
+                .concat(),
+                _ => ["another runtime version (bytecode ", header.version, ")"].concat(),
+            };
+            let message = [
+                "Bytecode was compiled by ",
+                &compiled_by,
+                " but this is LLRT v",
+                BYTECODE_RUNTIME_VERSION,
+                " (bytecode ",
+                BYTECODE_VERSION,
+                ", QuickJS ABI ",
+                abi_hash,
+                "). Recompile it with this runtime.",
+            ]
+            .concat();
+            return Err(io::Error::new(io::ErrorKind::InvalidData, message));
         }
 
-        let rest = &input[SIGNATURE_LENGTH..];
-        Ok((
-            signature,
-            compressed.ok_or(io::Error::new::<String>(
-                io::ErrorKind::InvalidInput,
-                "Invalid bytecode signature".into(),
-            ))?,
-            rest,
-        ))
+        let compression = header.uncompressed_size.zip(header.dictionary_id).map(
+            |(uncompressed_size, dictionary_id)| Compression {
+                uncompressed_size,
+                dictionary_id,
+            },
+        );
+        Ok((compression, rest))
     }
 
     fn load_cjs_module<'js>(name: &str, ctx: Ctx<'js>) -> Result<Module<'js>> {
@@ -132,6 +189,33 @@ impl CustomLoader {
         Module::declare(ctx, name, module)
     }
 
+    // JSON, text and bytes modules export the file contents as their default
+    fn load_value_module<'js>(
+        ctx: Ctx<'js>,
+        name: &str,
+        import_type: ImportType,
+        path: &str,
+    ) -> Result<Module<'js>> {
+        let bytes = match archive::embedded().and_then(|archive| archive.file(path)) {
+            Some(bytes) => bytes.to_vec(),
+            None => {
+                watch_report::record(path);
+                std::fs::read(path)?
+            },
+        };
+        let value = match import_type {
+            ImportType::Json => json_parse(&ctx, bytes)?,
+            ImportType::Text => {
+                rquickjs::String::from_str(ctx.clone(), &String::from_utf8_lossy(&bytes))?
+                    .into_value()
+            },
+            ImportType::Bytes => TypedArray::<u8>::new(ctx.clone(), bytes)?.into_value(),
+        };
+        let module = Module::declare(ctx, name, "export default import.meta.value;")?;
+        module.meta::<Object>()?.set("value", value)?;
+        Ok(module)
+    }
+
     fn normalize_name(name: &str) -> (bool, bool, &str, &str) {
         if !name.starts_with("__") {
             // If name doesn’t start with "__", return defaults
@@ -155,29 +239,38 @@ impl CustomLoader {
     fn load_module<'js>(name: &str, ctx: &Ctx<'js>) -> Result<(Module<'js>, Option<String>)> {
         let ctx = ctx.clone();
 
+        if let Some((import_type, path)) = ImportType::split(name) {
+            trace!("Loading {} module: {}", import_type, path);
+            return Ok((Self::load_value_module(ctx, name, import_type, path)?, None));
+        }
+
         let (from_cjs_import, is_cjs, normalized_name, path) = Self::normalize_name(name);
 
         trace!("Loading module: {}", normalized_name);
 
+        let embedded = archive::embedded();
+
         //json files can never be from CJS imports as they are handled by require
         if !from_cjs_import {
             if normalized_name.ends_with(".json") {
-                let mut file = File::open(path)?;
-                let prefix = "export default JSON.parse(`";
-                let suffix = "`);";
-                let mut json = String::with_capacity(prefix.len() + suffix.len());
-                json.push_str(prefix);
-                file.read_to_string(&mut json)?;
-                json.push_str(suffix);
-
-                return Ok((Module::declare(ctx, path, json)?, None));
+                return Ok((
+                    Self::load_value_module(ctx, path, ImportType::Json, path)?,
+                    None,
+                ));
             }
-            if is_cjs || normalized_name.ends_with(".cjs") {
+            if is_cjs || is_commonjs(normalized_name) {
                 let url = ["file://", path].concat();
                 return Ok((Self::load_cjs_module(path, ctx)?, Some(url)));
             }
         }
 
+        if let Some(bytes) = embedded.and_then(|archive| archive.module(normalized_name)) {
+            trace!("Loading archived module: {}", normalized_name);
+
+            let url = ["file://", path].concat();
+            return Ok((Self::load_bytecode_module(ctx, bytes)?, Some(url)));
+        }
+
         if let Some(bytes) = BYTECODE_CACHE.get(path) {
             #[cfg(feature = "lambda")]
             init_client_connection(&ctx, path)?;
@@ -187,19 +280,37 @@ impl CustomLoader {
             return Ok((Self::load_bytecode_module(ctx, bytes)?, Some(path.into())));
         }
 
-        let bytes = std::fs::read(path)?;
-        let mut bytes: &[u8] = &bytes;
+        watch_report::record(path);
+        let file = std::fs::read(path)?;
+        let mut bytes: &[u8] = &file;
 
         if normalized_name.ends_with(BYTECODE_FILE_EXT) {
             trace!("Loading binary module: {}", path);
+            source_map::register_for_bytecode(path);
             return Ok((Self::load_bytecode_module(ctx, bytes)?, Some(path.into())));
         }
+
+        let url = ["file://", path].concat();
+        if let Some(module) = compile_cache::load(&ctx, normalized_name, &file) {
+            return Ok((module, Some(url)));
+        }
+
         if !from_cjs_import && bytes.starts_with(b"#!") {
             bytes = bytes.splitn(2, |&c| c == b'\n').nth(1).unwrap_or(bytes);
         }
 
-        let url = ["file://", path].concat();
-        Ok((Module::declare(ctx, normalized_name, bytes)?, Some(url)))
+        let stripped;
+        if is_typescript(path) {
+            trace!("Stripping types: {}", path);
+            stripped = strip_types_or_throw(&ctx, bytes, path)?;
+            bytes = &stripped;
+        }
+        let module = match rewrite_import_attributes_or_throw(&ctx, bytes, path)? {
+            Some(source) => Module::declare(ctx, normalized_name, source)?,
+            None => Module::declare(ctx, normalized_name, bytes)?,
+        };
+        compile_cache::store(normalized_name, &file, &module);
+        Ok((module, Some(url)))
     }
 }
 
@@ -208,6 +319,10 @@ impl Loader for CustomLoader {
         let (module, url) = Self::load_module(name, ctx)?;
         if let Some(url) = url {
             let meta: Object = module.meta()?;
+            if let Some(filename) = url.strip_prefix("file://") {
+                meta.prop("dirname", dirname(filename))?;
+                meta.prop("filename", filename)?;
+            }
             meta.prop("url", url)?;
         }
 
@@ -266,3 +381,81 @@ fn init_client_connection(ctx: &Ctx<'_>, specifier: &str) -> Result<()> {
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bytecode::add_bytecode_header;
+
+    fn refused(bytes: &[u8]) -> String {
+        CustomLoader::get_bytecode_signature(bytes)
+            .unwrap_err()
+            .to_string()
+    }
+
+    // An lrt03 header as another runtime would write it
+    fn header(version: &str, runtime_version: &str, abi_hash: &str) -> Vec<u8> {
+        let mut bytes = version.as_bytes().to_vec();
+        bytes.push(b'u');
+        for field in [runtime_version, abi_hash] {
+            bytes.push(field.len() as u8);
+            bytes.extend_from_slice(field.as_bytes());
+        }
+        bytes.extend_from_slice(b"code");
+        bytes
+    }
+
+    #[test]
+    fn test_bytecode_signature() {
+        let bytes = add_bytecode_header(b"code".to_vec(), None, bytecode_abi_hash());
+        let (compression, rest) = CustomLoader::get_bytecode_signature(&bytes).unwrap();
+        assert_eq!(compression, None);
+        assert_eq!(rest, b"code");
+
+        let expected = Compression {
+            uncompressed_size: 1234,
+            dictionary_id: 42,
+        };
+        let bytes = add_bytecode_header(b"code".to_vec(), Some(expected), bytecode_abi_hash());
+        let (compression, rest) = CustomLoader::get_bytecode_signature(&bytes).unwrap();
+        assert_eq!(compression, Some(expected));
+        assert_eq!(rest, b"code");
+    }
+
+    #[test]
+    fn test_bytecode_signature_mismatch() {
+        let message = refused(&header(
+            BYTECODE_VERSION,
+            BYTECODE_RUNTIME_VERSION,
+            "other-abi",
+        ));
+        assert!(message.contains("QuickJS ABI other-abi"), "{message}");
+        assert!(message.contains(bytecode_abi_hash()), "{message}");
+        assert!(
+            message.ends_with("Recompile it with this runtime."),
+            "{message}"
+        );
+
+        let message = refused(&header(BYTECODE_VERSION, "0.0.1", bytecode_abi_hash()));
+        assert!(
+            message.starts_with("Bytecode was compiled by LLRT v0.0.1 (bytecode lrt03"),
+            "{message}"
+        );
+
+        let message = refused(&header(
+            "lrt02",
+            BYTECODE_RUNTIME_VERSION,
+            bytecode_abi_hash(),
+        ));
+        assert!(message.contains("(bytecode lrt02"), "{message}");
+
+        let message = refused(b"lrt01ucode");
+        assert!(
+            message
+                .starts_with("Bytecode was compiled by another runtime version (bytecode lrt01)"),
+            "{message}"
+        );
+
+        assert_eq!(refused(b"garbage"), "Invalid bytecode signature");
+    }
+}
@@ -1,21 +1,33 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
-use std::env;
-
 use once_cell::sync::Lazy;
 
 use crate::environment;
 
+mod cache;
+pub mod import_map;
 pub mod loader;
 pub mod resolver;
+mod tsconfig;
 
 // added when .cjs files are imported
 pub const CJS_IMPORT_PREFIX: &str = "__cjs:";
 // added to force CJS imports in loader
 pub const CJS_LOADER_PREFIX: &str = "__cjsm:";
+// added to specifiers imported with a `type` attribute
+pub const JSON_IMPORT_PREFIX: &str = "__json:";
+pub const TEXT_IMPORT_PREFIX: &str = "__text:";
+pub const BYTES_IMPORT_PREFIX: &str = "__bytes:";
+
+/// Forgets the parsed `package.json` and `tsconfig.json` files and package
+/// scopes, so resolving again sees files that changed.
+pub fn clear_resolver_caches() {
+    cache::clear();
+    tsconfig::clear_cache();
+}
 
 pub static LLRT_PLATFORM: Lazy<String> = Lazy::new(|| {
-    env::var(environment::ENV_LLRT_PLATFORM)
+    environment::var(environment::ENV_LLRT_PLATFORM)
         .ok()
         .filter(|platform| platform == "node")
         .unwrap_or_else(|| "browser".to_string())
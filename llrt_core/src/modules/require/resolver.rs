@@ -5,24 +5,29 @@ use std::{
     cell::RefCell,
     collections::HashMap,
     env,
-    fs::{self},
     path::{Path, PathBuf},
     rc::Rc,
     sync::Mutex,
 };
 
 use once_cell::sync::Lazy;
-use rquickjs::{loader::Resolver, Ctx, Error, Result};
+use rquickjs::{loader::Resolver, Ctx, Error, Exception, Result};
 use simd_json::{derived::ValueObjectAccessAsScalar, BorrowedValue};
 use tracing::trace;
 
-use crate::libs::utils::result::ResultExt;
+use crate::archive;
+use crate::import_attributes::ImportType;
 use crate::modules::path::{
     self, is_absolute, name_extname, replace_backslash, resolve_path_with_separator,
 };
-use crate::utils::io::{is_supported_ext, JS_EXTENSIONS, SUPPORTED_EXTENSIONS};
+use crate::typescript::typescript_counterpart;
+use crate::utils::io::{
+    bytecode_counterpart, is_supported_ext, JS_EXTENSIONS, SUPPORTED_EXTENSIONS,
+};
 
-use super::{CJS_IMPORT_PREFIX, CJS_LOADER_PREFIX, LLRT_PLATFORM};
+use super::{
+    cache, import_map, tsconfig::PathAliases, CJS_IMPORT_PREFIX, CJS_LOADER_PREFIX, LLRT_PLATFORM,
+};
 
 include!(concat!(env!("OUT_DIR"), "/bytecode_cache.rs"));
 
@@ -101,6 +106,27 @@ impl Resolver for CustomResolver {
 
         let base = base.trim_start_matches(CJS_IMPORT_PREFIX);
 
+        if let Some((import_type, name)) = ImportType::split(name) {
+            trace!("Try resolve '{}' as {} from '{}'", name, import_type, base);
+
+            let path = require_resolve(ctx, name, base, true)?;
+            let path = path.trim_start_matches(CJS_LOADER_PREFIX);
+            return match import_type {
+                // JSON modules are the same with or without the attribute
+                ImportType::Json if path.ends_with(".json") => Ok(path.to_string()),
+                ImportType::Json => Err(Exception::throw_type(
+                    ctx,
+                    &[
+                        "Module '",
+                        path,
+                        "' is not a JSON module and cannot be imported with type 'json'",
+                    ]
+                    .concat(),
+                )),
+                _ => Ok([import_type.prefix(), path].concat()),
+            };
+        }
+
         trace!("Try resolve '{}' from '{}'", name, base);
 
         require_resolve(ctx, name, base, true).map(|name| name.into_owned())
@@ -118,6 +144,22 @@ pub fn require_resolve<'a>(
     // trim schema
     let x = x.trim_start_matches("file://");
 
+    // 0. If the executable embeds an archive, use the resolutions recorded at build time
+    if let Some(archive) = archive::embedded() {
+        if let Some(path) = archive.resolve(y, x, is_esm) {
+            trace!("+- Resolved by `EMBEDDED_ARCHIVE`: {}\n", path);
+            return Ok(path.to_string().into());
+        }
+        if archive.module(x).is_some() || archive.file(x).is_some() {
+            trace!("+- Resolved by `EMBEDDED_ARCHIVE`: {}\n", x);
+            return Ok(x.into());
+        }
+    }
+
+    cache::resolution_pass(|| resolve(ctx, x, y, is_esm))
+}
+
+fn resolve<'a>(ctx: &Ctx<'_>, x: &'a str, y: &str, is_esm: bool) -> Result<Cow<'a, str>> {
     // resolve symlink
     let y = if let Ok(path) = Path::new(y).read_link() {
         if path.is_absolute() {
@@ -130,6 +172,44 @@ pub fn require_resolve<'a>(
     };
     let y = y.as_str();
 
+    // 0'. Import map and tsconfig.json/jsconfig.json aliases
+    if let Some(import_map) = import_map::installed() {
+        match import_map.resolve(x, y) {
+            Ok(Some(path)) => {
+                trace!("+- Mapped by `IMPORT_MAP`: {}\n", path);
+                return node_resolve(ctx, &path, y, is_esm).map(|path| path.into_owned().into());
+            },
+            Ok(None) => {},
+            Err(message) => return Err(Error::new_resolving_message(y, x, message)),
+        }
+    }
+    if is_bare_specifier(x) {
+        if let Some(aliases) = PathAliases::find(&path::dirname(y)) {
+            for candidate in aliases.candidates(x) {
+                if let Ok(path) = node_resolve(ctx, &candidate, y, is_esm) {
+                    trace!("+- Resolved by `TSCONFIG_PATHS`: {}\n", path);
+                    return Ok(path.into_owned().into());
+                }
+            }
+        }
+    }
+
+    // 0''. If `LLRT_RESOLVE_CACHE` is set, use the resolutions of earlier runs
+    if let Some(path) = cache::cached_resolution(y, x, is_esm) {
+        trace!("+- Resolved by `RESOLUTION_CACHE`: {}\n", path);
+        return Ok(path.into());
+    }
+
+    let path = node_resolve(ctx, x, y, is_esm)?;
+    cache::cache_resolution(y, x, is_esm, &path);
+    Ok(path)
+}
+
+fn is_bare_specifier(x: &str) -> bool {
+    !(x.starts_with('.') || x.starts_with('#') || path::is_absolute(x) || x.contains(':'))
+}
+
+fn node_resolve<'a>(ctx: &Ctx<'_>, x: &'a str, y: &str, is_esm: bool) -> Result<Cow<'a, str>> {
     trace!("require_resolve(x, y):({}, {})", x, y);
 
     // 1'. If X is a bytecode cache,
@@ -145,7 +225,7 @@ pub fn require_resolve<'a>(
     let x_starts_with_current_dir = x.starts_with("./");
     let x_starts_with_parent_dir = x.starts_with("../");
 
-    if is_supported_ext && Path::new(x).is_file() {
+    if is_supported_ext && cache::is_file(x) {
         return resolved_by_file_exists(x.into());
     }
 
@@ -154,7 +234,7 @@ pub fn require_resolve<'a>(
         return resolved_by_bytecode_cache(x_normalized.into());
     }
 
-    if !x_starts_with_parent_dir && is_supported_ext && Path::new(&x_normalized).is_file() {
+    if !x_starts_with_parent_dir && is_supported_ext && cache::is_file(&x_normalized) {
         return resolved_by_file_exists(x_normalized.into());
     }
 
@@ -167,7 +247,7 @@ pub fn require_resolve<'a>(
     };
 
     // Normalize path Y to generate dirname(Y)
-    let dirname_y = if Path::new(y).is_dir() {
+    let dirname_y = if cache::is_dir(y) {
         path::resolve_path([y].iter())?
     } else {
         let dirname_y = path::dirname(y);
@@ -258,11 +338,27 @@ fn load_as_file<'a>(ctx: &Ctx<'_>, x: Rc<String>) -> Result<Option<Cow<'a, str>>
     trace!("|  load_as_file(x): {}", x);
 
     // 1. If X is a file, load X as its file extension format. STOP
-    if Path::new(x.as_ref()).is_file() {
+    if cache::is_file(&x) {
         trace!("|  load_as_file(1): {}", x);
         return Ok(Some(rc_string_to_cow(x)));
     }
 
+    // 1'. If X is imported as X.js but X.ts is the file, load X.ts
+    if let Some(ts_file) = typescript_counterpart(&x) {
+        if cache::is_file(&ts_file) {
+            trace!("|  load_as_file(1'): {}", ts_file);
+            return Ok(Some(ts_file.into()));
+        }
+    }
+
+    // 1''. If X is imported as X.js but was compiled to X.lrt, load X.lrt
+    if let Some(lrt_file) = bytecode_counterpart(&x) {
+        if cache::is_file(&lrt_file) {
+            trace!("|  load_as_file(1''): {}", lrt_file);
+            return Ok(Some(lrt_file.into()));
+        }
+    }
+
     let mut base_file = String::with_capacity(x.len() + 4);
     base_file.push_str(x.as_ref());
     let base_file_length = base_file.len();
@@ -275,7 +371,7 @@ fn load_as_file<'a>(ctx: &Ctx<'_>, x: Rc<String>) -> Result<Option<Cow<'a, str>>
             current_file.truncate(base_file_length);
             current_file.push_str(extension);
 
-            if Path::new(&current_file).is_file() {
+            if cache::is_file(&current_file) {
                 // a. Find the closest package scope SCOPE to X.
                 match find_the_closest_package_scope(&x) {
                     // b. If no scope was found
@@ -285,9 +381,7 @@ fn load_as_file<'a>(ctx: &Ctx<'_>, x: Rc<String>) -> Result<Option<Cow<'a, str>>
                         return Ok(Some(current_file.into()));
                     },
                     Some(path) => {
-                        let mut package_json = fs::read(path.as_ref()).or_throw(ctx)?;
-                        let package_json =
-                            simd_json::to_borrowed_value(&mut package_json).or_throw(ctx)?;
+                        let package_json = cache::package_json(ctx, &path)?;
                         // c. If the SCOPE/package.json contains "type" field,
                         if let Some(_type) = get_string_field(&package_json, "type") {
                             // 1. If the "type" field is "module", load X.js as an ECMAScript module. STOP.
@@ -311,7 +405,7 @@ fn load_as_file<'a>(ctx: &Ctx<'_>, x: Rc<String>) -> Result<Option<Cow<'a, str>>
     if let Some(mut current_file) = base_file.take() {
         current_file.truncate(base_file_length);
         current_file.push_str(".json");
-        if Path::new(&current_file).is_file() {
+        if cache::is_file(&current_file) {
             trace!("|  load_as_file(3): {}", current_file);
             return Ok(Some(current_file.into()));
         }
@@ -338,7 +432,7 @@ fn load_index<'a>(ctx: &Ctx<'_>, x: Rc<String>) -> Result<Option<Cow<'a, str>>>
         if let Some(mut file) = base_file.take() {
             file.truncate(base_file_length);
             file.push_str(extension);
-            if Path::new(&file).is_file() {
+            if cache::is_file(&file) {
                 // a. Find the closest package scope SCOPE to X.
                 match find_the_closest_package_scope(&x) {
                     // b. If no scope was found, load X/index.js as a CommonJS module. STOP.
@@ -348,9 +442,7 @@ fn load_index<'a>(ctx: &Ctx<'_>, x: Rc<String>) -> Result<Option<Cow<'a, str>>>
                     },
                     // c. If the SCOPE/package.json contains "type" field,
                     Some(path) => {
-                        let mut package_json = fs::read(path.as_ref()).or_throw(ctx)?;
-                        let package_json =
-                            simd_json::to_borrowed_value(&mut package_json).or_throw(ctx)?;
+                        let package_json = cache::package_json(ctx, &path)?;
                         if let Some(_type) = get_string_field(&package_json, "type") {
                             // 1. If the "type" field is "module", load X/index.js as an ECMAScript module. STOP.
                             if _type == "module" {
@@ -373,7 +465,7 @@ fn load_index<'a>(ctx: &Ctx<'_>, x: Rc<String>) -> Result<Option<Cow<'a, str>>>
     if let Some(mut file) = base_file.take() {
         file.truncate(base_file_length);
         file.push_str(".json");
-        if Path::new(&file).is_file() {
+        if cache::is_file(&file) {
             trace!("|  load_index(2): {}", file);
             return Ok(Some(file.into()));
         }
@@ -390,10 +482,9 @@ fn load_as_directory<'a>(ctx: &Ctx<'_>, x: Rc<String>) -> Result<Option<Cow<'a,
 
     // 1. If X/package.json is a file,
     let file = [&x, "/package.json"].concat();
-    if Path::new(&file).is_file() {
+    if cache::is_file(&file) {
         // a. Parse X/package.json, and look for "main" field.
-        let mut package_json = fs::read(file).or_throw(ctx)?;
-        let package_json = simd_json::to_borrowed_value(&mut package_json).or_throw(ctx)?;
+        let package_json = cache::package_json(ctx, &file)?;
         // b. If "main" is a falsy value, GOTO 2.
         if let Some(main) = get_string_field(&package_json, "main") {
             // c. let M = X + (json main field)
@@ -535,9 +626,7 @@ fn load_package_imports(ctx: &Ctx<'_>, x: &str, dir: &str) -> Result<Option<Stri
     // 1. Find the closest package scope SCOPE to DIR.
     // 2. If no scope was found, return.
     if let Some(path) = find_the_closest_package_scope(dir) {
-        let mut package_json_file = fs::read(path.as_ref()).or_throw(ctx)?;
-        let package_json: BorrowedValue =
-            simd_json::to_borrowed_value(&mut package_json_file).or_throw(ctx)?;
+        let package_json = cache::package_json(ctx, &path)?;
 
         // 3. If the SCOPE/package.json "imports" is null or undefined, return.
         // 4. If `--experimental-require-module` is enabled
@@ -588,7 +677,7 @@ fn load_package_exports<'a>(
         package_json_path.push_str(scope);
         package_json_path.push_str("/package.json");
 
-        package_json_exists = Path::new(&package_json_path).exists();
+        package_json_exists = cache::exists(&package_json_path);
 
         if package_json_exists || is_last {
             break;
@@ -604,7 +693,7 @@ fn load_package_exports<'a>(
         package_json_path.truncate(base_path_length);
         package_json_path.push_str(x);
         package_json_path.push_str("/package.json");
-        if !Path::new(&package_json_path).exists() {
+        if !cache::exists(&package_json_path) {
             return Err(Error::new_resolving(dir.to_string(), x.to_string()));
         }
         (x, ".")
@@ -630,7 +719,7 @@ fn load_package_exports<'a>(
                 current_path.truncate(base_path_length);
                 current_path.push_str(ext);
 
-                if Path::new(&current_path).exists() {
+                if cache::exists(&current_path) {
                     if *ext == ".mjs" {
                         //we know its an ESM module
                         return Ok(current_path.into());
@@ -649,8 +738,7 @@ fn load_package_exports<'a>(
     //5. let MATCH = PACKAGE_EXPORTS_RESOLVE(pathToFileURL(DIR/NAME), "." + SUBPATH,
     //   `package.json` "exports", ["node", "require"]) <a href="esm.md#resolver-algorithm-specification">defined in the ESM resolver</a>.
     //6. RESOLVE_ESM_MATCH(MATCH)
-    let mut package_json = fs::read(&package_json_path).or_throw(ctx)?;
-    let package_json = simd_json::to_borrowed_value(&mut package_json).or_throw(ctx)?;
+    let package_json = cache::package_json(ctx, &package_json_path)?;
 
     if let Some(sub_module) = sub_module {
         if package_json.get_str("type") != Some("module") {
@@ -683,16 +771,14 @@ fn load_package_self(ctx: &Ctx<'_>, x: &str, dir: &str, is_esm: bool) -> Result<
     let (mut name, mut scope, mut is_last) = get_name_and_scope(x, n);
 
     // 1. Find the closest package scope SCOPE to DIR.
-    let mut package_json_file: Vec<u8>;
-    let package_json: BorrowedValue;
+    let package_json;
     let package_json_path: Box<str> = match find_the_closest_package_scope(dir) {
         // 2. If no scope was found, return.
         None => {
             return Ok(None);
         },
         Some(path) => {
-            package_json_file = fs::read(path.as_ref()).or_throw(ctx)?;
-            package_json = simd_json::to_borrowed_value(&mut package_json_file).or_throw(ctx)?;
+            package_json = cache::package_json(ctx, &path)?;
             // 3. If the SCOPE/package.json "exports" is null or undefined, return.
             loop {
                 trace!(
@@ -851,17 +937,19 @@ fn package_imports_resolve<'a>(
 }
 
 fn find_the_closest_package_scope(start: &str) -> Option<Box<str>> {
-    let mut current_dir = PathBuf::from(start);
-    loop {
-        let package_json_path = current_dir.join("package.json");
-        if package_json_path.exists() {
-            return package_json_path.to_str().map(Box::from);
-        }
-        if !current_dir.pop() {
-            break;
+    cache::PACKAGE_SCOPE_CACHE.get_or_insert_with(start, || {
+        let mut current_dir = PathBuf::from(start);
+        loop {
+            let package_json_path = current_dir.join("package.json");
+            if package_json_path.exists() {
+                return package_json_path.to_str().map(Box::from);
+            }
+            if !current_dir.pop() {
+                break;
+            }
         }
-    }
-    None
+        None
+    })
 }
 
 fn get_string_field<'a>(package_json: &'a BorrowedValue<'a>, str: &str) -> Option<&'a str> {
@@ -883,7 +971,7 @@ fn is_exports_field_exists<'a>(package_json: &'a BorrowedValue<'a>) -> bool {
 }
 
 fn correct_extensions<'a>(x: String) -> Cow<'a, str> {
-    let (x_is_file, x_is_dir) = if let Ok(md) = fs::metadata(&x) {
+    let (x_is_file, x_is_dir) = if let Some(md) = cache::metadata(&x) {
         (md.is_file(), md.is_dir())
     } else {
         (false, false)
@@ -892,6 +980,9 @@ fn correct_extensions<'a>(x: String) -> Cow<'a, str> {
     if x_is_file {
         return x.into();
     };
+    if let Some(lrt_file) = bytecode_counterpart(&x).filter(|file| cache::is_file(file)) {
+        return lrt_file.into();
+    }
 
     let index = if x_is_dir { "/index" } else { "" };
 
@@ -906,7 +997,7 @@ fn correct_extensions<'a>(x: String) -> Cow<'a, str> {
         if let Some(mut current_path) = path.take() {
             current_path.truncate(base_path_length);
             current_path.push_str(extension);
-            if Path::new(&current_path).is_file() {
+            if cache::is_file(&current_path) {
                 return current_path.into();
             }
             path = Some(current_path);
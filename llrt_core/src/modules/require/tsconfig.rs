@@ -0,0 +1,341 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+//! `compilerOptions.baseUrl` and `compilerOptions.paths` of the nearest
+//! `tsconfig.json` or `jsconfig.json`, applied to bare specifiers before the
+//! Node resolution algorithm.
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    result::Result as StdResult,
+    sync::{Arc, Mutex},
+};
+
+use once_cell::sync::Lazy;
+use simd_json::BorrowedValue;
+use tracing::trace;
+
+use crate::modules::path::{self, resolve_path};
+
+pub(super) const CONFIG_FILES: [&str; 2] = ["tsconfig.json", "jsconfig.json"];
+
+// `extends` chains longer than this are treated as cycles
+const MAX_EXTENDS_DEPTH: usize = 16;
+
+//None entry means that neither the directory nor its parents have a config
+type PathAliasesCache = HashMap<Box<str>, Option<Arc<PathAliases>>>;
+
+static TSCONFIG_CACHE: Lazy<Mutex<PathAliasesCache>> = Lazy::new(|| Mutex::new(HashMap::new()));
+
+pub fn clear_cache() {
+    TSCONFIG_CACHE.lock().unwrap().clear();
+}
+
+#[derive(Debug)]
+pub struct PathAliases {
+    base_url: Option<String>,
+    // Directory the `paths` substitutions are relative to
+    paths_base: String,
+    // Longest prefix first, so the first matching pattern is the most specific
+    paths: Vec<(String, Vec<String>)>,
+}
+
+impl PathAliases {
+    /// Returns the aliases that apply to modules in `dir`, from the nearest
+    /// config file in `dir` or one of its parents. Modules inside
+    /// `node_modules` are not affected by the project's config.
+    pub fn find(dir: &str) -> Option<Arc<Self>> {
+        if dir.contains("/node_modules/") || dir.ends_with("/node_modules") {
+            return None;
+        }
+        if let Some(aliases) = TSCONFIG_CACHE.lock().unwrap().get(dir) {
+            return aliases.clone();
+        }
+
+        let aliases = match CONFIG_FILES
+            .iter()
+            .map(|name| Path::new(dir).join(name))
+            .find(|file| file.is_file())
+        {
+            Some(file) => match Self::load(&file) {
+                Ok(aliases) => aliases.map(Arc::new),
+                Err(message) => {
+                    trace!("Ignoring {}: {}", file.display(), message);
+                    None
+                },
+            },
+            None => Path::new(dir)
+                .parent()
+                .and_then(|parent| Self::find(&parent.to_string_lossy())),
+        };
+
+        TSCONFIG_CACHE
+            .lock()
+            .unwrap()
+            .insert(dir.into(), aliases.clone());
+        aliases
+    }
+
+    /// Reads a config file and the files it extends. Returns `None` when
+    /// none of them sets `baseUrl` or `paths`.
+    pub fn load(file: &Path) -> StdResult<Option<Self>, String> {
+        let options = CompilerOptions::load(file, 0)?;
+        let Some(paths_base) = options.base_url.clone().or(options.paths_dir) else {
+            return Ok(None);
+        };
+        let mut paths = options.paths;
+        paths.sort_by_key(|(pattern, _)| {
+            std::cmp::Reverse(pattern.find('*').unwrap_or(pattern.len()))
+        });
+        Ok(Some(Self {
+            base_url: options.base_url,
+            paths_base,
+            paths,
+        }))
+    }
+
+    /// Paths to try for a bare `specifier`, in order: the substitutions of
+    /// the most specific matching `paths` pattern, or `baseUrl/specifier`
+    /// when no pattern matches.
+    pub fn candidates(&self, specifier: &str) -> Vec<String> {
+        let mut matched = None;
+        for (pattern, substitutions) in &self.paths {
+            match pattern.split_once('*') {
+                None if pattern == specifier => {
+                    matched = Some(("", substitutions));
+                    break;
+                },
+                Some((prefix, suffix))
+                    if matched.is_none()
+                        && specifier.len() >= prefix.len() + suffix.len()
+                        && specifier.starts_with(prefix)
+                        && specifier.ends_with(suffix) =>
+                {
+                    let star = &specifier[prefix.len()..specifier.len() - suffix.len()];
+                    matched = Some((star, substitutions));
+                },
+                _ => {},
+            }
+        }
+
+        match (matched, &self.base_url) {
+            (Some((star, substitutions)), _) => substitutions
+                .iter()
+                .filter_map(|substitution| {
+                    resolve_path([&self.paths_base, &substitution.replacen('*', star, 1)]).ok()
+                })
+                .collect(),
+            (None, Some(base_url)) => resolve_path([base_url.as_str(), specifier])
+                .ok()
+                .into_iter()
+                .collect(),
+            (None, None) => Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct CompilerOptions {
+    base_url: Option<String>,
+    paths: Vec<(String, Vec<String>)>,
+    // Directory of the config that declared `paths`
+    paths_dir: Option<String>,
+}
+
+impl CompilerOptions {
+    fn load(file: &Path, depth: usize) -> StdResult<Self, String> {
+        if depth > MAX_EXTENDS_DEPTH {
+            return Err("`extends` is circular".into());
+        }
+        let mut json = strip_jsonc(&fs::read(file).map_err(|err| err.to_string())?);
+        let config = simd_json::to_borrowed_value(&mut json).map_err(|err| err.to_string())?;
+        let BorrowedValue::Object(config) = config else {
+            return Err("expected a JSON object".into());
+        };
+        let dir = path::dirname(file.to_string_lossy());
+
+        // later entries of an `extends` array override earlier ones
+        let mut options = Self::default();
+        let extends = match config.get("extends") {
+            Some(BorrowedValue::String(extends)) => vec![&**extends],
+            Some(BorrowedValue::Array(extends)) => extends
+                .iter()
+                .filter_map(|extends| match extends {
+                    BorrowedValue::String(extends) => Some(&**extends),
+                    _ => None,
+                })
+                .collect(),
+            _ => Vec::new(),
+        };
+        for extends in extends {
+            if !(extends.starts_with('.') || path::is_absolute(extends)) {
+                trace!("Ignoring `extends` of package '{}'", extends);
+                continue;
+            }
+            let mut base = resolve_path([dir.as_str(), extends]).map_err(|err| err.to_string())?;
+            if !base.ends_with(".json") && !Path::new(&base).is_file() {
+                base.push_str(".json");
+            }
+            options.merge(Self::load(Path::new(&base), depth + 1)?);
+        }
+
+        if let Some(BorrowedValue::Object(compiler_options)) = config.get("compilerOptions") {
+            if let Some(BorrowedValue::String(base_url)) = compiler_options.get("baseUrl") {
+                options.base_url = resolve_path([dir.as_str(), &**base_url]).ok();
+            }
+            if let Some(BorrowedValue::Object(paths)) = compiler_options.get("paths") {
+                options.paths = paths
+                    .iter()
+                    .map(|(pattern, substitutions)| {
+                        let substitutions = match substitutions {
+                            BorrowedValue::Array(substitutions) => substitutions
+                                .iter()
+                                .filter_map(|substitution| match substitution {
+                                    BorrowedValue::String(substitution) => {
+                                        Some(substitution.to_string())
+                                    },
+                                    _ => None,
+                                })
+                                .collect(),
+                            _ => Vec::new(),
+                        };
+                        (pattern.to_string(), substitutions)
+                    })
+                    .collect();
+                options.paths_dir = Some(dir);
+            }
+        }
+        Ok(options)
+    }
+
+    fn merge(&mut self, base: Self) {
+        if base.base_url.is_some() {
+            self.base_url = base.base_url;
+        }
+        if base.paths_dir.is_some() {
+            self.paths = base.paths;
+            self.paths_dir = base.paths_dir;
+        }
+    }
+}
+
+// Config files allow comments and trailing commas, replace both with spaces
+fn strip_jsonc(source: &[u8]) -> Vec<u8> {
+    let mut output = source.to_vec();
+    let mut i = 0;
+    let mut last_comma = None;
+    while i < output.len() {
+        match output[i] {
+            b'"' => {
+                i += 1;
+                while i < output.len() && output[i] != b'"' {
+                    if output[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                last_comma = None;
+            },
+            b'/' if output.get(i + 1) == Some(&b'/') => {
+                while i < output.len() && output[i] != b'\n' {
+                    output[i] = b' ';
+                    i += 1;
+                }
+                continue;
+            },
+            b'/' if output.get(i + 1) == Some(&b'*') => {
+                let end = output[i + 2..]
+                    .windows(2)
+                    .position(|window| window == b"*/")
+                    .map_or(output.len(), |end| i + 2 + end + 2);
+                for byte in &mut output[i..end] {
+                    if *byte != b'\n' {
+                        *byte = b' ';
+                    }
+                }
+                i = end;
+                continue;
+            },
+            b',' => last_comma = Some(i),
+            b'}' | b']' => {
+                if let Some(comma) = last_comma.take() {
+                    output[comma] = b' ';
+                }
+            },
+            byte if byte.is_ascii_whitespace() => {},
+            _ => last_comma = None,
+        }
+        i += 1;
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::{strip_jsonc, PathAliases};
+
+    #[test]
+    fn test_strip_jsonc() {
+        let source = br#"{
+            // line comment
+            "a": "http://x/*y*/", /* block */
+            "b": [1, 2,],
+        }"#;
+        let stripped = String::from_utf8(strip_jsonc(source)).unwrap();
+        assert_eq!(stripped.len(), source.len());
+        assert!(stripped.contains(r#""a": "http://x/*y*/","#));
+        assert!(!stripped.contains("comment") && !stripped.contains("block"));
+        assert!(stripped.contains("[1, 2 ]"));
+    }
+
+    #[test]
+    fn test_candidates() {
+        let dir = std::env::temp_dir().join("llrt-test-tsconfig");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("packages/app")).unwrap();
+        fs::write(
+            dir.join("tsconfig.base.json"),
+            r#"{
+                "compilerOptions": {
+                    // shared by every package
+                    "baseUrl": ".",
+                    "paths": {
+                        "@app/*": ["packages/app/src/*", "packages/app/generated/*"],
+                        "@app/config": ["packages/app/config.ts"],
+                        "@lib/*/index": ["packages/*/index.ts"],
+                    },
+                },
+            }"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.join("packages/app/tsconfig.json"),
+            r#"{ "extends": "../../tsconfig.base" }"#,
+        )
+        .unwrap();
+
+        let root = dir.to_string_lossy();
+        let aliases = PathAliases::find(&dir.join("packages/app").to_string_lossy()).unwrap();
+        assert_eq!(
+            aliases.candidates("@app/util/a"),
+            [
+                format!("{root}/packages/app/src/util/a"),
+                format!("{root}/packages/app/generated/util/a"),
+            ]
+        );
+        assert_eq!(
+            aliases.candidates("@app/config"),
+            [format!("{root}/packages/app/config.ts")]
+        );
+        assert_eq!(
+            aliases.candidates("@lib/core/index"),
+            [format!("{root}/packages/core/index.ts")]
+        );
+        assert_eq!(aliases.candidates("lodash"), [format!("{root}/lodash")]);
+        assert!(PathAliases::find(&dir.join("node_modules/x").to_string_lossy()).is_none());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -27,7 +27,7 @@ use crate::libs::{
         parse::json_parse,
         stringify::{self, json_stringify},
     },
-    logging::{format_values, replace_newline_with_carriage_return},
+    logging::{format_values, map_stack_trace, replace_newline_with_carriage_return},
     utils::{class::get_class_name, result::ResultExt},
 };
 
@@ -499,7 +499,7 @@ async fn post_error<'js>(
 
             error_type = Some(error_name);
 
-            if let Some(mut stack) = ex.stack() {
+            if let Some(mut stack) = ex.stack().map(map_stack_trace) {
                 replace_newline_with_carriage_return(&mut stack);
                 error_stack = Some(stack);
             }
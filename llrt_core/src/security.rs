@@ -1,20 +1,20 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
-use std::{env, result::Result as StdResult};
+use std::result::Result as StdResult;
 
 use hyper::{http::uri::InvalidUri, Uri};
 
-use crate::environment::{ENV_LLRT_NET_ALLOW, ENV_LLRT_NET_DENY};
+use crate::environment::{self, ENV_LLRT_NET_ALLOW, ENV_LLRT_NET_DENY};
 use crate::modules::{http, net};
 
 pub fn init() -> StdResult<(), Box<dyn std::error::Error + Send + Sync>> {
-    if let Ok(env_value) = env::var(ENV_LLRT_NET_ALLOW) {
+    if let Ok(env_value) = environment::var(ENV_LLRT_NET_ALLOW) {
         let allow_list = build_access_list(env_value);
         http::set_allow_list(build_http_access_list(&allow_list)?);
         net::set_allow_list(allow_list);
     }
 
-    if let Ok(env_value) = env::var(ENV_LLRT_NET_DENY) {
+    if let Ok(env_value) = environment::var(ENV_LLRT_NET_DENY) {
         let deny_list = build_access_list(env_value);
         http::set_deny_list(build_http_access_list(&deny_list)?);
         net::set_deny_list(deny_list);
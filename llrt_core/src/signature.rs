@@ -0,0 +1,237 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use std::{fmt, fs, io, path::Path};
+
+use ring::signature::{Ed25519KeyPair, KeyPair, UnparsedPublicKey, ED25519};
+use rustls::pki_types::PrivateKeyDer;
+
+use crate::libs::encoding::{bytes_from_hex, bytes_to_hex_string};
+
+pub const SIGNED_PAYLOAD_VERSION: &str = "lxs01";
+
+/// Exit code of an executable whose embedded payload fails verification
+pub const INTEGRITY_EXIT_CODE: i32 = 3;
+
+pub const PUBLIC_KEY_LEN: usize = 32;
+const SIGNATURE_LEN: usize = 64;
+const HEADER_LEN: usize = SIGNED_PAYLOAD_VERSION.len() + PUBLIC_KEY_LEN + SIGNATURE_LEN;
+
+/// Why an embedded payload was rejected.
+#[derive(Debug, PartialEq, Eq)]
+pub enum IntegrityError {
+    Truncated,
+    BadSignature,
+    /// The runtime pins a public key but the payload is not signed
+    Unsigned,
+    /// Signed with a key other than the one the runtime pins, hex encoded
+    UntrustedKey(String),
+}
+
+impl fmt::Display for IntegrityError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            IntegrityError::Truncated => f.write_str("signed payload is truncated"),
+            IntegrityError::BadSignature => {
+                f.write_str("signature does not match, the payload has been modified")
+            },
+            IntegrityError::Unsigned => {
+                f.write_str("payload is not signed but this runtime requires a signature")
+            },
+            IntegrityError::UntrustedKey(key) => write!(
+                f,
+                "payload is signed by {} but this runtime only trusts {}",
+                key,
+                pinned_public_key()
+                    .map(|key| bytes_to_hex_string(&key))
+                    .unwrap_or_default()
+            ),
+        }
+    }
+}
+
+/// A payload that passed verification.
+pub struct VerifiedPayload<'a> {
+    pub payload: &'a [u8],
+    /// Key the payload was signed with, `None` for unsigned payloads
+    pub public_key: Option<[u8; PUBLIC_KEY_LEN]>,
+}
+
+pub fn is_signed(bytes: &[u8]) -> bool {
+    bytes.starts_with(SIGNED_PAYLOAD_VERSION.as_bytes())
+}
+
+/// Ed25519 key used by `llrt build` to sign payloads.
+pub struct SigningKey(Ed25519KeyPair);
+
+impl SigningKey {
+    /// Reads a PKCS#8 private key, PEM or DER encoded, as written by
+    /// `openssl genpkey -algorithm ed25519`.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let bytes = fs::read(path).map_err(|err| {
+            io::Error::new(
+                err.kind(),
+                [
+                    "Unable to read signing key ",
+                    &path.to_string_lossy(),
+                    ": ",
+                    &err.to_string(),
+                ]
+                .concat(),
+            )
+        })?;
+        Self::from_bytes(&bytes).map_err(|message| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                [
+                    "Invalid signing key ",
+                    &path.to_string_lossy(),
+                    ": ",
+                    &message,
+                ]
+                .concat(),
+            )
+        })
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let der = if bytes.starts_with(b"-----") {
+            match rustls_pemfile::private_key(&mut &bytes[..]) {
+                Ok(Some(PrivateKeyDer::Pkcs8(key))) => key.secret_pkcs8_der().to_vec(),
+                Ok(Some(_)) => return Err("expected a PKCS#8 Ed25519 key".into()),
+                Ok(None) => return Err("no private key found".into()),
+                Err(err) => return Err(err.to_string()),
+            }
+        } else {
+            bytes.to_vec()
+        };
+        Ed25519KeyPair::from_pkcs8_maybe_unchecked(&der)
+            .map(Self)
+            .map_err(|err| err.to_string())
+    }
+
+    pub fn public_key(&self) -> &[u8] {
+        self.0.public_key().as_ref()
+    }
+
+    /// Wraps `payload` as version signature, public key, signature and the
+    /// payload itself.
+    pub fn sign(&self, payload: &[u8]) -> Vec<u8> {
+        let signature = self.0.sign(payload);
+        let mut signed = Vec::with_capacity(HEADER_LEN + payload.len());
+        signed.extend_from_slice(SIGNED_PAYLOAD_VERSION.as_bytes());
+        signed.extend_from_slice(self.public_key());
+        signed.extend_from_slice(signature.as_ref());
+        signed.extend_from_slice(payload);
+        signed
+    }
+}
+
+/// Checks the signature of an embedded payload against the key stored with
+/// it and, when the runtime was built with one, the pinned key.
+pub fn verify(bytes: &[u8]) -> Result<VerifiedPayload<'_>, IntegrityError> {
+    verify_with(bytes, pinned_public_key().as_ref())
+}
+
+/// Like [`verify`], with `pinned` in place of the key the runtime pins.
+pub fn verify_with<'a>(
+    bytes: &'a [u8],
+    pinned: Option<&[u8; PUBLIC_KEY_LEN]>,
+) -> Result<VerifiedPayload<'a>, IntegrityError> {
+    if !is_signed(bytes) {
+        if pinned.is_some() {
+            return Err(IntegrityError::Unsigned);
+        }
+        return Ok(VerifiedPayload {
+            payload: bytes,
+            public_key: None,
+        });
+    }
+    if bytes.len() < HEADER_LEN {
+        return Err(IntegrityError::Truncated);
+    }
+
+    let (public_key, rest) = bytes[SIGNED_PAYLOAD_VERSION.len()..].split_at(PUBLIC_KEY_LEN);
+    let (signature, payload) = rest.split_at(SIGNATURE_LEN);
+    let public_key: [u8; PUBLIC_KEY_LEN] = public_key.try_into().unwrap();
+
+    if let Some(pinned) = pinned {
+        if pinned != &public_key {
+            return Err(IntegrityError::UntrustedKey(bytes_to_hex_string(
+                &public_key,
+            )));
+        }
+    }
+    UnparsedPublicKey::new(&ED25519, public_key)
+        .verify(payload, signature)
+        .map_err(|_| IntegrityError::BadSignature)?;
+
+    Ok(VerifiedPayload {
+        payload,
+        public_key: Some(public_key),
+    })
+}
+
+/// Public key set through `LLRT_PINNED_PUBLIC_KEY` (hex) when the runtime was
+/// built. Executables of such a runtime only run payloads signed with it.
+pub fn pinned_public_key() -> Option<[u8; PUBLIC_KEY_LEN]> {
+    let hex = option_env!("LLRT_PINNED_PUBLIC_KEY").filter(|key| !key.is_empty())?;
+    // validated by build.rs
+    bytes_from_hex(hex.as_bytes()).ok()?.try_into().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use ring::rand::SystemRandom;
+
+    use super::*;
+
+    fn signing_key() -> SigningKey {
+        let pkcs8 = Ed25519KeyPair::generate_pkcs8(&SystemRandom::new()).unwrap();
+        SigningKey::from_bytes(pkcs8.as_ref()).unwrap()
+    }
+
+    #[test]
+    fn test_sign_and_verify() {
+        let key = signing_key();
+        let signed = key.sign(b"lxa01payload");
+        let public_key: [u8; PUBLIC_KEY_LEN] = key.public_key().try_into().unwrap();
+
+        let verified = verify_with(&signed, None).unwrap();
+        assert_eq!(verified.payload, b"lxa01payload");
+        assert_eq!(verified.public_key, Some(public_key));
+        assert!(verify_with(&signed, Some(&public_key)).is_ok());
+
+        let mut tampered = signed.clone();
+        *tampered.last_mut().unwrap() ^= 1;
+        assert_eq!(
+            verify_with(&tampered, None).err(),
+            Some(IntegrityError::BadSignature)
+        );
+        assert_eq!(
+            verify_with(&signed[..HEADER_LEN - 1], None).err(),
+            Some(IntegrityError::Truncated)
+        );
+    }
+
+    #[test]
+    fn test_pinned_key() {
+        let key = signing_key();
+        let other: [u8; PUBLIC_KEY_LEN] = signing_key().public_key().try_into().unwrap();
+        let signed = key.sign(b"payload");
+
+        assert_eq!(
+            verify_with(&signed, Some(&other)).err(),
+            Some(IntegrityError::UntrustedKey(bytes_to_hex_string(
+                key.public_key()
+            )))
+        );
+        assert_eq!(
+            verify_with(b"lxa01unsigned", Some(&other)).err(),
+            Some(IntegrityError::Unsigned)
+        );
+        assert_eq!(
+            verify_with(b"lxa01unsigned", None).unwrap().payload,
+            b"lxa01unsigned"
+        );
+    }
+}
@@ -0,0 +1,518 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    collections::HashMap,
+    fs, io,
+    path::{Component, Path, PathBuf},
+    sync::Mutex,
+};
+
+use once_cell::sync::Lazy;
+use simd_json::{derived::ValueObjectAccessAsScalar, BorrowedValue};
+use tracing::trace;
+
+use crate::libs::{encoding::bytes_from_b64, json::escape::escape_json};
+
+/// Appended to the name of compiled bytecode for its source map
+pub const SOURCE_MAP_EXT: &str = ".map";
+
+const SOURCE_MAPPING_URL: &str = "# sourceMappingURL=";
+// QuickJS location of frames in bytecode compiled without debug info
+const MISSING_LOCATION: &str = "missing";
+const STRIPPED_LOCATION: &str = "no debug info";
+const BASE64_CHARS: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+static SOURCE_MAPS: Lazy<Mutex<HashMap<String, SourceMapEntry>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+// Embedded maps are only parsed once a stack trace needs them
+enum SourceMapEntry {
+    Raw(Vec<u8>),
+    Parsed(Option<SourceMap>),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Mapping {
+    column: u32,
+    source: u32,
+    line: u32,
+    source_column: u32,
+}
+
+/// A decoded version 3 source map. Positions are zero based.
+#[derive(Debug)]
+pub struct SourceMap {
+    file: Option<String>,
+    sources: Vec<String>,
+    mappings: String,
+    lines: Vec<Vec<Mapping>>,
+}
+
+impl SourceMap {
+    pub fn parse(json: &[u8]) -> Result<Self, String> {
+        let mut json = json.to_vec();
+        let map = simd_json::to_borrowed_value(&mut json).map_err(|e| e.to_string())?;
+        let BorrowedValue::Object(fields) = &map else {
+            return Err("Source map is not an object".into());
+        };
+
+        if fields.contains_key("sections") {
+            return Err("Indexed source maps are not supported".into());
+        }
+        if map.get_u64("version") != Some(3) {
+            return Err("Only version 3 source maps are supported".into());
+        }
+
+        let source_root = map.get_str("sourceRoot").unwrap_or_default();
+        let sources = match fields.get("sources") {
+            Some(BorrowedValue::Array(sources)) => sources
+                .iter()
+                .map(|source| match source {
+                    BorrowedValue::String(source) => join_source_root(source_root, source),
+                    _ => String::new(),
+                })
+                .collect(),
+            _ => return Err("Source map has no sources".into()),
+        };
+        let mappings = map
+            .get_str("mappings")
+            .ok_or("Source map has no mappings")?
+            .to_string();
+
+        let lines = decode_mappings(&mappings)?;
+
+        Ok(Self {
+            file: map.get_str("file").map(String::from),
+            sources,
+            mappings,
+            lines,
+        })
+    }
+
+    /// Finds the original position of a zero based generated position.
+    pub fn lookup(&self, line: u32, column: u32) -> Option<(&str, u32, u32)> {
+        let segments = self.lines.get(line as usize)?;
+        let index = segments.partition_point(|mapping| mapping.column <= column);
+        let mapping = segments.get(index.checked_sub(1)?)?;
+        let source = self.sources.get(mapping.source as usize)?;
+        Some((source, mapping.line, mapping.source_column))
+    }
+
+    /// Rewrites the sources so they are relative to `base`. Sources that are
+    /// URLs are kept as is.
+    pub fn relativize_sources(&mut self, base: &Path) {
+        for source in &mut self.sources {
+            if source.is_empty() || source.contains("://") {
+                continue;
+            }
+            let path = normalize(Path::new(source.as_str()));
+            *source = relative_to(&path, &normalize(base))
+                .to_string_lossy()
+                .replace('\\', "/");
+        }
+    }
+
+    // Resolves relative sources against the directory of the map
+    fn with_path(mut self, map_path: &Path) -> Self {
+        let map_dir = map_path.parent().unwrap_or(Path::new(""));
+        for source in &mut self.sources {
+            if !source.is_empty()
+                && !source.contains("://")
+                && Path::new(source.as_str()).is_relative()
+            {
+                *source = map_dir.join(source.as_str()).to_string_lossy().to_string();
+            }
+        }
+        self
+    }
+
+    pub fn to_json(&self, file: &str) -> String {
+        let mut json = String::with_capacity(self.mappings.len() + 64);
+        json.push_str(r#"{"version":3,"file":""#);
+        json.push_str(&escape_json(file.as_bytes()));
+        json.push_str(r#"","sources":["#);
+        for (i, source) in self.sources.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            json.push('"');
+            json.push_str(&escape_json(source.as_bytes()));
+            json.push('"');
+        }
+        json.push_str(r#"],"mappings":""#);
+        json.push_str(&escape_json(self.mappings.as_bytes()));
+        json.push_str(r#""}"#);
+        json
+    }
+}
+
+/// Loads the source map a compiled source points to through its
+/// `sourceMappingURL` comment, either inline as a data URL or as a file next
+/// to it. Returns `None` when there is no comment or the file is missing.
+pub fn load_for_source(source: &[u8], path: &Path) -> io::Result<Option<SourceMap>> {
+    let Some(url) = source_mapping_url(source) else {
+        return Ok(None);
+    };
+
+    let (json, map_path) = if let Some(data) = url.strip_prefix("data:") {
+        let (_, base64) = data.split_once(";base64,").ok_or_else(|| {
+            invalid_source_map(path, "only base64 data URLs are supported".into())
+        })?;
+        let json = bytes_from_b64(base64.as_bytes()).map_err(|e| invalid_source_map(path, e))?;
+        (json, path.to_path_buf())
+    } else {
+        let map_path = path.parent().unwrap_or(Path::new("")).join(url);
+        match fs::read(&map_path) {
+            Ok(json) => (json, map_path),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                trace!("Source map {} not found", map_path.display());
+                return Ok(None);
+            },
+            Err(err) => return Err(err),
+        }
+    };
+
+    SourceMap::parse(&json)
+        .map(|map| Some(map.with_path(&map_path)))
+        .map_err(|e| invalid_source_map(&map_path, e))
+}
+
+/// Loads a source map file given explicitly.
+pub fn load_file(path: &Path) -> io::Result<SourceMap> {
+    let json = fs::read(path).map_err(|err| {
+        io::Error::new(
+            err.kind(),
+            [
+                "Unable to read source map ",
+                &path.to_string_lossy(),
+                ": ",
+                &err.to_string(),
+            ]
+            .concat(),
+        )
+    })?;
+    SourceMap::parse(&json)
+        .map(|map| map.with_path(path))
+        .map_err(|e| invalid_source_map(path, e))
+}
+
+/// Makes the source map of `module_name` available for stack traces.
+pub fn register(module_name: String, json: Vec<u8>) {
+    SOURCE_MAPS
+        .lock()
+        .unwrap()
+        .insert(module_name, SourceMapEntry::Raw(json));
+}
+
+/// Registers the source map `llrt compile` wrote next to the bytecode at
+/// `path`, if there is one. It applies to the module named in its `file`.
+pub fn register_for_bytecode(path: &str) {
+    let Ok(json) = fs::read([path, SOURCE_MAP_EXT].concat()) else {
+        return;
+    };
+    match SourceMap::parse(&json) {
+        Ok(map) => {
+            if let Some(file) = map.file.clone() {
+                SOURCE_MAPS
+                    .lock()
+                    .unwrap()
+                    .insert(file, SourceMapEntry::Parsed(Some(map)));
+            }
+        },
+        Err(err) => trace!("Invalid source map for {}: {}", path, err),
+    }
+}
+
+/// Rewrites the `file:line:column` locations of a stack trace to their
+/// original positions, and explains frames of bytecode compiled without debug
+/// info. Returns `None` when no frame changed.
+pub fn remap_stack_trace(stack: &str) -> Option<String> {
+    let mut source_maps = SOURCE_MAPS.lock().unwrap();
+    if source_maps.is_empty() && !stack.contains(MISSING_LOCATION) {
+        return None;
+    }
+
+    let mut result = String::with_capacity(stack.len());
+    let mut remapped = false;
+    for (i, frame) in stack.split('\n').enumerate() {
+        if i > 0 {
+            result.push('\n');
+        }
+        let Some((start, end)) = frame_location(frame) else {
+            result.push_str(frame);
+            continue;
+        };
+        let location = &frame[start..end];
+        if location == MISSING_LOCATION {
+            result.push_str(&frame[..start]);
+            result.push_str(STRIPPED_LOCATION);
+            result.push_str(&frame[end..]);
+            remapped = true;
+            continue;
+        }
+        let original = parse_location(location).and_then(|(file, line, column)| {
+            let map = source_map_for(&mut source_maps, file)?;
+            // QuickJS reports one based lines and columns
+            let (source, line, column) =
+                map.lookup(line.checked_sub(1)?, column.saturating_sub(1))?;
+            Some(
+                [
+                    source,
+                    ":",
+                    &(line + 1).to_string(),
+                    ":",
+                    &(column + 1).to_string(),
+                ]
+                .concat(),
+            )
+        });
+        match original {
+            Some(original) => {
+                result.push_str(&frame[..start]);
+                result.push_str(&original);
+                result.push_str(&frame[end..]);
+                remapped = true;
+            },
+            None => result.push_str(frame),
+        }
+    }
+
+    remapped.then_some(result)
+}
+
+fn source_map_for<'a>(
+    source_maps: &'a mut HashMap<String, SourceMapEntry>,
+    file: &str,
+) -> Option<&'a SourceMap> {
+    let entry = source_maps.get_mut(file)?;
+    if let SourceMapEntry::Raw(json) = entry {
+        let parsed = SourceMap::parse(json)
+            .map_err(|err| trace!("Invalid source map for {}: {}", file, err))
+            .ok();
+        *entry = SourceMapEntry::Parsed(parsed);
+    }
+    match entry {
+        SourceMapEntry::Parsed(map) => map.as_ref(),
+        SourceMapEntry::Raw(_) => None,
+    }
+}
+
+// Byte range of the location in "    at fn (file:1:2)" or "    at file:1:2"
+fn frame_location(frame: &str) -> Option<(usize, usize)> {
+    if let Some(stripped) = frame.strip_suffix(')') {
+        let start = stripped.rfind('(')? + 1;
+        return Some((start, stripped.len()));
+    }
+    let start = frame.find("at ")? + 3;
+    Some((start, frame.len()))
+}
+
+fn parse_location(location: &str) -> Option<(&str, u32, u32)> {
+    let mut parts = location.rsplitn(3, ':');
+    let column = parts.next()?.parse().ok()?;
+    let line = parts.next()?.parse().ok()?;
+    Some((parts.next()?, line, column))
+}
+
+fn source_mapping_url(source: &[u8]) -> Option<&str> {
+    let source = std::str::from_utf8(source).ok()?;
+    source.lines().rev().find_map(|line| {
+        let comment = line.trim().strip_prefix("//")?;
+        let url = comment
+            .strip_prefix(SOURCE_MAPPING_URL)
+            .or_else(|| comment.strip_prefix("@ sourceMappingURL="))?;
+        Some(url.trim())
+    })
+}
+
+fn join_source_root(source_root: &str, source: &str) -> String {
+    if source_root.is_empty() || source.contains("://") || Path::new(source).is_absolute() {
+        return source.to_string();
+    }
+    [source_root.trim_end_matches('/'), "/", source].concat()
+}
+
+fn decode_mappings(mappings: &str) -> Result<Vec<Vec<Mapping>>, String> {
+    let mut lines = Vec::new();
+    // Every field but the generated column is relative to the previous segment
+    let mut previous = Mapping {
+        column: 0,
+        source: 0,
+        line: 0,
+        source_column: 0,
+    };
+
+    for line in mappings.split(';') {
+        let mut segments = Vec::new();
+        let mut column = 0i64;
+        for segment in line.split(',').filter(|segment| !segment.is_empty()) {
+            let fields = decode_vlq(segment)?;
+            column += fields[0];
+            // Segments without a source position do not map to anything
+            if fields.len() < 4 {
+                continue;
+            }
+            let source = previous.source as i64 + fields[1];
+            let source_line = previous.line as i64 + fields[2];
+            let source_column = previous.source_column as i64 + fields[3];
+            if column < 0 || source < 0 || source_line < 0 || source_column < 0 {
+                return Err("Source map has negative positions".into());
+            }
+            previous = Mapping {
+                column: column as u32,
+                source: source as u32,
+                line: source_line as u32,
+                source_column: source_column as u32,
+            };
+            segments.push(previous);
+        }
+        segments.sort_by_key(|mapping| mapping.column);
+        lines.push(segments);
+    }
+
+    Ok(lines)
+}
+
+fn decode_vlq(segment: &str) -> Result<Vec<i64>, String> {
+    let mut fields = Vec::with_capacity(5);
+    let mut value = 0i64;
+    let mut shift = 0;
+    for byte in segment.bytes() {
+        let digit = BASE64_CHARS
+            .iter()
+            .position(|&c| c == byte)
+            .ok_or("Invalid character in source map mappings")? as i64;
+        if shift > 32 {
+            return Err("Source map mappings value is too large".into());
+        }
+        value += (digit & 31) << shift;
+        if digit & 32 != 0 {
+            shift += 5;
+            continue;
+        }
+        fields.push(if value & 1 == 1 {
+            -(value >> 1)
+        } else {
+            value >> 1
+        });
+        value = 0;
+        shift = 0;
+    }
+    if shift != 0 || fields.is_empty() {
+        return Err("Truncated source map mappings".into());
+    }
+    Ok(fields)
+}
+
+fn normalize(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir => {
+                if !normalized.pop() {
+                    normalized.push("..");
+                }
+            },
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+fn relative_to(path: &Path, base: &Path) -> PathBuf {
+    if path.is_absolute() != base.is_absolute() {
+        return path.to_path_buf();
+    }
+    let path_components: Vec<_> = path.components().collect();
+    let base_components: Vec<_> = base.components().collect();
+    let common = path_components
+        .iter()
+        .zip(&base_components)
+        .take_while(|(a, b)| a == b)
+        .count();
+    // Paths on different drives have nothing in common
+    if common == 0 && path.has_root() {
+        return path.to_path_buf();
+    }
+
+    let mut relative = PathBuf::new();
+    for _ in common..base_components.len() {
+        relative.push("..");
+    }
+    for component in &path_components[common..] {
+        relative.push(component);
+    }
+    relative
+}
+
+fn invalid_source_map(path: &Path, message: String) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        [
+            "Invalid source map ",
+            &path.to_string_lossy(),
+            ": ",
+            &message,
+        ]
+        .concat(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Generated from `let a = 1;\nthrow new Error("x");` in src/index.ts,
+    // compiled to two lines that are each indented by 4 spaces
+    const MAP: &str = r#"{"version":3,"sourceRoot":"../","sources":["src/index.ts"],"mappings":"IAAA;IACA,MAAM"}"#;
+
+    #[test]
+    fn test_decode_and_lookup() {
+        let map = SourceMap::parse(MAP.as_bytes()).unwrap();
+        assert_eq!(map.lookup(0, 4), Some(("../src/index.ts", 0, 0)));
+        assert_eq!(map.lookup(1, 4), Some(("../src/index.ts", 1, 0)));
+        assert_eq!(map.lookup(1, 12), Some(("../src/index.ts", 1, 6)));
+        assert_eq!(map.lookup(1, 0), None);
+        assert_eq!(map.lookup(5, 0), None);
+    }
+
+    #[test]
+    fn test_decode_vlq() {
+        assert_eq!(decode_vlq("AAgBC").unwrap(), vec![0, 0, 16, 1]);
+        assert_eq!(decode_vlq("D").unwrap(), vec![-1]);
+        assert!(decode_vlq("g").is_err());
+    }
+
+    #[test]
+    fn test_remap_stack_trace() {
+        register("/app/dist/index.js".into(), MAP.as_bytes().to_vec());
+        let stack = "    at f (/app/dist/index.js:2:13)\n    at /app/dist/index.js:1:5\n    at g (other.js:1:1)\n";
+        assert_eq!(
+            remap_stack_trace(stack).unwrap(),
+            "    at f (../src/index.ts:2:7)\n    at ../src/index.ts:1:1\n    at g (other.js:1:1)\n"
+        );
+        assert_eq!(remap_stack_trace("    at g (other.js:1:1)"), None);
+        assert_eq!(
+            remap_stack_trace("    at f (missing)\n    at g (native)").unwrap(),
+            "    at f (no debug info)\n    at g (native)"
+        );
+    }
+
+    #[test]
+    fn test_source_mapping_url_and_relative_sources() {
+        let source = b"console.log(1);\n//# sourceMappingURL=index.js.map\n";
+        assert_eq!(source_mapping_url(source), Some("index.js.map"));
+        assert_eq!(source_mapping_url(b"console.log(1);"), None);
+
+        let mut map = SourceMap::parse(MAP.as_bytes())
+            .unwrap()
+            .with_path(Path::new("/app/dist/index.js.map"));
+        map.relativize_sources(Path::new("/app"));
+        assert_eq!(map.sources, vec!["src/index.ts"]);
+        assert_eq!(
+            map.to_json("index.js"),
+            r#"{"version":3,"file":"index.js","sources":["src/index.ts"],"mappings":"IAAA;IACA,MAAM"}"#
+        );
+    }
+}
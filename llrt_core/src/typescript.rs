@@ -0,0 +1,1800 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+//! Erases TypeScript type syntax so `.ts`, `.mts` and `.cts` files can be
+//! loaded as JavaScript.
+//!
+//! Types are replaced by whitespace instead of being removed, so every line
+//! and column of the output matches the original source and stack traces
+//! point at the TypeScript file without a source map. Constructs that need
+//! code generation (enums, namespaces with values, parameter properties,
+//! `import x = require()` and `export =`) are rejected with their position.
+use std::fmt;
+
+use rquickjs::{Ctx, Exception, Result};
+
+pub const TYPESCRIPT_EXTENSIONS: &[&str] = &[".ts", ".mts", ".cts"];
+
+pub fn is_typescript(path: &str) -> bool {
+    TYPESCRIPT_EXTENSIONS.iter().any(|ext| path.ends_with(ext))
+}
+
+/// TypeScript files that are conventionally imported through their emitted
+/// name, `./a.js` for `./a.ts`. Returns the source path for such a name.
+pub fn typescript_counterpart(path: &str) -> Option<String> {
+    [(".js", ".ts"), (".mjs", ".mts"), (".cjs", ".cts")]
+        .iter()
+        .find_map(|(js, ts)| path.strip_suffix(js).map(|base| [base, ts].concat()))
+}
+
+/// A construct that cannot be erased, 1-based position.
+#[derive(Debug, PartialEq, Eq)]
+pub struct TypeScriptError {
+    pub message: &'static str,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl fmt::Display for TypeScriptError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+const ENUM_UNSUPPORTED: &str =
+    "enums are not supported, use a const object or a union of literal types";
+const NAMESPACE_UNSUPPORTED: &str =
+    "namespaces containing values are not supported, use an ES module";
+const PARAMETER_PROPERTY_UNSUPPORTED: &str =
+    "parameter properties are not supported, assign the field in the constructor";
+const IMPORT_ALIAS_UNSUPPORTED: &str =
+    "`import x = ...` is not supported, use an import declaration or require()";
+const EXPORT_ASSIGNMENT_UNSUPPORTED: &str = "`export =` is not supported, use `export default`";
+const TYPE_ASSERTION_UNSUPPORTED: &str =
+    "angle-bracket type assertions are not supported, use `value as Type`";
+const INVALID_TYPE: &str = "unsupported type syntax";
+const UNBALANCED: &str = "unbalanced brackets";
+const UNEXPECTED: &str = "unexpected token";
+
+/// Returns `source` with all type syntax replaced by spaces.
+pub fn strip_types(source: &[u8]) -> std::result::Result<Vec<u8>, TypeScriptError> {
+    let tokens = tokenize(source);
+    let mut stripper = Stripper {
+        source,
+        output: source.to_vec(),
+        tokens,
+        pos: 0,
+    };
+    stripper
+        .statements(false)
+        .map_err(|err| stripper.error_at(err))?;
+    Ok(stripper.output)
+}
+
+/// Like [`strip_types`], throwing a `SyntaxError` that names `path`.
+pub fn strip_types_or_throw(ctx: &Ctx<'_>, source: &[u8], path: &str) -> Result<Vec<u8>> {
+    strip_types(source)
+        .map_err(|err| Exception::throw_syntax(ctx, &[path, ":", &err.to_string()].concat()))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Ident,
+    Private,
+    Number,
+    String,
+    Template,
+    TemplateHead,
+    TemplateMiddle,
+    TemplateTail,
+    Regex,
+    Punct,
+    Eof,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Token {
+    kind: Kind,
+    start: usize,
+    end: usize,
+    newline_before: bool,
+}
+
+const PUNCTUATORS: &[&str] = &[
+    "...", "===", "!==", "**=", "<<=", "&&=", "||=", "??=", "=>", "==", "!=", "<=", "<<", "&&",
+    "||", "??", "**", "++", "--", "+=", "-=", "*=", "/=", "%=", "&=", "|=", "^=",
+];
+
+// Keywords after which an expression, not an operator, follows
+const EXPRESSION_KEYWORDS: &[&str] = &[
+    "return",
+    "typeof",
+    "instanceof",
+    "in",
+    "of",
+    "new",
+    "delete",
+    "void",
+    "throw",
+    "case",
+    "do",
+    "else",
+    "yield",
+    "await",
+    "extends",
+];
+
+fn is_ident_byte(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'$' || byte >= 0x80
+}
+
+fn ends_expression(source: &[u8], token: &Token) -> bool {
+    let text = &source[token.start..token.end];
+    match token.kind {
+        Kind::Ident => !EXPRESSION_KEYWORDS
+            .iter()
+            .any(|keyword| keyword.as_bytes() == text),
+        Kind::Private
+        | Kind::Number
+        | Kind::String
+        | Kind::Template
+        | Kind::TemplateTail
+        | Kind::Regex => true,
+        Kind::Punct => matches!(text, b")" | b"]" | b"}" | b"++" | b"--"),
+        _ => false,
+    }
+}
+
+fn tokenize(source: &[u8]) -> Vec<Token> {
+    let mut tokens: Vec<Token> = Vec::new();
+    // one entry per open brace, true for template substitutions
+    let mut braces: Vec<bool> = Vec::new();
+    let mut i = 0;
+    let mut newline_before = false;
+
+    if source.starts_with(b"\xEF\xBB\xBF") {
+        i = 3;
+    }
+    if source[i..].starts_with(b"#!") {
+        while i < source.len() && source[i] != b'\n' {
+            i += 1;
+        }
+    }
+
+    while i < source.len() {
+        let byte = source[i];
+        let start = i;
+        let kind = match byte {
+            b'\n' | b'\r' => {
+                newline_before = true;
+                i += 1;
+                continue;
+            },
+            b' ' | b'\t' | 0x0b | 0x0c => {
+                i += 1;
+                continue;
+            },
+            b'/' if source.get(i + 1) == Some(&b'/') => {
+                while i < source.len() && source[i] != b'\n' {
+                    i += 1;
+                }
+                continue;
+            },
+            b'/' if source.get(i + 1) == Some(&b'*') => {
+                i += 2;
+                while i < source.len() && !source[i..].starts_with(b"*/") {
+                    newline_before |= source[i] == b'\n';
+                    i += 1;
+                }
+                i = (i + 2).min(source.len());
+                continue;
+            },
+            b'/' if !tokens
+                .last()
+                .is_some_and(|token| ends_expression(source, token)) =>
+            {
+                let mut in_class = false;
+                i += 1;
+                while i < source.len() && source[i] != b'\n' {
+                    match source[i] {
+                        b'\\' => i += 1,
+                        b'[' => in_class = true,
+                        b']' => in_class = false,
+                        b'/' if !in_class => break,
+                        _ => {},
+                    }
+                    i += 1;
+                }
+                i += 1;
+                while i < source.len() && is_ident_byte(source[i]) {
+                    i += 1;
+                }
+                Kind::Regex
+            },
+            b'\'' | b'"' => {
+                i += 1;
+                while i < source.len() && source[i] != byte && source[i] != b'\n' {
+                    if source[i] == b'\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                i += 1;
+                Kind::String
+            },
+            b'`' => {
+                let (end, head) = scan_template(source, i + 1);
+                i = end;
+                if head {
+                    braces.push(true);
+                    Kind::TemplateHead
+                } else {
+                    Kind::Template
+                }
+            },
+            b'}' if braces.last() == Some(&true) => {
+                braces.pop();
+                let (end, head) = scan_template(source, i + 1);
+                i = end;
+                if head {
+                    braces.push(true);
+                    Kind::TemplateMiddle
+                } else {
+                    Kind::TemplateTail
+                }
+            },
+            b'#' if source.get(i + 1).is_some_and(|&byte| is_ident_byte(byte)) => {
+                i += 1;
+                while i < source.len() && is_ident_byte(source[i]) {
+                    i += 1;
+                }
+                Kind::Private
+            },
+            b'0'..=b'9' => {
+                i = scan_number(source, i);
+                Kind::Number
+            },
+            b'.' if source.get(i + 1).is_some_and(u8::is_ascii_digit) => {
+                i = scan_number(source, i);
+                Kind::Number
+            },
+            _ if is_ident_byte(byte) => {
+                while i < source.len() && is_ident_byte(source[i]) {
+                    i += 1;
+                }
+                Kind::Ident
+            },
+            _ => {
+                match byte {
+                    b'{' => braces.push(false),
+                    b'}' => {
+                        braces.pop();
+                    },
+                    _ => {},
+                }
+                let rest = &source[i..];
+                let optional_chain =
+                    rest.starts_with(b"?.") && !rest.get(2).is_some_and(u8::is_ascii_digit);
+                i += if optional_chain {
+                    2
+                } else {
+                    PUNCTUATORS
+                        .iter()
+                        .find(|punctuator| rest.starts_with(punctuator.as_bytes()))
+                        .map(|punctuator| punctuator.len())
+                        .unwrap_or(1)
+                };
+                Kind::Punct
+            },
+        };
+        tokens.push(Token {
+            kind,
+            start,
+            end: i.min(source.len()),
+            newline_before,
+        });
+        newline_before = false;
+    }
+    tokens.push(Token {
+        kind: Kind::Eof,
+        start: source.len(),
+        end: source.len(),
+        newline_before: true,
+    });
+    tokens
+}
+
+// Returns the end of a template chunk and whether it ends with `${`
+fn scan_template(source: &[u8], mut i: usize) -> (usize, bool) {
+    while i < source.len() {
+        match source[i] {
+            b'\\' => i += 1,
+            b'`' => return (i + 1, false),
+            b'$' if source.get(i + 1) == Some(&b'{') => return (i + 2, true),
+            _ => {},
+        }
+        i += 1;
+    }
+    (source.len(), false)
+}
+
+fn scan_number(source: &[u8], mut i: usize) -> usize {
+    let hex = source[i..].starts_with(b"0x") || source[i..].starts_with(b"0X");
+    while i < source.len() {
+        let byte = source[i];
+        let exponent_sign =
+            matches!(byte, b'+' | b'-') && !hex && matches!(source[i - 1], b'e' | b'E');
+        if !(byte.is_ascii_alphanumeric() || byte == b'_' || byte == b'.' || exponent_sign) {
+            break;
+        }
+        i += 1;
+    }
+    i
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Stop {
+    /// `;`, `}` or the end of a line that ends the statement
+    Statement,
+    /// Like `Statement` and also `,`, `)`, `]` and template substitution ends
+    Comma,
+    /// `:` of a `case` clause
+    Colon,
+    /// The body or `implements` clause of a class
+    Heritage,
+    /// The closing bracket of the enclosing group
+    Close,
+}
+
+// Errors carry the offending token and what is wrong with it
+type Parse<T> = std::result::Result<T, (usize, &'static str)>;
+
+struct Stripper<'a> {
+    source: &'a [u8],
+    output: Vec<u8>,
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Stripper<'_> {
+    fn error_at(&self, (index, message): (usize, &'static str)) -> TypeScriptError {
+        let before = &self.source[..self.token(index).start];
+        let line = before.iter().filter(|&&byte| byte == b'\n').count() + 1;
+        let line_start = before
+            .iter()
+            .rposition(|&byte| byte == b'\n')
+            .map_or(0, |index| index + 1);
+        let column = String::from_utf8_lossy(&before[line_start..])
+            .chars()
+            .count()
+            + 1;
+        TypeScriptError {
+            message,
+            line,
+            column,
+        }
+    }
+
+    fn token(&self, index: usize) -> &Token {
+        &self.tokens[index.min(self.tokens.len() - 1)]
+    }
+
+    fn text(&self, index: usize) -> &str {
+        let token = self.token(index);
+        std::str::from_utf8(&self.source[token.start..token.end]).unwrap_or_default()
+    }
+
+    fn kind(&self, index: usize) -> Kind {
+        self.token(index).kind
+    }
+
+    fn is(&self, index: usize, text: &str) -> bool {
+        matches!(self.kind(index), Kind::Ident | Kind::Punct) && self.text(index) == text
+    }
+
+    fn is_ident(&self, index: usize) -> bool {
+        self.kind(index) == Kind::Ident
+    }
+
+    fn same_line(&self, index: usize) -> bool {
+        !self.token(index).newline_before
+    }
+
+    fn at(&self, text: &str) -> bool {
+        self.is(self.pos, text)
+    }
+
+    fn is_eof(&self, index: usize) -> bool {
+        self.kind(index) == Kind::Eof
+    }
+
+    fn ends_expression(&self, index: usize) -> bool {
+        ends_expression(self.source, self.token(index)) || self.is_non_null(index)
+    }
+
+    // `value!`, written without whitespace and not followed by an operand
+    fn is_non_null(&self, index: usize) -> bool {
+        if index == 0
+            || !self.is(index, "!")
+            || self.token(index - 1).end != self.token(index).start
+            || !ends_expression(self.source, self.token(index - 1))
+                && !self.is_non_null_chain(index - 1)
+        {
+            return false;
+        }
+        // after `if (..)` or a block, `!` may start the next statement
+        if !self.is(index - 1, ")") && !self.is(index - 1, "}") {
+            return true;
+        }
+        let next = index + 1;
+        match self.kind(next) {
+            Kind::Eof => true,
+            _ if !self.same_line(next) => true,
+            Kind::Ident => matches!(self.text(next), "as" | "satisfies" | "in" | "instanceof"),
+            Kind::Punct => match self.text(next) {
+                "!" => self.token(index).end == self.token(next).start,
+                "(" | "{" | "~" | "++" | "--" | "+" | "-" | "..." | "<" | "@" => false,
+                _ => true,
+            },
+            _ => false,
+        }
+    }
+
+    // the first `!` of `value!!`
+    fn is_non_null_chain(&self, index: usize) -> bool {
+        index > 0
+            && self.is(index, "!")
+            && self.token(index - 1).end == self.token(index).start
+            && ends_expression(self.source, self.token(index - 1))
+    }
+
+    fn prev_ends_expression(&self) -> bool {
+        self.pos > 0 && self.ends_expression(self.pos - 1)
+    }
+
+    /// Replaces tokens `from..to` and everything between them by spaces
+    fn erase(&mut self, from: usize, to: usize) {
+        if to <= from {
+            return;
+        }
+        let start = self.token(from).start;
+        let end = self.token(to - 1).end;
+        for byte in &mut self.output[start..end] {
+            if *byte != b'\n' && *byte != b'\r' {
+                *byte = b' ';
+            }
+        }
+    }
+
+    fn erase_to(&mut self, to: usize) {
+        self.erase(self.pos, to);
+        self.pos = to;
+    }
+
+    fn expect(&mut self, text: &str) -> Parse<()> {
+        if !self.at(text) {
+            return Err((self.pos, UNEXPECTED));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    // A line break ends a statement when the next line cannot continue it
+    fn at_line_end(&self) -> bool {
+        let index = self.pos;
+        if index == 0 || self.same_line(index) || !self.ends_expression(index - 1) {
+            return false;
+        }
+        match self.kind(index) {
+            Kind::Ident => !matches!(
+                self.text(index),
+                "in" | "instanceof" | "as" | "satisfies" | "of"
+            ),
+            Kind::Private | Kind::Number | Kind::String | Kind::Regex | Kind::Eof => true,
+            Kind::Punct => matches!(self.text(index), "{" | "!" | "~" | "++" | "--" | "@"),
+            _ => false,
+        }
+    }
+
+    // ---- statements ----
+
+    fn statements(&mut self, in_block: bool) -> Parse<()> {
+        loop {
+            if self.is_eof(self.pos) || (in_block && self.at("}")) {
+                return Ok(());
+            }
+            let start = self.pos;
+            self.statement()?;
+            if self.pos == start {
+                self.pos += 1;
+            }
+        }
+    }
+
+    fn block(&mut self) -> Parse<()> {
+        self.expect("{")?;
+        self.statements(true)?;
+        self.expect("}")
+    }
+
+    fn statement(&mut self) -> Parse<()> {
+        let start = self.pos;
+        if self.declaration(start)? {
+            return Ok(());
+        }
+        let next = self.pos + 1;
+        match self.text(self.pos) {
+            "import" if !self.is(next, "(") && !self.is(next, ".") => self.import_declaration(),
+            "export" => self.export_declaration(),
+            "{" => self.block(),
+            "case" => {
+                self.pos += 1;
+                self.scan(Stop::Colon)?;
+                self.expect(":")
+            },
+            "default" if self.is(next, ":") => {
+                self.pos += 2;
+                Ok(())
+            },
+            _ if self.is_ident(self.pos) && self.is(next, ":") => {
+                // label
+                self.pos += 2;
+                Ok(())
+            },
+            _ => {
+                self.scan(Stop::Statement)?;
+                if self.at(";") {
+                    self.pos += 1;
+                }
+                Ok(())
+            },
+        }
+    }
+
+    /// Handles a declaration at the current token, `start` is the first
+    /// token of the statement including `export`. Returns false if there is
+    /// no declaration.
+    fn declaration(&mut self, start: usize) -> Parse<bool> {
+        let pos = self.pos;
+        let next = pos + 1;
+        let named = self.is_ident(next) && self.same_line(next);
+        match self.text(pos) {
+            "type" if named && (self.is(next + 1, "=") || self.is(next + 1, "<")) => {
+                let end = self.skip_type_alias(pos)?;
+                self.erase(start, end);
+                self.pos = end;
+            },
+            "interface" if named => {
+                let end = self.skip_interface(pos)?;
+                self.erase(start, end);
+                self.pos = end;
+            },
+            "declare" if named => {
+                let end = self.skip_ambient(next)?;
+                self.erase(start, end);
+                self.pos = end;
+            },
+            "enum" if named => return Err((pos, ENUM_UNSUPPORTED)),
+            "const" if self.is(next, "enum") => return Err((pos, ENUM_UNSUPPORTED)),
+            "namespace" | "module"
+                if (named || self.kind(next) == Kind::String) && self.same_line(next) =>
+            {
+                let end = self
+                    .skip_namespace(pos)?
+                    .ok_or((pos, NAMESPACE_UNSUPPORTED))?;
+                self.erase(start, end);
+                self.pos = end;
+            },
+            "abstract" if self.is(next, "class") && self.same_line(next) => {
+                self.erase_to(next);
+                self.class()?;
+            },
+            "class" => self.class()?,
+            "function" => self.function(Some(start))?,
+            "async" if self.is(next, "function") && self.same_line(next) => {
+                self.pos += 1;
+                self.function(Some(start))?;
+            },
+            "let" | "const" | "var"
+                if matches!(self.kind(next), Kind::Ident)
+                    || self.is(next, "{")
+                    || self.is(next, "[") =>
+            {
+                self.variable_declaration()?;
+                if self.at(";") {
+                    self.pos += 1;
+                }
+            },
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn variable_declaration(&mut self) -> Parse<()> {
+        self.pos += 1;
+        loop {
+            match self.text(self.pos) {
+                "{" => self.object_literal()?,
+                "[" => self.array()?,
+                _ if self.is_ident(self.pos) => self.pos += 1,
+                _ => return Ok(()),
+            }
+            if self.at("!") {
+                self.erase_to(self.pos + 1);
+            }
+            if self.at(":") {
+                self.erase_annotation()?;
+            }
+            if self.at("=") {
+                self.pos += 1;
+                self.scan(Stop::Comma)?;
+            }
+            if !self.at(",") {
+                return Ok(());
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn import_declaration(&mut self) -> Parse<()> {
+        let start = self.pos;
+        let next = start + 1;
+        if self.is(next, "type")
+            && (self.is(next + 1, "{")
+                || self.is(next + 1, "*")
+                || (self.is_ident(next + 1) && !self.is(next + 1, "from")))
+        {
+            let end = self.skip_module_clause(next + 1)?;
+            self.erase(start, end);
+            self.pos = end;
+            return Ok(());
+        }
+        if self.is_ident(next) && self.is(next + 1, "=") {
+            return Err((start, IMPORT_ALIAS_UNSUPPORTED));
+        }
+
+        self.pos += 1;
+        let mut has_values = false;
+        let mut only_types = false;
+        loop {
+            match self.text(self.pos) {
+                "from" if self.kind(self.pos + 1) == Kind::String => {
+                    self.pos += 1;
+                    break;
+                },
+                "{" => {
+                    let (values, types) = self.specifiers()?;
+                    has_values |= values;
+                    only_types = types && !values;
+                },
+                "," | "*" | "as" => self.pos += 1,
+                _ if self.is_ident(self.pos) => {
+                    has_values = true;
+                    self.pos += 1;
+                },
+                _ => break,
+            }
+        }
+        let end = self.skip_module_clause(self.pos)?;
+        if only_types && !has_values {
+            self.erase(start, end);
+        }
+        self.pos = end;
+        Ok(())
+    }
+
+    // Erases `type` specifiers, returns whether value and type specifiers were found
+    fn specifiers(&mut self) -> Parse<(bool, bool)> {
+        self.expect("{")?;
+        let (mut values, mut types) = (false, false);
+        loop {
+            if self.at("}") {
+                self.pos += 1;
+                return Ok((values, types));
+            }
+            if self.is_eof(self.pos) {
+                return Err((self.pos, UNBALANCED));
+            }
+            let start = self.pos;
+            let is_type = self.at("type") && !self.is(start + 1, ",") && !self.is(start + 1, "}");
+            let name = start + usize::from(is_type);
+            let mut end = name + 1;
+            if self.is(end, "as") {
+                end += 2;
+            }
+            if self.is(end, ",") {
+                end += 1;
+            }
+            if is_type {
+                types = true;
+                self.erase(start, end);
+            } else {
+                values = true;
+            }
+            self.pos = end;
+        }
+    }
+
+    // Skips `{ .. }`/`* as x` and `from "..."`, import attributes and `;`
+    fn skip_module_clause(&self, mut index: usize) -> Parse<usize> {
+        while !self.is_eof(index) && self.kind(index) != Kind::String {
+            if self.is(index, ";") || (index > 0 && !self.same_line(index) && self.is_ident(index))
+            {
+                return Ok(index);
+            }
+            index = if self.is(index, "{") {
+                self.skip_balanced(index)?
+            } else {
+                index + 1
+            };
+        }
+        index += 1;
+        if (self.is(index, "with") || self.is(index, "assert")) && self.is(index + 1, "{") {
+            index = self.skip_balanced(index + 1)?;
+        }
+        if self.is(index, ";") {
+            index += 1;
+        }
+        Ok(index)
+    }
+
+    fn export_declaration(&mut self) -> Parse<()> {
+        let start = self.pos;
+        self.pos += 1;
+        let next = self.pos + 1;
+        match self.text(self.pos) {
+            "type" if self.is(next, "{") || self.is(next, "*") => {
+                let end = self.skip_module_clause(next)?;
+                self.erase(start, end);
+                self.pos = end;
+            },
+            "=" => return Err((start, EXPORT_ASSIGNMENT_UNSUPPORTED)),
+            "import" => return Err((self.pos, IMPORT_ALIAS_UNSUPPORTED)),
+            "as" if self.is(next, "namespace") => {
+                let mut end = next + 2;
+                if self.is(end, ";") {
+                    end += 1;
+                }
+                self.erase(start, end);
+                self.pos = end;
+            },
+            "{" => {
+                let (values, types) = self.specifiers()?;
+                let end = self.skip_module_clause(self.pos)?;
+                if types && !values {
+                    self.erase(start, end);
+                }
+                self.pos = end;
+            },
+            "default" => {
+                self.pos += 1;
+                if self.at("interface") {
+                    let end = self.skip_interface(self.pos)?;
+                    self.erase(start, end);
+                    self.pos = end;
+                } else if !self.declaration(start)? {
+                    self.scan(Stop::Statement)?;
+                }
+            },
+            _ => {
+                if !self.declaration(start)? {
+                    self.scan(Stop::Statement)?;
+                }
+            },
+        }
+        if self.at(";") {
+            self.pos += 1;
+        }
+        Ok(())
+    }
+
+    // ---- functions and classes ----
+
+    /// `statement` is the first token of a function declaration, which
+    /// may be an overload signature without a body.
+    fn function(&mut self, statement: Option<usize>) -> Parse<()> {
+        self.pos += 1;
+        if self.at("*") {
+            self.pos += 1;
+        }
+        if self.is_ident(self.pos) && !self.at("(") {
+            self.pos += 1;
+        }
+        if self.at("<") {
+            self.erase_to(self.skip_balanced(self.pos)?);
+        }
+        if !self.at("(") {
+            return Ok(());
+        }
+        self.parameters()?;
+        if self.at(":") {
+            self.erase_annotation()?;
+        }
+        if self.at("{") {
+            return self.block();
+        }
+        if let Some(start) = statement {
+            let end = self.pos + usize::from(self.at(";"));
+            self.erase(start, end);
+            self.pos = end;
+        }
+        Ok(())
+    }
+
+    fn class(&mut self) -> Parse<()> {
+        self.pos += 1;
+        if self.is_ident(self.pos) && !self.at("extends") && !self.at("implements") {
+            self.pos += 1;
+        }
+        if self.at("<") {
+            self.erase_to(self.skip_balanced(self.pos)?);
+        }
+        if self.at("extends") {
+            self.pos += 1;
+            self.scan(Stop::Heritage)?;
+        }
+        if self.at("implements") {
+            let mut end = self.pos;
+            loop {
+                end = self.type_at(end + 1)?;
+                if !self.is(end, ",") {
+                    break;
+                }
+            }
+            self.erase_to(end);
+        }
+        self.class_body()
+    }
+
+    fn class_body(&mut self) -> Parse<()> {
+        self.expect("{")?;
+        loop {
+            if self.at("}") {
+                self.pos += 1;
+                return Ok(());
+            }
+            if self.is_eof(self.pos) {
+                return Err((self.pos, UNBALANCED));
+            }
+            if self.at(";") {
+                self.pos += 1;
+                continue;
+            }
+            let member = self.pos;
+            let mut declaration_only = false;
+            while self.is_member_modifier(self.pos) {
+                match self.text(self.pos) {
+                    "public" | "private" | "protected" | "readonly" | "override" => {
+                        self.erase(self.pos, self.pos + 1);
+                    },
+                    "declare" | "abstract" => declaration_only = true,
+                    _ => {},
+                }
+                self.pos += 1;
+            }
+            if self.at("static") && self.is(self.pos + 1, "{") {
+                self.pos += 1;
+                self.block()?;
+                continue;
+            }
+            if self.at("*") {
+                self.pos += 1;
+            }
+
+            if self.at("[") && self.is_ident(self.pos + 1) && self.is(self.pos + 2, ":") {
+                // index signature
+                let mut end = self.skip_balanced(self.pos)?;
+                if self.is(end, ":") {
+                    end = self.type_at(end + 1)?;
+                }
+                if self.is(end, ";") || self.is(end, ",") {
+                    end += 1;
+                }
+                self.erase(member, end);
+                self.pos = end;
+                continue;
+            }
+            if !self.property_key()? {
+                self.expression_token()?;
+                continue;
+            }
+            if (self.at("?") || self.at("!")) && !self.is(self.pos + 1, ".") {
+                self.erase_to(self.pos + 1);
+            }
+
+            if self.at("(") || self.at("<") {
+                if self.at("<") {
+                    self.erase_to(self.skip_balanced(self.pos)?);
+                }
+                self.parameters()?;
+                if self.at(":") {
+                    self.erase_annotation()?;
+                }
+                if self.at("{") {
+                    self.block()?;
+                } else {
+                    // overload or abstract method
+                    declaration_only = true;
+                }
+            } else {
+                if self.at(":") {
+                    self.erase_annotation()?;
+                }
+                if self.at("=") {
+                    self.pos += 1;
+                    self.scan(Stop::Statement)?;
+                }
+            }
+            if self.at(";") {
+                self.pos += 1;
+            }
+            if declaration_only {
+                self.erase(member, self.pos);
+            }
+        }
+    }
+
+    fn is_member_modifier(&self, index: usize) -> bool {
+        let modifier = matches!(
+            self.text(index),
+            "public"
+                | "private"
+                | "protected"
+                | "readonly"
+                | "override"
+                | "declare"
+                | "abstract"
+                | "static"
+                | "accessor"
+                | "async"
+                | "get"
+                | "set"
+        );
+        let next = index + 1;
+        modifier
+            && self.is_ident(index)
+            && (matches!(
+                self.kind(next),
+                Kind::Ident | Kind::Private | Kind::String | Kind::Number
+            ) || self.is(next, "[")
+                || self.is(next, "*"))
+    }
+
+    // Consumes a property name, returns false if there is none
+    fn property_key(&mut self) -> Parse<bool> {
+        match self.kind(self.pos) {
+            Kind::Ident | Kind::Private | Kind::String | Kind::Number => self.pos += 1,
+            _ if self.at("[") => self.array()?,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    fn parameters(&mut self) -> Parse<()> {
+        self.expect("(")?;
+        loop {
+            if self.at(")") {
+                self.pos += 1;
+                return Ok(());
+            }
+            if self.is_eof(self.pos) {
+                return Err((self.pos, UNBALANCED));
+            }
+            if matches!(
+                self.text(self.pos),
+                "public" | "private" | "protected" | "readonly" | "override"
+            ) && (self.is_ident(self.pos + 1)
+                || self.is(self.pos + 1, "{")
+                || self.is(self.pos + 1, "["))
+            {
+                return Err((self.pos, PARAMETER_PROPERTY_UNSUPPORTED));
+            }
+            if self.at("this") && (self.is(self.pos + 1, ":") || self.is(self.pos + 1, ",")) {
+                let mut end = self.pos + 1;
+                if self.is(end, ":") {
+                    end = self.type_at(end + 1)?;
+                }
+                if self.is(end, ",") {
+                    end += 1;
+                }
+                self.erase_to(end);
+                continue;
+            }
+            if self.at("...") {
+                self.pos += 1;
+            }
+            match self.text(self.pos) {
+                "{" => self.object_literal()?,
+                "[" => self.array()?,
+                _ => self.pos += 1,
+            }
+            if self.at("?") {
+                self.erase_to(self.pos + 1);
+            }
+            if self.at(":") {
+                self.erase_annotation()?;
+            }
+            if self.at("=") {
+                self.pos += 1;
+                self.scan(Stop::Comma)?;
+            }
+            if self.at(",") {
+                self.pos += 1;
+            }
+        }
+    }
+
+    // Erases `: Type` at the current token
+    fn erase_annotation(&mut self) -> Parse<()> {
+        let end = self.type_at(self.pos + 1)?;
+        self.erase_to(end);
+        Ok(())
+    }
+
+    // ---- expressions ----
+
+    fn scan(&mut self, stop: Stop) -> Parse<()> {
+        loop {
+            let pos = self.pos;
+            let text = self.text(pos);
+            let kind = self.kind(pos);
+            let closes = matches!(text, ")" | "]" | "}")
+                || matches!(kind, Kind::TemplateMiddle | Kind::TemplateTail);
+            let stopped = kind == Kind::Eof
+                || match stop {
+                    Stop::Statement => text == ";" || text == "}" || self.at_line_end(),
+                    Stop::Comma => text == "," || text == ";" || closes || self.at_line_end(),
+                    Stop::Colon => text == ":" || closes,
+                    Stop::Heritage => text == "{" || text == "implements" || closes,
+                    Stop::Close => closes,
+                };
+            if stopped {
+                return Ok(());
+            }
+            if closes {
+                // stray closing bracket inside a statement
+                self.pos += 1;
+                continue;
+            }
+            self.expression_token()?;
+        }
+    }
+
+    fn expression_token(&mut self) -> Parse<()> {
+        let pos = self.pos;
+        let next = pos + 1;
+        let after_dot = pos > 0 && (self.is(pos - 1, ".") || self.is(pos - 1, "?."));
+        match self.text(pos) {
+            _ if self.kind(pos) == Kind::TemplateHead => return self.template(),
+            "(" => return self.parenthesized(),
+            "[" => return self.array(),
+            "{" => {
+                let block = pos == 0
+                    || self.is(pos - 1, ")")
+                    || self.is(pos - 1, "=>")
+                    || matches!(
+                        self.text(pos - 1),
+                        "else" | "do" | "try" | "catch" | "finally"
+                    );
+                return if block {
+                    self.block()
+                } else {
+                    self.object_literal()
+                };
+            },
+            "function" if !after_dot => return self.function(None),
+            "class" if !after_dot => return self.class(),
+            "let" | "const" | "var" if pos > 0 && self.is(pos - 1, "(") => {
+                return self.variable_declaration()
+            },
+            "catch" if !after_dot && self.is(next, "(") => {
+                self.pos += 2;
+                match self.text(self.pos) {
+                    "{" => self.object_literal()?,
+                    "[" => self.array()?,
+                    _ => self.pos += 1,
+                }
+                if self.at(":") {
+                    self.erase_annotation()?;
+                }
+                return self.expect(")");
+            },
+            "as" | "satisfies" if self.prev_ends_expression() && self.same_line(pos) => {
+                let end = self.type_at(next)?;
+                self.erase_to(end);
+                return Ok(());
+            },
+            "!" if self.is_non_null(pos) => {
+                self.erase_to(next);
+                return Ok(());
+            },
+            "<" if self.prev_ends_expression() => {
+                if let Some(end) = self.type_arguments(pos) {
+                    self.erase_to(end);
+                    return Ok(());
+                }
+            },
+            "<" => {
+                // generic arrow function
+                let end = self.skip_balanced(pos)?;
+                if self.is(end, "(") && self.arrow_return_type(end).is_some() {
+                    self.erase_to(end);
+                    return self.parenthesized();
+                }
+                return Err((pos, TYPE_ASSERTION_UNSUPPORTED));
+            },
+            _ => {},
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn array(&mut self) -> Parse<()> {
+        self.expect("[")?;
+        self.scan(Stop::Close)?;
+        self.expect("]")
+    }
+
+    fn template(&mut self) -> Parse<()> {
+        self.pos += 1;
+        loop {
+            self.scan(Stop::Close)?;
+            match self.kind(self.pos) {
+                Kind::TemplateMiddle => self.pos += 1,
+                Kind::TemplateTail => {
+                    self.pos += 1;
+                    return Ok(());
+                },
+                _ => return Err((self.pos, UNBALANCED)),
+            }
+        }
+    }
+
+    fn parenthesized(&mut self) -> Parse<()> {
+        let open = self.pos;
+        // arguments of a call are never parameters
+        let call = self.prev_ends_expression() && !self.is(open - 1, "async");
+        let Some(return_type) = self.arrow_return_type(open).filter(|_| !call) else {
+            self.pos += 1;
+            self.scan(Stop::Close)?;
+            return self.expect(")");
+        };
+        self.parameters()?;
+        let close = self.pos - 1;
+        if let Some(arrow) = return_type {
+            self.erase_to(arrow);
+            // `=>` has to stay on the line of the `)`
+            let close_end = self.token(close).end;
+            let arrow_start = self.token(arrow).start;
+            if self.source[close_end..arrow_start].contains(&b'\n') {
+                let type_end = self.token(arrow - 1).end;
+                self.output[close_end - 1] = b' ';
+                self.output[type_end - 1] = b')';
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether the parentheses at `open` are arrow function parameters,
+    /// with the index of the `=>` if there is a return type.
+    fn arrow_return_type(&self, open: usize) -> Option<Option<usize>> {
+        let close = self.skip_balanced(open).ok()? - 1;
+        let after = close + 1;
+        if self.is(after, "=>") {
+            return Some(None);
+        }
+        if self.is(after, ":") {
+            let arrow = self.parse_type(after + 1)?;
+            if self.is(arrow, "=>") {
+                return Some(Some(arrow));
+            }
+        }
+        None
+    }
+
+    fn object_literal(&mut self) -> Parse<()> {
+        self.expect("{")?;
+        loop {
+            match self.text(self.pos) {
+                "}" => {
+                    self.pos += 1;
+                    return Ok(());
+                },
+                "," => {
+                    self.pos += 1;
+                    continue;
+                },
+                "..." => {
+                    self.pos += 1;
+                    self.scan(Stop::Comma)?;
+                    continue;
+                },
+                _ if self.is_eof(self.pos) => return Err((self.pos, UNBALANCED)),
+                _ => {},
+            }
+            while (matches!(self.text(self.pos), "async" | "get" | "set")
+                && self.is_member_modifier(self.pos))
+                || self.at("*")
+            {
+                self.pos += 1;
+            }
+            if !self.property_key()? {
+                let start = self.pos;
+                self.scan(Stop::Comma)?;
+                if self.pos == start {
+                    return Err((start, UNEXPECTED));
+                }
+                continue;
+            }
+            if self.at("(") || self.at("<") {
+                if self.at("<") {
+                    self.erase_to(self.skip_balanced(self.pos)?);
+                }
+                self.parameters()?;
+                if self.at(":") {
+                    self.erase_annotation()?;
+                }
+                self.block()?;
+            } else if self.at(":") || self.at("=") {
+                self.pos += 1;
+                self.scan(Stop::Comma)?;
+            }
+        }
+    }
+
+    /// Type arguments of a call or instantiation at `open`, returns the
+    /// token after the closing `>`.
+    fn type_arguments(&self, open: usize) -> Option<usize> {
+        let mut index = open;
+        loop {
+            index = self.parse_type(index + 1)?;
+            if !self.is(index, ",") {
+                break;
+            }
+        }
+        if !self.is(index, ">") {
+            return None;
+        }
+        index += 1;
+        let follows = !self.same_line(index)
+            || matches!(
+                self.kind(index),
+                Kind::Eof | Kind::Template | Kind::TemplateHead
+            )
+            || matches!(
+                self.text(index),
+                "(" | ")" | "]" | "," | ";" | "." | "?." | "}" | "{" | "implements"
+            );
+        follows.then_some(index)
+    }
+
+    // ---- skipping types and declarations ----
+
+    /// Returns the index after the bracket group opening at `open`.
+    fn skip_balanced(&self, open: usize) -> Parse<usize> {
+        let angle = self.is(open, "<");
+        let mut depth = 0usize;
+        let mut index = open;
+        loop {
+            let token = self.token(index);
+            match token.kind {
+                Kind::Eof => return Err((open, UNBALANCED)),
+                Kind::TemplateHead => depth += 1,
+                Kind::TemplateTail => depth -= 1,
+                Kind::Punct => match self.text(index) {
+                    "(" | "[" | "{" => depth += 1,
+                    "<" if angle => depth += 1,
+                    ")" | "]" | "}" => depth = depth.checked_sub(1).ok_or((open, UNBALANCED))?,
+                    ">" if angle => depth -= 1,
+                    _ => {},
+                },
+                _ => {},
+            }
+            index += 1;
+            if depth == 0 {
+                return Ok(index);
+            }
+        }
+    }
+
+    fn type_at(&self, index: usize) -> Parse<usize> {
+        self.parse_type(index).ok_or((index, INVALID_TYPE))
+    }
+
+    /// Parses a type starting at `index`, returns the index after it.
+    fn parse_type(&self, index: usize) -> Option<usize> {
+        let index = self.parse_union(index)?;
+        if self.is(index, "extends") && self.same_line(index) {
+            // conditional type
+            let check = self.parse_union(index + 1)?;
+            if !self.is(check, "?") {
+                return None;
+            }
+            let then = self.parse_type(check + 1)?;
+            if !self.is(then, ":") {
+                return None;
+            }
+            return self.parse_type(then + 1);
+        }
+        Some(index)
+    }
+
+    fn parse_union(&self, mut index: usize) -> Option<usize> {
+        if self.is(index, "|") {
+            index += 1;
+        }
+        index = self.parse_intersection(index)?;
+        while self.is(index, "|") {
+            index = self.parse_intersection(index + 1)?;
+        }
+        Some(index)
+    }
+
+    fn parse_intersection(&self, mut index: usize) -> Option<usize> {
+        if self.is(index, "&") {
+            index += 1;
+        }
+        index = self.parse_operator(index)?;
+        while self.is(index, "&") {
+            index = self.parse_operator(index + 1)?;
+        }
+        Some(index)
+    }
+
+    fn parse_operator(&self, index: usize) -> Option<usize> {
+        let next = index + 1;
+        let operand = self.is_ident(next) || self.kind(next) == Kind::Punct;
+        match self.text(index) {
+            "keyof" | "unique" | "readonly" if operand && !self.is(next, ",") => {
+                self.parse_operator(next)
+            },
+            "infer" if self.is_ident(next) => {
+                let mut end = next + 1;
+                if self.is(end, "extends") && !self.is(self.parse_type(end + 1)?, "?") {
+                    end = self.parse_type(end + 1)?;
+                }
+                Some(end)
+            },
+            _ => {
+                let mut end = self.parse_primary(index)?;
+                while self.is(end, "[") && self.same_line(end) {
+                    end = if self.is(end + 1, "]") {
+                        end + 2
+                    } else {
+                        let inner = self.parse_type(end + 1)?;
+                        self.is(inner, "]").then_some(inner + 1)?
+                    };
+                }
+                Some(end)
+            },
+        }
+    }
+
+    fn parse_primary(&self, index: usize) -> Option<usize> {
+        let next = index + 1;
+        match self.kind(index) {
+            Kind::String | Kind::Number | Kind::Template => return Some(next),
+            Kind::TemplateHead => {
+                let mut index = next;
+                loop {
+                    index = self.parse_type(index)?;
+                    match self.kind(index) {
+                        Kind::TemplateMiddle => index += 1,
+                        Kind::TemplateTail => return Some(index + 1),
+                        _ => return None,
+                    }
+                }
+            },
+            Kind::Ident => {},
+            Kind::Punct => {},
+            _ => return None,
+        }
+        match self.text(index) {
+            "(" => {
+                let close = self.skip_balanced(index).ok()?;
+                if self.is(close, "=>") {
+                    return self.parse_type(close + 1);
+                }
+                let inner = self.parse_type(next)?;
+                self.is(inner, ")").then_some(inner + 1)
+            },
+            "<" => {
+                let params = self.skip_balanced(index).ok()?;
+                self.function_type(params)
+            },
+            "{" | "[" => self.skip_balanced(index).ok(),
+            "-" if self.kind(next) == Kind::Number => Some(next + 1),
+            "new" => {
+                let mut params = next;
+                if self.is(params, "<") {
+                    params = self.skip_balanced(params).ok()?;
+                }
+                self.function_type(params)
+            },
+            "abstract" if self.is(next, "new") => self.parse_primary(next),
+            "typeof" => {
+                let mut end = if self.is(next, "import") {
+                    self.parse_primary(next)?
+                } else {
+                    self.entity_name(next)?
+                };
+                if self.is(end, "<") && self.same_line(end) {
+                    end = self.type_argument_list(end)?;
+                }
+                Some(end)
+            },
+            "import" if self.is(next, "(") => {
+                let mut end = self.skip_balanced(next).ok()?;
+                while self.is(end, ".") && self.is_ident(end + 1) {
+                    end += 2;
+                }
+                if self.is(end, "<") {
+                    end = self.type_argument_list(end)?;
+                }
+                Some(end)
+            },
+            "asserts" if self.is_ident(next) && self.same_line(next) && !self.is(next, "is") => {
+                let end = next + 1;
+                if self.is(end, "is") {
+                    return self.parse_type(end + 1);
+                }
+                Some(end)
+            },
+            _ if self.is_ident(index) => {
+                if self.is(next, "is") && self.same_line(next) {
+                    return self.parse_type(next + 1);
+                }
+                let mut end = self.entity_name(index)?;
+                if self.is(end, "<") && self.same_line(end) {
+                    end = self.type_argument_list(end)?;
+                }
+                Some(end)
+            },
+            _ => None,
+        }
+    }
+
+    fn function_type(&self, params: usize) -> Option<usize> {
+        if !self.is(params, "(") {
+            return None;
+        }
+        let close = self.skip_balanced(params).ok()?;
+        self.is(close, "=>").then_some(())?;
+        self.parse_type(close + 1)
+    }
+
+    fn entity_name(&self, index: usize) -> Option<usize> {
+        if !self.is_ident(index) {
+            return None;
+        }
+        let mut end = index + 1;
+        while self.is(end, ".") && self.is_ident(end + 1) {
+            end += 2;
+        }
+        Some(end)
+    }
+
+    fn type_argument_list(&self, open: usize) -> Option<usize> {
+        let mut index = open;
+        loop {
+            index = self.parse_type(index + 1)?;
+            if !self.is(index, ",") {
+                break;
+            }
+        }
+        self.is(index, ">").then_some(index + 1)
+    }
+
+    // `type Name<T> = Type;`
+    fn skip_type_alias(&self, index: usize) -> Parse<usize> {
+        let mut end = index + 2;
+        if self.is(end, "<") {
+            end = self.skip_balanced(end)?;
+        }
+        if !self.is(end, "=") {
+            return Err((end, UNEXPECTED));
+        }
+        end = self.type_at(end + 1)?;
+        if self.is(end, ";") {
+            end += 1;
+        }
+        Ok(end)
+    }
+
+    // `interface Name<T> extends A, B { .. }`
+    fn skip_interface(&self, index: usize) -> Parse<usize> {
+        let mut end = index + 2;
+        if self.is(end, "<") {
+            end = self.skip_balanced(end)?;
+        }
+        if self.is(end, "extends") {
+            loop {
+                end = self.type_at(end + 1)?;
+                if !self.is(end, ",") {
+                    break;
+                }
+            }
+        }
+        if !self.is(end, "{") {
+            return Err((end, UNEXPECTED));
+        }
+        self.skip_balanced(end)
+    }
+
+    // Skips the declaration after `declare`
+    fn skip_ambient(&self, index: usize) -> Parse<usize> {
+        let mut end = index + 1;
+        match self.text(index) {
+            "type" => return self.skip_type_alias(index),
+            "interface" => return self.skip_interface(index),
+            "const" if self.is(end, "enum") => return self.skip_ambient(end),
+            "const" | "let" | "var" => loop {
+                end += 1;
+                if self.is(end, ":") || self.is(end, "=") {
+                    end = self.type_at(end + 1)?;
+                }
+                if !self.is(end, ",") {
+                    break;
+                }
+                end += 1;
+            },
+            "function" => {
+                end += 1;
+                if self.is(end, "<") {
+                    end = self.skip_balanced(end)?;
+                }
+                end = self.skip_balanced(end)?;
+                if self.is(end, ":") {
+                    end = self.type_at(end + 1)?;
+                }
+            },
+            "abstract" | "class" | "enum" | "module" | "namespace" | "global" => {
+                while !self.is(end, "{") && !self.is(end, ";") {
+                    if self.is_eof(end)
+                        || (!self.same_line(end) && self.is_ident(end) && end > index + 2)
+                    {
+                        return Ok(end);
+                    }
+                    end = if self.is(end, "<") || self.is(end, "(") {
+                        self.skip_balanced(end)?
+                    } else {
+                        end + 1
+                    };
+                }
+                if self.is(end, "{") {
+                    return self.skip_balanced(end);
+                }
+            },
+            _ => return Err((index, UNEXPECTED)),
+        }
+        if self.is(end, ";") {
+            end += 1;
+        }
+        Ok(end)
+    }
+
+    /// Skips a namespace at `index` if it only declares types, returns
+    /// `None` if it contains values.
+    fn skip_namespace(&self, index: usize) -> Parse<Option<usize>> {
+        let mut open = index + 2;
+        while self.is(open, ".") {
+            open += 2;
+        }
+        if !self.is(open, "{") {
+            return Err((index, UNEXPECTED));
+        }
+        let close = self.skip_balanced(open)? - 1;
+        let mut statement = open + 1;
+        while statement < close {
+            if self.is(statement, ";") {
+                statement += 1;
+                continue;
+            }
+            if self.is(statement, "export") {
+                statement += 1;
+            }
+            let next = statement + 1;
+            statement = match self.text(statement) {
+                "type" => self.skip_type_alias(statement)?,
+                "interface" => self.skip_interface(statement)?,
+                "declare" => self.skip_ambient(next)?,
+                "namespace" | "module" => match self.skip_namespace(statement)? {
+                    Some(end) => end,
+                    None => return Ok(None),
+                },
+                "import" if self.is(next, "type") => self.skip_module_clause(next + 1)?,
+                _ => return Ok(None),
+            };
+        }
+        Ok(Some(close + 1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn strip(source: &str) -> String {
+        let output = strip_types(source.as_bytes()).unwrap();
+        assert_eq!(output.len(), source.len());
+        String::from_utf8(output).unwrap()
+    }
+
+    fn compact(source: &str) -> String {
+        strip(source)
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    fn error(source: &str) -> TypeScriptError {
+        strip_types(source.as_bytes()).unwrap_err()
+    }
+
+    #[test]
+    fn test_annotations() {
+        assert_eq!(
+            strip("let a: number = 1, b: Array<string> = [];"),
+            "let a         = 1, b                = [];"
+        );
+        assert_eq!(
+            compact("function f<T>(a: T, b?: string, ...rest: T[]): Promise<void> { return g(a) }"),
+            "function f (a , b , ...rest ) { return g(a) }"
+        );
+        assert_eq!(
+            compact("const f = async ({ a, b }: Props, [c]: [number]): Promise<A | B> => a;"),
+            "const f = async ({ a, b } , [c] ) => a;"
+        );
+        assert_eq!(
+            compact("const g = <T,>(x: T): x is string => typeof x === 'string';"),
+            "const g = (x ) => typeof x === 'string';"
+        );
+        assert_eq!(
+            compact("try { f() } catch (e: unknown) { throw e as Error }"),
+            "try { f() } catch (e ) { throw e }"
+        );
+        assert_eq!(
+            compact("const o = { m<T>(this: Window, a: T): void {}, n: (x: number) => x };"),
+            "const o = { m ( a ) {}, n: (x ) => x };"
+        );
+    }
+
+    #[test]
+    fn test_expressions() {
+        assert_eq!(
+            compact("const x = a! + (b as unknown as number) + (c satisfies C) + d!.e;"),
+            "const x = a + (b ) + (c ) + d .e;"
+        );
+        assert_eq!(
+            compact("const m = new Map<string, Set<number>>(); f<T>(1); x = a < b && c > (d);"),
+            "const m = new Map (); f (1); x = a < b && c > (d);"
+        );
+        assert_eq!(
+            compact("for (let i: number = 0; i < n; i++) {} if (a < b) { c = d ? e : f }"),
+            "for (let i = 0; i < n; i++) {} if (a < b) { c = d ? e : f }"
+        );
+        assert_eq!(
+            compact("const r = /a:b<c>/g.test(`${x as string}:${y}`);"),
+            "const r = /a:b<c>/g.test(`${x }:${y}`);"
+        );
+    }
+
+    #[test]
+    fn test_declarations() {
+        assert_eq!(
+            compact(
+                "type A<T> = { a: T } | B\ninterface I extends J<K> { x: number }\n\
+                 declare const c: string\ndeclare module 'm' { export const x: 1 }\nrun()"
+            ),
+            "run()"
+        );
+        assert_eq!(
+            compact(
+                "function f(a: string): void;\nfunction f(a: any) {}\n\
+                 export function g(): void;\nexport function g() {}"
+            ),
+            "function f(a ) {} export function g() {}"
+        );
+        assert_eq!(
+            compact("namespace N { export type T = string; interface I {} }\nexport {}"),
+            "export {}"
+        );
+    }
+
+    #[test]
+    fn test_imports_and_exports() {
+        assert_eq!(
+            compact(
+                "import type { A } from './a';\nimport { type B, c } from './b';\n\
+                 import { type D } from './d';\nimport e, { type F } from './e';"
+            ),
+            "import { c } from './b'; import e, { } from './e';"
+        );
+        assert_eq!(
+            compact("export type { A } from './a';\nexport { type B, c };\nexport type T = 1;"),
+            "export { c };"
+        );
+        assert_eq!(
+            compact("export interface I {}\nexport default interface J {}\nexport const x = 1;"),
+            "export const x = 1;"
+        );
+    }
+
+    #[test]
+    fn test_classes() {
+        let source = "abstract class A<T> extends B<T> implements C, D<T> {
+  private readonly x: number = 1;
+  declare y: string;
+  static z?: T;
+  w!: string
+  [key: string]: unknown;
+  abstract m(): void;
+  n(a: string): void;
+  n(a: any): T { return this.x as any }
+  get v(): number { return 1 }
+  #p: number = 2
+}";
+        assert_eq!(
+            compact(source),
+            "class A extends B { x = 1; static z ; w n(a ) { return this.x } get v() { return 1 } #p = 2 }"
+        );
+    }
+
+    #[test]
+    fn test_positions_preserved() {
+        let source = "const f = (a: string): Promise<\n  void\n> => g(a);\nthrow new Error()";
+        let output = strip(source);
+        assert_eq!(output.lines().count(), source.lines().count());
+        assert!(output
+            .lines()
+            .last()
+            .unwrap()
+            .starts_with("throw new Error()"));
+        // `=>` has to follow `)` on the same line
+        assert!(output.contains(")=> g(a)") || output.contains(") => g(a)"));
+    }
+
+    #[test]
+    fn test_rejected() {
+        let err = error("const a = 1;\n  enum E { A }");
+        assert_eq!(
+            (err.line, err.column, err.message),
+            (2, 3, ENUM_UNSUPPORTED)
+        );
+        assert_eq!(error("const enum E { A }").message, ENUM_UNSUPPORTED);
+        assert_eq!(
+            error("namespace N { export const x = 1 }").message,
+            NAMESPACE_UNSUPPORTED
+        );
+        assert_eq!(
+            error("class A { constructor(private x: number) {} }").message,
+            PARAMETER_PROPERTY_UNSUPPORTED
+        );
+        assert_eq!(
+            error("import fs = require('fs');").message,
+            IMPORT_ALIAS_UNSUPPORTED
+        );
+        assert_eq!(
+            error("export = foo;").message,
+            EXPORT_ASSIGNMENT_UNSUPPORTED
+        );
+        assert_eq!(
+            error("const x = <any>y;").message,
+            TYPE_ASSERTION_UNSUPPORTED
+        );
+    }
+
+    #[test]
+    fn test_typescript_counterpart() {
+        assert_eq!(
+            typescript_counterpart("/a/b.js").as_deref(),
+            Some("/a/b.ts")
+        );
+        assert_eq!(
+            typescript_counterpart("/a/b.cjs").as_deref(),
+            Some("/a/b.cts")
+        );
+        assert_eq!(typescript_counterpart("/a/b.json"), None);
+        assert!(is_typescript("/a/b.mts"));
+        assert!(!is_typescript("/a/b.js"));
+    }
+}
@@ -18,4 +18,26 @@ macro_rules! define_supported_extensions {
     };
 }
 
-define_supported_extensions!(BYTECODE_FILE_EXT, ".js", ".mjs", ".cjs");
+define_supported_extensions!(
+    BYTECODE_FILE_EXT,
+    ".js",
+    ".mjs",
+    ".cjs",
+    ".ts",
+    ".mts",
+    ".cts"
+);
+
+/// Files that are CommonJS whatever the package type says
+pub fn is_commonjs(path: &str) -> bool {
+    path.ends_with(".cjs") || path.ends_with(".cts")
+}
+
+/// `a.lrt` for `a.js` and the other JS extensions, what `compile` turns a
+/// source file into
+pub fn bytecode_counterpart(path: &str) -> Option<String> {
+    JS_EXTENSIONS
+        .iter()
+        .find_map(|ext| path.strip_suffix(ext))
+        .map(|base| [base, BYTECODE_FILE_EXT].concat())
+}
@@ -15,9 +15,18 @@ use rquickjs::{
 
 pub static COMPRESSION_DICT: &[u8] = include_bytes!("../../prebuild/compression.dict");
 
+/// Hash of the QuickJS sources this runtime was built from. Bytecode only
+/// runs on runtimes with the same hash.
+pub const BYTECODE_ABI: &str = env!("LLRT_BYTECODE_ABI");
+
+pub fn bytecode_abi_hash() -> &'static str {
+    BYTECODE_ABI
+}
+
 use crate::libs::{
     context::set_spawn_error_handler,
     json::{parse::json_parse_string, stringify::json_stringify_replacer_space},
+    logging::{map_stack_trace, set_stack_trace_mapper},
     numbers::number_to_string,
     utils::{
         clone::structured_clone,
@@ -33,7 +42,7 @@ use crate::modules::{
     module::{self},
     require::{loader::CustomLoader, resolver::CustomResolver},
 };
-use crate::{environment, http, security};
+use crate::{environment, http, security, source_map};
 
 pub struct Vm {
     pub runtime: AsyncRuntime,
@@ -57,9 +66,10 @@ impl Default for VmOptions {
             gc_threshold_mb: {
                 const DEFAULT_GC_THRESHOLD_MB: usize = 20;
 
-                let gc_threshold_mb: usize = env::var(environment::ENV_LLRT_GC_THRESHOLD_MB)
-                    .map(|threshold| threshold.parse().unwrap_or(DEFAULT_GC_THRESHOLD_MB))
-                    .unwrap_or(DEFAULT_GC_THRESHOLD_MB);
+                let gc_threshold_mb: usize =
+                    environment::var(environment::ENV_LLRT_GC_THRESHOLD_MB)
+                        .map(|threshold| threshold.parse().unwrap_or(DEFAULT_GC_THRESHOLD_MB))
+                        .unwrap_or(DEFAULT_GC_THRESHOLD_MB);
 
                 gc_threshold_mb * 1024 * 1024
             },
@@ -179,7 +189,7 @@ impl Vm {
         write!(error_str, "Error: {:?}", err).unwrap();
         if let Ok(error) = err.into_value(ctx) {
             if console::log_fatal(ctx.clone(), Rest(vec![error.clone()])).is_err() {
-                eprintln!("{}", error_str);
+                eprintln!("{}", map_stack_trace(error_str));
             };
             if cfg!(test) {
                 panic!("{:?}", error);
@@ -189,7 +199,7 @@ impl Vm {
         } else if cfg!(test) {
             panic!("{}", error_str);
         } else {
-            eprintln!("{}", error_str);
+            eprintln!("{}", map_stack_trace(error_str));
             exit(1)
         };
     }
@@ -204,6 +214,7 @@ fn init(ctx: &Ctx<'_>) -> Result<()> {
     set_spawn_error_handler(|ctx, err| {
         Vm::print_error_and_exit(ctx, err);
     });
+    set_stack_trace_mapper(source_map::remap_stack_trace);
 
     let globals = ctx.globals();
 
@@ -0,0 +1,69 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+//! Tells the `llrt --watch` supervisor which files the loader read. When
+//! `LLRT_WATCH_REPORT` names a file, the path of every module, JSON, text or
+//! bytes file loaded from disk is appended to it once, one per line. Worker
+//! processes inherit the variable and append to the same file.
+use std::{
+    collections::HashSet,
+    fs::{self, File, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use once_cell::sync::Lazy;
+use tracing::trace;
+
+use crate::environment;
+
+struct Report {
+    file: File,
+    recorded: HashSet<String>,
+}
+
+static REPORT: Lazy<Option<Mutex<Report>>> = Lazy::new(|| {
+    let path = environment::var(environment::ENV_LLRT_WATCH_REPORT).ok()?;
+    if path.is_empty() {
+        return None;
+    }
+    match OpenOptions::new().create(true).append(true).open(&path) {
+        Ok(file) => Some(Mutex::new(Report {
+            file,
+            recorded: HashSet::new(),
+        })),
+        Err(err) => {
+            trace!("Ignoring watch report {}: {}", path, err);
+            None
+        },
+    }
+});
+
+/// Records that the loader read `path`.
+pub fn record(path: &str) {
+    let Some(report) = REPORT.as_ref() else {
+        return;
+    };
+    if path.contains('\n') {
+        return;
+    }
+    let mut report = report.lock().unwrap();
+    if !report.recorded.insert(path.to_string()) {
+        return;
+    }
+    // One write per line, so lines of concurrent processes never interleave
+    if let Err(err) = report.file.write_all([path, "\n"].concat().as_bytes()) {
+        trace!("Failed to write watch report: {}", err);
+    }
+}
+
+/// The files recorded in the report at `path`.
+pub fn read(path: &Path) -> Vec<PathBuf> {
+    let contents = fs::read_to_string(path).unwrap_or_default();
+    let mut seen = HashSet::new();
+    contents
+        .lines()
+        .filter(|line| !line.is_empty() && seen.insert(*line))
+        .map(PathBuf::from)
+        .collect()
+}
@@ -2,31 +2,54 @@
 // SPDX-License-Identifier: Apache-2.0
 use std::fs::Metadata;
 
-use llrt_utils::result::ResultExt;
+use llrt_utils::fs::IoResultExt;
 use rquickjs::{prelude::Opt, Ctx, Exception, Result};
 use tokio::fs;
 
+use crate::vfs;
+
 #[allow(dead_code, unused_imports)]
 use super::{CONSTANT_F_OK, CONSTANT_R_OK, CONSTANT_W_OK, CONSTANT_X_OK};
 
 pub async fn access(ctx: Ctx<'_>, path: String, mode: Opt<u32>) -> Result<()> {
-    let metadata = fs::metadata(&path).await.or_throw_msg(
-        &ctx,
-        &["No such file or directory \"", &path, "\""].concat(),
-    )?;
+    if vfs::stat(&path).is_some() {
+        return verify_virtual(&ctx, mode);
+    }
+
+    let metadata =
+        fs::metadata(&path)
+            .await
+            .or_throw_io(&ctx, &access_error(&path), "access", &path)?;
 
     verify_metadata(&ctx, mode, metadata)
 }
 
 pub fn access_sync(ctx: Ctx<'_>, path: String, mode: Opt<u32>) -> Result<()> {
-    let metadata = std::fs::metadata(path.clone()).or_throw_msg(
-        &ctx,
-        &["No such file or directory \"", &path, "\""].concat(),
-    )?;
+    if vfs::stat(&path).is_some() {
+        return verify_virtual(&ctx, mode);
+    }
+
+    let metadata =
+        std::fs::metadata(&path).or_throw_io(&ctx, &access_error(&path), "access", &path)?;
 
     verify_metadata(&ctx, mode, metadata)
 }
 
+fn access_error(path: &str) -> String {
+    ["No such file or directory \"", path, "\""].concat()
+}
+
+// Embedded files are read-only and never executable
+fn verify_virtual(ctx: &Ctx, mode: Opt<u32>) -> Result<()> {
+    let mode = mode.unwrap_or(CONSTANT_F_OK);
+
+    if mode & (CONSTANT_W_OK | CONSTANT_X_OK) != 0 {
+        return Err(Exception::throw_message(ctx, "Permission denied"));
+    }
+
+    Ok(())
+}
+
 fn verify_metadata(ctx: &Ctx, mode: Opt<u32>, metadata: Metadata) -> Result<()> {
     let permissions = metadata.permissions();
 
@@ -0,0 +1,104 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use std::path::Path;
+
+use llrt_context::CtxExtension;
+use llrt_utils::{fs::io_error, function::bind, result::ResultExt};
+use rquickjs::{
+    function::IntoJsFunc, prelude::Rest, Ctx, Error, Exception, Function, IntoJs, Null, Object,
+    Promise, Result, Undefined, Value,
+};
+
+use crate::vfs;
+
+/// Wraps `func`, which returns a promise, in a function taking a Node.js
+/// style callback as its last argument. The callback gets the error first,
+/// then the resolved value, or its `results` properties in order when given.
+/// I/O errors that are not exceptions yet get `name` as their `syscall` and
+/// the first argument, when it is a string, as their `path`.
+pub(crate) fn callbackify<'js>(
+    ctx: &Ctx<'js>,
+    func: Function<'js>,
+    name: &'static str,
+    results: &'static [&'static str],
+) -> Result<Function<'js>> {
+    let wrapper = Function::new(
+        ctx.clone(),
+        move |ctx: Ctx<'js>, func: Function<'js>, args: Rest<Value<'js>>| -> Result<()> {
+            let mut args = args.0;
+            let callback = args
+                .pop()
+                .and_then(|value| value.into_function())
+                .or_throw_type(&ctx, "Callback parameter is not a function")?;
+            let path = match args.first().and_then(|arg| arg.as_string()) {
+                Some(path) => Some(path.to_string()?),
+                None => None,
+            };
+            let promise: Promise = func.call((Rest(args),))?;
+
+            ctx.clone().spawn_exit_simple(async move {
+                let value = match promise.into_future::<Value>().await {
+                    Ok(value) => value,
+                    Err(err) => {
+                        let error = to_error(&ctx, err, name, path.as_deref())?;
+                        return callback.call((error,));
+                    },
+                };
+
+                let mut args = vec![Null.into_js(&ctx)?];
+                match value.as_object() {
+                    Some(object) if !results.is_empty() => {
+                        for name in results {
+                            args.push(object.get::<_, Value>(*name)?);
+                        }
+                    },
+                    _ => args.push(value),
+                }
+                callback.call((Rest(args),))
+            });
+            Ok(())
+        },
+    )?;
+    bind(&wrapper, vec![Undefined.into_js(ctx)?, func.into_value()])
+}
+
+/// Sets `name` on `object` to the callback taking version of `func`, see
+/// [`callbackify`].
+pub(crate) fn set_callback<'js, P>(
+    ctx: &Ctx<'js>,
+    object: &Object<'js>,
+    name: &'static str,
+    func: impl IntoJsFunc<'js, P> + 'js,
+    results: &'static [&'static str],
+) -> Result<()> {
+    let func = Function::new(ctx.clone(), func)?;
+    object.set(name, callbackify(ctx, func, name, results)?)
+}
+
+// The value thrown for `err`, I/O errors carry their code like those thrown
+// by the functions of `fs/promises`
+fn to_error<'js>(
+    ctx: &Ctx<'js>,
+    err: Error,
+    syscall: &str,
+    path: Option<&str>,
+) -> Result<Value<'js>> {
+    match err {
+        Error::Exception => Ok(ctx.catch()),
+        Error::Io(err) => io_error(ctx, &err, "", Some(syscall), path)?.into_js(ctx),
+        err => Exception::from_message(ctx.clone(), &err.to_string())?.into_js(ctx),
+    }
+}
+
+pub fn exists<'js>(ctx: Ctx<'js>, path: String, callback: Function<'js>) -> Result<()> {
+    ctx.clone().spawn_exit_simple(async move {
+        let exists =
+            vfs::stat(&path).is_some() || tokio::fs::try_exists(&path).await.unwrap_or_default();
+        callback.call((exists,))
+    });
+    Ok(())
+}
+
+pub fn exists_sync(path: String) -> bool {
+    vfs::stat(&path).is_some() || Path::new(&path).exists()
+}
@@ -1,5 +1,5 @@
 #[cfg(unix)]
-use llrt_utils::result::ResultExt;
+use llrt_utils::fs::IoResultExt;
 use rquickjs::{Ctx, Result};
 #[cfg(unix)]
 use std::os::unix::prelude::PermissionsExt;
@@ -14,7 +14,7 @@ pub(crate) async fn set_mode(ctx: Ctx<'_>, path: &str, mode: u32) -> Result<()>
     {
         tokio::fs::set_permissions(path, PermissionsExt::from_mode(mode))
             .await
-            .or_throw_msg(&ctx, &chmod_error(path))?;
+            .or_throw_io(&ctx, &chmod_error(path), "chmod", path)?;
     }
     #[cfg(not(unix))]
     {
@@ -28,8 +28,12 @@ pub(crate) async fn set_mode(ctx: Ctx<'_>, path: &str, mode: u32) -> Result<()>
 pub(crate) fn set_mode_sync(ctx: Ctx<'_>, path: &str, mode: u32) -> Result<()> {
     #[cfg(unix)]
     {
-        std::fs::set_permissions(path, PermissionsExt::from_mode(mode))
-            .or_throw_msg(&ctx, &chmod_error(path))?;
+        std::fs::set_permissions(path, PermissionsExt::from_mode(mode)).or_throw_io(
+            &ctx,
+            &chmod_error(path),
+            "chmod",
+            path,
+        )?;
     }
     #[cfg(not(unix))]
     {
@@ -0,0 +1,36 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use std::io;
+
+use llrt_utils::{fs::IoResultExt, result::ResultExt};
+use rquickjs::{Ctx, Result};
+
+pub async fn chown(ctx: Ctx<'_>, path: String, uid: u32, gid: u32) -> Result<()> {
+    let error_message = chown_error(&path);
+    let error_path = path.clone();
+    tokio::task::spawn_blocking(move || set_owner(&path, uid, gid))
+        .await
+        .or_throw(&ctx)?
+        .or_throw_io(&ctx, &error_message, "chown", &error_path)
+}
+
+pub fn chown_sync(ctx: Ctx<'_>, path: String, uid: u32, gid: u32) -> Result<()> {
+    set_owner(&path, uid, gid).or_throw_io(&ctx, &chown_error(&path), "chown", &path)
+}
+
+fn chown_error(path: &str) -> String {
+    ["Can't change owner of \"", path, "\""].concat()
+}
+
+// Ownership only exists on Unix, elsewhere this is a no-op like in Node.js
+fn set_owner(path: &str, uid: u32, gid: u32) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::chown(path, Some(uid), Some(gid))
+    }
+    #[cfg(not(unix))]
+    {
+        _ = (path, uid, gid);
+        Ok(())
+    }
+}
@@ -0,0 +1,278 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use llrt_utils::{fs::io_error, result::ResultExt};
+use rquickjs::{
+    function::Opt, Coerced, Ctx, Error, Exception, FromJs, Function, Object, Result, Value,
+};
+
+use crate::{
+    link::{create_symlink, to_string},
+    utimes::set_times,
+    vfs, CONSTANT_COPYFILE_EXCL,
+};
+
+// Throws `err` with the `path` and `dest` of the failed copy
+fn throw_copy_error(ctx: &Ctx, err: &io::Error, syscall: &str, src: &str, dest: &str) -> Error {
+    let message = ["Can't copy \"", src, "\" to \"", dest, "\""].concat();
+    let error = io_error(ctx, err, &message, Some(syscall), Some(src))
+        .and_then(|error| error.set("dest", dest).map(|_| error));
+    match error {
+        Ok(error) => ctx.throw(error.into_value()),
+        Err(err) => err,
+    }
+}
+
+pub async fn copy_file(ctx: Ctx<'_>, src: String, dest: String, mode: Opt<u32>) -> Result<()> {
+    let mode = mode.0.unwrap_or_default();
+
+    let (from, to) = (src.clone(), dest.clone());
+    tokio::task::spawn_blocking(move || copy_one_file(&from, &to, mode))
+        .await
+        .or_throw(&ctx)?
+        .map_err(|err| throw_copy_error(&ctx, &err, "copyfile", &src, &dest))
+}
+
+pub fn copy_file_sync(ctx: Ctx<'_>, src: String, dest: String, mode: Opt<u32>) -> Result<()> {
+    copy_one_file(&src, &dest, mode.0.unwrap_or_default())
+        .map_err(|err| throw_copy_error(&ctx, &err, "copyfile", &src, &dest))
+}
+
+// Copy-on-write cloning is left to the platform, so `COPYFILE_FICLONE` and
+// `COPYFILE_FICLONE_FORCE` copy like without them
+fn copy_one_file(src: &str, dest: &str, mode: u32) -> io::Result<()> {
+    if mode & CONSTANT_COPYFILE_EXCL != 0 && fs::symlink_metadata(dest).is_ok() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            "Destination already exists",
+        ));
+    }
+    if let Some(bytes) = vfs::read(src) {
+        return fs::write(dest, bytes);
+    }
+    fs::copy(src, dest).map(|_| ())
+}
+
+#[derive(Clone, Copy)]
+struct CpFlags {
+    recursive: bool,
+    force: bool,
+    error_on_exist: bool,
+    preserve_timestamps: bool,
+    dereference: bool,
+}
+
+fn get_params_cp<'js>(options: Opt<Object<'js>>) -> Result<(CpFlags, Option<Function<'js>>)> {
+    let mut flags = CpFlags {
+        recursive: false,
+        force: true,
+        error_on_exist: false,
+        preserve_timestamps: false,
+        dereference: false,
+    };
+    let mut filter = None;
+
+    if let Some(options) = options.0 {
+        flags.recursive = options.get("recursive").unwrap_or_default();
+        flags.force = options.get::<_, Option<bool>>("force")?.unwrap_or(true);
+        flags.error_on_exist = options.get("errorOnExist").unwrap_or_default();
+        flags.preserve_timestamps = options.get("preserveTimestamps").unwrap_or_default();
+        flags.dereference = options.get("dereference").unwrap_or_default();
+        filter = options.get("filter")?;
+    }
+    Ok((flags, filter))
+}
+
+pub async fn cp<'js>(
+    ctx: Ctx<'js>,
+    src: String,
+    dest: String,
+    options: Opt<Object<'js>>,
+) -> Result<()> {
+    let (flags, filter) = get_params_cp(options)?;
+
+    let (src_path, dest_path) = (PathBuf::from(&src), PathBuf::from(&dest));
+    tokio::task::spawn_blocking(move || check_paths(&src_path, &dest_path, flags))
+        .await
+        .or_throw(&ctx)?
+        .map_err(|err| throw_copy_error(&ctx, &err, "cp", &src, &dest))?;
+
+    let mut stack = vec![(PathBuf::from(src), PathBuf::from(dest))];
+    while let Some((from, to)) = stack.pop() {
+        let (from_str, to_str) = (to_string(&from), to_string(&to));
+        if let Some(filter) = &filter {
+            let included: Value = filter.call((from_str.clone(), to_str.clone()))?;
+            let included = match included.as_promise() {
+                Some(promise) => promise.clone().into_future::<Coerced<bool>>().await?,
+                None => Coerced::<bool>::from_js(&ctx, included)?,
+            };
+            if !included.0 {
+                continue;
+            }
+        }
+
+        let children = tokio::task::spawn_blocking(move || copy_entry(&from, &to, flags))
+            .await
+            .or_throw(&ctx)?
+            .map_err(|err| throw_copy_error(&ctx, &err, "cp", &from_str, &to_str))?;
+        stack.extend(children);
+    }
+
+    Ok(())
+}
+
+pub fn cp_sync<'js>(
+    ctx: Ctx<'js>,
+    src: String,
+    dest: String,
+    options: Opt<Object<'js>>,
+) -> Result<()> {
+    let (flags, filter) = get_params_cp(options)?;
+
+    check_paths(Path::new(&src), Path::new(&dest), flags)
+        .map_err(|err| throw_copy_error(&ctx, &err, "cp", &src, &dest))?;
+
+    let mut stack = vec![(PathBuf::from(src), PathBuf::from(dest))];
+    while let Some((from, to)) = stack.pop() {
+        let (from_str, to_str) = (to_string(&from), to_string(&to));
+        if let Some(filter) = &filter {
+            let included: Value = filter.call((from_str.clone(), to_str.clone()))?;
+            if included.is_promise() {
+                return Err(Exception::throw_type(
+                    &ctx,
+                    "The filter of cpSync can't return a Promise",
+                ));
+            }
+            if !Coerced::<bool>::from_js(&ctx, included)?.0 {
+                continue;
+            }
+        }
+
+        let children = copy_entry(&from, &to, flags)
+            .map_err(|err| throw_copy_error(&ctx, &err, "cp", &from_str, &to_str))?;
+        stack.extend(children);
+    }
+
+    Ok(())
+}
+
+// Rejects copying a directory without `recursive`, and copying onto the
+// source or, for a directory, into it
+fn check_paths(src: &Path, dest: &Path, flags: CpFlags) -> io::Result<()> {
+    let is_dir = match src.to_str().and_then(vfs::stat) {
+        Some(entry) => entry.is_dir,
+        None => metadata(src, flags)?.is_dir(),
+    };
+    if is_dir && !flags.recursive {
+        return Err(io::Error::other(
+            "Source is a directory, the recursive option is required",
+        ));
+    }
+
+    let (src, dest) = (resolve(src)?, resolve(dest)?);
+    if src == dest {
+        return Err(io::Error::other("Source and destination are the same"));
+    }
+    if is_dir && dest.starts_with(&src) {
+        return Err(io::Error::other(
+            "Can't copy a directory to a subdirectory of itself",
+        ));
+    }
+    Ok(())
+}
+
+// Resolves `path` through its nearest existing ancestor
+fn resolve(path: &Path) -> io::Result<PathBuf> {
+    let mut missing = Vec::new();
+    let mut existing = path;
+    loop {
+        let candidate = if existing.as_os_str().is_empty() {
+            Path::new(".")
+        } else {
+            existing
+        };
+        match candidate.canonicalize() {
+            Ok(resolved) => return Ok(missing.into_iter().rev().fold(resolved, |p, n| p.join(n))),
+            Err(err) => match (existing.file_name(), existing.parent()) {
+                (Some(name), Some(parent)) => {
+                    missing.push(name);
+                    existing = parent;
+                },
+                _ => return Err(err),
+            },
+        }
+    }
+}
+
+fn metadata(path: &Path, flags: CpFlags) -> io::Result<fs::Metadata> {
+    if flags.dereference {
+        fs::metadata(path)
+    } else {
+        fs::symlink_metadata(path)
+    }
+}
+
+// Copies a single entry, creating directories without their contents, which
+// are returned as the entries to copy next
+fn copy_entry(from: &Path, to: &Path, flags: CpFlags) -> io::Result<Vec<(PathBuf, PathBuf)>> {
+    if let Some((from_str, entry)) = from.to_str().and_then(|s| Some((s, vfs::stat(s)?))) {
+        if entry.is_dir {
+            fs::create_dir_all(to)?;
+            let children = vfs::read_dir(from_str, false).unwrap_or_default();
+            return Ok(children
+                .into_iter()
+                .map(|(name, _)| (from.join(&name), to.join(&name)))
+                .collect());
+        }
+        if replace(to, flags)? {
+            fs::write(to, vfs::read(from_str).unwrap_or_default())?;
+        }
+        return Ok(Vec::new());
+    }
+
+    let metadata = metadata(from, flags)?;
+    if metadata.is_dir() {
+        fs::create_dir_all(to)?;
+        return fs::read_dir(from)?
+            .map(|entry| entry.map(|entry| (entry.path(), to.join(entry.file_name()))))
+            .collect();
+    }
+
+    if let Some(parent) = to.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    if !replace(to, flags)? {
+        return Ok(Vec::new());
+    }
+    if metadata.is_symlink() {
+        create_symlink(&fs::read_link(from)?, to, None)?;
+        return Ok(Vec::new());
+    }
+    fs::copy(from, to)?;
+    if flags.preserve_timestamps {
+        set_times(to, metadata.accessed()?, metadata.modified()?)?;
+    }
+    Ok(Vec::new())
+}
+
+// Whether a file can be copied to `to`, removing the file already there when
+// `force` is set
+fn replace(to: &Path, flags: CpFlags) -> io::Result<bool> {
+    match fs::symlink_metadata(to) {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(true),
+        Err(err) => Err(err),
+        Ok(_) if !flags.force && flags.error_on_exist => Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            ["\"", &to_string(to), "\" already exists"].concat(),
+        )),
+        Ok(_) if !flags.force => Ok(false),
+        Ok(metadata) if metadata.is_dir() => Err(io::Error::other(
+            ["Can't overwrite directory \"", &to_string(to), "\""].concat(),
+        )),
+        Ok(_) => fs::remove_file(to).map(|_| true),
+    }
+}
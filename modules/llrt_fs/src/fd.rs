@@ -0,0 +1,448 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs::File,
+    io::{self, Read, Write},
+    sync::Arc,
+};
+
+use either::Either;
+use llrt_buffer::{ArrayBufferView, Buffer};
+use llrt_encoding::Encoder;
+use llrt_utils::{
+    fs::{throw_io_error, IoResultExt},
+    result::{OptionExt, ResultExt},
+};
+use rquickjs::{
+    function::Opt, prelude::Rest, Ctx, Error, Exception, FromJs, JsLifetime, Object, Result, Value,
+};
+
+use crate::{
+    file_handle::{
+        validate_length_offset, ReadOptions, WriteOptions, DEFAULT_BUFFER_SIZE, DEFAULT_ENCODING,
+    },
+    open::open_options,
+    Stats,
+};
+
+// 0 to 2 are the standard streams
+const FIRST_FD: i32 = 3;
+
+#[cfg(unix)]
+const EBADF: i32 = 9;
+// ERROR_INVALID_HANDLE
+#[cfg(windows)]
+const EBADF: i32 = 6;
+
+// The files opened with `fs.open()` and `fs.openSync()`. Descriptors are
+// numbered per runtime and are not those of the operating system.
+struct FdTable {
+    next: i32,
+    files: HashMap<i32, Arc<File>>,
+}
+
+unsafe impl JsLifetime<'_> for FdTable {
+    type Changed<'to> = FdTable;
+}
+
+fn with_table<R>(ctx: &Ctx<'_>, f: impl FnOnce(&mut FdTable) -> R) -> Result<R> {
+    if ctx.userdata::<RefCell<FdTable>>().is_none() {
+        ctx.store_userdata(RefCell::new(FdTable {
+            next: FIRST_FD,
+            files: HashMap::new(),
+        }))?;
+    }
+    let table = ctx
+        .userdata::<RefCell<FdTable>>()
+        .or_throw_msg(ctx, "File descriptor table is not available")?;
+    let mut table = table.borrow_mut();
+    Ok(f(&mut table))
+}
+
+#[derive(Clone)]
+enum Descriptor {
+    File(Arc<File>),
+    Stdin,
+    Stdout,
+    Stderr,
+}
+
+impl Descriptor {
+    fn get(ctx: &Ctx<'_>, fd: i32, syscall: &str) -> Result<Self> {
+        match (with_table(ctx, |table| table.files.get(&fd).cloned())?, fd) {
+            (Some(file), _) => Ok(Self::File(file)),
+            (None, 0) => Ok(Self::Stdin),
+            (None, 1) => Ok(Self::Stdout),
+            (None, 2) => Ok(Self::Stderr),
+            (None, _) => Err(bad_fd(ctx, fd, syscall)),
+        }
+    }
+
+    fn file(ctx: &Ctx<'_>, fd: i32, syscall: &str) -> Result<Arc<File>> {
+        match Self::get(ctx, fd, syscall)? {
+            Self::File(file) => Ok(file),
+            _ => Err(Exception::throw_message(
+                ctx,
+                "Not supported for the standard streams",
+            )),
+        }
+    }
+
+    fn read(&self, buf: &mut [u8], position: Option<u64>) -> io::Result<usize> {
+        match (self, position) {
+            (Self::File(file), Some(position)) => read_at(file, buf, position),
+            (Self::File(file), None) => (&**file).read(buf),
+            (Self::Stdin, _) => io::stdin().read(buf),
+            _ => Err(io::Error::other("File descriptor is not readable")),
+        }
+    }
+
+    fn write(&self, buf: &[u8], position: Option<u64>) -> io::Result<()> {
+        match (self, position) {
+            (Self::File(file), Some(position)) => write_at(file, buf, position),
+            (Self::File(file), None) => (&**file).write_all(buf),
+            (Self::Stdout, _) => {
+                let mut stdout = io::stdout().lock();
+                stdout.write_all(buf)?;
+                stdout.flush()
+            },
+            (Self::Stderr, _) => io::stderr().write_all(buf),
+            (Self::Stdin, _) => Err(io::Error::other("File descriptor is not writable")),
+        }
+    }
+}
+
+fn bad_fd(ctx: &Ctx<'_>, fd: i32, syscall: &str) -> Error {
+    let err = io::Error::from_raw_os_error(EBADF);
+    throw_io_error(
+        ctx,
+        &err,
+        &["File descriptor ", &fd.to_string()].concat(),
+        Some(syscall),
+        None,
+    )
+}
+
+// Positional reads and writes leave the cursor of the file untouched, except
+// on Windows where it moves
+fn read_at(file: &File, buf: &mut [u8], position: u64) -> io::Result<usize> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::FileExt::read_at(file, buf, position)
+    }
+    #[cfg(windows)]
+    {
+        std::os::windows::fs::FileExt::seek_read(file, buf, position)
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        use std::io::{Seek, SeekFrom};
+        let mut file = file;
+        file.seek(SeekFrom::Start(position))?;
+        file.read(buf)
+    }
+}
+
+fn write_at(file: &File, buf: &[u8], position: u64) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::FileExt::write_all_at(file, buf, position)
+    }
+    #[cfg(windows)]
+    {
+        let mut written = 0;
+        while written < buf.len() {
+            match std::os::windows::fs::FileExt::seek_write(
+                file,
+                &buf[written..],
+                position + written as u64,
+            )? {
+                0 => return Err(io::ErrorKind::WriteZero.into()),
+                n => written += n,
+            }
+        }
+        Ok(())
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        use std::io::{Seek, SeekFrom};
+        let mut file = file;
+        file.seek(SeekFrom::Start(position))?;
+        file.write_all(buf)
+    }
+}
+
+pub fn open_sync(ctx: Ctx<'_>, path: String, flags: Opt<String>, mode: Opt<u32>) -> Result<i32> {
+    let file = open_options(&ctx, flags.0.as_deref(), mode.0)?
+        .open(&path)
+        .or_throw_io(&ctx, &open_error(&path), "open", &path)?;
+    insert(&ctx, file)
+}
+
+pub async fn open_fd(
+    ctx: Ctx<'_>,
+    path: String,
+    flags: Opt<String>,
+    mode: Opt<u32>,
+) -> Result<i32> {
+    let options = open_options(&ctx, flags.0.as_deref(), mode.0)?;
+    let error_message = open_error(&path);
+    let error_path = path.clone();
+    let file = tokio::task::spawn_blocking(move || options.open(path))
+        .await
+        .or_throw(&ctx)?
+        .or_throw_io(&ctx, &error_message, "open", &error_path)?;
+    insert(&ctx, file)
+}
+
+fn open_error(path: &str) -> String {
+    ["Can't open \"", path, "\""].concat()
+}
+
+fn insert(ctx: &Ctx<'_>, file: File) -> Result<i32> {
+    with_table(ctx, |table| {
+        let fd = table.next;
+        table.next += 1;
+        table.files.insert(fd, Arc::new(file));
+        fd
+    })
+}
+
+// The file is closed once pending reads and writes are done with it
+pub fn close_sync(ctx: Ctx<'_>, fd: i32) -> Result<()> {
+    match with_table(&ctx, |table| table.files.remove(&fd))? {
+        Some(_) => Ok(()),
+        None if (0..FIRST_FD).contains(&fd) => Ok(()),
+        None => Err(bad_fd(&ctx, fd, "close")),
+    }
+}
+
+pub async fn close_fd(ctx: Ctx<'_>, fd: i32) -> Result<()> {
+    close_sync(ctx, fd)
+}
+
+type Region = (Option<usize>, Option<usize>, Option<u64>);
+
+fn optional<'js, T: FromJs<'js>>(ctx: &Ctx<'js>, value: Option<&Value<'js>>) -> Result<Option<T>> {
+    match value {
+        Some(value) => Option::<T>::from_js(ctx, value.clone()),
+        None => Ok(None),
+    }
+}
+
+// The `offset`, `length` and `position` arguments, or the options object in
+// their place. A `null` or negative position uses the cursor of the file.
+fn region<'js>(
+    ctx: &Ctx<'js>,
+    args: &[Value<'js>],
+    from_options: impl FnOnce(Value<'js>) -> Result<Region>,
+) -> Result<Region> {
+    if let Some(options) = args.first().filter(|value| value.is_object()) {
+        return from_options(options.clone());
+    }
+    let position = optional::<i64>(ctx, args.get(2))?;
+    Ok((
+        optional(ctx, args.first())?,
+        optional(ctx, args.get(1))?,
+        position.and_then(|position| u64::try_from(position).ok()),
+    ))
+}
+
+// The part of `buffer` to read into
+fn read_params<'js>(
+    ctx: &Ctx<'js>,
+    buffer: &ArrayBufferView<'js>,
+    args: &[Value<'js>],
+) -> Result<(usize, usize, Option<u64>)> {
+    let (offset, length, position) = region(ctx, args, |value| {
+        let options = ReadOptions::from_js(ctx, value)?;
+        Ok((options.offset, options.length, options.position))
+    })?;
+    let offset = offset.unwrap_or(0);
+    let length = length.unwrap_or(buffer.len().saturating_sub(offset));
+    validate_length_offset(ctx, length, offset, buffer.len())?;
+    Ok((offset, length, position))
+}
+
+// The buffer of `fs.read(fd, buffer, ...)`, or of `fs.read(fd[, options])`
+// which reads into `options.buffer` or a new buffer, and the part to read into
+fn read_target<'js>(
+    ctx: &Ctx<'js>,
+    buffer_or_options: Option<Either<ArrayBufferView<'js>, ReadOptions<'js>>>,
+    args: &[Value<'js>],
+) -> Result<(ArrayBufferView<'js>, usize, usize, Option<u64>)> {
+    let options = match buffer_or_options {
+        Some(Either::Left(buffer)) => {
+            let (offset, length, position) = read_params(ctx, &buffer, args)?;
+            return Ok((buffer, offset, length, position));
+        },
+        Some(Either::Right(options)) => options,
+        None => ReadOptions::default(),
+    };
+    let buffer = options.buffer.unwrap_or_else_ok(|| {
+        ArrayBufferView::from_buffer(ctx, Buffer::alloc(DEFAULT_BUFFER_SIZE))
+    })?;
+    let offset = options.offset.unwrap_or(0);
+    let length = options
+        .length
+        .unwrap_or(buffer.len().saturating_sub(offset));
+    validate_length_offset(ctx, length, offset, buffer.len())?;
+    Ok((buffer, offset, length, options.position))
+}
+
+fn copy_into(
+    ctx: &Ctx<'_>,
+    buffer: &ArrayBufferView<'_>,
+    offset: usize,
+    bytes: &[u8],
+) -> Result<()> {
+    let dst_buf = buffer
+        .as_bytes_mut()
+        .or_throw_msg(ctx, "Buffer is detached")?;
+    dst_buf[offset..offset + bytes.len()].copy_from_slice(bytes);
+    Ok(())
+}
+
+pub fn read_sync<'js>(
+    ctx: Ctx<'js>,
+    fd: i32,
+    buffer: ArrayBufferView<'js>,
+    args: Rest<Value<'js>>,
+) -> Result<usize> {
+    let (offset, length, position) = read_params(&ctx, &buffer, &args.0)?;
+    let descriptor = Descriptor::get(&ctx, fd, "read")?;
+
+    let mut buf = vec![0u8; length];
+    let bytes_read = descriptor
+        .read(&mut buf, position)
+        .or_throw_msg(&ctx, "Failed to read file")?;
+    copy_into(&ctx, &buffer, offset, &buf[..bytes_read])?;
+    Ok(bytes_read)
+}
+
+pub async fn read_fd<'js>(
+    ctx: Ctx<'js>,
+    fd: i32,
+    buffer_or_options: Opt<Either<ArrayBufferView<'js>, ReadOptions<'js>>>,
+    args: Rest<Value<'js>>,
+) -> Result<Object<'js>> {
+    let (buffer, offset, length, position) = read_target(&ctx, buffer_or_options.0, &args.0)?;
+    let descriptor = Descriptor::get(&ctx, fd, "read")?;
+
+    // The buffer can't be shared with the blocking thread, see `FileHandle::read`
+    let buf = tokio::task::spawn_blocking(move || {
+        let mut buf = vec![0u8; length];
+        let bytes_read = descriptor.read(&mut buf, position)?;
+        buf.truncate(bytes_read);
+        Ok::<_, io::Error>(buf)
+    })
+    .await
+    .or_throw(&ctx)?
+    .or_throw_msg(&ctx, "Failed to read file")?;
+    copy_into(&ctx, &buffer, offset, &buf)?;
+
+    let result = Object::new(ctx)?;
+    result.set("bytesRead", buf.len())?;
+    result.set("buffer", buffer)?;
+    Ok(result)
+}
+
+// The bytes to write and where. The arguments after a string are `position`
+// and `encoding`.
+fn write_params<'js>(
+    ctx: &Ctx<'js>,
+    data: &Either<ArrayBufferView<'js>, String>,
+    args: &[Value<'js>],
+) -> Result<(Vec<u8>, Option<u64>)> {
+    match data {
+        Either::Left(buffer) => {
+            let bytes = buffer.as_bytes().or_throw_msg(ctx, "Buffer is detached")?;
+            let (offset, length, position) = region(ctx, args, |value| {
+                let options = WriteOptions::from_js(ctx, value)?;
+                Ok((options.offset, options.length, options.position))
+            })?;
+            let offset = offset.unwrap_or(0);
+            let length = length.unwrap_or(bytes.len().saturating_sub(offset));
+            validate_length_offset(ctx, length, offset, bytes.len())?;
+            Ok((bytes[offset..offset + length].to_vec(), position))
+        },
+        Either::Right(string) => {
+            let position = optional::<i64>(ctx, args.first())?;
+            let encoding = optional::<String>(ctx, args.get(1))?
+                .unwrap_or_else(|| DEFAULT_ENCODING.to_string());
+            let bytes = Encoder::from_str(&encoding)
+                .and_then(|enc| enc.decode_from_string(string.clone()))
+                .or_throw(ctx)?;
+            Ok((
+                bytes,
+                position.and_then(|position| u64::try_from(position).ok()),
+            ))
+        },
+    }
+}
+
+pub fn write_sync<'js>(
+    ctx: Ctx<'js>,
+    fd: i32,
+    data: Either<ArrayBufferView<'js>, String>,
+    args: Rest<Value<'js>>,
+) -> Result<usize> {
+    let (bytes, position) = write_params(&ctx, &data, &args.0)?;
+    Descriptor::get(&ctx, fd, "write")?
+        .write(&bytes, position)
+        .or_throw_msg(&ctx, "Failed to write to file")?;
+    Ok(bytes.len())
+}
+
+pub async fn write_fd<'js>(
+    ctx: Ctx<'js>,
+    fd: i32,
+    data: Either<ArrayBufferView<'js>, String>,
+    args: Rest<Value<'js>>,
+) -> Result<Object<'js>> {
+    let (bytes, position) = write_params(&ctx, &data, &args.0)?;
+    let descriptor = Descriptor::get(&ctx, fd, "write")?;
+
+    let bytes_written = bytes.len();
+    tokio::task::spawn_blocking(move || descriptor.write(&bytes, position))
+        .await
+        .or_throw(&ctx)?
+        .or_throw_msg(&ctx, "Failed to write to file")?;
+
+    let result = Object::new(ctx)?;
+    result.set("bytesWritten", bytes_written)?;
+    result.set("buffer", data)?;
+    Ok(result)
+}
+
+pub fn fstat_sync(ctx: Ctx<'_>, fd: i32) -> Result<Stats> {
+    let metadata = Descriptor::file(&ctx, fd, "fstat")?
+        .metadata()
+        .or_throw_msg(&ctx, "Can't stat file")?;
+    Ok(Stats::new(metadata))
+}
+
+pub async fn fstat_fd(ctx: Ctx<'_>, fd: i32) -> Result<Stats> {
+    let file = Descriptor::file(&ctx, fd, "fstat")?;
+    let metadata = tokio::task::spawn_blocking(move || file.metadata())
+        .await
+        .or_throw(&ctx)?
+        .or_throw_msg(&ctx, "Can't stat file")?;
+    Ok(Stats::new(metadata))
+}
+
+pub fn fsync_sync(ctx: Ctx<'_>, fd: i32) -> Result<()> {
+    Descriptor::file(&ctx, fd, "fsync")?
+        .sync_all()
+        .or_throw_msg(&ctx, "Can't sync file")
+}
+
+pub async fn fsync_fd(ctx: Ctx<'_>, fd: i32) -> Result<()> {
+    let file = Descriptor::file(&ctx, fd, "fsync")?;
+    tokio::task::spawn_blocking(move || file.sync_all())
+        .await
+        .or_throw(&ctx)?
+        .or_throw_msg(&ctx, "Can't sync file")
+}
@@ -17,8 +17,8 @@ use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt, SeekFrom};
 
 use super::{read_file, Stats};
 
-const DEFAULT_BUFFER_SIZE: usize = 16384;
-const DEFAULT_ENCODING: &str = "utf8";
+pub(crate) const DEFAULT_BUFFER_SIZE: usize = 16384;
+pub(crate) const DEFAULT_ENCODING: &str = "utf8";
 
 #[allow(dead_code)]
 #[rquickjs::class]
@@ -387,7 +387,7 @@ impl FileHandle {
     }
 }
 
-fn validate_length_offset(
+pub(crate) fn validate_length_offset(
     ctx: &Ctx<'_>,
     length: usize,
     offset: usize,
@@ -409,11 +409,11 @@ fn validate_length_offset(
 }
 
 #[derive(Default)]
-struct ReadOptions<'js> {
-    buffer: Option<ArrayBufferView<'js>>,
-    offset: Option<usize>,
-    length: Option<usize>,
-    position: Option<u64>,
+pub(crate) struct ReadOptions<'js> {
+    pub buffer: Option<ArrayBufferView<'js>>,
+    pub offset: Option<usize>,
+    pub length: Option<usize>,
+    pub position: Option<u64>,
 }
 
 impl<'js> FromJs<'js> for ReadOptions<'js> {
@@ -438,10 +438,10 @@ impl<'js> FromJs<'js> for ReadOptions<'js> {
 }
 
 #[derive(Default)]
-struct WriteOptions {
-    offset: Option<usize>,
-    length: Option<usize>,
-    position: Option<u64>,
+pub(crate) struct WriteOptions {
+    pub offset: Option<usize>,
+    pub length: Option<usize>,
+    pub position: Option<u64>,
 }
 
 impl<'js> FromJs<'js> for WriteOptions {
@@ -1,38 +1,106 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 mod access;
+mod callback;
 mod chmod;
+mod chown;
+mod copy;
+mod fd;
 mod file_handle;
+mod link;
 mod mkdir;
 mod open;
+mod open_dir;
 mod read_dir;
 mod read_file;
+mod realpath;
+mod rename;
 mod rm;
 mod stats;
+mod stream;
+mod truncate;
+mod utimes;
+pub mod vfs;
+mod watch;
+mod watch_file;
 mod write_file;
 
-use llrt_utils::module::{export_default, ModuleInfo};
+use llrt_events::Emitter;
+use llrt_stream::readable::ReadableStream;
+use llrt_utils::{
+    module::{export_default, ModuleInfo},
+    primordials::Primordial,
+};
 use rquickjs::{
     module::{Declarations, Exports, ModuleDef},
     prelude::{Async, Func},
 };
-use rquickjs::{Class, Ctx, Object, Result};
+use rquickjs::{Class, Ctx, JsLifetime, Object, Result};
 
 use self::access::{access, access_sync};
+use self::callback::{callbackify, exists, exists_sync, set_callback};
 use self::chmod::{chmod, chmod_sync};
+use self::chown::{chown, chown_sync};
+use self::copy::{copy_file, copy_file_sync, cp, cp_sync};
+use self::fd::{
+    close_fd, close_sync, fstat_fd, fstat_sync, fsync_fd, fsync_sync, open_fd, open_sync, read_fd,
+    read_sync, write_fd, write_sync,
+};
 use self::file_handle::FileHandle;
+use self::link::{
+    link, link_sync, readlink, readlink_sync, symlink, symlink_sync, unlink, unlink_sync,
+};
 use self::mkdir::{mkdir, mkdir_sync, mkdtemp, mkdtemp_sync};
 use self::open::open;
+use self::open_dir::{open_dir, open_dir_sync};
 use self::read_dir::{read_dir, read_dir_sync, Dirent};
 use self::read_file::{read_file, read_file_sync};
+use self::realpath::{realpath, realpath_sync};
+use self::rename::{rename, rename_sync};
 use self::rm::{rmdir, rmdir_sync, rmfile, rmfile_sync};
-use self::stats::{stat_fn, stat_fn_sync, Stats};
-use self::write_file::{write_file, write_file_sync};
+use self::stats::{lstat_fn, lstat_fn_sync, stat_fn, stat_fn_sync, Stats};
+use self::stream::{create_read_stream, create_write_stream, ReadStream, WriteStream};
+use self::truncate::{truncate, truncate_sync};
+use self::utimes::{utimes, utimes_sync};
+use self::watch::{watch, watch_promises, FSWatcher};
+use self::watch_file::{unwatch_file, watch_file, StatWatcher};
+use self::write_file::{append_file, append_file_sync, write_file, write_file_sync};
 
 pub const CONSTANT_F_OK: u32 = 0;
 pub const CONSTANT_R_OK: u32 = 4;
 pub const CONSTANT_W_OK: u32 = 2;
 pub const CONSTANT_X_OK: u32 = 1;
+pub const CONSTANT_COPYFILE_EXCL: u32 = 1;
+pub const CONSTANT_COPYFILE_FICLONE: u32 = 2;
+pub const CONSTANT_COPYFILE_FICLONE_FORCE: u32 = 4;
+
+// The functions of `fs` taking a callback that wrap those of `fs/promises`
+const CALLBACK_FUNCTIONS: &[&str] = &[
+    "access",
+    "appendFile",
+    "chmod",
+    "chown",
+    "copyFile",
+    "cp",
+    "link",
+    "lstat",
+    "mkdir",
+    "mkdtemp",
+    "opendir",
+    "readdir",
+    "readFile",
+    "readlink",
+    "realpath",
+    "rename",
+    "rm",
+    "rmdir",
+    "stat",
+    "symlink",
+    "truncate",
+    "unlink",
+    "utimes",
+    "writeFile",
+];
 
 pub struct FsPromisesModule;
 
@@ -40,6 +108,7 @@ impl ModuleDef for FsPromisesModule {
     fn declare(declare: &Declarations) -> Result<()> {
         declare.declare("access")?;
         declare.declare("open")?;
+        declare.declare("opendir")?;
         declare.declare("readFile")?;
         declare.declare("writeFile")?;
         declare.declare("appendFile")?;
@@ -51,8 +120,19 @@ impl ModuleDef for FsPromisesModule {
         declare.declare("rm")?;
         declare.declare("rmdir")?;
         declare.declare("stat")?;
+        declare.declare("lstat")?;
         declare.declare("constants")?;
         declare.declare("chmod")?;
+        declare.declare("chown")?;
+        declare.declare("cp")?;
+        declare.declare("link")?;
+        declare.declare("readlink")?;
+        declare.declare("realpath")?;
+        declare.declare("symlink")?;
+        declare.declare("truncate")?;
+        declare.declare("unlink")?;
+        declare.declare("utimes")?;
+        declare.declare("watch")?;
 
         declare.declare("default")?;
 
@@ -88,17 +168,53 @@ pub struct FsModule;
 impl ModuleDef for FsModule {
     fn declare(declare: &Declarations) -> Result<()> {
         declare.declare("promises")?;
+        for name in CALLBACK_FUNCTIONS {
+            declare.declare(*name)?;
+        }
+        declare.declare("close")?;
+        declare.declare("createReadStream")?;
+        declare.declare("createWriteStream")?;
+        declare.declare("exists")?;
+        declare.declare("fstat")?;
+        declare.declare("fsync")?;
+        declare.declare("open")?;
+        declare.declare("read")?;
+        declare.declare("unwatchFile")?;
+        declare.declare("watch")?;
+        declare.declare("watchFile")?;
+        declare.declare("write")?;
         declare.declare("accessSync")?;
+        declare.declare("appendFileSync")?;
+        declare.declare("closeSync")?;
+        declare.declare("copyFileSync")?;
+        declare.declare("cpSync")?;
+        declare.declare("existsSync")?;
+        declare.declare("fstatSync")?;
+        declare.declare("fsyncSync")?;
         declare.declare("mkdirSync")?;
         declare.declare("mkdtempSync")?;
+        declare.declare("openSync")?;
+        declare.declare("opendirSync")?;
+        declare.declare("readSync")?;
         declare.declare("readdirSync")?;
         declare.declare("readFileSync")?;
         declare.declare("rmdirSync")?;
         declare.declare("rmSync")?;
         declare.declare("statSync")?;
+        declare.declare("lstatSync")?;
         declare.declare("writeFileSync")?;
+        declare.declare("writeSync")?;
         declare.declare("constants")?;
         declare.declare("chmodSync")?;
+        declare.declare("chownSync")?;
+        declare.declare("linkSync")?;
+        declare.declare("readlinkSync")?;
+        declare.declare("realpathSync")?;
+        declare.declare("renameSync")?;
+        declare.declare("symlinkSync")?;
+        declare.declare("truncateSync")?;
+        declare.declare("unlinkSync")?;
+        declare.declare("utimesSync")?;
 
         declare.declare("default")?;
 
@@ -111,23 +227,68 @@ impl ModuleDef for FsModule {
         Class::<Dirent>::define(&globals)?;
         Class::<FileHandle>::define(&globals)?;
         Class::<Stats>::define(&globals)?;
+        FSWatcher::add_event_emitter_prototype(ctx)?;
+        StatWatcher::add_event_emitter_prototype(ctx)?;
+        ReadStream::add_readable_stream_prototype(ctx)?;
+        ReadStream::add_event_emitter_prototype(ctx)?;
+        WriteStream::add_event_emitter_prototype(ctx)?;
 
         export_default(ctx, exports, |default| {
             let promises = Object::new(ctx.clone())?;
             export_promises(ctx, &promises)?;
-            export_constants(ctx, default)?;
+            default.set("constants", promises.get::<_, Object>("constants")?)?;
+
+            for name in CALLBACK_FUNCTIONS {
+                let func = promises.get(*name)?;
+                default.set(*name, callbackify(ctx, func, name, &[])?)?;
+            }
+            set_callback(ctx, default, "close", Async(close_fd), &[])?;
+            set_callback(ctx, default, "fstat", Async(fstat_fd), &[])?;
+            set_callback(ctx, default, "fsync", Async(fsync_fd), &[])?;
+            set_callback(ctx, default, "open", Async(open_fd), &[])?;
+            let read_results = &["bytesRead", "buffer"];
+            set_callback(ctx, default, "read", Async(read_fd), read_results)?;
+            let write_results = &["bytesWritten", "buffer"];
+            set_callback(ctx, default, "write", Async(write_fd), write_results)?;
+            default.set("createReadStream", Func::from(create_read_stream))?;
+            default.set("createWriteStream", Func::from(create_write_stream))?;
+            default.set("exists", Func::from(exists))?;
+            default.set("unwatchFile", Func::from(unwatch_file))?;
+            default.set("watch", Func::from(watch))?;
+            default.set("watchFile", Func::from(watch_file))?;
 
             default.set("promises", promises)?;
             default.set("accessSync", Func::from(access_sync))?;
+            default.set("appendFileSync", Func::from(append_file_sync))?;
+            default.set("closeSync", Func::from(close_sync))?;
+            default.set("copyFileSync", Func::from(copy_file_sync))?;
+            default.set("cpSync", Func::from(cp_sync))?;
+            default.set("existsSync", Func::from(exists_sync))?;
+            default.set("fstatSync", Func::from(fstat_sync))?;
+            default.set("fsyncSync", Func::from(fsync_sync))?;
             default.set("mkdirSync", Func::from(mkdir_sync))?;
             default.set("mkdtempSync", Func::from(mkdtemp_sync))?;
+            default.set("openSync", Func::from(open_sync))?;
+            default.set("opendirSync", Func::from(open_dir_sync))?;
+            default.set("readSync", Func::from(read_sync))?;
             default.set("readdirSync", Func::from(read_dir_sync))?;
             default.set("readFileSync", Func::from(read_file_sync))?;
             default.set("rmdirSync", Func::from(rmdir_sync))?;
             default.set("rmSync", Func::from(rmfile_sync))?;
             default.set("statSync", Func::from(stat_fn_sync))?;
+            default.set("lstatSync", Func::from(lstat_fn_sync))?;
             default.set("writeFileSync", Func::from(write_file_sync))?;
+            default.set("writeSync", Func::from(write_sync))?;
             default.set("chmodSync", Func::from(chmod_sync))?;
+            default.set("chownSync", Func::from(chown_sync))?;
+            default.set("linkSync", Func::from(link_sync))?;
+            default.set("readlinkSync", Func::from(readlink_sync))?;
+            default.set("realpathSync", Func::from(realpath_sync))?;
+            default.set("renameSync", Func::from(rename_sync))?;
+            default.set("symlinkSync", Func::from(symlink_sync))?;
+            default.set("truncateSync", Func::from(truncate_sync))?;
+            default.set("unlinkSync", Func::from(unlink_sync))?;
+            default.set("utimesSync", Func::from(utimes_sync))?;
 
             Ok(())
         })
@@ -139,29 +300,56 @@ fn export_promises<'js>(ctx: &Ctx<'js>, exports: &Object<'js>) -> Result<()> {
 
     exports.set("access", Func::from(Async(access)))?;
     exports.set("open", Func::from(Async(open)))?;
+    exports.set("opendir", Func::from(Async(open_dir)))?;
     exports.set("readFile", Func::from(Async(read_file)))?;
     exports.set("writeFile", Func::from(Async(write_file)))?;
-    // exports.set("appendFile", Func::from(Async(append_file)))?;
-    // exports.set("copyFile", Func::from(Async(copy_file)))?;
-    // exports.set("rename", Func::from(Async(rename)))?;
+    exports.set("appendFile", Func::from(Async(append_file)))?;
+    exports.set("copyFile", Func::from(Async(copy_file)))?;
+    exports.set("rename", Func::from(Async(rename)))?;
     exports.set("readdir", Func::from(Async(read_dir)))?;
     exports.set("mkdir", Func::from(Async(mkdir)))?;
     exports.set("mkdtemp", Func::from(Async(mkdtemp)))?;
     exports.set("rm", Func::from(Async(rmfile)))?;
     exports.set("rmdir", Func::from(Async(rmdir)))?;
     exports.set("stat", Func::from(Async(stat_fn)))?;
+    exports.set("lstat", Func::from(Async(lstat_fn)))?;
     exports.set("chmod", Func::from(Async(chmod)))?;
+    exports.set("chown", Func::from(Async(chown)))?;
+    exports.set("cp", Func::from(Async(cp)))?;
+    exports.set("link", Func::from(Async(link)))?;
+    exports.set("readlink", Func::from(Async(readlink)))?;
+    exports.set("realpath", Func::from(Async(realpath)))?;
+    exports.set("symlink", Func::from(Async(symlink)))?;
+    exports.set("truncate", Func::from(Async(truncate)))?;
+    exports.set("unlink", Func::from(Async(unlink)))?;
+    exports.set("utimes", Func::from(Async(utimes)))?;
+    exports.set("watch", Func::from(watch_promises))?;
 
     Ok(())
 }
 
-fn export_constants<'js>(ctx: &Ctx<'js>, exports: &Object<'js>) -> Result<()> {
-    let constants = Object::new(ctx.clone())?;
-    constants.set("F_OK", CONSTANT_F_OK)?;
-    constants.set("R_OK", CONSTANT_R_OK)?;
-    constants.set("W_OK", CONSTANT_W_OK)?;
-    constants.set("X_OK", CONSTANT_X_OK)?;
+// `fs.constants` and the `constants` of `fs/promises` are the same object
+#[derive(JsLifetime)]
+struct FsConstants<'js> {
+    constants: Object<'js>,
+}
+
+impl<'js> Primordial<'js> for FsConstants<'js> {
+    fn new(ctx: &Ctx<'js>) -> Result<Self> {
+        let constants = Object::new(ctx.clone())?;
+        constants.set("F_OK", CONSTANT_F_OK)?;
+        constants.set("R_OK", CONSTANT_R_OK)?;
+        constants.set("W_OK", CONSTANT_W_OK)?;
+        constants.set("X_OK", CONSTANT_X_OK)?;
+        constants.set("COPYFILE_EXCL", CONSTANT_COPYFILE_EXCL)?;
+        constants.set("COPYFILE_FICLONE", CONSTANT_COPYFILE_FICLONE)?;
+        constants.set("COPYFILE_FICLONE_FORCE", CONSTANT_COPYFILE_FICLONE_FORCE)?;
+        Ok(Self { constants })
+    }
+}
 
+fn export_constants<'js>(ctx: &Ctx<'js>, exports: &Object<'js>) -> Result<()> {
+    let constants = FsConstants::get(ctx)?.constants.clone();
     exports.set("constants", constants)?;
 
     Ok(())
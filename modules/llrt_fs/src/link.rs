@@ -0,0 +1,116 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use std::{io, path::Path};
+
+use llrt_utils::{fs::IoResultExt, result::ResultExt};
+use rquickjs::{function::Opt, Ctx, Result};
+use tokio::fs;
+
+pub async fn link(ctx: Ctx<'_>, existing_path: String, new_path: String) -> Result<()> {
+    fs::hard_link(&existing_path, &new_path).await.or_throw_io(
+        &ctx,
+        &link_error(&existing_path, &new_path),
+        "link",
+        &existing_path,
+    )
+}
+
+pub fn link_sync(ctx: Ctx<'_>, existing_path: String, new_path: String) -> Result<()> {
+    std::fs::hard_link(&existing_path, &new_path).or_throw_io(
+        &ctx,
+        &link_error(&existing_path, &new_path),
+        "link",
+        &existing_path,
+    )
+}
+
+fn link_error(existing_path: &str, new_path: &str) -> String {
+    ["Can't link \"", new_path, "\" to \"", existing_path, "\""].concat()
+}
+
+pub async fn symlink(ctx: Ctx<'_>, target: String, path: String, kind: Opt<String>) -> Result<()> {
+    let error_message = link_error(&target, &path);
+    let error_path = target.clone();
+    tokio::task::spawn_blocking(move || {
+        create_symlink(Path::new(&target), Path::new(&path), kind.0.as_deref())
+    })
+    .await
+    .or_throw(&ctx)?
+    .or_throw_io(&ctx, &error_message, "symlink", &error_path)
+}
+
+pub fn symlink_sync(ctx: Ctx<'_>, target: String, path: String, kind: Opt<String>) -> Result<()> {
+    create_symlink(Path::new(&target), Path::new(&path), kind.0.as_deref()).or_throw_io(
+        &ctx,
+        &link_error(&target, &path),
+        "symlink",
+        &target,
+    )
+}
+
+// `kind` is only used on Windows, where it is "file", "dir" or "junction".
+// Without it, the kind is that of the target.
+pub(crate) fn create_symlink(target: &Path, path: &Path, kind: Option<&str>) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        _ = kind;
+        std::os::unix::fs::symlink(target, path)
+    }
+    #[cfg(windows)]
+    {
+        let is_dir = match kind {
+            Some("dir" | "junction") => true,
+            Some(_) => false,
+            None => path
+                .parent()
+                .map(|parent| parent.join(target))
+                .is_some_and(|target| target.is_dir()),
+        };
+        if is_dir {
+            std::os::windows::fs::symlink_dir(target, path)
+        } else {
+            std::os::windows::fs::symlink_file(target, path)
+        }
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        _ = (target, path, kind);
+        Err(io::ErrorKind::Unsupported.into())
+    }
+}
+
+pub async fn readlink(ctx: Ctx<'_>, path: String) -> Result<String> {
+    let target =
+        fs::read_link(&path)
+            .await
+            .or_throw_io(&ctx, &readlink_error(&path), "readlink", &path)?;
+    Ok(to_string(&target))
+}
+
+pub fn readlink_sync(ctx: Ctx<'_>, path: String) -> Result<String> {
+    let target =
+        std::fs::read_link(&path).or_throw_io(&ctx, &readlink_error(&path), "readlink", &path)?;
+    Ok(to_string(&target))
+}
+
+fn readlink_error(path: &str) -> String {
+    ["Can't read link \"", path, "\""].concat()
+}
+
+pub async fn unlink(ctx: Ctx<'_>, path: String) -> Result<()> {
+    fs::remove_file(&path)
+        .await
+        .or_throw_io(&ctx, &unlink_error(&path), "unlink", &path)
+}
+
+pub fn unlink_sync(ctx: Ctx<'_>, path: String) -> Result<()> {
+    std::fs::remove_file(&path).or_throw_io(&ctx, &unlink_error(&path), "unlink", &path)
+}
+
+fn unlink_error(path: &str) -> String {
+    ["Can't unlink \"", path, "\""].concat()
+}
+
+pub(crate) fn to_string(path: &Path) -> String {
+    path.to_string_lossy().into_owned()
+}
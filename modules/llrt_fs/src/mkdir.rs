@@ -3,7 +3,7 @@
 use crate::chmod::{set_mode, set_mode_sync};
 
 use llrt_path::resolve_path;
-use llrt_utils::result::ResultExt;
+use llrt_utils::fs::IoResultExt;
 use ring::rand::{SecureRandom, SystemRandom};
 use rquickjs::{function::Opt, Ctx, Object, Result};
 use tokio::fs;
@@ -16,7 +16,7 @@ pub async fn mkdir<'js>(ctx: Ctx<'js>, path: String, options: Opt<Object<'js>>)
     } else {
         fs::create_dir(&path).await
     }
-    .or_throw_msg(&ctx, &["Can't create dir \"", &path, "\""].concat())?;
+    .or_throw_io(&ctx, &mkdir_error(&path), "mkdir", &path)?;
 
     set_mode(ctx, &path, mode).await?;
 
@@ -31,13 +31,17 @@ pub fn mkdir_sync<'js>(ctx: Ctx<'js>, path: String, options: Opt<Object<'js>>) -
     } else {
         std::fs::create_dir(&path)
     }
-    .or_throw_msg(&ctx, &["Can't create dir \"", &path, "\""].concat())?;
+    .or_throw_io(&ctx, &mkdir_error(&path), "mkdir", &path)?;
 
     set_mode_sync(ctx, &path, mode)?;
 
     Ok(path)
 }
 
+fn mkdir_error(path: &str) -> String {
+    ["Can't create dir \"", path, "\""].concat()
+}
+
 fn get_params(path: &str, options: Opt<Object>) -> Result<(bool, u32, String)> {
     let mut recursive = false;
     let mut mode = 0o777;
@@ -70,13 +74,12 @@ pub async fn mkdtemp(ctx: Ctx<'_>, prefix: String) -> Result<String> {
     let path = [prefix.as_str(), random_chars(6).as_str()].join(",");
     fs::create_dir_all(&path)
         .await
-        .or_throw_msg(&ctx, &["Can't create dir \"", &path, "\""].concat())?;
+        .or_throw_io(&ctx, &mkdir_error(&path), "mkdtemp", &path)?;
     Ok(path)
 }
 
 pub fn mkdtemp_sync(ctx: Ctx<'_>, prefix: String) -> Result<String> {
     let path = [prefix.as_str(), random_chars(6).as_str()].join(",");
-    std::fs::create_dir_all(&path)
-        .or_throw_msg(&ctx, &["Can't create dir \"", &path, "\""].concat())?;
+    std::fs::create_dir_all(&path).or_throw_io(&ctx, &mkdir_error(&path), "mkdtemp", &path)?;
     Ok(path)
 }
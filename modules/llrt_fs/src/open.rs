@@ -2,7 +2,7 @@
 // SPDX-License-Identifier: Apache-2.0
 use std::path::PathBuf;
 
-use llrt_utils::result::ResultExt;
+use llrt_utils::fs::IoResultExt;
 use rquickjs::{function::Opt, Ctx, Exception, Result};
 use tokio::fs::OpenOptions;
 
@@ -14,8 +14,25 @@ pub async fn open(
     flags: Opt<String>,
     mode: Opt<u32>,
 ) -> Result<FileHandle> {
-    let mut options = OpenOptions::new();
-    match flags.0.as_deref().unwrap_or("r") {
+    let options = open_options(&ctx, flags.0.as_deref(), mode.0)?;
+
+    let file = OpenOptions::from(options).open(&path).await.or_throw_io(
+        &ctx,
+        "Cannot open file",
+        "open",
+        &path,
+    )?;
+
+    Ok(FileHandle::new(file, PathBuf::from(path)))
+}
+
+pub(crate) fn open_options(
+    ctx: &Ctx<'_>,
+    flags: Option<&str>,
+    mode: Option<u32>,
+) -> Result<std::fs::OpenOptions> {
+    let mut options = std::fs::OpenOptions::new();
+    match flags.unwrap_or("r") {
         // We are not supporting the sync modes
         "a" => options.append(true).create(true),
         "ax" => options.append(true).create_new(true),
@@ -28,28 +45,21 @@ pub async fn open(
         "wx+" => options.write(true).read(true).create_new(true),
         flags => {
             return Err(Exception::throw_message(
-                &ctx,
+                ctx,
                 &["Invalid flags '", flags, "'"].concat(),
             ))
         },
     };
     #[cfg(unix)]
     {
-        let mode = mode.0.unwrap_or(0o666);
-        options.mode(mode);
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(mode.unwrap_or(0o666));
     }
     #[cfg(not(unix))]
     {
         _ = mode;
     }
-
-    let path = PathBuf::from(path);
-    let file = options
-        .open(&path)
-        .await
-        .or_throw_msg(&ctx, "Cannot open file")?;
-
-    Ok(FileHandle::new(file, path))
+    Ok(options)
 }
 
 #[cfg(test)]
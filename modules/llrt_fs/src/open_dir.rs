@@ -0,0 +1,303 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use std::{collections::VecDeque, io, rc::Rc, vec};
+
+use llrt_utils::{
+    fs::{DirectoryWalker, IoResultExt},
+    result::ResultExt,
+};
+use rquickjs::{
+    atom::PredefinedAtom,
+    class::{Trace, Tracer},
+    prelude::{Opt, This},
+    Class, Ctx, Exception, IntoJs, JsLifetime, Null, Object, Result, Undefined, Value,
+};
+use tokio::sync::Mutex;
+
+use crate::{
+    read_dir::{create_directory_walker, read_dir_error, DirEntry},
+    vfs::{self, VirtualEntry},
+    watch::iterator_result,
+};
+
+const DIR_CLOSED: &str = "Directory handle was closed";
+const DEFAULT_BUFFER_SIZE: usize = 32;
+
+// The entries of a directory are read from the file system as they are
+// needed, `buffer_size` at a time
+struct DirEntries {
+    source: EntrySource,
+    buffer: VecDeque<DirEntry>,
+    buffer_size: usize,
+}
+
+impl DirEntries {
+    fn new(path: &str, recursive: bool, buffer_size: usize) -> Self {
+        Self {
+            source: EntrySource::new(path, recursive),
+            buffer: VecDeque::with_capacity(buffer_size),
+            buffer_size,
+        }
+    }
+
+    async fn next(&mut self) -> io::Result<Option<DirEntry>> {
+        if self.buffer.is_empty() {
+            while self.buffer.len() < self.buffer_size {
+                match self.source.next().await? {
+                    Some(entry) => self.buffer.push_back(entry),
+                    None => break,
+                }
+            }
+        }
+        Ok(self.buffer.pop_front())
+    }
+
+    fn next_sync(&mut self) -> io::Result<Option<DirEntry>> {
+        if self.buffer.is_empty() {
+            while self.buffer.len() < self.buffer_size {
+                match self.source.next_sync()? {
+                    Some(entry) => self.buffer.push_back(entry),
+                    None => break,
+                }
+            }
+        }
+        Ok(self.buffer.pop_front())
+    }
+}
+
+enum EntrySource {
+    Walker(DirectoryWalker<fn(&str) -> bool>),
+    Virtual(String, vec::IntoIter<(String, VirtualEntry)>),
+}
+
+impl EntrySource {
+    fn new(path: &str, recursive: bool) -> Self {
+        if let Some(entries) = vfs::read_dir(path, recursive) {
+            let root = path.trim_end_matches('/').to_string();
+            return Self::Virtual(root, entries.into_iter());
+        }
+        let (_, walker) = create_directory_walker(&mut path.to_string(), recursive);
+        Self::Walker(walker)
+    }
+
+    async fn next(&mut self) -> io::Result<Option<DirEntry>> {
+        match self {
+            Self::Walker(walker) => Ok(walker
+                .walk()
+                .await?
+                .map(|(child, metadata)| DirEntry::new(&child, metadata))),
+            Self::Virtual(root, entries) => Ok(Self::next_virtual(root, entries)),
+        }
+    }
+
+    fn next_sync(&mut self) -> io::Result<Option<DirEntry>> {
+        match self {
+            Self::Walker(walker) => Ok(walker
+                .walk_sync()?
+                .map(|(child, metadata)| DirEntry::new(&child, metadata))),
+            Self::Virtual(root, entries) => Ok(Self::next_virtual(root, entries)),
+        }
+    }
+
+    fn next_virtual(
+        root: &str,
+        entries: &mut vec::IntoIter<(String, VirtualEntry)>,
+    ) -> Option<DirEntry> {
+        entries
+            .next()
+            .map(|(name, entry)| DirEntry::from_virtual(root, &name, entry))
+    }
+}
+
+#[derive(rquickjs::class::Trace, rquickjs::JsLifetime)]
+#[rquickjs::class]
+pub struct Dir {
+    #[qjs(skip_trace)]
+    path: String,
+    #[qjs(skip_trace)]
+    entries: Rc<Mutex<Option<DirEntries>>>,
+}
+
+#[rquickjs::methods(rename_all = "camelCase")]
+impl Dir {
+    #[qjs(get)]
+    pub fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    pub async fn read<'js>(&self, ctx: Ctx<'js>) -> Result<Value<'js>> {
+        let entry = Self::read_entry(&ctx, &self.path, &self.entries).await?;
+        entry_or_null(&ctx, entry)
+    }
+
+    pub fn read_sync<'js>(&self, ctx: Ctx<'js>) -> Result<Value<'js>> {
+        let mut entries = self.try_lock(&ctx)?;
+        let entries = entries.as_mut().or_throw_msg(&ctx, DIR_CLOSED)?;
+        let entry = entries.next_sync().or_throw_io(
+            &ctx,
+            &read_dir_error(&self.path),
+            "scandir",
+            &self.path,
+        )?;
+        entry_or_null(&ctx, entry)
+    }
+
+    pub async fn close(&self, ctx: Ctx<'_>) -> Result<()> {
+        Self::close_entries(&ctx, &self.entries).await
+    }
+
+    pub fn close_sync(&self, ctx: Ctx<'_>) -> Result<()> {
+        self.try_lock(&ctx)?
+            .take()
+            .map(drop)
+            .or_throw_msg(&ctx, DIR_CLOSED)
+    }
+
+    #[qjs(rename = PredefinedAtom::SymbolAsyncIterator)]
+    fn async_iterator<'js>(this: This<Class<'js, Self>>) -> DirIterator<'js> {
+        DirIterator { dir: this.0 }
+    }
+}
+
+impl Dir {
+    async fn read_entry(
+        ctx: &Ctx<'_>,
+        path: &str,
+        entries: &Mutex<Option<DirEntries>>,
+    ) -> Result<Option<DirEntry>> {
+        let mut entries = entries.lock().await;
+        let entries = entries.as_mut().or_throw_msg(ctx, DIR_CLOSED)?;
+        entries
+            .next()
+            .await
+            .or_throw_io(ctx, &read_dir_error(path), "scandir", path)
+    }
+
+    async fn close_entries(ctx: &Ctx<'_>, entries: &Mutex<Option<DirEntries>>) -> Result<()> {
+        entries
+            .lock()
+            .await
+            .take()
+            .map(drop)
+            .or_throw_msg(ctx, DIR_CLOSED)
+    }
+
+    fn try_lock(&self, ctx: &Ctx<'_>) -> Result<tokio::sync::MutexGuard<'_, Option<DirEntries>>> {
+        self.entries
+            .try_lock()
+            .ok()
+            .or_throw_msg(ctx, "Directory handle has an operation in progress")
+    }
+}
+
+// Iterates the entries of a `Dir` and closes it when done or returned early
+#[rquickjs::class]
+#[derive(rquickjs::JsLifetime)]
+pub struct DirIterator<'js> {
+    dir: Class<'js, Dir>,
+}
+
+impl<'js> Trace<'js> for DirIterator<'js> {
+    fn trace<'a>(&self, tracer: Tracer<'a, 'js>) {
+        self.dir.trace(tracer);
+    }
+}
+
+#[rquickjs::methods]
+impl<'js> DirIterator<'js> {
+    async fn next(&self, ctx: Ctx<'js>) -> Result<Object<'js>> {
+        let (path, entries) = {
+            let dir = self.dir.borrow();
+            (dir.path.clone(), dir.entries.clone())
+        };
+        match Dir::read_entry(&ctx, &path, &entries).await {
+            Ok(Some(entry)) => iterator_result(&ctx, entry.into_js(&ctx)?, false),
+            Ok(None) => {
+                Dir::close_entries(&ctx, &entries).await?;
+                iterator_result(&ctx, Undefined.into_value(ctx.clone()), true)
+            },
+            Err(err) => {
+                entries.lock().await.take();
+                Err(err)
+            },
+        }
+    }
+
+    #[qjs(rename = "return")]
+    async fn return_(&self, ctx: Ctx<'js>) -> Result<Object<'js>> {
+        let entries = self.dir.borrow().entries.clone();
+        entries.lock().await.take();
+        iterator_result(&ctx, Undefined.into_value(ctx.clone()), true)
+    }
+
+    #[qjs(rename = PredefinedAtom::SymbolAsyncIterator)]
+    fn async_iterator(this: This<Class<'js, Self>>) -> Class<'js, Self> {
+        this.0
+    }
+}
+
+fn entry_or_null<'js>(ctx: &Ctx<'js>, entry: Option<DirEntry>) -> Result<Value<'js>> {
+    match entry {
+        Some(entry) => entry.into_js(ctx),
+        None => Ok(Null.into_value(ctx.clone())),
+    }
+}
+
+fn open_error(path: &str) -> String {
+    ["Can't open dir \"", path, "\""].concat()
+}
+
+// Returns whether the walk is recursive and its buffer size
+fn get_params(ctx: &Ctx<'_>, options: Opt<Object>) -> Result<(bool, usize)> {
+    let Some(options) = options.0 else {
+        return Ok((false, DEFAULT_BUFFER_SIZE));
+    };
+    let recursive = options
+        .get::<_, Value>("recursive")
+        .ok()
+        .and_then(|recursive| recursive.as_bool())
+        .unwrap_or_default();
+    let buffer_size = match options.get::<_, Option<f64>>("bufferSize")? {
+        None => DEFAULT_BUFFER_SIZE,
+        Some(size) if size >= 1.0 && size.fract() == 0.0 && size <= u32::MAX as f64 => {
+            size as usize
+        },
+        Some(size) => {
+            return Err(Exception::throw_range(
+                ctx,
+                &format!("The value of \"bufferSize\" must be an integer >= 1, received {size}"),
+            ));
+        },
+    };
+    Ok((recursive, buffer_size))
+}
+
+pub async fn open_dir<'js>(ctx: Ctx<'js>, path: String, options: Opt<Object<'js>>) -> Result<Dir> {
+    let (recursive, buffer_size) = get_params(&ctx, options)?;
+    let entries = DirEntries::new(&path, recursive, buffer_size);
+    if let EntrySource::Walker(_) = entries.source {
+        tokio::fs::read_dir(&path).await.map(drop).or_throw_io(
+            &ctx,
+            &open_error(&path),
+            "opendir",
+            &path,
+        )?;
+    }
+    Ok(new_dir(path, entries))
+}
+
+pub fn open_dir_sync<'js>(ctx: Ctx<'js>, path: String, options: Opt<Object<'js>>) -> Result<Dir> {
+    let (recursive, buffer_size) = get_params(&ctx, options)?;
+    let entries = DirEntries::new(&path, recursive, buffer_size);
+    if let EntrySource::Walker(_) = entries.source {
+        std::fs::read_dir(&path).or_throw_io(&ctx, &open_error(&path), "opendir", &path)?;
+    }
+    Ok(new_dir(path, entries))
+}
+
+fn new_dir(path: String, entries: DirEntries) -> Dir {
+    Dir {
+        path,
+        entries: Rc::new(Mutex::new(Some(entries))),
+    }
+}
@@ -2,39 +2,74 @@
 // SPDX-License-Identifier: Apache-2.0
 #[cfg(unix)]
 use std::os::unix::fs::FileTypeExt;
-use std::{fs::Metadata, path::PathBuf};
+use std::{
+    fs::Metadata,
+    path::{Path, PathBuf},
+};
 
 use llrt_path::{ends_with_sep, CURRENT_DIR_STR};
-use llrt_utils::fs::DirectoryWalker;
+use llrt_utils::fs::{DirectoryWalker, IoResultExt};
 use rquickjs::{
     atom::PredefinedAtom, prelude::Opt, Array, Class, Ctx, IntoJs, Object, Result, Value,
 };
 
+use crate::vfs::{self, VirtualEntry};
+
 #[derive(rquickjs::class::Trace, rquickjs::JsLifetime)]
 #[rquickjs::class]
 pub struct Dirent {
     #[qjs(skip_trace)]
-    metadata: Metadata,
+    metadata: Option<Metadata>,
+    #[qjs(skip_trace)]
+    virtual_entry: Option<VirtualEntry>,
+}
+
+impl Dirent {
+    fn new(metadata: Metadata) -> Self {
+        Self {
+            metadata: Some(metadata),
+            virtual_entry: None,
+        }
+    }
+
+    fn from_virtual(entry: VirtualEntry) -> Self {
+        Self {
+            metadata: None,
+            virtual_entry: Some(entry),
+        }
+    }
 }
 
 #[rquickjs::methods(rename_all = "camelCase")]
 impl Dirent {
     pub fn is_file(&self) -> bool {
-        self.metadata.is_file()
+        match (&self.metadata, &self.virtual_entry) {
+            (Some(metadata), _) => metadata.is_file(),
+            (None, Some(entry)) => !entry.is_dir,
+            (None, None) => false,
+        }
     }
     pub fn is_directory(&self) -> bool {
-        self.metadata.is_dir()
+        match (&self.metadata, &self.virtual_entry) {
+            (Some(metadata), _) => metadata.is_dir(),
+            (None, Some(entry)) => entry.is_dir,
+            (None, None) => false,
+        }
     }
 
     pub fn is_symbolic_link(&self) -> bool {
-        self.metadata.is_symlink()
+        self.metadata
+            .as_ref()
+            .is_some_and(|metadata| metadata.is_symlink())
     }
 
     #[qjs(rename = "isFIFO")]
     pub fn is_fifo(&self) -> bool {
         #[cfg(unix)]
         {
-            self.metadata.file_type().is_fifo()
+            self.metadata
+                .as_ref()
+                .is_some_and(|metadata| metadata.file_type().is_fifo())
         }
         #[cfg(not(unix))]
         {
@@ -45,7 +80,9 @@ impl Dirent {
     pub fn is_block_device(&self) -> bool {
         #[cfg(unix)]
         {
-            self.metadata.file_type().is_block_device()
+            self.metadata
+                .as_ref()
+                .is_some_and(|metadata| metadata.file_type().is_block_device())
         }
         #[cfg(not(unix))]
         {
@@ -56,7 +93,9 @@ impl Dirent {
     pub fn is_character_device(&self) -> bool {
         #[cfg(unix)]
         {
-            self.metadata.file_type().is_char_device()
+            self.metadata
+                .as_ref()
+                .is_some_and(|metadata| metadata.file_type().is_char_device())
         }
         #[cfg(not(unix))]
         {
@@ -67,7 +106,9 @@ impl Dirent {
     pub fn is_socket(&self) -> bool {
         #[cfg(unix)]
         {
-            self.metadata.file_type().is_socket()
+            self.metadata
+                .as_ref()
+                .is_some_and(|metadata| metadata.file_type().is_socket())
         }
         #[cfg(not(unix))]
         {
@@ -76,26 +117,61 @@ impl Dirent {
     }
 }
 
+// A `Dirent` with the basename of the entry and the directory it is in
+pub(crate) struct DirEntry {
+    dirent: Dirent,
+    name: String,
+    parent_path: String,
+}
+
+impl DirEntry {
+    pub(crate) fn new(child: &Path, metadata: Metadata) -> Self {
+        let name = child.file_name().unwrap_or_default().to_string_lossy();
+        let parent_path = child.parent().unwrap_or(child).to_string_lossy();
+        Self {
+            dirent: Dirent::new(metadata),
+            name: name.into_owned(),
+            parent_path: parent_path.into_owned(),
+        }
+    }
+
+    // Virtual entries are named relative to `root` with '/' separators
+    pub(crate) fn from_virtual(root: &str, name: &str, entry: VirtualEntry) -> Self {
+        let (parent_path, name) = match name.rsplit_once('/') {
+            Some((dir, name)) => ([root, "/", dir].concat(), name),
+            None => (root.to_string(), name),
+        };
+        Self {
+            dirent: Dirent::from_virtual(entry),
+            name: name.to_string(),
+            parent_path,
+        }
+    }
+}
+
+impl<'js> IntoJs<'js> for DirEntry {
+    fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
+        let dirent = Class::instance(ctx.clone(), self.dirent)?;
+        dirent.set(PredefinedAtom::Name, self.name)?;
+        dirent.set("parentPath", self.parent_path)?;
+        dirent.into_js(ctx)
+    }
+}
+
 struct ReadDirItem {
     name: String,
-    metadata: Option<Metadata>,
+    dirent: Option<DirEntry>,
 }
 
 pub struct ReadDir {
     items: Vec<ReadDirItem>,
-    root: String,
 }
 
 impl<'js> IntoJs<'js> for ReadDir {
     fn into_js(self, ctx: &Ctx<'js>) -> Result<Value<'js>> {
         let arr = Array::new(ctx.clone())?;
         for (index, item) in self.items.into_iter().enumerate() {
-            if let Some(metadata) = item.metadata {
-                let dirent = Dirent { metadata };
-
-                let dirent = Class::instance(ctx.clone(), dirent)?;
-                dirent.set(PredefinedAtom::Name, item.name)?;
-                dirent.set("parentPath", &self.root)?;
+            if let Some(dirent) = item.dirent {
                 arr.set(index, dirent)?;
             } else {
                 arr.set(index, item.name)?;
@@ -105,13 +181,28 @@ impl<'js> IntoJs<'js> for ReadDir {
     }
 }
 
-pub async fn read_dir(mut path: String, options: Opt<Object<'_>>) -> Result<ReadDir> {
-    let (with_file_types, skip_root_pos, mut directory_walker) =
-        process_options_and_create_directory_walker(&mut path, options);
+pub async fn read_dir<'js>(
+    ctx: Ctx<'js>,
+    mut path: String,
+    options: Opt<Object<'js>>,
+) -> Result<ReadDir> {
+    let (with_file_types, is_recursive) = get_params(options);
+
+    if let Some(read_dir) = read_virtual_dir(&path, with_file_types, is_recursive) {
+        return Ok(read_dir);
+    }
+
+    let (skip_root_pos, mut directory_walker) = create_directory_walker(&mut path, is_recursive);
+    let error_message = read_dir_error(&path);
 
     let mut items = Vec::with_capacity(64);
 
-    while let Some((child, metadata)) = directory_walker.walk().await? {
+    while let Some((child, metadata)) =
+        directory_walker
+            .walk()
+            .await
+            .or_throw_io(&ctx, &error_message, "scandir", &path)?
+    {
         append_directory_and_metadata_to_vec(
             with_file_types,
             skip_root_pos,
@@ -123,15 +214,29 @@ pub async fn read_dir(mut path: String, options: Opt<Object<'_>>) -> Result<Read
 
     items.sort_by(|a, b| a.name.partial_cmp(&b.name).unwrap());
 
-    Ok(ReadDir { items, root: path })
+    Ok(ReadDir { items })
 }
 
-pub fn read_dir_sync(mut path: String, options: Opt<Object<'_>>) -> Result<ReadDir> {
-    let (with_file_types, skip_root_pos, mut directory_walker) =
-        process_options_and_create_directory_walker(&mut path, options);
+pub fn read_dir_sync<'js>(
+    ctx: Ctx<'js>,
+    mut path: String,
+    options: Opt<Object<'js>>,
+) -> Result<ReadDir> {
+    let (with_file_types, is_recursive) = get_params(options);
+
+    if let Some(read_dir) = read_virtual_dir(&path, with_file_types, is_recursive) {
+        return Ok(read_dir);
+    }
+
+    let (skip_root_pos, mut directory_walker) = create_directory_walker(&mut path, is_recursive);
+    let error_message = read_dir_error(&path);
 
     let mut items = Vec::with_capacity(64);
-    while let Some((child, metadata)) = directory_walker.walk_sync()? {
+    while let Some((child, metadata)) =
+        directory_walker
+            .walk_sync()
+            .or_throw_io(&ctx, &error_message, "scandir", &path)?
+    {
         append_directory_and_metadata_to_vec(
             with_file_types,
             skip_root_pos,
@@ -143,15 +248,14 @@ pub fn read_dir_sync(mut path: String, options: Opt<Object<'_>>) -> Result<ReadD
 
     items.sort_by(|a, b| a.name.partial_cmp(&b.name).unwrap());
 
-    Ok(ReadDir { items, root: path })
+    Ok(ReadDir { items })
 }
 
-type OptionsAndDirectoryWalker = (bool, usize, DirectoryWalker<fn(&str) -> bool>);
+pub(crate) fn read_dir_error(path: &str) -> String {
+    ["Can't read dir \"", path, "\""].concat()
+}
 
-fn process_options_and_create_directory_walker(
-    path: &mut String,
-    options: Opt<Object>,
-) -> OptionsAndDirectoryWalker {
+fn get_params(options: Opt<Object>) -> (bool, bool) {
     let mut with_file_types = false;
     let mut is_recursive = false;
 
@@ -169,6 +273,27 @@ fn process_options_and_create_directory_walker(
             .unwrap_or_default();
     };
 
+    (with_file_types, is_recursive)
+}
+
+fn read_virtual_dir(path: &str, with_file_types: bool, is_recursive: bool) -> Option<ReadDir> {
+    let entries = vfs::read_dir(path, is_recursive)?;
+    let root = path.trim_end_matches('/');
+    let items = entries
+        .into_iter()
+        .map(|(name, entry)| ReadDirItem {
+            dirent: with_file_types.then(|| DirEntry::from_virtual(root, &name, entry)),
+            name,
+        })
+        .collect();
+
+    Some(ReadDir { items })
+}
+
+pub(crate) fn create_directory_walker(
+    path: &mut String,
+    is_recursive: bool,
+) -> (usize, DirectoryWalker<fn(&str) -> bool>) {
     if ends_with_sep(path) {
         path.pop();
     }
@@ -188,7 +313,7 @@ fn process_options_and_create_directory_walker(
     if is_recursive {
         directory_walker.set_recursive(true);
     }
-    (with_file_types, skip_root_pos, directory_walker)
+    (skip_root_pos, directory_walker)
 }
 
 fn append_directory_and_metadata_to_vec(
@@ -198,13 +323,13 @@ fn append_directory_and_metadata_to_vec(
     child: PathBuf,
     metadata: Metadata,
 ) {
-    let metadata = if with_file_types {
-        Some(metadata)
+    let dirent = if with_file_types {
+        Some(DirEntry::new(&child, metadata))
     } else {
         None
     };
 
     let name = child.into_os_string().to_string_lossy()[skip_root_pos..].to_string();
 
-    items.push(ReadDirItem { name, metadata })
+    items.push(ReadDirItem { name, dirent })
 }
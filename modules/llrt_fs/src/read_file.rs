@@ -2,18 +2,24 @@
 // SPDX-License-Identifier: Apache-2.0
 use either::Either;
 use llrt_buffer::Buffer;
-use llrt_utils::{object::ObjectExt, result::ResultExt};
+use llrt_utils::{fs::IoResultExt, object::ObjectExt};
 use rquickjs::{function::Opt, Ctx, Error, FromJs, IntoJs, Result, Value};
 use tokio::fs;
 
+use crate::vfs;
+
 pub async fn read_file(
     ctx: Ctx<'_>,
     path: String,
     options: Opt<Either<String, ReadFileOptions>>,
 ) -> Result<Value<'_>> {
+    if let Some(bytes) = vfs::read(&path) {
+        return handle_read_file_bytes(&ctx, options, bytes.to_vec());
+    }
+
     let bytes = fs::read(&path)
         .await
-        .or_throw_msg(&ctx, &["Can't read \"", &path, "\""].concat())?;
+        .or_throw_io(&ctx, &read_error(&path), "open", &path)?;
 
     handle_read_file_bytes(&ctx, options, bytes)
 }
@@ -23,12 +29,19 @@ pub fn read_file_sync(
     path: String,
     options: Opt<Either<String, ReadFileOptions>>,
 ) -> Result<Value<'_>> {
-    let bytes =
-        std::fs::read(&path).or_throw_msg(&ctx, &["Can't read \"", &path, "\""].concat())?;
+    if let Some(bytes) = vfs::read(&path) {
+        return handle_read_file_bytes(&ctx, options, bytes.to_vec());
+    }
+
+    let bytes = std::fs::read(&path).or_throw_io(&ctx, &read_error(&path), "open", &path)?;
 
     handle_read_file_bytes(&ctx, options, bytes)
 }
 
+fn read_error(path: &str) -> String {
+    ["Can't read \"", path, "\""].concat()
+}
+
 pub(crate) fn handle_read_file_bytes<'a>(
     ctx: &Ctx<'a>,
     options: Opt<Either<String, ReadFileOptions>>,
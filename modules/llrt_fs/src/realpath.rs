@@ -0,0 +1,40 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use llrt_utils::fs::IoResultExt;
+use rquickjs::{Ctx, Result};
+use tokio::fs;
+
+use crate::{link::to_string, vfs};
+
+pub async fn realpath(ctx: Ctx<'_>, path: String) -> Result<String> {
+    // Embedded files have no links to resolve
+    if vfs::stat(&path).is_some() {
+        return Ok(path);
+    }
+
+    let resolved = fs::canonicalize(&path).await.or_throw_io(
+        &ctx,
+        &realpath_error(&path),
+        "realpath",
+        &path,
+    )?;
+    Ok(to_string(&resolved))
+}
+
+pub fn realpath_sync(ctx: Ctx<'_>, path: String) -> Result<String> {
+    if vfs::stat(&path).is_some() {
+        return Ok(path);
+    }
+
+    let resolved = std::fs::canonicalize(&path).or_throw_io(
+        &ctx,
+        &realpath_error(&path),
+        "realpath",
+        &path,
+    )?;
+    Ok(to_string(&resolved))
+}
+
+fn realpath_error(path: &str) -> String {
+    ["Can't resolve path \"", path, "\""].concat()
+}
@@ -0,0 +1,27 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use llrt_utils::fs::IoResultExt;
+use rquickjs::{Ctx, Result};
+use tokio::fs;
+
+fn rename_error(old_path: &str, new_path: &str) -> String {
+    ["Can't rename \"", old_path, "\" to \"", new_path, "\""].concat()
+}
+
+pub async fn rename(ctx: Ctx<'_>, old_path: String, new_path: String) -> Result<()> {
+    fs::rename(&old_path, &new_path).await.or_throw_io(
+        &ctx,
+        &rename_error(&old_path, &new_path),
+        "rename",
+        &old_path,
+    )
+}
+
+pub fn rename_sync(ctx: Ctx<'_>, old_path: String, new_path: String) -> Result<()> {
+    std::fs::rename(&old_path, &new_path).or_throw_io(
+        &ctx,
+        &rename_error(&old_path, &new_path),
+        "rename",
+        &old_path,
+    )
+}
@@ -1,6 +1,6 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
-use llrt_utils::result::ResultExt;
+use llrt_utils::fs::IoResultExt;
 use rquickjs::{function::Opt, Ctx, Object, Result};
 use tokio::fs;
 
@@ -13,7 +13,7 @@ pub async fn rmdir<'js>(ctx: Ctx<'js>, path: String, options: Opt<Object<'js>>)
     } else {
         fs::remove_dir(&path).await
     }
-    .or_throw_msg(&ctx, &["Can't remove dir \"", &path, "\""].concat())?;
+    .or_throw_io(&ctx, &rmdir_error(&path), "rmdir", &path)?;
 
     Ok(())
 }
@@ -27,7 +27,7 @@ pub fn rmdir_sync<'js>(ctx: Ctx<'js>, path: String, options: Opt<Object<'js>>) -
     } else {
         std::fs::remove_dir(&path)
     }
-    .or_throw_msg(&ctx, &["Can't remove dir \"", &path, "\""].concat())?;
+    .or_throw_io(&ctx, &rmdir_error(&path), "rmdir", &path)?;
 
     Ok(())
 }
@@ -39,7 +39,7 @@ pub async fn rmfile<'js>(ctx: Ctx<'js>, path: String, options: Opt<Object<'js>>)
         let is_dir = fs::metadata(&path)
             .await
             .map(|metadata| metadata.is_dir())
-            .or_throw(&ctx)?;
+            .or_throw_io(&ctx, "", "stat", &path)?;
 
         (if is_dir && recursive {
             fs::remove_dir_all(&path).await
@@ -48,7 +48,7 @@ pub async fn rmfile<'js>(ctx: Ctx<'js>, path: String, options: Opt<Object<'js>>)
         } else {
             fs::remove_file(&path).await
         })
-        .or_throw_msg(&ctx, &["Can't remove file \"", &path, "\""].concat())?;
+        .or_throw_io(&ctx, &rm_error(&path), "rm", &path)?;
 
         Ok(())
     }
@@ -61,11 +61,13 @@ pub async fn rmfile<'js>(ctx: Ctx<'js>, path: String, options: Opt<Object<'js>>)
     Ok(())
 }
 
-pub fn rmfile_sync(path: String, options: Opt<Object<'_>>) -> Result<()> {
+pub fn rmfile_sync<'js>(ctx: Ctx<'js>, path: String, options: Opt<Object<'js>>) -> Result<()> {
     let (recursive, force) = get_params_rm(options);
 
     let res = (|| -> Result<()> {
-        let is_dir = std::fs::metadata(&path).map(|metadata| metadata.is_dir())?;
+        let is_dir = std::fs::metadata(&path)
+            .map(|metadata| metadata.is_dir())
+            .or_throw_io(&ctx, "", "stat", &path)?;
 
         (if is_dir && recursive {
             std::fs::remove_dir_all(&path)
@@ -73,7 +75,8 @@ pub fn rmfile_sync(path: String, options: Opt<Object<'_>>) -> Result<()> {
             std::fs::remove_dir(&path)
         } else {
             std::fs::remove_file(&path)
-        })?;
+        })
+        .or_throw_io(&ctx, &rm_error(&path), "rm", &path)?;
 
         Ok(())
     })();
@@ -85,6 +88,14 @@ pub fn rmfile_sync(path: String, options: Opt<Object<'_>>) -> Result<()> {
     Ok(())
 }
 
+fn rmdir_error(path: &str) -> String {
+    ["Can't remove dir \"", path, "\""].concat()
+}
+
+fn rm_error(path: &str) -> String {
+    ["Can't remove file \"", path, "\""].concat()
+}
+
 fn get_params_rm_dir(options: Opt<Object>) -> bool {
     let mut recursive = false;
 
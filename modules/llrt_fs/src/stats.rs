@@ -12,10 +12,12 @@ use std::{
     time::{Duration, SystemTime},
 };
 
-use llrt_utils::result::ResultExt;
+use llrt_utils::{fs::IoResultExt, result::ResultExt};
 use rquickjs::{Ctx, Result};
 use tokio::fs;
 
+use crate::vfs::{self, VirtualEntry};
+
 // The Stats implementation is very much based on Unix. The Windows implementation
 // tries its best to mimic the implementation of libuv since it is the standard.
 // See: https://github.com/libuv/libuv/blob/90648ea3e55125a5a819b32106da6462da310da6/src/win/fs.c
@@ -29,189 +31,285 @@ use tokio::fs;
 #[derive(rquickjs::class::Trace, rquickjs::JsLifetime)]
 pub struct Stats {
     #[qjs(skip_trace)]
-    metadata: Metadata,
+    source: StatsSource,
+}
+
+enum StatsSource {
+    Disk(Metadata),
+    Virtual(VirtualEntry),
+    // A file that does not exist, all fields are zero
+    Missing,
+}
+
+impl Stats {
+    pub(crate) fn from_virtual(entry: VirtualEntry) -> Self {
+        Self {
+            source: StatsSource::Virtual(entry),
+        }
+    }
+
+    pub(crate) fn missing() -> Self {
+        Self {
+            source: StatsSource::Missing,
+        }
+    }
+
+    fn metadata(&self) -> Option<&Metadata> {
+        match &self.source {
+            StatsSource::Disk(metadata) => Some(metadata),
+            StatsSource::Virtual(_) | StatsSource::Missing => None,
+        }
+    }
+
+    fn virtual_time() -> SystemTime {
+        SystemTime::UNIX_EPOCH
+    }
 }
 
 #[rquickjs::methods(rename_all = "camelCase")]
 impl Stats {
     #[qjs(skip)]
     pub fn new(metadata: Metadata) -> Self {
-        Self { metadata }
+        Self {
+            source: StatsSource::Disk(metadata),
+        }
     }
 
     #[qjs(get, enumerable)]
     pub fn dev(&self) -> u64 {
+        let Some(metadata) = self.metadata() else {
+            return 0;
+        };
         #[cfg(unix)]
         {
-            self.metadata.dev()
+            metadata.dev()
         }
         #[cfg(not(unix))]
         {
             // Unstable feature, see https://github.com/rust-lang/rust/issues/63010
+            _ = metadata;
             0
         }
     }
 
     #[qjs(get, enumerable)]
     pub fn ino(&self) -> u64 {
+        let Some(metadata) = self.metadata() else {
+            return 0;
+        };
         #[cfg(unix)]
         {
-            self.metadata.ino()
+            metadata.ino()
         }
         #[cfg(not(unix))]
         {
             // Unstable feature, see https://github.com/rust-lang/rust/issues/63010
+            _ = metadata;
             0
         }
     }
 
     #[qjs(get, enumerable)]
     pub fn mode(&self) -> u32 {
+        let metadata = match &self.source {
+            StatsSource::Disk(metadata) => metadata,
+            // Embedded files are read-only
+            StatsSource::Virtual(entry) if entry.is_dir => return 0o40555,
+            StatsSource::Virtual(_) => return 0o100444,
+            StatsSource::Missing => return 0,
+        };
         #[cfg(unix)]
         {
-            self.metadata.mode()
+            metadata.mode()
         }
         #[cfg(not(unix))]
         {
+            _ = metadata;
             0o666
         }
     }
 
     #[qjs(get, enumerable)]
     pub fn nlink(&self) -> u64 {
+        let Some(metadata) = self.metadata() else {
+            return 1;
+        };
         #[cfg(unix)]
         {
-            self.metadata.nlink()
+            metadata.nlink()
         }
         #[cfg(not(unix))]
         {
             // Unstable feature, see https://github.com/rust-lang/rust/issues/63010
+            _ = metadata;
             1
         }
     }
 
     #[qjs(get, enumerable)]
     pub fn uid(&self) -> u32 {
+        let Some(metadata) = self.metadata() else {
+            return 0;
+        };
         #[cfg(unix)]
         {
-            self.metadata.uid()
+            metadata.uid()
         }
         #[cfg(not(unix))]
         {
+            _ = metadata;
             0
         }
     }
 
     #[qjs(get, enumerable)]
     pub fn gid(&self) -> u32 {
+        let Some(metadata) = self.metadata() else {
+            return 0;
+        };
         #[cfg(unix)]
         {
-            self.metadata.gid()
+            metadata.gid()
         }
         #[cfg(not(unix))]
         {
+            _ = metadata;
             0
         }
     }
 
     #[qjs(get, enumerable)]
     pub fn rdev(&self) -> u64 {
+        let Some(metadata) = self.metadata() else {
+            return 0;
+        };
         #[cfg(unix)]
         {
-            self.metadata.rdev()
+            metadata.rdev()
         }
         #[cfg(not(unix))]
         {
+            _ = metadata;
             0
         }
     }
 
     #[qjs(get, enumerable)]
     pub fn size(&self) -> u64 {
+        let metadata = match &self.source {
+            StatsSource::Disk(metadata) => metadata,
+            StatsSource::Virtual(entry) => return entry.size,
+            StatsSource::Missing => return 0,
+        };
         #[cfg(unix)]
         {
-            self.metadata.size()
+            metadata.size()
         }
         #[cfg(windows)]
         {
-            if self.metadata.is_dir() {
+            if metadata.is_dir() {
                 0
             } else {
-                self.metadata.file_size()
+                metadata.file_size()
             }
         }
         #[cfg(not(any(unix, windows)))]
         {
+            _ = metadata;
             0
         }
     }
 
     #[qjs(get, enumerable)]
     pub fn blksize(&self) -> u64 {
+        let metadata = match &self.source {
+            StatsSource::Disk(metadata) => metadata,
+            StatsSource::Virtual(_) => return 4096,
+            StatsSource::Missing => return 0,
+        };
         #[cfg(unix)]
         {
-            self.metadata.blksize()
+            metadata.blksize()
         }
         #[cfg(not(unix))]
         {
+            _ = metadata;
             4096
         }
     }
 
     #[qjs(get, enumerable)]
     pub fn blocks(&self) -> u64 {
+        let metadata = match &self.source {
+            StatsSource::Disk(metadata) => metadata,
+            StatsSource::Virtual(entry) => return entry.size.div_ceil(512),
+            StatsSource::Missing => return 0,
+        };
         #[cfg(unix)]
         {
-            self.metadata.blocks()
+            metadata.blocks()
         }
         #[cfg(not(unix))]
         {
+            _ = metadata;
             0
         }
     }
 
     #[qjs(get, enumerable)]
     pub fn atime_ms(&self, ctx: Ctx<'_>) -> Result<u64> {
+        let Some(metadata) = self.metadata() else {
+            return Ok(0);
+        };
         #[cfg(unix)]
         {
             _ = ctx;
-            Ok(self.metadata.atime_nsec() as u64 / 1e6 as u64)
+            Ok(metadata.atime_nsec() as u64 / 1e6 as u64)
         }
         #[cfg(not(unix))]
         {
-            self.metadata.accessed().map(to_msec).or_throw(&ctx)
+            metadata.accessed().map(to_msec).or_throw(&ctx)
         }
     }
 
     #[qjs(get, enumerable)]
     pub fn mtime_ms(&self, ctx: Ctx<'_>) -> Result<u64> {
+        let Some(metadata) = self.metadata() else {
+            return Ok(0);
+        };
         #[cfg(unix)]
         {
             _ = ctx;
-            Ok(self.metadata.mtime_nsec() as u64 / 1e6 as u64)
+            Ok(metadata.mtime_nsec() as u64 / 1e6 as u64)
         }
         #[cfg(not(unix))]
         {
-            self.metadata.modified().map(to_msec).or_throw(&ctx)
+            metadata.modified().map(to_msec).or_throw(&ctx)
         }
     }
 
     #[qjs(get, enumerable)]
     pub fn ctime_ms(&self, ctx: Ctx<'_>) -> Result<u64> {
+        let Some(metadata) = self.metadata() else {
+            return Ok(0);
+        };
         #[cfg(unix)]
         {
             _ = ctx;
-            Ok(self.metadata.ctime_nsec() as u64 / 1e6 as u64)
+            Ok(metadata.ctime_nsec() as u64 / 1e6 as u64)
         }
         #[cfg(not(unix))]
         {
-            self.metadata.modified().map(to_msec).or_throw(&ctx)
+            metadata.modified().map(to_msec).or_throw(&ctx)
         }
     }
 
     #[qjs(get, enumerable)]
     pub fn birthtime_ms(&self, ctx: Ctx<'_>) -> Result<u64> {
-        self.metadata
+        let Some(metadata) = self.metadata() else {
+            return Ok(0);
+        };
+        metadata
             .created()
             .or_throw(&ctx)
             .and_then(|c| c.elapsed().or_throw(&ctx))
@@ -220,59 +318,81 @@ impl Stats {
 
     #[qjs(get, enumerable)]
     pub fn atime(&self, ctx: Ctx<'_>) -> Result<SystemTime> {
-        self.metadata.accessed().or_throw(&ctx)
+        let Some(metadata) = self.metadata() else {
+            return Ok(Self::virtual_time());
+        };
+        metadata.accessed().or_throw(&ctx)
     }
 
     #[qjs(get, enumerable)]
     pub fn mtime(&self, ctx: Ctx<'_>) -> Result<SystemTime> {
-        self.metadata.modified().or_throw(&ctx)
+        let Some(metadata) = self.metadata() else {
+            return Ok(Self::virtual_time());
+        };
+        metadata.modified().or_throw(&ctx)
     }
 
     #[qjs(get, enumerable)]
     pub fn ctime(&self, ctx: Ctx<'_>) -> Result<SystemTime> {
+        let Some(metadata) = self.metadata() else {
+            return Ok(Self::virtual_time());
+        };
         #[cfg(unix)]
         {
             _ = ctx;
-            Ok(SystemTime::UNIX_EPOCH + Duration::from_nanos(self.metadata.ctime_nsec() as u64))
+            Ok(SystemTime::UNIX_EPOCH + Duration::from_nanos(metadata.ctime_nsec() as u64))
         }
         #[cfg(not(unix))]
         {
-            self.metadata.modified().or_throw(&ctx)
+            metadata.modified().or_throw(&ctx)
         }
     }
 
     #[qjs(get, enumerable)]
     pub fn birthtime(&self, ctx: Ctx<'_>) -> Result<SystemTime> {
-        self.metadata.created().or_throw(&ctx)
+        let Some(metadata) = self.metadata() else {
+            return Ok(Self::virtual_time());
+        };
+        metadata.created().or_throw(&ctx)
     }
 
     pub fn is_file(&self) -> bool {
-        self.metadata.is_file()
+        match &self.source {
+            StatsSource::Disk(metadata) => metadata.is_file(),
+            StatsSource::Virtual(entry) => !entry.is_dir,
+            StatsSource::Missing => false,
+        }
     }
 
     /// @deprecated Use `is_directory` instead
     pub fn is_dir(&self) -> bool {
-        self.metadata.is_dir()
+        self.is_directory()
     }
 
     pub fn is_directory(&self) -> bool {
-        self.metadata.is_dir()
+        match &self.source {
+            StatsSource::Disk(metadata) => metadata.is_dir(),
+            StatsSource::Virtual(entry) => entry.is_dir,
+            StatsSource::Missing => false,
+        }
     }
 
     /// @deprecated Use `is_symbolic_link` instead
     pub fn is_symlink(&self) -> bool {
-        self.metadata.is_symlink()
+        self.is_symbolic_link()
     }
 
     pub fn is_symbolic_link(&self) -> bool {
-        self.metadata.is_symlink()
+        self.metadata()
+            .is_some_and(|metadata| metadata.is_symlink())
     }
 
     #[qjs(rename = "isFIFO")]
     pub fn is_fifo(&self) -> bool {
         #[cfg(unix)]
         {
-            self.metadata.file_type().is_fifo()
+            self.metadata()
+                .is_some_and(|metadata| metadata.file_type().is_fifo())
         }
         #[cfg(not(unix))]
         {
@@ -283,7 +403,8 @@ impl Stats {
     pub fn is_block_device(&self) -> bool {
         #[cfg(unix)]
         {
-            self.metadata.file_type().is_block_device()
+            self.metadata()
+                .is_some_and(|metadata| metadata.file_type().is_block_device())
         }
         #[cfg(not(unix))]
         {
@@ -294,7 +415,8 @@ impl Stats {
     pub fn is_character_device(&self) -> bool {
         #[cfg(unix)]
         {
-            self.metadata.file_type().is_char_device()
+            self.metadata()
+                .is_some_and(|metadata| metadata.file_type().is_char_device())
         }
         #[cfg(not(unix))]
         {
@@ -305,7 +427,8 @@ impl Stats {
     pub fn is_socket(&self) -> bool {
         #[cfg(unix)]
         {
-            self.metadata.file_type().is_socket()
+            self.metadata()
+                .is_some_and(|metadata| metadata.file_type().is_socket())
         }
         #[cfg(not(unix))]
         {
@@ -315,9 +438,14 @@ impl Stats {
 }
 
 pub async fn stat_fn(ctx: Ctx<'_>, path: String) -> Result<Stats> {
-    let metadata = fs::metadata(&path)
-        .await
-        .or_throw_msg(&ctx, &["Can't stat \"", &path, "\""].concat())?;
+    if let Some(entry) = vfs::stat(&path) {
+        return Ok(Stats::from_virtual(entry));
+    }
+
+    let metadata =
+        fs::metadata(&path)
+            .await
+            .or_throw_io(&ctx, &stat_error(&path), "stat", &path)?;
 
     let stats = Stats::new(metadata);
 
@@ -325,14 +453,45 @@ pub async fn stat_fn(ctx: Ctx<'_>, path: String) -> Result<Stats> {
 }
 
 pub fn stat_fn_sync(ctx: Ctx<'_>, path: String) -> Result<Stats> {
-    let metadata =
-        std::fs::metadata(&path).or_throw_msg(&ctx, &["Can't stat \"", &path, "\""].concat())?;
+    if let Some(entry) = vfs::stat(&path) {
+        return Ok(Stats::from_virtual(entry));
+    }
+
+    let metadata = std::fs::metadata(&path).or_throw_io(&ctx, &stat_error(&path), "stat", &path)?;
 
     let stats = Stats::new(metadata);
 
     Ok(stats)
 }
 
+pub async fn lstat_fn(ctx: Ctx<'_>, path: String) -> Result<Stats> {
+    if let Some(entry) = vfs::stat(&path) {
+        return Ok(Stats::from_virtual(entry));
+    }
+
+    let metadata =
+        fs::symlink_metadata(&path)
+            .await
+            .or_throw_io(&ctx, &stat_error(&path), "lstat", &path)?;
+
+    Ok(Stats::new(metadata))
+}
+
+pub fn lstat_fn_sync(ctx: Ctx<'_>, path: String) -> Result<Stats> {
+    if let Some(entry) = vfs::stat(&path) {
+        return Ok(Stats::from_virtual(entry));
+    }
+
+    let metadata =
+        std::fs::symlink_metadata(&path).or_throw_io(&ctx, &stat_error(&path), "lstat", &path)?;
+
+    Ok(Stats::new(metadata))
+}
+
+fn stat_error(path: &str) -> String {
+    ["Can't stat \"", path, "\""].concat()
+}
+
 #[allow(dead_code)]
 #[inline(always)]
 fn to_msec(time: SystemTime) -> u64 {
@@ -0,0 +1,434 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    cell::Cell,
+    future::Future,
+    io::{self, SeekFrom},
+    pin::Pin,
+    rc::Rc,
+    sync::{Arc, RwLock},
+    task::{Context, Poll},
+};
+
+use either::Either;
+use llrt_context::CtxExtension;
+use llrt_encoding::Encoder;
+use llrt_events::{EmitError, Emitter, EventEmitter, EventKey, EventList};
+use llrt_stream::{
+    impl_stream_events,
+    readable::{ReadableStream, ReadableStreamInner},
+    writable::{WritableStream, WritableStreamInner},
+    SteamEvents,
+};
+use llrt_utils::{fs::io_error, object::ObjectExt, result::ResultExt};
+use rquickjs::{
+    class::{Trace, Tracer},
+    prelude::{Opt, This},
+    Class, Ctx, Exception, Function, JsLifetime, Object, Result, Value,
+};
+use tokio::{
+    fs::{File, OpenOptions},
+    io::{AsyncRead, AsyncReadExt, AsyncSeekExt, AsyncWrite, ReadBuf, Take},
+};
+
+use crate::open::open_options;
+
+const DEFAULT_READ_HIGH_WATER_MARK: usize = 64 * 1024;
+const DEFAULT_WRITE_HIGH_WATER_MARK: usize = 16 * 1024;
+
+impl_stream_events!(ReadStream, WriteStream);
+
+#[rquickjs::class]
+pub struct ReadStream<'js> {
+    emitter: EventEmitter<'js>,
+    readable_stream_inner: ReadableStreamInner<'js>,
+    path: String,
+    bytes_read: Rc<Cell<u64>>,
+}
+
+unsafe impl<'js> JsLifetime<'js> for ReadStream<'js> {
+    type Changed<'to> = ReadStream<'to>;
+}
+
+impl<'js> Trace<'js> for ReadStream<'js> {
+    fn trace<'a>(&self, tracer: Tracer<'a, 'js>) {
+        self.emitter.trace(tracer);
+    }
+}
+
+impl<'js> Emitter<'js> for ReadStream<'js> {
+    fn get_event_list(&self) -> Arc<RwLock<EventList<'js>>> {
+        self.emitter.get_event_list()
+    }
+
+    fn on_event_changed(&mut self, event: EventKey<'js>, added: bool) -> Result<()> {
+        self.readable_stream_inner.on_event_changed(event, added)
+    }
+}
+
+impl<'js> ReadableStream<'js> for ReadStream<'js> {
+    fn inner_mut(&mut self) -> &mut ReadableStreamInner<'js> {
+        &mut self.readable_stream_inner
+    }
+
+    fn inner(&self) -> &ReadableStreamInner<'js> {
+        &self.readable_stream_inner
+    }
+}
+
+#[rquickjs::methods(rename_all = "camelCase")]
+impl<'js> ReadStream<'js> {
+    #[qjs(get, enumerable)]
+    pub fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    #[qjs(get, enumerable)]
+    pub fn bytes_read(&self) -> u64 {
+        self.bytes_read.get()
+    }
+
+    pub fn close(
+        this: This<Class<'js, Self>>,
+        ctx: Ctx<'js>,
+        callback: Opt<Function<'js>>,
+    ) -> Result<()> {
+        if let Some(cb) = callback.0 {
+            Self::add_event_listener_str(This(this.clone()), &ctx, "close", cb, false, true)?;
+        }
+        ReadableStream::destroy(this, Opt(None));
+        Ok(())
+    }
+}
+
+#[rquickjs::class]
+pub struct WriteStream<'js> {
+    emitter: EventEmitter<'js>,
+    writable_stream_inner: WritableStreamInner<'js>,
+    path: String,
+    bytes_written: Rc<Cell<u64>>,
+}
+
+unsafe impl<'js> JsLifetime<'js> for WriteStream<'js> {
+    type Changed<'to> = WriteStream<'to>;
+}
+
+impl<'js> Trace<'js> for WriteStream<'js> {
+    fn trace<'a>(&self, tracer: Tracer<'a, 'js>) {
+        self.emitter.trace(tracer);
+    }
+}
+
+impl<'js> Emitter<'js> for WriteStream<'js> {
+    fn get_event_list(&self) -> Arc<RwLock<EventList<'js>>> {
+        self.emitter.get_event_list()
+    }
+}
+
+impl<'js> WritableStream<'js> for WriteStream<'js> {
+    fn inner_mut(&mut self) -> &mut WritableStreamInner<'js> {
+        &mut self.writable_stream_inner
+    }
+
+    fn inner(&self) -> &WritableStreamInner<'js> {
+        &self.writable_stream_inner
+    }
+}
+
+#[rquickjs::methods(rename_all = "camelCase")]
+impl<'js> WriteStream<'js> {
+    #[qjs(get, enumerable)]
+    pub fn path(&self) -> String {
+        self.path.clone()
+    }
+
+    #[qjs(get, enumerable)]
+    pub fn bytes_written(&self) -> u64 {
+        self.bytes_written.get()
+    }
+
+    pub fn write(
+        this: This<Class<'js, Self>>,
+        ctx: Ctx<'js>,
+        value: Value<'js>,
+        encoding: Opt<Value<'js>>,
+        cb: Opt<Function<'js>>,
+    ) -> Result<bool> {
+        WritableStream::write(this, ctx, value, encoding, cb)
+    }
+
+    pub fn end(
+        this: This<Class<'js, Self>>,
+        ctx: Ctx<'js>,
+        chunk: Opt<Value<'js>>,
+        callback: Opt<Function<'js>>,
+    ) -> Result<Class<'js, Self>> {
+        let (chunk, callback) = match chunk.0 {
+            Some(chunk) if chunk.is_function() => (None, chunk.into_function()),
+            chunk => (chunk.filter(|chunk| !chunk.is_undefined()), callback.0),
+        };
+        if let Some(chunk) = chunk {
+            WritableStream::write(This(this.clone()), ctx.clone(), chunk, Opt(None), Opt(None))?;
+        }
+        if let Some(cb) = callback {
+            Self::add_event_listener_str(This(this.clone()), &ctx, "finish", cb, false, true)?;
+        }
+        Ok(WritableStream::end(this))
+    }
+
+    pub fn close(
+        this: This<Class<'js, Self>>,
+        ctx: Ctx<'js>,
+        callback: Opt<Function<'js>>,
+    ) -> Result<()> {
+        if let Some(cb) = callback.0 {
+            Self::add_event_listener_str(This(this.clone()), &ctx, "close", cb, false, true)?;
+        }
+        WritableStream::end(this);
+        Ok(())
+    }
+
+    pub fn destroy(this: This<Class<'js, Self>>, error: Opt<Value<'js>>) -> Class<'js, Self> {
+        WritableStream::destroy(this, error)
+    }
+}
+
+struct StreamOptions {
+    flags: Option<String>,
+    mode: Option<u32>,
+    encoding: Option<Encoder>,
+    start: u64,
+    end: Option<u64>,
+    high_water_mark: Option<usize>,
+}
+
+impl StreamOptions {
+    fn from_js<'js>(ctx: &Ctx<'js>, options: Opt<Either<String, Object<'js>>>) -> Result<Self> {
+        let mut stream_options = Self {
+            flags: None,
+            mode: None,
+            encoding: None,
+            start: 0,
+            end: None,
+            high_water_mark: None,
+        };
+        let encoding = match options.0 {
+            Some(Either::Left(encoding)) => Some(encoding),
+            Some(Either::Right(options)) => {
+                stream_options.flags = options.get_optional("flags")?;
+                stream_options.mode = options.get_optional("mode")?;
+                stream_options.start = options.get_optional("start")?.unwrap_or_default();
+                stream_options.end = options.get_optional("end")?;
+                stream_options.high_water_mark = options.get_optional("highWaterMark")?;
+                options.get_optional("encoding")?
+            },
+            None => None,
+        };
+        if let Some(encoding) = encoding.filter(|encoding| encoding != "buffer") {
+            stream_options.encoding = Some(Encoder::from_str(&encoding).or_throw(ctx)?);
+        }
+        Ok(stream_options)
+    }
+
+    // Invalid flags throw right away, the file is opened by the returned
+    // future
+    fn open(
+        &self,
+        ctx: &Ctx<'_>,
+        path: &str,
+        flags: &str,
+    ) -> Result<impl Future<Output = io::Result<File>>> {
+        let flags = self.flags.as_deref().unwrap_or(flags);
+        let options = OpenOptions::from(open_options(ctx, Some(flags), self.mode)?);
+        let (path, start) = (path.to_string(), self.start);
+        Ok(async move {
+            let mut file = options.open(path).await?;
+            if start > 0 {
+                file.seek(SeekFrom::Start(start)).await?;
+            }
+            Ok(file)
+        })
+    }
+}
+
+// Counts the bytes read from the file for `bytesRead`
+struct FileReader {
+    file: Take<File>,
+    bytes_read: Rc<Cell<u64>>,
+}
+
+impl AsyncRead for FileReader {
+    fn poll_read(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &mut ReadBuf<'_>,
+    ) -> Poll<io::Result<()>> {
+        let filled = buf.filled().len();
+        let result = Pin::new(&mut self.file).poll_read(cx, buf);
+        let read = (buf.filled().len() - filled) as u64;
+        self.bytes_read.set(self.bytes_read.get() + read);
+        result
+    }
+}
+
+// Counts the bytes written to the file for `bytesWritten`
+struct FileWriter {
+    file: File,
+    bytes_written: Rc<Cell<u64>>,
+}
+
+impl AsyncWrite for FileWriter {
+    fn poll_write(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        buf: &[u8],
+    ) -> Poll<io::Result<usize>> {
+        let result = Pin::new(&mut self.file).poll_write(cx, buf);
+        if let Poll::Ready(Ok(written)) = result {
+            self.bytes_written
+                .set(self.bytes_written.get() + written as u64);
+        }
+        result
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.file).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        Pin::new(&mut self.file).poll_shutdown(cx)
+    }
+}
+
+// Opens the file without blocking the caller. The stream emits 'open' and
+// 'ready' once the file is open and is then processed by `process`, or
+// emits the error of the file that could not be opened like any other error
+// of the stream.
+fn open_stream<'js, C, F>(
+    ctx: &Ctx<'js>,
+    this: Class<'js, C>,
+    path: String,
+    open: impl Future<Output = io::Result<File>> + 'js,
+    process: F,
+) where
+    C: Emitter<'js> + SteamEvents<'js>,
+    F: FnOnce(Class<'js, C>, &Ctx<'js>, File) -> Result<()> + 'js,
+{
+    let ctx2 = ctx.clone();
+    ctx.spawn_exit_simple(async move {
+        match open.await {
+            Ok(file) => {
+                C::emit_str(This(this.clone()), &ctx2, "open", vec![], false)?;
+                C::emit_str(This(this.clone()), &ctx2, "ready", vec![], false)?;
+                process(this, &ctx2, file)
+            },
+            Err(error) => {
+                let message = ["Can't open \"", &path, "\""].concat();
+                let error = io_error(&ctx2, &error, &message, Some("open"), Some(&path))?;
+                let error = Err::<(), _>(ctx2.throw(error.into_value()));
+                let had_error = error.emit_error("open", &ctx2, this.clone())?;
+                C::emit_close(this, &ctx2, had_error)
+            },
+        }
+    });
+}
+
+pub fn create_read_stream<'js>(
+    ctx: Ctx<'js>,
+    path: String,
+    options: Opt<Either<String, Object<'js>>>,
+) -> Result<Class<'js, ReadStream<'js>>> {
+    let options = StreamOptions::from_js(&ctx, options)?;
+    let len = match options.end {
+        Some(end) if end < options.start => {
+            return Err(Exception::throw_range(
+                &ctx,
+                "The value of \"start\" must be <= \"end\"",
+            ));
+        },
+        Some(end) => end - options.start + 1,
+        None => u64::MAX,
+    };
+    let open = options.open(&ctx, &path, "r")?;
+
+    let emitter = EventEmitter::new();
+    let mut readable_stream_inner = ReadableStreamInner::new(emitter.clone(), true);
+    readable_stream_inner.set_high_water_mark(
+        options
+            .high_water_mark
+            .unwrap_or(DEFAULT_READ_HIGH_WATER_MARK),
+    );
+    if let Some(encoding) = options.encoding {
+        readable_stream_inner.set_encoding(encoding);
+    }
+    let bytes_read = Rc::new(Cell::new(0));
+    let stream = Class::instance(
+        ctx.clone(),
+        ReadStream {
+            emitter,
+            readable_stream_inner,
+            path: path.clone(),
+            bytes_read: bytes_read.clone(),
+        },
+    )?;
+
+    open_stream(
+        &ctx,
+        stream.clone(),
+        path,
+        open,
+        move |stream, ctx, file| {
+            let reader = FileReader {
+                file: file.take(len),
+                bytes_read,
+            };
+            ReadStream::process(stream, ctx, reader).map(|_| ())
+        },
+    );
+    Ok(stream)
+}
+
+pub fn create_write_stream<'js>(
+    ctx: Ctx<'js>,
+    path: String,
+    options: Opt<Either<String, Object<'js>>>,
+) -> Result<Class<'js, WriteStream<'js>>> {
+    let options = StreamOptions::from_js(&ctx, options)?;
+    let open = options.open(&ctx, &path, "w")?;
+
+    let emitter = EventEmitter::new();
+    let mut writable_stream_inner = WritableStreamInner::new(emitter.clone(), true);
+    writable_stream_inner.set_high_water_mark(
+        options
+            .high_water_mark
+            .unwrap_or(DEFAULT_WRITE_HIGH_WATER_MARK),
+    );
+    if let Some(encoding) = options.encoding {
+        writable_stream_inner.set_default_encoding(encoding);
+    }
+    let bytes_written = Rc::new(Cell::new(0));
+    let stream = Class::instance(
+        ctx.clone(),
+        WriteStream {
+            emitter,
+            writable_stream_inner,
+            path: path.clone(),
+            bytes_written: bytes_written.clone(),
+        },
+    )?;
+
+    open_stream(
+        &ctx,
+        stream.clone(),
+        path,
+        open,
+        move |stream, ctx, file| {
+            let writer = FileWriter {
+                file,
+                bytes_written,
+            };
+            WriteStream::process(stream, ctx, writer).map(|_| ())
+        },
+    );
+    Ok(stream)
+}
@@ -0,0 +1,36 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use llrt_utils::fs::IoResultExt;
+use rquickjs::{function::Opt, Ctx, Result};
+use tokio::fs;
+
+pub async fn truncate(ctx: Ctx<'_>, path: String, len: Opt<u64>) -> Result<()> {
+    let error_message = truncate_error(&path);
+
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .open(&path)
+        .await
+        .or_throw_io(&ctx, &error_message, "open", &path)?;
+    file.set_len(len.0.unwrap_or_default()).await.or_throw_io(
+        &ctx,
+        &error_message,
+        "ftruncate",
+        &path,
+    )
+}
+
+pub fn truncate_sync(ctx: Ctx<'_>, path: String, len: Opt<u64>) -> Result<()> {
+    let error_message = truncate_error(&path);
+
+    let file = std::fs::OpenOptions::new()
+        .write(true)
+        .open(&path)
+        .or_throw_io(&ctx, &error_message, "open", &path)?;
+    file.set_len(len.0.unwrap_or_default())
+        .or_throw_io(&ctx, &error_message, "ftruncate", &path)
+}
+
+fn truncate_error(path: &str) -> String {
+    ["Can't truncate \"", path, "\""].concat()
+}
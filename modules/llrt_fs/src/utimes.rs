@@ -0,0 +1,104 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    fs::{File, FileTimes},
+    io,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+use llrt_utils::{fs::IoResultExt, result::ResultExt};
+use rquickjs::{function::This, Ctx, Exception, Function, Result, Value};
+
+pub async fn utimes<'js>(
+    ctx: Ctx<'js>,
+    path: String,
+    atime: Value<'js>,
+    mtime: Value<'js>,
+) -> Result<()> {
+    let atime = to_system_time(&ctx, &atime)?;
+    let mtime = to_system_time(&ctx, &mtime)?;
+    let error_message = utimes_error(&path);
+    let error_path = path.clone();
+
+    tokio::task::spawn_blocking(move || set_times(Path::new(&path), atime, mtime))
+        .await
+        .or_throw(&ctx)?
+        .or_throw_io(&ctx, &error_message, "utime", &error_path)
+}
+
+pub fn utimes_sync<'js>(
+    ctx: Ctx<'js>,
+    path: String,
+    atime: Value<'js>,
+    mtime: Value<'js>,
+) -> Result<()> {
+    let atime = to_system_time(&ctx, &atime)?;
+    let mtime = to_system_time(&ctx, &mtime)?;
+
+    set_times(Path::new(&path), atime, mtime).or_throw_io(
+        &ctx,
+        &utimes_error(&path),
+        "utime",
+        &path,
+    )
+}
+
+fn utimes_error(path: &str) -> String {
+    ["Can't set times of \"", path, "\""].concat()
+}
+
+pub(crate) fn set_times(path: &Path, atime: SystemTime, mtime: SystemTime) -> io::Result<()> {
+    let times = FileTimes::new().set_accessed(atime).set_modified(mtime);
+    open_for_times(path)?.set_times(times)
+}
+
+fn open_for_times(path: &Path) -> io::Result<File> {
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        const FILE_WRITE_ATTRIBUTES: u32 = 0x100;
+        // Needed to open directories
+        const FILE_FLAG_BACKUP_SEMANTICS: u32 = 0x02000000;
+        std::fs::OpenOptions::new()
+            .access_mode(FILE_WRITE_ATTRIBUTES)
+            .custom_flags(FILE_FLAG_BACKUP_SEMANTICS)
+            .open(path)
+    }
+    #[cfg(not(windows))]
+    {
+        File::open(path)
+    }
+}
+
+// Like Node.js, times are a Date, or seconds since the epoch as a number or a
+// numeric string
+fn to_system_time(ctx: &Ctx<'_>, value: &Value<'_>) -> Result<SystemTime> {
+    let seconds = if let Some(number) = value.as_number() {
+        Some(number)
+    } else if let Some(string) = value.as_string() {
+        string.to_string()?.trim().parse::<f64>().ok()
+    } else if let Some(object) = value.as_object() {
+        match object.get::<_, Option<Function>>("getTime")? {
+            Some(get_time) => get_time
+                .call::<_, f64>((This(object.clone()),))
+                .map(|millis| Some(millis / 1000.0))?,
+            None => None,
+        }
+    } else {
+        None
+    };
+
+    match seconds {
+        Some(seconds) if seconds.is_finite() && seconds >= 0.0 => {
+            Ok(SystemTime::UNIX_EPOCH + Duration::from_secs_f64(seconds))
+        },
+        Some(seconds) if seconds.is_finite() => {
+            Ok(SystemTime::UNIX_EPOCH - Duration::from_secs_f64(-seconds))
+        },
+        _ => Err(Exception::throw_type(
+            ctx,
+            "Time must be a Date, a number or a numeric string",
+        )),
+    }
+}
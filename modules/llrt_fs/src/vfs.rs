@@ -0,0 +1,128 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::OnceLock,
+};
+
+use llrt_path::normalize;
+
+static VIRTUAL_FS: OnceLock<VirtualFs> = OnceLock::new();
+
+// Read-only files embedded in the executable, served by the fs functions
+// before falling back to the real file system
+struct VirtualFs {
+    root: String,
+    files: HashMap<String, &'static [u8]>,
+    dirs: HashMap<String, BTreeSet<String>>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct VirtualEntry {
+    pub size: u64,
+    pub is_dir: bool,
+}
+
+/// Mounts `files`, keyed by absolute path, under `root`. Directories are
+/// derived from the file paths. Only the first mount is kept.
+pub fn mount<I>(root: &str, files: I)
+where
+    I: IntoIterator<Item = (&'static str, &'static [u8])>,
+{
+    let root = root.trim_end_matches('/').to_string();
+    let mut dirs: HashMap<String, BTreeSet<String>> = HashMap::new();
+    let mut file_map = HashMap::new();
+
+    for (path, bytes) in files {
+        if !path.starts_with(&root) {
+            continue;
+        }
+        let mut child = path;
+        while let Some(idx) = child.rfind('/') {
+            let parent = &child[..idx];
+            let name = &child[idx + 1..];
+            let is_new = dirs
+                .entry(parent.to_string())
+                .or_default()
+                .insert(name.to_string());
+            if !is_new || parent.len() <= root.len() {
+                break;
+            }
+            child = parent;
+        }
+        file_map.insert(path.to_string(), bytes);
+    }
+    dirs.entry(root.clone()).or_default();
+
+    let _ = VIRTUAL_FS.set(VirtualFs {
+        root,
+        files: file_map,
+        dirs,
+    });
+}
+
+fn lookup(path: &str) -> Option<(&'static VirtualFs, String)> {
+    let vfs = VIRTUAL_FS.get()?;
+    if !path.starts_with(&vfs.root) {
+        return None;
+    }
+    let path = normalize(path).replace('\\', "/");
+    let path = match path.trim_end_matches('/') {
+        "" => "/".to_string(),
+        trimmed => trimmed.to_string(),
+    };
+    Some((vfs, path))
+}
+
+pub(crate) fn read(path: &str) -> Option<&'static [u8]> {
+    let (vfs, path) = lookup(path)?;
+    vfs.files.get(&path).copied()
+}
+
+pub(crate) fn stat(path: &str) -> Option<VirtualEntry> {
+    let (vfs, path) = lookup(path)?;
+    if let Some(bytes) = vfs.files.get(&path) {
+        return Some(VirtualEntry {
+            size: bytes.len() as u64,
+            is_dir: false,
+        });
+    }
+    vfs.dirs.get(&path).map(|_| VirtualEntry {
+        size: 0,
+        is_dir: true,
+    })
+}
+
+/// Lists a virtual directory. Names are relative to `path` and sorted.
+pub(crate) fn read_dir(path: &str, recursive: bool) -> Option<Vec<(String, VirtualEntry)>> {
+    let (vfs, path) = lookup(path)?;
+    vfs.dirs.get(&path)?;
+
+    let mut entries = Vec::new();
+    let mut stack = vec![(path, String::new())];
+    while let Some((dir, prefix)) = stack.pop() {
+        let Some(children) = vfs.dirs.get(&dir) else {
+            continue;
+        };
+        for name in children {
+            let child_path = [dir.as_str(), "/", name].concat();
+            let relative = [prefix.as_str(), name].concat();
+            let entry = match vfs.files.get(&child_path) {
+                Some(bytes) => VirtualEntry {
+                    size: bytes.len() as u64,
+                    is_dir: false,
+                },
+                None => VirtualEntry {
+                    size: 0,
+                    is_dir: true,
+                },
+            };
+            if recursive && entry.is_dir {
+                stack.push((child_path, [relative.as_str(), "/"].concat()));
+            }
+            entries.push((relative, entry));
+        }
+    }
+    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    Some(entries)
+}
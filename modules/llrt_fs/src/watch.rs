@@ -0,0 +1,723 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+use std::{
+    cell::RefCell,
+    collections::{HashMap, VecDeque},
+    io, mem,
+    path::{Path, PathBuf},
+    rc::Rc,
+    sync::{
+        mpsc::{self as std_mpsc, TryRecvError},
+        Arc, RwLock,
+    },
+    thread,
+};
+
+use llrt_abort::AbortSignal;
+use llrt_buffer::Buffer;
+use llrt_context::CtxExtension;
+use llrt_events::{EmitError, Emitter, EventEmitter, EventList};
+use llrt_utils::{
+    fs::IoResultExt,
+    function::bind,
+    result::ResultExt,
+    watch::{Event, EventKind, Waker, Watcher},
+};
+use rquickjs::{
+    atom::PredefinedAtom,
+    class::{Trace, Tracer},
+    function::Constructor,
+    prelude::{Opt, This},
+    Class, Ctx, Function, IntoJs, JsLifetime, Object, Result, Undefined, Value,
+};
+use tokio::{
+    select,
+    sync::{
+        mpsc::{self, UnboundedReceiver, UnboundedSender},
+        Mutex, Notify,
+    },
+};
+
+type Events = io::Result<Vec<Event>>;
+
+// The events of a subscription, sent along with its id
+type Sender = UnboundedSender<(u64, Events)>;
+type Receiver = UnboundedReceiver<(u64, Events)>;
+
+enum Command {
+    Watch {
+        id: u64,
+        path: PathBuf,
+        recursive: bool,
+        sender: Sender,
+        reply: std_mpsc::Sender<io::Result<()>>,
+    },
+    Unwatch(u64),
+}
+
+// A path watched on the watch thread
+struct Subscriber {
+    path: PathBuf,
+    recursive: bool,
+    sender: Sender,
+}
+
+impl Subscriber {
+    fn matches(&self, path: &Path) -> bool {
+        path == self.path
+            || path.parent() == Some(&self.path)
+            || self.recursive && path.starts_with(&self.path)
+    }
+}
+
+// The thread waiting for the events of every path watched by a runtime. It
+// sleeps until there are events or commands and stops once every handle to
+// it is dropped, or when waiting fails.
+#[derive(Clone)]
+struct WatchThread {
+    commands: std_mpsc::Sender<Command>,
+    waker: Waker,
+}
+
+impl WatchThread {
+    fn spawn() -> io::Result<Self> {
+        let watcher = Watcher::new()?;
+        let waker = watcher.waker();
+        let (commands, receiver) = std_mpsc::channel();
+        thread::Builder::new()
+            .name("fs-watch".into())
+            .spawn(move || Self::run(watcher, receiver))?;
+        Ok(Self { commands, waker })
+    }
+
+    /// Returns the command when the thread stopped.
+    fn send(&self, command: Command) -> std::result::Result<(), Command> {
+        self.commands.send(command).map_err(|err| err.0)?;
+        self.waker.wake();
+        Ok(())
+    }
+
+    fn run(mut watcher: Watcher, commands: std_mpsc::Receiver<Command>) {
+        let mut subscribers = HashMap::new();
+        loop {
+            loop {
+                let command = if subscribers.is_empty() {
+                    match commands.recv() {
+                        Ok(command) => command,
+                        Err(_) => return,
+                    }
+                } else {
+                    match commands.try_recv() {
+                        Ok(command) => command,
+                        Err(TryRecvError::Empty) => break,
+                        Err(TryRecvError::Disconnected) => return,
+                    }
+                };
+                Self::apply(&mut watcher, &mut subscribers, command);
+            }
+
+            match watcher.wait(None) {
+                Ok(events) => Self::dispatch(&subscribers, events),
+                Err(err) => {
+                    for (id, subscriber) in &subscribers {
+                        let err = io::Error::new(err.kind(), err.to_string());
+                        let _ = subscriber.sender.send((*id, Err(err)));
+                    }
+                    return;
+                },
+            }
+        }
+    }
+
+    fn apply(watcher: &mut Watcher, subscribers: &mut HashMap<u64, Subscriber>, command: Command) {
+        match command {
+            Command::Watch {
+                id,
+                path,
+                recursive,
+                sender,
+                reply,
+            } => {
+                let result = watcher.watch(&path, recursive);
+                if result.is_ok() {
+                    let subscriber = Subscriber {
+                        path,
+                        recursive,
+                        sender,
+                    };
+                    subscribers.insert(id, subscriber);
+                }
+                let _ = reply.send(result);
+            },
+            Command::Unwatch(id) => {
+                if let Some(removed) = subscribers.remove(&id) {
+                    watcher.unwatch(&removed.path, removed.recursive);
+                }
+            },
+        }
+    }
+
+    fn dispatch(subscribers: &HashMap<u64, Subscriber>, events: Vec<Event>) {
+        for (id, subscriber) in subscribers {
+            let mut matched = Vec::new();
+            for event in &events {
+                // a file in a watched directory can be watched on its own too
+                if subscriber.matches(&event.path) && !matched.contains(event) {
+                    matched.push(event.clone());
+                }
+            }
+            if !matched.is_empty() {
+                let _ = subscriber.sender.send((*id, Ok(matched)));
+            }
+        }
+    }
+}
+
+// Watches a path on the watch thread until dropped
+struct Subscription {
+    id: u64,
+    thread: WatchThread,
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        let _ = self.thread.send(Command::Unwatch(self.id));
+    }
+}
+
+// Referenced watchers are kept alive like the handles of Node.js, the
+// others can be collected once they are no longer used
+enum WatcherRef<'js> {
+    Strong(Class<'js, FSWatcher<'js>>),
+    Weak(Object<'js>),
+}
+
+// The watch thread of a runtime and its `FSWatcher`s. Their events are
+// emitted by a single unref'd task, another one keeps the runtime alive
+// while any of them is referenced.
+struct Watches<'js> {
+    thread: Option<WatchThread>,
+    next_id: u64,
+    watchers: HashMap<u64, WatcherRef<'js>>,
+    sender: Sender,
+    // Taken when the task emitting the events is started
+    receiver: Option<Receiver>,
+    refs: usize,
+    keeping_alive: bool,
+    wake: Rc<Notify>,
+}
+
+unsafe impl<'js> JsLifetime<'js> for Watches<'js> {
+    type Changed<'to> = Watches<'to>;
+}
+
+fn with_watches<'js, R>(ctx: &Ctx<'js>, f: impl FnOnce(&mut Watches<'js>) -> R) -> Result<R> {
+    if ctx.userdata::<RefCell<Watches<'js>>>().is_none() {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        ctx.store_userdata(RefCell::new(Watches {
+            thread: None,
+            next_id: 0,
+            watchers: HashMap::new(),
+            sender,
+            receiver: Some(receiver),
+            refs: 0,
+            keeping_alive: false,
+            wake: Rc::new(Notify::new()),
+        }))?;
+    }
+    let watches = ctx
+        .userdata::<RefCell<Watches<'js>>>()
+        .or_throw_msg(ctx, "File watchers are not available")?;
+    let mut watches = watches.borrow_mut();
+    Ok(f(&mut watches))
+}
+
+fn next_id(ctx: &Ctx<'_>) -> Result<u64> {
+    with_watches(ctx, |watches| {
+        watches.next_id += 1;
+        watches.next_id
+    })
+}
+
+// Watches `root` on the watch thread of the runtime, which is started when
+// there is none or it stopped
+fn subscribe(
+    ctx: &Ctx<'_>,
+    id: u64,
+    path: &str,
+    root: &Path,
+    recursive: bool,
+    sender: Sender,
+) -> Result<Subscription> {
+    let (reply, replied) = std_mpsc::channel();
+    let mut command = Command::Watch {
+        id,
+        path: root.to_path_buf(),
+        recursive,
+        sender,
+        reply,
+    };
+    let thread = loop {
+        let thread = match with_watches(ctx, |watches| watches.thread.clone())? {
+            Some(thread) => thread,
+            None => {
+                let thread = WatchThread::spawn().or_throw(ctx)?;
+                with_watches(ctx, |watches| watches.thread = Some(thread.clone()))?;
+                thread
+            },
+        };
+        match thread.send(command) {
+            Ok(()) => break thread,
+            Err(unsent) => {
+                command = unsent;
+                with_watches(ctx, |watches| watches.thread = None)?;
+            },
+        }
+    };
+    replied
+        .recv()
+        .unwrap_or_else(|_| Err(io::ErrorKind::BrokenPipe.into()))
+        .or_throw_io(ctx, &watch_error(path), "watch", path)?;
+    Ok(Subscription { id, thread })
+}
+
+struct WatchOptions<'js> {
+    persistent: bool,
+    recursive: bool,
+    buffer: bool,
+    signal: Option<Class<'js, AbortSignal<'js>>>,
+}
+
+fn get_params_watch<'js>(options: Option<Value<'js>>) -> Result<WatchOptions<'js>> {
+    let mut params = WatchOptions {
+        persistent: true,
+        recursive: false,
+        buffer: false,
+        signal: None,
+    };
+
+    if let Some(encoding) = options.as_ref().and_then(|options| options.as_string()) {
+        params.buffer = encoding.to_string()? == "buffer";
+    } else if let Some(options) = options.as_ref().and_then(|options| options.as_object()) {
+        params.persistent = options
+            .get::<_, Option<bool>>("persistent")?
+            .unwrap_or(true);
+        params.recursive = options.get("recursive").unwrap_or_default();
+        params.buffer = options
+            .get::<_, Option<String>>("encoding")?
+            .is_some_and(|encoding| encoding == "buffer");
+        params.signal = options.get("signal")?;
+    }
+    Ok(params)
+}
+
+fn weak_ref<'js>(ctx: &Ctx<'js>, watcher: &Class<'js, FSWatcher<'js>>) -> Result<Object<'js>> {
+    let constructor: Constructor = ctx.globals().get("WeakRef")?;
+    constructor.construct((watcher.clone(),))
+}
+
+fn watch_error(path: &str) -> String {
+    ["Can't watch \"", path, "\""].concat()
+}
+
+fn event_type(kind: EventKind) -> &'static str {
+    match kind {
+        EventKind::Change => "change",
+        EventKind::Rename => "rename",
+    }
+}
+
+// The name reported for a change of `path` when watching `root`: the path
+// relative to the watched directory, or the name of the watched file
+fn filename<'js>(ctx: &Ctx<'js>, root: &Path, path: &Path, buffer: bool) -> Result<Value<'js>> {
+    let name = match path.strip_prefix(root) {
+        Ok(relative) if !relative.as_os_str().is_empty() => relative,
+        _ => path.file_name().map_or(path, Path::new),
+    };
+    let name = name.to_string_lossy().into_owned();
+    if buffer {
+        Buffer(name.into_bytes()).into_js(ctx)
+    } else {
+        name.into_js(ctx)
+    }
+}
+
+#[rquickjs::class]
+#[derive(rquickjs::JsLifetime)]
+pub struct FSWatcher<'js> {
+    emitter: EventEmitter<'js>,
+    path: String,
+    root: PathBuf,
+    buffer: bool,
+    subscription: Option<Subscription>,
+    referenced: bool,
+}
+
+impl<'js> Trace<'js> for FSWatcher<'js> {
+    fn trace<'a>(&self, tracer: Tracer<'a, 'js>) {
+        self.emitter.trace(tracer);
+    }
+}
+
+impl<'js> Emitter<'js> for FSWatcher<'js> {
+    fn get_event_list(&self) -> Arc<RwLock<EventList<'js>>> {
+        self.emitter.get_event_list()
+    }
+}
+
+#[rquickjs::methods]
+impl<'js> FSWatcher<'js> {
+    pub fn close(this: This<Class<'js, Self>>, ctx: Ctx<'js>) -> Result<()> {
+        let mut watcher = this.borrow_mut();
+        let Some(subscription) = watcher.subscription.take() else {
+            return Ok(());
+        };
+        let referenced = mem::take(&mut watcher.referenced);
+        drop(watcher);
+        with_watches(&ctx, |watches| watches.watchers.remove(&subscription.id))?;
+        if referenced {
+            Self::release(&ctx)?;
+        }
+        drop(subscription);
+        Self::emit_str(this, &ctx, "close", vec![], false)
+    }
+
+    #[qjs(rename = "ref")]
+    pub fn ref_(this: This<Class<'js, Self>>, ctx: Ctx<'js>) -> Result<Class<'js, Self>> {
+        Self::set_referenced(&ctx, &this.0, true)?;
+        Ok(this.0)
+    }
+
+    pub fn unref(this: This<Class<'js, Self>>, ctx: Ctx<'js>) -> Result<Class<'js, Self>> {
+        Self::set_referenced(&ctx, &this.0, false)?;
+        Ok(this.0)
+    }
+}
+
+impl<'js> FSWatcher<'js> {
+    fn set_referenced(ctx: &Ctx<'js>, this: &Class<'js, Self>, referenced: bool) -> Result<()> {
+        let mut watcher = this.borrow_mut();
+        let Some(id) = watcher
+            .subscription
+            .as_ref()
+            .map(|subscription| subscription.id)
+        else {
+            return Ok(());
+        };
+        if watcher.referenced == referenced {
+            return Ok(());
+        }
+        watcher.referenced = referenced;
+        drop(watcher);
+
+        let watcher_ref = if referenced {
+            WatcherRef::Strong(this.clone())
+        } else {
+            WatcherRef::Weak(weak_ref(ctx, this)?)
+        };
+        with_watches(ctx, |watches| watches.watchers.insert(id, watcher_ref))?;
+        if referenced {
+            Self::keep_alive(ctx)
+        } else {
+            Self::release(ctx)
+        }
+    }
+
+    // Keeps the runtime alive until no watcher is referenced
+    fn keep_alive(ctx: &Ctx<'js>) -> Result<()> {
+        let start = with_watches(ctx, |watches| {
+            watches.refs += 1;
+            !mem::replace(&mut watches.keeping_alive, true)
+        })?;
+        if !start {
+            return Ok(());
+        }
+        let ctx = ctx.clone();
+        let wake = with_watches(&ctx, |watches| watches.wake.clone())?;
+        ctx.clone().spawn_exit_simple(async move {
+            loop {
+                wake.notified().await;
+                let released = with_watches(&ctx, |watches| {
+                    watches.keeping_alive = watches.refs > 0;
+                    !watches.keeping_alive
+                })?;
+                if released {
+                    return Ok(());
+                }
+            }
+        });
+        Ok(())
+    }
+
+    fn release(ctx: &Ctx<'js>) -> Result<()> {
+        with_watches(ctx, |watches| {
+            watches.refs -= 1;
+            if watches.refs == 0 {
+                watches.wake.notify_one();
+            }
+        })
+    }
+
+    // Emits the events of every watcher of the runtime, referenced or not,
+    // whenever the runtime is running
+    fn listen(ctx: &Ctx<'js>) -> Result<()> {
+        let Some(mut receiver) = with_watches(ctx, |watches| watches.receiver.take())? else {
+            return Ok(());
+        };
+        let ctx = ctx.clone();
+        ctx.clone().spawn_unref(async move {
+            while let Some((id, events)) = receiver.recv().await {
+                Self::deliver(&ctx, id, events)?;
+            }
+            Ok(())
+        });
+        Ok(())
+    }
+
+    fn get(ctx: &Ctx<'js>, id: u64) -> Result<Option<Class<'js, Self>>> {
+        let watcher_ref = with_watches(ctx, |watches| match watches.watchers.get(&id) {
+            Some(WatcherRef::Strong(watcher)) => Ok(Some(watcher.clone())),
+            Some(WatcherRef::Weak(weak)) => Err(weak.clone()),
+            None => Ok(None),
+        })?;
+        let weak = match watcher_ref {
+            Ok(watcher) => return Ok(watcher),
+            Err(weak) => weak,
+        };
+        let deref: Function = weak.get("deref")?;
+        let watcher: Option<Class<'js, Self>> = deref.call((This(weak),))?;
+        if watcher.is_none() {
+            with_watches(ctx, |watches| watches.watchers.remove(&id))?;
+        }
+        Ok(watcher)
+    }
+
+    fn deliver(ctx: &Ctx<'js>, id: u64, events: Events) -> Result<()> {
+        // closed and collected watchers are no longer listed
+        let Some(this) = Self::get(ctx, id)? else {
+            return Ok(());
+        };
+        match events {
+            Ok(events) => Self::emit_events(ctx, &this, events),
+            Err(err) => {
+                let path = this.borrow().path.clone();
+                Err::<(), _>(err)
+                    .or_throw_msg(ctx, &watch_error(&path))
+                    .emit_error("fs.watch", ctx, this.clone())?;
+                Self::close(This(this), ctx.clone())
+            },
+        }
+    }
+
+    fn emit_events(ctx: &Ctx<'js>, this: &Class<'js, Self>, events: Vec<Event>) -> Result<()> {
+        for event in events {
+            let filename = {
+                let watcher = this.borrow();
+                if watcher.subscription.is_none() {
+                    break;
+                }
+                filename(ctx, &watcher.root, &event.path, watcher.buffer)?
+            };
+            let event_type = event_type(event.kind).into_js(ctx)?;
+            Self::emit_str(
+                This(this.clone()),
+                ctx,
+                "change",
+                vec![event_type, filename],
+                false,
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub fn watch<'js>(
+    ctx: Ctx<'js>,
+    filename: String,
+    options: Opt<Value<'js>>,
+    listener: Opt<Function<'js>>,
+) -> Result<Class<'js, FSWatcher<'js>>> {
+    let (options, listener) = match options.0 {
+        Some(options) if options.is_function() => (None, options.into_function()),
+        options => (options, listener.0),
+    };
+    let options = get_params_watch(options)?;
+
+    let root = std::path::absolute(&filename).or_throw(&ctx)?;
+    let id = next_id(&ctx)?;
+    let sender = with_watches(&ctx, |watches| watches.sender.clone())?;
+    let subscription = subscribe(&ctx, id, &filename, &root, options.recursive, sender)?;
+    let watcher = Class::instance(
+        ctx.clone(),
+        FSWatcher {
+            emitter: EventEmitter::new(),
+            path: filename,
+            root,
+            buffer: options.buffer,
+            subscription: Some(subscription),
+            referenced: false,
+        },
+    )?;
+    let weak = weak_ref(&ctx, &watcher)?;
+    with_watches(&ctx, |watches| {
+        watches.watchers.insert(id, WatcherRef::Weak(weak))
+    })?;
+    FSWatcher::listen(&ctx)?;
+
+    if let Some(listener) = listener {
+        FSWatcher::add_event_listener_str(
+            This(watcher.clone()),
+            &ctx,
+            "change",
+            listener,
+            false,
+            false,
+        )?;
+    }
+    if options.persistent {
+        FSWatcher::set_referenced(&ctx, &watcher, true)?;
+    }
+
+    if let Some(signal) = options.signal {
+        if signal.borrow().aborted {
+            FSWatcher::close(This(watcher.clone()), ctx)?;
+        } else {
+            let close: Function = watcher.get("close")?;
+            let close = bind(&close, vec![watcher.clone().into_js(&ctx)?])?;
+            AbortSignal::add_event_listener_str(This(signal), &ctx, "abort", close, false, true)?;
+        }
+    }
+
+    Ok(watcher)
+}
+
+struct PendingEvents {
+    receiver: Receiver,
+    events: VecDeque<Event>,
+}
+
+/// The async iterator returned by `fs/promises.watch`.
+#[rquickjs::class]
+#[derive(rquickjs::JsLifetime)]
+pub struct WatchIterator<'js> {
+    path: String,
+    root: PathBuf,
+    buffer: bool,
+    pending: Mutex<PendingEvents>,
+    subscription: RefCell<Option<Subscription>>,
+    signal: Option<Class<'js, AbortSignal<'js>>>,
+}
+
+impl<'js> Trace<'js> for WatchIterator<'js> {
+    fn trace<'a>(&self, tracer: Tracer<'a, 'js>) {
+        if let Some(signal) = &self.signal {
+            signal.trace(tracer);
+        }
+    }
+}
+
+#[rquickjs::methods]
+impl<'js> WatchIterator<'js> {
+    async fn next(&self, ctx: Ctx<'js>) -> Result<Object<'js>> {
+        let mut pending = self.pending.lock().await;
+        loop {
+            if let Some(reason) = self.abort_reason() {
+                self.close();
+                return Err(ctx.throw(reason));
+            }
+            if self.subscription.borrow().is_none() {
+                return iterator_result(&ctx, Undefined.into_value(ctx.clone()), true);
+            }
+            if let Some(event) = pending.events.pop_front() {
+                let value = Object::new(ctx.clone())?;
+                value.set("eventType", event_type(event.kind))?;
+                let filename = filename(&ctx, &self.root, &event.path, self.buffer)?;
+                value.set("filename", filename)?;
+                return iterator_result(&ctx, value.into_value(), false);
+            }
+
+            let received = match &self.signal {
+                Some(signal) => {
+                    let aborted = signal.borrow().sender.subscribe();
+                    select! {
+                        _ = aborted.recv() => continue,
+                        received = pending.receiver.recv() => received,
+                    }
+                },
+                None => pending.receiver.recv().await,
+            };
+            match received {
+                Some((_, Ok(events))) => pending.events.extend(events),
+                Some((_, Err(err))) => {
+                    self.close();
+                    return Err(err).or_throw_msg(&ctx, &watch_error(&self.path));
+                },
+                None => self.close(),
+            }
+        }
+    }
+
+    #[qjs(rename = "return")]
+    async fn return_(&self, ctx: Ctx<'js>) -> Result<Object<'js>> {
+        self.close();
+        iterator_result(&ctx, Undefined.into_value(ctx.clone()), true)
+    }
+
+    #[qjs(rename = PredefinedAtom::SymbolAsyncIterator)]
+    fn async_iterator(this: This<Class<'js, Self>>) -> Class<'js, Self> {
+        this.0
+    }
+}
+
+impl<'js> WatchIterator<'js> {
+    fn close(&self) {
+        self.subscription.borrow_mut().take();
+    }
+
+    fn abort_reason(&self) -> Option<Value<'js>> {
+        let signal = self.signal.as_ref()?.borrow();
+        if signal.aborted {
+            signal.reason()
+        } else {
+            None
+        }
+    }
+}
+
+pub(crate) fn iterator_result<'js>(
+    ctx: &Ctx<'js>,
+    value: Value<'js>,
+    done: bool,
+) -> Result<Object<'js>> {
+    let result = Object::new(ctx.clone())?;
+    result.set(PredefinedAtom::Value, value)?;
+    result.set(PredefinedAtom::Done, done)?;
+    Ok(result)
+}
+
+pub fn watch_promises<'js>(
+    ctx: Ctx<'js>,
+    filename: String,
+    options: Opt<Value<'js>>,
+) -> Result<Class<'js, WatchIterator<'js>>> {
+    let options = get_params_watch(options.0)?;
+    let root = std::path::absolute(&filename).or_throw(&ctx)?;
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let id = next_id(&ctx)?;
+    let subscription = subscribe(&ctx, id, &filename, &root, options.recursive, sender)?;
+
+    Class::instance(
+        ctx,
+        WatchIterator {
+            path: filename,
+            root,
+            buffer: options.buffer,
+            pending: Mutex::new(PendingEvents {
+                receiver,
+                events: VecDeque::new(),
+            }),
+            subscription: RefCell::new(Some(subscription)),
+            signal: options.signal,
+        },
+    )
+}
@@ -0,0 +1,292 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+use std::{
+    cell::RefCell,
+    collections::HashMap,
+    fs::Metadata,
+    mem,
+    path::PathBuf,
+    rc::Rc,
+    sync::{Arc, RwLock},
+    time::Duration,
+};
+
+use llrt_context::CtxExtension;
+use llrt_events::{Emitter, EventEmitter, EventList};
+use llrt_utils::result::ResultExt;
+use rquickjs::{
+    class::{Trace, Tracer},
+    prelude::{Opt, This},
+    Class, Ctx, Function, IntoJs, JsLifetime, Result, Value,
+};
+use tokio::{select, sync::Notify};
+
+use crate::stats::Stats;
+
+const DEFAULT_INTERVAL: u64 = 5007;
+
+/// Tracks whether a watcher is referenced. Only a referenced watcher runs the
+/// task polling its file, which keeps the event loop alive.
+struct Activity {
+    referenced: bool,
+    running: bool,
+    closed: bool,
+    wake: Rc<Notify>,
+}
+
+impl Activity {
+    fn new() -> Self {
+        Self {
+            referenced: false,
+            running: false,
+            closed: false,
+            wake: Rc::new(Notify::new()),
+        }
+    }
+
+    /// Returns whether the task has to be started.
+    fn set_referenced(&mut self, referenced: bool) -> bool {
+        if self.closed {
+            return false;
+        }
+        self.referenced = referenced;
+        if referenced && !self.running {
+            self.running = true;
+            return true;
+        }
+        if !referenced && self.running {
+            self.wake.notify_one();
+        }
+        false
+    }
+
+    /// Returns `false` when it was already closed.
+    fn close(&mut self) -> bool {
+        if self.closed {
+            return false;
+        }
+        self.closed = true;
+        self.wake.notify_one();
+        true
+    }
+
+    /// Called by the task after it woke up, returns whether it keeps running.
+    fn keep_running(&mut self) -> bool {
+        self.running = self.referenced && !self.closed;
+        self.running
+    }
+
+    /// Notified when the task has to check [`Activity::keep_running`].
+    fn wake(&self) -> Rc<Notify> {
+        self.wake.clone()
+    }
+}
+
+#[rquickjs::class]
+#[derive(rquickjs::JsLifetime)]
+pub struct StatWatcher<'js> {
+    emitter: EventEmitter<'js>,
+    path: String,
+    interval: Duration,
+    previous: Option<Metadata>,
+    activity: Activity,
+}
+
+impl<'js> Trace<'js> for StatWatcher<'js> {
+    fn trace<'a>(&self, tracer: Tracer<'a, 'js>) {
+        self.emitter.trace(tracer);
+    }
+}
+
+impl<'js> Emitter<'js> for StatWatcher<'js> {
+    fn get_event_list(&self) -> Arc<RwLock<EventList<'js>>> {
+        self.emitter.get_event_list()
+    }
+}
+
+#[rquickjs::methods]
+impl<'js> StatWatcher<'js> {
+    #[qjs(rename = "ref")]
+    pub fn ref_(this: This<Class<'js, Self>>, ctx: Ctx<'js>) -> Class<'js, Self> {
+        Self::set_referenced(&ctx, this.0.clone(), true);
+        this.0
+    }
+
+    pub fn unref(this: This<Class<'js, Self>>, ctx: Ctx<'js>) -> Class<'js, Self> {
+        Self::set_referenced(&ctx, this.0.clone(), false);
+        this.0
+    }
+}
+
+impl<'js> StatWatcher<'js> {
+    fn set_referenced(ctx: &Ctx<'js>, this: Class<'js, Self>, referenced: bool) {
+        if this.borrow_mut().activity.set_referenced(referenced) {
+            Self::poll(ctx, this);
+        }
+    }
+
+    // Stats the file every interval until the watcher is stopped or
+    // unreferenced, and emits "change" when it differs from the last time
+    fn poll(ctx: &Ctx<'js>, this: Class<'js, Self>) {
+        let ctx = ctx.clone();
+        ctx.clone().spawn_exit_simple(async move {
+            let (path, interval, wake) = {
+                let watcher = this.borrow();
+                let wake = watcher.activity.wake();
+                (watcher.path.clone(), watcher.interval, wake)
+            };
+
+            loop {
+                select! {
+                    _ = tokio::time::sleep(interval) => {},
+                    _ = wake.notified() => {},
+                }
+                if !this.borrow_mut().activity.keep_running() {
+                    return Ok(());
+                }
+
+                let current = tokio::fs::metadata(&path).await.ok();
+                let previous = mem::replace(&mut this.borrow_mut().previous, current.clone());
+                if !changed(previous.as_ref(), current.as_ref()) {
+                    continue;
+                }
+                let current = to_stats(current).into_js(&ctx)?;
+                let previous = to_stats(previous).into_js(&ctx)?;
+                Self::emit_str(
+                    This(this.clone()),
+                    &ctx,
+                    "change",
+                    vec![current, previous],
+                    false,
+                )?;
+            }
+        });
+    }
+}
+
+fn to_stats(metadata: Option<Metadata>) -> Stats {
+    metadata.map_or_else(Stats::missing, Stats::new)
+}
+
+// A file missing at both polls did not change
+fn changed(previous: Option<&Metadata>, current: Option<&Metadata>) -> bool {
+    let (previous, current) = match (previous, current) {
+        (Some(previous), Some(current)) => (previous, current),
+        (previous, current) => return previous.is_some() != current.is_some(),
+    };
+    let changed = previous.modified().ok() != current.modified().ok()
+        || previous.len() != current.len()
+        || previous.permissions() != current.permissions();
+    #[cfg(unix)]
+    let changed = changed
+        || previous.ino() != current.ino()
+        || previous.ctime() != current.ctime()
+        || previous.ctime_nsec() != current.ctime_nsec();
+    changed
+}
+
+// The watchers of `watchFile`, by absolute path
+struct StatWatchers<'js> {
+    watchers: HashMap<PathBuf, Class<'js, StatWatcher<'js>>>,
+}
+
+unsafe impl<'js> JsLifetime<'js> for StatWatchers<'js> {
+    type Changed<'to> = StatWatchers<'to>;
+}
+
+fn with_watchers<'js, R>(
+    ctx: &Ctx<'js>,
+    f: impl FnOnce(&mut HashMap<PathBuf, Class<'js, StatWatcher<'js>>>) -> R,
+) -> Result<R> {
+    if ctx.userdata::<RefCell<StatWatchers<'js>>>().is_none() {
+        ctx.store_userdata(RefCell::new(StatWatchers {
+            watchers: HashMap::new(),
+        }))?;
+    }
+    let watchers = ctx
+        .userdata::<RefCell<StatWatchers<'js>>>()
+        .or_throw_msg(ctx, "File watchers are not available")?;
+    let mut watchers = watchers.borrow_mut();
+    Ok(f(&mut watchers.watchers))
+}
+
+pub fn watch_file<'js>(
+    ctx: Ctx<'js>,
+    filename: String,
+    options: Opt<Value<'js>>,
+    listener: Opt<Function<'js>>,
+) -> Result<Class<'js, StatWatcher<'js>>> {
+    let (options, listener) = match options.0 {
+        Some(options) if options.is_function() => (None, options.into_function()),
+        options => (
+            options.and_then(|options| options.into_object()),
+            listener.0,
+        ),
+    };
+    let listener = listener.or_throw_type(&ctx, "Listener parameter is not a function")?;
+
+    let mut persistent = true;
+    let mut interval = DEFAULT_INTERVAL;
+    if let Some(options) = options {
+        persistent = options
+            .get::<_, Option<bool>>("persistent")?
+            .unwrap_or(true);
+        interval = options
+            .get::<_, Option<u64>>("interval")?
+            .unwrap_or(DEFAULT_INTERVAL);
+    }
+
+    let key = std::path::absolute(&filename).or_throw(&ctx)?;
+    let watcher = match with_watchers(&ctx, |watchers| watchers.get(&key).cloned())? {
+        Some(watcher) => watcher,
+        None => {
+            let watcher = Class::instance(
+                ctx.clone(),
+                StatWatcher {
+                    emitter: EventEmitter::new(),
+                    previous: std::fs::metadata(&filename).ok(),
+                    path: filename,
+                    interval: Duration::from_millis(interval),
+                    activity: Activity::new(),
+                },
+            )?;
+            with_watchers(&ctx, |watchers| watchers.insert(key, watcher.clone()))?;
+            StatWatcher::set_referenced(&ctx, watcher.clone(), persistent);
+            watcher
+        },
+    };
+
+    StatWatcher::add_event_listener_str(
+        This(watcher.clone()),
+        &ctx,
+        "change",
+        listener,
+        false,
+        false,
+    )?;
+    Ok(watcher)
+}
+
+pub fn unwatch_file<'js>(
+    ctx: Ctx<'js>,
+    filename: String,
+    listener: Opt<Function<'js>>,
+) -> Result<()> {
+    let key = std::path::absolute(&filename).or_throw(&ctx)?;
+    let Some(watcher) = with_watchers(&ctx, |watchers| watchers.get(&key).cloned())? else {
+        return Ok(());
+    };
+
+    if let Some(listener) = listener.0 {
+        StatWatcher::remove_event_listener_str(This(watcher.clone()), &ctx, "change", listener)?;
+        if watcher.borrow().has_listener_str("change") {
+            return Ok(());
+        }
+    }
+
+    with_watchers(&ctx, |watchers| watchers.remove(&key))?;
+    watcher.borrow_mut().activity.close();
+    StatWatcher::emit_str(This(watcher), &ctx, "stop", vec![], false)
+}
@@ -1,7 +1,7 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
 
-use llrt_utils::{bytes::ObjectBytes, result::ResultExt};
+use llrt_utils::{bytes::ObjectBytes, fs::IoResultExt};
 use rquickjs::{Ctx, Result, Value};
 use tokio::fs;
 use tokio::io::AsyncWriteExt;
@@ -9,22 +9,75 @@ use tokio::io::AsyncWriteExt;
 pub async fn write_file<'js>(ctx: Ctx<'js>, path: String, data: Value<'js>) -> Result<()> {
     let write_error_message = &["Can't write file \"", &path, "\""].concat();
 
-    let mut file = fs::File::create(&path)
-        .await
-        .or_throw_msg(&ctx, write_error_message)?;
+    let mut file =
+        fs::File::create(&path)
+            .await
+            .or_throw_io(&ctx, write_error_message, "open", &path)?;
 
     let bytes = ObjectBytes::from(&ctx, &data)?;
-    file.write_all(bytes.as_bytes(&ctx)?)
+    file.write_all(bytes.as_bytes(&ctx)?).await.or_throw_io(
+        &ctx,
+        write_error_message,
+        "write",
+        &path,
+    )?;
+    file.flush()
         .await
-        .or_throw_msg(&ctx, write_error_message)?;
-    file.flush().await.or_throw_msg(&ctx, write_error_message)?;
+        .or_throw_io(&ctx, write_error_message, "write", &path)?;
 
     Ok(())
 }
 
 pub fn write_file_sync<'js>(ctx: Ctx<'js>, path: String, bytes: ObjectBytes<'js>) -> Result<()> {
-    std::fs::write(&path, bytes.as_bytes(&ctx)?)
-        .or_throw_msg(&ctx, &["Can't write \"{}\"", &path].concat())?;
+    std::fs::write(&path, bytes.as_bytes(&ctx)?).or_throw_io(
+        &ctx,
+        &["Can't write \"{}\"", &path].concat(),
+        "open",
+        &path,
+    )?;
+
+    Ok(())
+}
+
+pub async fn append_file<'js>(ctx: Ctx<'js>, path: String, data: Value<'js>) -> Result<()> {
+    let append_error_message = &["Can't append to file \"", &path, "\""].concat();
+
+    let mut file = fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(&path)
+        .await
+        .or_throw_io(&ctx, append_error_message, "open", &path)?;
+
+    let bytes = ObjectBytes::from(&ctx, &data)?;
+    file.write_all(bytes.as_bytes(&ctx)?).await.or_throw_io(
+        &ctx,
+        append_error_message,
+        "write",
+        &path,
+    )?;
+    file.flush()
+        .await
+        .or_throw_io(&ctx, append_error_message, "write", &path)?;
+
+    Ok(())
+}
+
+pub fn append_file_sync<'js>(ctx: Ctx<'js>, path: String, bytes: ObjectBytes<'js>) -> Result<()> {
+    let append_error_message = &["Can't append to file \"", &path, "\""].concat();
+
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .create(true)
+        .open(&path)
+        .or_throw_io(&ctx, append_error_message, "open", &path)?;
+
+    std::io::Write::write_all(&mut file, bytes.as_bytes(&ctx)?).or_throw_io(
+        &ctx,
+        append_error_message,
+        "write",
+        &path,
+    )?;
 
     Ok(())
 }
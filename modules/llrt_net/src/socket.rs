@@ -121,9 +121,10 @@ impl<'js> Socket<'js> {
         this: This<Class<'js, Self>>,
         ctx: Ctx<'js>,
         value: Value<'js>,
+        encoding: Opt<Value<'js>>,
         cb: Opt<Function<'js>>,
     ) -> Result<()> {
-        WritableStream::write_flushed(this, ctx.clone(), value, cb)?;
+        WritableStream::write_flushed(this, ctx.clone(), value, encoding, cb)?;
         Ok(())
     }
 
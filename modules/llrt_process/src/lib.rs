@@ -2,9 +2,12 @@
 // SPDX-License-Identifier: Apache-2.0
 use std::collections::HashMap;
 use std::env;
+use std::fs;
+use std::sync::OnceLock;
 
 pub use llrt_utils::sysinfo;
 use llrt_utils::{
+    dotenv,
     module::{export_default, ModuleInfo},
     object::Proxy,
     result::ResultExt,
@@ -15,10 +18,19 @@ use rquickjs::{
     convert::Coerced,
     module::{Declarations, Exports, ModuleDef},
     object::Property,
-    prelude::Func,
+    prelude::{Func, Opt},
     Array, BigInt, Ctx, Function, IntoJs, Object, Result, Value,
 };
 
+static ARGS: OnceLock<(Vec<String>, Vec<String>)> = OnceLock::new();
+
+/// Sets `process.argv` and `process.execArgv` from a command line parsed by
+/// the runtime. Must be called before `init`; only the first call has an
+/// effect. Without it `process.argv` is the command line of the process.
+pub fn set_args(argv: Vec<String>, exec_argv: Vec<String>) {
+    let _ = ARGS.set((argv, exec_argv));
+}
+
 fn cwd(ctx: Ctx<'_>) -> Result<String> {
     env::current_dir()
         .or_throw(&ctx)
@@ -54,6 +66,23 @@ fn exit(code: i32) {
     std::process::exit(code)
 }
 
+// Variables that are set already in `process.env` keep their value. The
+// environment of the process is left alone, as other threads of the runtime
+// may read it meanwhile.
+fn load_env_file(ctx: Ctx<'_>, path: Opt<String>) -> Result<()> {
+    let path = path.0.unwrap_or_else(|| ".env".into());
+    let source = fs::read_to_string(&path).or_throw_msg(&ctx, &path)?;
+    let process: Object = ctx.globals().get("process")?;
+    let env: Object = process.get("env")?;
+    let entries = dotenv::parse_with(&source, |name| env.get(name).ok().flatten());
+    for (key, value) in entries {
+        if !env.contains_key(key.as_str())? {
+            env.set(key, value)?;
+        }
+    }
+    Ok(())
+}
+
 fn env_proxy_setter<'js>(
     target: Object<'js>,
     prop: Value<'js>,
@@ -118,14 +147,19 @@ pub fn init(ctx: &Ctx<'_>) -> Result<()> {
     release.prop("name", Property::from("llrt").enumerable())?;
 
     let env_map: HashMap<String, String> = env::vars().collect();
-    let mut args: Vec<String> = env::args().collect();
-
-    if let Some(arg) = args.get(1) {
-        if arg == "-e" || arg == "--eval" {
-            args.remove(1);
-            args.remove(1);
-        }
-    }
+    let (args, exec_args) = match ARGS.get() {
+        Some((argv, exec_argv)) => (argv.clone(), exec_argv.clone()),
+        None => {
+            let mut args: Vec<String> = env::args().collect();
+            if let Some(arg) = args.get(1) {
+                if arg == "-e" || arg == "--eval" {
+                    args.remove(1);
+                    args.remove(1);
+                }
+            }
+            (args, Vec::new())
+        },
+    };
 
     let env_obj = env_map.into_js(ctx)?;
 
@@ -137,6 +171,7 @@ pub fn init(ctx: &Ctx<'_>) -> Result<()> {
     process.set("argv0", args.clone().first().cloned().unwrap_or_default())?;
     process.set("id", std::process::id())?;
     process.set("argv", args)?;
+    process.set("execArgv", exec_args)?;
     process.set("platform", PLATFORM)?;
     process.set("arch", ARCH)?;
     process.set("hrtime", hr_time)?;
@@ -144,6 +179,7 @@ pub fn init(ctx: &Ctx<'_>) -> Result<()> {
     process.set("version", VERSION)?;
     process.set("versions", process_versions)?;
     process.set("exit", Func::from(exit))?;
+    process.set("loadEnvFile", Func::from(load_env_file))?;
 
     #[cfg(unix)]
     {
@@ -171,6 +207,7 @@ impl ModuleDef for ProcessModule {
         declare.declare("argv0")?;
         declare.declare("id")?;
         declare.declare("argv")?;
+        declare.declare("execArgv")?;
         declare.declare("platform")?;
         declare.declare("arch")?;
         declare.declare("hrtime")?;
@@ -178,6 +215,7 @@ impl ModuleDef for ProcessModule {
         declare.declare("version")?;
         declare.declare("versions")?;
         declare.declare("exit")?;
+        declare.declare("loadEnvFile")?;
 
         #[cfg(unix)]
         {
@@ -1,15 +1,21 @@
 // Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
 // SPDX-License-Identifier: Apache-2.0
-use std::sync::{atomic::AtomicUsize, Arc, RwLock};
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc, RwLock,
+};
 
 use llrt_buffer::Buffer;
 use llrt_context::CtxExtension;
+use llrt_encoding::Encoder;
 use llrt_events::{EmitError, Emitter, EventEmitter, EventKey, EventList};
-use llrt_utils::{bytearray_buffer::BytearrayBuffer, result::ResultExt};
+use llrt_utils::{
+    bytearray_buffer::BytearrayBuffer, function::bind, object::ObjectExt, result::ResultExt,
+};
 use rquickjs::{
     class::{Trace, Tracer},
     prelude::{Func, Opt, This},
-    Class, Ctx, Error, IntoJs, JsLifetime, Null, Result, Value,
+    Class, Ctx, Error, Function, IntoJs, JsLifetime, Null, Object, Result, Undefined, Value,
 };
 use tokio::{
     io::{AsyncRead, AsyncReadExt, BufReader},
@@ -41,6 +47,10 @@ pub struct ReadableStreamInner<'js> {
     high_water_mark: AtomicUsize,
     listener: Option<&'static str>,
     data_listener_attached_tx: Sender<()>,
+    encoding: Option<Encoder>,
+    undecoded: Vec<u8>,
+    paused: bool,
+    resume_tx: Sender<()>,
 }
 
 impl<'js> Trace<'js> for ReadableStreamInner<'js> {
@@ -81,6 +91,7 @@ impl<'js> ReadableStreamInner<'js> {
     pub fn new(emitter: EventEmitter<'js>, emit_close: bool) -> Self {
         let (destroy_tx, _) = broadcast::channel::<Option<Value<'js>>>(1);
         let (listener_attached_tx, _) = broadcast::channel::<()>(1);
+        let (resume_tx, _) = broadcast::channel::<()>(1);
         Self {
             emitter,
             destroy_tx,
@@ -93,10 +104,82 @@ impl<'js> ReadableStreamInner<'js> {
             is_destroyed: false,
             emit_close,
             errored: false,
+            encoding: None,
+            undecoded: Vec::new(),
+            paused: false,
+            resume_tx,
+        }
+    }
+
+    /// Sets the size of the chunks read at once, must be called before
+    /// processing starts
+    pub fn set_high_water_mark(&mut self, high_water_mark: usize) {
+        *self.high_water_mark.get_mut() = high_water_mark;
+        self.buffer = BytearrayBuffer::new(high_water_mark);
+    }
+
+    /// Makes the stream pass strings in `encoding` instead of buffers
+    pub fn set_encoding(&mut self, encoding: Encoder) {
+        self.encoding = Some(encoding);
+    }
+
+    // Bytes of a character split across chunks are kept for the next one
+    fn chunk_to_js(&mut self, ctx: &Ctx<'js>, mut bytes: Vec<u8>) -> Result<Option<Value<'js>>> {
+        let Some(encoding) = &self.encoding else {
+            return Buffer(bytes).into_js(ctx).map(Some);
+        };
+        if !self.undecoded.is_empty() {
+            bytes.splice(0..0, self.undecoded.drain(..));
+        }
+        let complete = complete_len(encoding, &bytes);
+        self.undecoded.extend_from_slice(&bytes[complete..]);
+        bytes.truncate(complete);
+        if bytes.is_empty() {
+            return Ok(None);
+        }
+        encoding
+            .encode_to_string(&bytes, true)
+            .or_throw(ctx)?
+            .into_js(ctx)
+            .map(Some)
+    }
+
+    fn undecoded_to_js(&mut self, ctx: &Ctx<'js>) -> Result<Option<Value<'js>>> {
+        match &self.encoding {
+            Some(encoding) if !self.undecoded.is_empty() => {
+                let bytes = std::mem::take(&mut self.undecoded);
+                let string = encoding.encode_to_string(&bytes, true).or_throw(ctx)?;
+                string.into_js(ctx).map(Some)
+            },
+            _ => Ok(None),
         }
     }
 }
 
+// Length of the start of `bytes` that does not end in the middle of a character
+fn complete_len(encoding: &Encoder, bytes: &[u8]) -> usize {
+    let len = bytes.len();
+    match encoding {
+        Encoder::Utf8 => {
+            // A character takes at most 4 bytes, find where the last one starts
+            for (index, byte) in bytes.iter().enumerate().rev().take(4) {
+                let width = match byte {
+                    0x00..=0x7f => 1,
+                    0xc0..=0xdf => 2,
+                    0xe0..=0xef => 3,
+                    0xf0..=0xf7 => 4,
+                    _ => continue,
+                };
+                return if index + width > len { index } else { len };
+            }
+            len
+        },
+        Encoder::Utf16le | Encoder::Utf16be => len - len % 2,
+        Encoder::Base64 => len - len % 3,
+        Encoder::Hex | Encoder::Windows1252 => len,
+    }
+}
+
 #[rquickjs::class]
 #[derive(rquickjs::class::Trace)]
 pub struct DefaultReadableStream<'js> {
@@ -158,6 +241,16 @@ where
 
         proto.set("destroy", Func::from(Self::destroy))?;
 
+        proto.set("pause", Func::from(Self::pause))?;
+
+        proto.set("resume", Func::from(Self::resume))?;
+
+        proto.set("isPaused", Func::from(Self::is_paused))?;
+
+        proto.set("setEncoding", Func::from(Self::set_encoding))?;
+
+        proto.set("pipe", Func::from(Self::pipe))?;
+
         Ok(())
     }
 
@@ -170,9 +263,91 @@ where
         this.0
     }
 
+    fn pause(this: This<Class<'js, Self>>) -> Class<'js, Self> {
+        this.borrow_mut().inner_mut().paused = true;
+        this.0
+    }
+
+    fn resume(this: This<Class<'js, Self>>) -> Class<'js, Self> {
+        let mut borrow = this.borrow_mut();
+        let inner = borrow.inner_mut();
+        inner.paused = false;
+        let _ = inner.resume_tx.send(());
+        drop(borrow);
+        this.0
+    }
+
+    fn is_paused(this: This<Class<'js, Self>>) -> bool {
+        this.borrow().inner().paused
+    }
+
+    fn set_encoding(
+        this: This<Class<'js, Self>>,
+        ctx: Ctx<'js>,
+        encoding: Opt<String>,
+    ) -> Result<Class<'js, Self>> {
+        let encoding = Encoder::from_optional_str(encoding.0.as_deref()).or_throw(&ctx)?;
+        this.borrow_mut().inner_mut().set_encoding(encoding);
+        Ok(this.0)
+    }
+
+    /// Writes all data to `destination`, pausing while it is above its high
+    /// water mark, and ends it at the end of this stream unless `end` is false
+    fn pipe(
+        this: This<Class<'js, Self>>,
+        ctx: Ctx<'js>,
+        destination: Object<'js>,
+        options: Opt<Object<'js>>,
+    ) -> Result<Object<'js>> {
+        let mut end = true;
+        if let Some(options) = options.0 {
+            end = options.get_optional("end")?.unwrap_or(true);
+        }
+
+        let on_data = Function::new(ctx.clone(), Self::pipe_data)?;
+        let on_data = bind(
+            &on_data,
+            vec![
+                Undefined.into_js(&ctx)?,
+                this.0.clone().into_value(),
+                destination.clone().into_value(),
+            ],
+        )?;
+        Self::add_event_listener_str(This(this.0.clone()), &ctx, "data", on_data, false, false)?;
+
+        if end {
+            let end: Function = destination.get("end")?;
+            let end = bind(&end, vec![destination.clone().into_value()])?;
+            Self::add_event_listener_str(This(this.0), &ctx, "end", end, false, true)?;
+        }
+
+        Ok(destination)
+    }
+
+    fn pipe_data(
+        source: Class<'js, Self>,
+        destination: Object<'js>,
+        chunk: Value<'js>,
+    ) -> Result<()> {
+        let write: Function = destination.get("write")?;
+        let written: Value = write.call((This(destination.clone()), chunk))?;
+        if written.as_bool() != Some(false) {
+            return Ok(());
+        }
+
+        let resume: Function = source.get("resume")?;
+        let resume = bind(&resume, vec![source.clone().into_value()])?;
+        Self::pause(This(source));
+        let once: Function = destination.get("once")?;
+        once.call((This(destination), "drain", resume))
+    }
+
     fn read(this: This<Class<'js, Self>>, ctx: Ctx<'js>, size: Opt<usize>) -> Result<Value<'js>> {
-        if let Some(data) = this.borrow().inner().buffer.read(size.0) {
-            return Buffer(data).into_js(&ctx);
+        let data = this.borrow().inner().buffer.read(size.0);
+        if let Some(data) = data {
+            if let Some(chunk) = this.borrow_mut().inner_mut().chunk_to_js(&ctx, data)? {
+                return Ok(chunk);
+            }
         }
 
         Ok(Null.into_value(ctx))
@@ -194,7 +369,11 @@ where
                         if buffer.is_empty() {
                             return Ok(());
                         }
-                        vec![Buffer(buffer).into_js(ctx)?]
+                        let chunk = this.borrow_mut().inner_mut().chunk_to_js(ctx, buffer)?;
+                        match chunk {
+                            Some(chunk) => vec![chunk],
+                            None => return Ok(()),
+                        }
                     },
                     "readable" => {
                         vec![]
@@ -245,18 +424,33 @@ where
             let emit_close = inner.emit_close;
 
             let mut listener_attached_tx = inner.data_listener_attached_tx.subscribe();
+            let mut resume_rx = inner.resume_tx.subscribe();
             let ba_buffer = inner.buffer.clone();
+            let high_water_mark = inner.high_water_mark.load(Ordering::Relaxed);
             let mut has_data = false;
             drop(borrow);
 
             let read_function = async move {
-                let mut reader: BufReader<T> = BufReader::new(readable);
-                let mut buffer = Vec::<u8>::with_capacity(DEFAULT_BUFFER_SIZE);
+                let mut reader: BufReader<T> = BufReader::with_capacity(high_water_mark, readable);
+                let mut buffer = Vec::<u8>::with_capacity(high_water_mark);
                 let mut last_state = ReadableState::Init;
                 let mut error_value = None;
 
                 if !is_ended && !is_destroyed {
                     loop {
+                        while this2.borrow().inner().paused {
+                            tokio::select! {
+                                _ = resume_rx.recv() => {},
+                                error = destroy_rx.recv() => {
+                                    set_destroyed_and_error(&mut is_destroyed, &mut error_value, error);
+                                    break;
+                                }
+                            }
+                        }
+                        if is_destroyed {
+                            break;
+                        }
+
                         tokio::select! {
                             result = reader.read_buf(&mut buffer) => {
                                 let bytes_read = result.or_throw(&ctx3)?;
@@ -280,13 +474,16 @@ where
                                             break;
                                         }
 
-                                        Self::emit_str(
-                                            This(this2.clone()),
-                                            &ctx3,
-                                            "data",
-                                            vec![Buffer(buffer.clone()).into_js(&ctx3)?],
-                                            false
-                                        )?;
+                                        let chunk = this2.borrow_mut().inner_mut().chunk_to_js(&ctx3, buffer.clone())?;
+                                        if let Some(chunk) = chunk {
+                                            Self::emit_str(
+                                                This(this2.clone()),
+                                                &ctx3,
+                                                "data",
+                                                vec![chunk],
+                                                false
+                                            )?;
+                                        }
                                         buffer.clear();
                                     },
                                     ReadableState::Paused => {
@@ -350,6 +547,10 @@ where
 
                 if !is_destroyed {
                     on_end();
+                    let chunk = this2.borrow_mut().inner_mut().undecoded_to_js(&ctx3)?;
+                    if let Some(chunk) = chunk {
+                        Self::emit_str(This(this2.clone()), &ctx3, "data", vec![chunk], false)?;
+                    }
                     Self::emit_str(This(this2), &ctx3, "end", vec![], false)?;
                 }
 
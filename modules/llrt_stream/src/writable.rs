@@ -3,6 +3,7 @@
 use std::sync::{Arc, RwLock};
 
 use llrt_context::CtxExtension;
+use llrt_encoding::Encoder;
 use llrt_events::{EmitError, Emitter, EventEmitter, EventList};
 use llrt_utils::{bytes::ObjectBytes, error::ErrorExtensions, result::ResultExt};
 use rquickjs::{
@@ -19,7 +20,7 @@ use tokio::{
     },
 };
 
-use super::{impl_stream_events, set_destroyed_and_error, SteamEvents};
+use super::{impl_stream_events, set_destroyed_and_error, SteamEvents, DEFAULT_BUFFER_SIZE};
 
 pub struct WritableStreamInner<'js> {
     emitter: EventEmitter<'js>,
@@ -31,6 +32,10 @@ pub struct WritableStreamInner<'js> {
     emit_close: bool,
     is_destroyed: bool,
     destroy_tx: Sender<Option<Value<'js>>>,
+    high_water_mark: usize,
+    buffered: usize,
+    need_drain: bool,
+    encoding: Option<Encoder>,
 }
 
 impl<'js> Trace<'js> for WritableStreamInner<'js> {
@@ -54,15 +59,29 @@ impl<'js> WritableStreamInner<'js> {
             destroy_tx,
             emit_close,
             errored: false,
+            high_water_mark: DEFAULT_BUFFER_SIZE,
+            buffered: 0,
+            need_drain: false,
+            encoding: None,
         }
     }
+
+    /// Sets how many bytes can be waiting to be written before `write`
+    /// returns false
+    pub fn set_high_water_mark(&mut self, high_water_mark: usize) {
+        self.high_water_mark = high_water_mark;
+    }
+
+    /// Sets the encoding of the strings that are written
+    pub fn set_default_encoding(&mut self, encoding: Encoder) {
+        self.encoding = Some(encoding);
+    }
 }
 
-#[derive(Debug)]
 #[allow(dead_code)]
 pub enum WriteCommand<'js> {
     End,
-    Write(Value<'js>, Option<Function<'js>>, bool),
+    Write(ObjectBytes<'js>, Option<Function<'js>>, bool, usize),
     Flush,
 }
 
@@ -124,6 +143,8 @@ where
 
         proto.set("end", Func::from(Self::end))?;
 
+        proto.set("destroy", Func::from(Self::destroy))?;
+
         Ok(())
     }
 
@@ -169,45 +190,98 @@ where
         this: This<Class<'js, Self>>,
         ctx: Ctx<'js>,
         value: Value<'js>,
+        encoding: Opt<Value<'js>>,
         cb: Opt<Function<'js>>,
-    ) -> Result<()> {
-        Self::do_write(this, ctx, value, cb, true)
+    ) -> Result<bool> {
+        Self::do_write(this, ctx, value, encoding, cb, true)
     }
 
+    /// Writes `value`, a buffer or a string in `encoding`, which defaults to
+    /// the encoding of the stream. The callback can take the place of the
+    /// encoding, like in Node.js.
     fn write(
         this: This<Class<'js, Self>>,
         ctx: Ctx<'js>,
         value: Value<'js>,
+        encoding: Opt<Value<'js>>,
         cb: Opt<Function<'js>>,
-    ) -> Result<()> {
-        Self::do_write(this, ctx, value, cb, false)
+    ) -> Result<bool> {
+        Self::do_write(this, ctx, value, encoding, cb, false)
     }
 
     fn do_write(
         this: This<Class<'js, Self>>,
         ctx: Ctx<'js>,
         value: Value<'js>,
+        encoding: Opt<Value<'js>>,
         cb: Opt<Function<'js>>,
         flush: bool,
-    ) -> Result<()> {
-        let callback = cb.0;
+    ) -> Result<bool> {
+        let (encoding, callback) = match encoding.0 {
+            Some(encoding) if encoding.is_function() => (None, encoding.into_function()),
+            Some(encoding) => {
+                let encoding = encoding.as_string().map(|s| s.to_string()).transpose()?;
+                (encoding, cb.0)
+            },
+            None => (None, cb.0),
+        };
+        let bytes = match value.as_string() {
+            Some(string) => {
+                let encoder = match encoding {
+                    Some(encoding) => Encoder::from_str(&encoding).or_throw(&ctx)?,
+                    None => this
+                        .borrow()
+                        .inner()
+                        .encoding
+                        .clone()
+                        .unwrap_or(Encoder::Utf8),
+                };
+                let bytes = encoder
+                    .decode_from_string(string.to_string()?)
+                    .or_throw(&ctx)?;
+                ObjectBytes::Vec(bytes)
+            },
+            None => ObjectBytes::from(&ctx, &value)?,
+        };
+        let len = bytes.as_bytes(&ctx)?.len();
 
-        if this
-            .borrow()
-            .inner()
+        let mut borrow = this.borrow_mut();
+        let inner = borrow.inner_mut();
+        if inner
             .command_tx
-            .send(WriteCommand::Write(value, callback.clone(), flush))
+            .send(WriteCommand::Write(bytes, callback.clone(), flush, len))
             .is_err()
         {
+            drop(borrow);
             if let Some(cb) = callback {
                 let err = Exception::throw_message(&ctx, "This stream has been ended")
                     .into_value(&ctx)?;
 
                 () = cb.call((err,))?;
             }
+            return Ok(false);
         }
 
-        Ok(())
+        inner.buffered += len;
+        let below_high_water_mark = inner.buffered < inner.high_water_mark;
+        if !below_high_water_mark {
+            inner.need_drain = true;
+        }
+        Ok(below_high_water_mark)
+    }
+
+    // Emits "drain" once everything that was waiting when `write` returned
+    // false is written
+    fn written(this: &Class<'js, Self>, ctx: &Ctx<'js>, len: usize) -> Result<()> {
+        let mut borrow = this.borrow_mut();
+        let inner = borrow.inner_mut();
+        inner.buffered -= len;
+        if inner.buffered > 0 || !inner.need_drain {
+            return Ok(());
+        }
+        inner.need_drain = false;
+        drop(borrow);
+        Self::emit_str(This(this.clone()), ctx, "drain", vec![], false)
     }
 
     fn process<T: AsyncWrite + 'js + Unpin>(
@@ -217,7 +291,6 @@ where
     ) -> Result<Receiver<bool>> {
         let mut borrow = this.borrow_mut();
         let inner = borrow.inner_mut();
-        let is_ended = inner.is_finished;
         let mut is_destroyed = inner.is_destroyed;
         let emit_close = inner.emit_close;
         let mut command_rx = inner
@@ -237,13 +310,13 @@ where
             let write_function = async move {
                 let mut writer = BufWriter::new(writable);
 
-                if !is_ended && !is_destroyed {
+                // writes made before `end` are still queued ahead of it
+                if !is_destroyed {
                     loop {
                         tokio::select! {
                             command = command_rx.recv() => {
                                  match command {
-                                    Some(WriteCommand::Write(value, cb, flush)) => {
-                                        let bytes = ObjectBytes::from(&ctx3, &value)?;
+                                    Some(WriteCommand::Write(bytes, cb, flush, len)) => {
                                         let data = bytes.as_bytes(&ctx4)?;
                                         let result = async {
                                             writer.write_all(data).await?;
@@ -258,6 +331,7 @@ where
                                                 if let Some(cb) = cb {
                                                     () = cb.call(())?;
                                                 }
+                                                Self::written(&this2, &ctx3, len)?;
                                             },
                                             Err(err) => {
                                                 let err2 = Exception::throw_message(&ctx3, &err.to_string());
@@ -4,7 +4,10 @@ pub mod text_decoder;
 pub mod text_encoder;
 
 use llrt_logging::format_plain;
-use llrt_utils::module::{export_default, ModuleInfo};
+use llrt_utils::{
+    dotenv,
+    module::{export_default, ModuleInfo},
+};
 use rquickjs::{
     function::Func,
     module::{Declarations, Exports, ModuleDef},
@@ -13,6 +16,14 @@ use rquickjs::{
 use text_decoder::TextDecoder;
 use text_encoder::TextEncoder;
 
+fn parse_env(ctx: Ctx<'_>, content: String) -> Result<Object<'_>> {
+    let env = Object::new(ctx)?;
+    for (key, value) in dotenv::parse(&content) {
+        env.set(key, value)?;
+    }
+    Ok(env)
+}
+
 fn inherits<'js>(ctor: Function<'js>, super_ctor: Function<'js>) -> Result<()> {
     let super_proto: Object<'js> = super_ctor.get("prototype")?;
     let proto: Object<'js> = ctor.get("prototype")?;
@@ -29,6 +40,7 @@ impl ModuleDef for UtilModule {
         declare.declare(stringify!(TextEncoder))?;
         declare.declare(stringify!(format))?;
         declare.declare(stringify!(inherits))?;
+        declare.declare(stringify!(parseEnv))?;
         declare.declare("default")?;
         Ok(())
     }
@@ -47,6 +59,7 @@ impl ModuleDef for UtilModule {
                 Func::from(|ctx, args| format_plain(ctx, true, args)),
             )?;
             default.set("inherits", Func::from(inherits))?;
+            default.set("parseEnv", Func::from(parse_env))?;
 
             Ok(())
         })
@@ -55,6 +55,21 @@ describe("readdir", () => {
       ].sort(compare)
     );
   });
+  it("should read a directory with recursive and types", async () => {
+    const root = "fixtures/fs/readdir";
+    const dir = await fs.readdir(root, {
+      recursive: true,
+      withFileTypes: true,
+    });
+    const entries = dir
+      .map((dirent) => [dirent.parentPath, dirent.name, dirent.isDirectory()])
+      .sort((a, b) => (`${a[0]}/${a[1]}` >= `${b[0]}/${b[1]}` ? 1 : -1));
+    expect(entries).toEqual([
+      [root, "readdir.js", false],
+      [root, "recursive", true],
+      [path.join(root, "recursive"), "readdir.js", false],
+    ]);
+  });
 });
 
 describe("readdirSync", () => {
@@ -100,6 +115,90 @@ describe("readdirSync", () => {
   });
 });
 
+describe("opendir", () => {
+  const root = "fixtures/fs/readdir";
+
+  it("should read the entries of a directory one at a time", async () => {
+    const dir = await fs.opendir(root);
+    expect(dir.path).toEqual(root);
+    const names: string[] = [];
+    let dirent;
+    while ((dirent = await dir.read()) !== null) {
+      expect(dirent.parentPath).toEqual(root);
+      names.push(dirent.name);
+    }
+    await dir.close();
+    expect(names.sort()).toEqual(["readdir.js", "recursive"]);
+  });
+
+  it("should iterate a directory recursively and close it", async () => {
+    const dir = await fs.opendir(root, { recursive: true });
+    const entries: string[] = [];
+    for await (const dirent of dir) {
+      entries.push(path.join(dirent.parentPath, dirent.name));
+    }
+    expect(entries.sort()).toEqual(
+      [
+        path.join(root, "readdir.js"),
+        path.join(root, "recursive"),
+        path.join(root, "recursive", "readdir.js"),
+      ].sort()
+    );
+    await expect(dir.read()).rejects.toThrow(/closed/);
+  });
+
+  it("should close a directory when the iteration is stopped", async () => {
+    const dir = await fs.opendir(root);
+    for await (const dirent of dir) {
+      expect(dirent.isFile() || dirent.isDirectory()).toBeTruthy();
+      break;
+    }
+    expect(() => dir.closeSync()).toThrow(/closed/);
+  });
+
+  it("should read a directory synchronously", () => {
+    const dir = defaultFsImport.opendirSync(root);
+    const names: string[] = [];
+    let dirent;
+    while ((dirent = dir.readSync()) !== null) {
+      names.push(dirent.name);
+    }
+    dir.closeSync();
+    expect(names.sort()).toEqual(["readdir.js", "recursive"]);
+    expect(() => dir.readSync()).toThrow(/closed/);
+  });
+
+  it("should read a number of entries at a time", async () => {
+    const dir = await fs.opendir(root, { recursive: true, bufferSize: 1 });
+    const names: string[] = [];
+    let dirent;
+    while ((dirent = dir.readSync()) !== null) {
+      names.push(dirent.name);
+    }
+    await dir.close();
+    expect(names.sort()).toEqual(["readdir.js", "readdir.js", "recursive"]);
+    expect(() => defaultFsImport.opendirSync(root, { bufferSize: 0 })).toThrow(
+      RangeError
+    );
+  });
+
+  it("should open a directory with a callback", (done) => {
+    defaultFsImport.opendir(root, (err, dir) => {
+      expect(err).toBeNull();
+      expect(dir.readSync()).not.toBeNull();
+      dir.closeSync();
+      done();
+    });
+  });
+
+  it("should throw if the directory doesn't exist", async () => {
+    await expect(fs.opendir(`${root}/missing`)).rejects.toThrow(
+      /no such file or directory/i
+    );
+    expect(() => defaultFsImport.opendirSync(`${root}/readdir.js`)).toThrow();
+  });
+});
+
 describe("readfile", () => {
   it("should read a file", async () => {
     const buf = await fs.readFile("fixtures/hello.txt");
@@ -436,3 +535,629 @@ describe("accessSync", () => {
     );
   });
 });
+
+describe("appendFile", () => {
+  it("should append to a file, creating it first", async () => {
+    const tmpDir = await fs.mkdtemp(path.join(os.tmpdir(), "test-"));
+    const filePath = path.join(tmpDir, "test");
+    await fs.appendFile(filePath, "hello");
+    await fs.appendFile(filePath, " world");
+
+    expect((await fs.readFile(filePath)).toString()).toEqual("hello world");
+
+    defaultFsImport.appendFileSync(filePath, "!");
+    expect(defaultFsImport.readFileSync(filePath).toString()).toEqual(
+      "hello world!"
+    );
+
+    await fs.rm(tmpDir, { recursive: true });
+  });
+});
+
+describe("copyFile and rename", () => {
+  it("should copy and rename a file", async () => {
+    const tmpDir = await fs.mkdtemp(path.join(os.tmpdir(), "test-"));
+    const src = path.join(tmpDir, "src");
+    const copy = path.join(tmpDir, "copy");
+    const renamed = path.join(tmpDir, "renamed");
+    await fs.writeFile(src, "hello");
+
+    await fs.copyFile(src, copy);
+    await expect(
+      fs.copyFile(src, copy, fs.constants.COPYFILE_EXCL)
+    ).rejects.toThrow(/already exists/);
+    await fs.rename(copy, renamed);
+
+    expect((await fs.readFile(renamed)).toString()).toEqual("hello");
+    await expect(fs.access(copy)).rejects.toThrow();
+
+    defaultFsImport.copyFileSync(renamed, copy);
+    defaultFsImport.renameSync(copy, src);
+    expect(defaultFsImport.readFileSync(src).toString()).toEqual("hello");
+
+    await fs.rm(tmpDir, { recursive: true });
+  });
+
+  it("should give copy errors Node.js error codes", async () => {
+    const tmpDir = await fs.mkdtemp(path.join(os.tmpdir(), "test-"));
+    const src = path.join(tmpDir, "src");
+    const copy = path.join(tmpDir, "copy");
+    const missing = path.join(tmpDir, "missing");
+    await fs.writeFile(src, "hello");
+    await fs.writeFile(copy, "hello");
+
+    let error: any;
+    try {
+      await fs.copyFile(src, copy, fs.constants.COPYFILE_EXCL);
+    } catch (err) {
+      error = err;
+    }
+    expect(error.code).toEqual("EEXIST");
+    expect(error.syscall).toEqual("copyfile");
+    expect(error.path).toEqual(src);
+    expect(error.dest).toEqual(copy);
+
+    try {
+      defaultFsImport.copyFileSync(missing, copy);
+    } catch (err) {
+      error = err;
+    }
+    expect(error.code).toEqual("ENOENT");
+    expect(error.path).toEqual(missing);
+
+    try {
+      await fs.cp(missing, copy);
+    } catch (err) {
+      error = err;
+    }
+    expect(error.code).toEqual("ENOENT");
+    expect(error.syscall).toEqual("cp");
+
+    await fs.rm(tmpDir, { recursive: true });
+  });
+});
+
+describe("cp", () => {
+  it("should copy a directory recursively", async () => {
+    const tmpDir = await fs.mkdtemp(path.join(os.tmpdir(), "test-"));
+    const src = path.join(tmpDir, "src");
+    const dest = path.join(tmpDir, "dest");
+    await fs.mkdir(path.join(src, "nested"), { recursive: true });
+    await fs.writeFile(path.join(src, "a.txt"), "a");
+    await fs.writeFile(path.join(src, "nested", "b.txt"), "b");
+    await fs.writeFile(path.join(src, "skip.log"), "skip");
+
+    await expect(fs.cp(src, dest)).rejects.toThrow(/recursive/);
+    await fs.cp(src, dest, {
+      recursive: true,
+      filter: async (source) => !source.endsWith(".log"),
+    });
+
+    expect((await fs.readFile(path.join(dest, "a.txt"))).toString()).toEqual(
+      "a"
+    );
+    expect(
+      (await fs.readFile(path.join(dest, "nested", "b.txt"))).toString()
+    ).toEqual("b");
+    await expect(fs.access(path.join(dest, "skip.log"))).rejects.toThrow();
+
+    await expect(
+      fs.cp(src, path.join(src, "nested", "inner"), { recursive: true })
+    ).rejects.toThrow(/subdirectory of itself/);
+
+    await fs.rm(tmpDir, { recursive: true });
+  });
+
+  it("should honor force and errorOnExist", () => {
+    const tmpDir = defaultFsImport.mkdtempSync(path.join(os.tmpdir(), "test-"));
+    const src = path.join(tmpDir, "src");
+    const dest = path.join(tmpDir, "dest");
+    defaultFsImport.writeFileSync(src, "new");
+    defaultFsImport.writeFileSync(dest, "old");
+
+    defaultFsImport.cpSync(src, dest, { force: false });
+    expect(defaultFsImport.readFileSync(dest).toString()).toEqual("old");
+    expect(() =>
+      defaultFsImport.cpSync(src, dest, { force: false, errorOnExist: true })
+    ).toThrow(/already exists/);
+    defaultFsImport.cpSync(src, dest);
+    expect(defaultFsImport.readFileSync(dest).toString()).toEqual("new");
+
+    defaultFsImport.rmSync(tmpDir, { recursive: true });
+  });
+});
+
+describe("links", () => {
+  it("should create, read and remove links", async () => {
+    // creating symlinks needs extra privileges on Windows
+    if (IS_WINDOWS) {
+      return;
+    }
+    const tmpDir = await fs.realpath(
+      await fs.mkdtemp(path.join(os.tmpdir(), "test-"))
+    );
+    const target = path.join(tmpDir, "target");
+    const symlink = path.join(tmpDir, "symlink");
+    const hardLink = path.join(tmpDir, "hardlink");
+    await fs.writeFile(target, "hello");
+
+    await fs.symlink(target, symlink);
+    await fs.link(target, hardLink);
+
+    expect(await fs.readlink(symlink)).toEqual(target);
+    expect(await fs.realpath(symlink)).toEqual(target);
+    expect((await fs.lstat(symlink)).isSymbolicLink()).toBe(true);
+    expect((await fs.stat(symlink)).isSymbolicLink()).toBe(false);
+    expect(defaultFsImport.lstatSync(hardLink).isFile()).toBe(true);
+    expect((await fs.readFile(hardLink)).toString()).toEqual("hello");
+
+    await fs.unlink(symlink);
+    defaultFsImport.unlinkSync(hardLink);
+    await expect(fs.access(symlink)).rejects.toThrow();
+    await fs.access(target);
+
+    await fs.rm(tmpDir, { recursive: true });
+  });
+});
+
+describe("utimes and truncate", () => {
+  it("should set times and truncate a file", async () => {
+    const tmpDir = await fs.mkdtemp(path.join(os.tmpdir(), "test-"));
+    const filePath = path.join(tmpDir, "test");
+    await fs.writeFile(filePath, "hello world");
+
+    await fs.utimes(filePath, 1000, new Date(2000000));
+    const stats = await fs.stat(filePath);
+    expect(stats.atime.getTime()).toEqual(1000000);
+    expect(stats.mtime.getTime()).toEqual(2000000);
+    defaultFsImport.utimesSync(filePath, "3000", 3000);
+    expect(defaultFsImport.statSync(filePath).mtime.getTime()).toEqual(3000000);
+
+    await fs.truncate(filePath, 5);
+    expect((await fs.readFile(filePath)).toString()).toEqual("hello");
+    defaultFsImport.truncateSync(filePath);
+    expect((await fs.stat(filePath)).size).toEqual(0);
+
+    await fs.rm(tmpDir, { recursive: true });
+  });
+});
+
+describe("callback API", () => {
+  it("should pass the result after a null error", (done) => {
+    defaultFsImport.readFile("fixtures/hello.txt", "utf8", (err, data) => {
+      expect(err).toBeNull();
+      expect(data).toEqual(
+        defaultFsImport.readFileSync("fixtures/hello.txt").toString()
+      );
+      done();
+    });
+  });
+
+  it("should pass the error first", (done) => {
+    defaultFsImport.stat("fixtures/nothing", (err, stats) => {
+      expect(err).toBeInstanceOf(Error);
+      expect(err!.message).toMatch(/fixtures\/nothing/);
+      expect(err!.code).toEqual("ENOENT");
+      expect(typeof err!.errno).toEqual("number");
+      expect(err!.syscall).toEqual("stat");
+      expect(err!.path).toEqual("fixtures/nothing");
+      expect(stats).toBeUndefined();
+      done();
+    });
+  });
+
+  it("should reject with the error code", async () => {
+    let error: any;
+    try {
+      await fs.readFile("fixtures/nothing");
+    } catch (err) {
+      error = err;
+    }
+    expect(error.code).toEqual("ENOENT");
+    expect(error.syscall).toEqual("open");
+    expect(error.path).toEqual("fixtures/nothing");
+  });
+
+  it("should tell whether a path exists", (done) => {
+    expect(defaultFsImport.existsSync("fixtures/hello.txt")).toBe(true);
+    expect(defaultFsImport.existsSync("fixtures/nothing")).toBe(false);
+    defaultFsImport.exists("fixtures/hello.txt", (exists) => {
+      expect(exists).toBe(true);
+      done();
+    });
+  });
+
+  it("should share the constants", () => {
+    expect(defaultFsImport.constants).toBe(defaultFsImport.promises.constants);
+    expect(defaultFsImport.constants).toBe(fs.constants);
+  });
+});
+
+describe("file descriptors", () => {
+  it("should write, read and close a file synchronously", () => {
+    const tmpDir = defaultFsImport.mkdtempSync(path.join(os.tmpdir(), "test-"));
+    const filePath = path.join(tmpDir, "test");
+
+    const fd = defaultFsImport.openSync(filePath, "w+");
+    expect(fd).toBeGreaterThan(2);
+    expect(defaultFsImport.writeSync(fd, "hello world")).toEqual(11);
+    const hello = Buffer.from("HELLO");
+    expect(defaultFsImport.writeSync(fd, hello, 0, 5, 0)).toEqual(5);
+    expect(defaultFsImport.fstatSync(fd).size).toEqual(11);
+
+    const buffer = Buffer.alloc(16);
+    const bytesRead = defaultFsImport.readSync(fd, buffer, 2, 11, 0);
+    expect(bytesRead).toEqual(11);
+    expect(buffer.subarray(2, 13).toString()).toEqual("HELLO world");
+
+    defaultFsImport.closeSync(fd);
+    let error: any;
+    try {
+      defaultFsImport.fstatSync(fd);
+    } catch (err) {
+      error = err;
+    }
+    expect(error.code).toEqual("EBADF");
+    expect(error.syscall).toEqual("fstat");
+
+    defaultFsImport.rmSync(tmpDir, { recursive: true });
+  });
+
+  it("should open, write, read and close a file with callbacks", (done) => {
+    const tmpDir = defaultFsImport.mkdtempSync(path.join(os.tmpdir(), "test-"));
+    const filePath = path.join(tmpDir, "test");
+
+    defaultFsImport.open(filePath, "w+", (err, fd) => {
+      expect(err).toBeNull();
+      defaultFsImport.write(fd, "hello", (err, written, str) => {
+        expect(err).toBeNull();
+        expect(written).toEqual(5);
+        expect(str).toEqual("hello");
+        const buffer = Buffer.alloc(5);
+        defaultFsImport.read(fd, buffer, { position: 0 }, (err, bytesRead) => {
+          expect(err).toBeNull();
+          expect(bytesRead).toEqual(5);
+          expect(buffer.toString()).toEqual("hello");
+          defaultFsImport.close(fd, (err) => {
+            expect(err).toBeNull();
+            defaultFsImport.rmSync(tmpDir, { recursive: true });
+            done();
+          });
+        });
+      });
+    });
+  });
+
+  it("should read into a new buffer or the one of the options", (done) => {
+    const fd = defaultFsImport.openSync("fixtures/hello.txt", "r");
+
+    defaultFsImport.read(fd, (err, bytesRead, buffer) => {
+      expect(err).toBeNull();
+      expect(bytesRead).toEqual(12);
+      expect(buffer.length).toEqual(16384);
+      expect(buffer.subarray(0, bytesRead).toString()).toEqual("hello world!");
+
+      const options = { buffer: Buffer.alloc(8), offset: 2, position: 6 };
+      defaultFsImport.read(fd, options, (err, bytesRead, buffer) => {
+        expect(err).toBeNull();
+        expect(bytesRead).toEqual(6);
+        expect(buffer).toBe(options.buffer);
+        expect(buffer.subarray(2).toString()).toEqual("world!");
+        defaultFsImport.closeSync(fd);
+        defaultFsImport.close(fd, (err: any) => {
+          expect(err.code).toEqual("EBADF");
+          expect(err.syscall).toEqual("close");
+          done();
+        });
+      });
+    });
+  });
+});
+
+describe("watch", () => {
+  it("should report changes of a directory", (done) => {
+    const tmpDir = defaultFsImport.mkdtempSync(path.join(os.tmpdir(), "test-"));
+
+    const watcher = defaultFsImport.watch(tmpDir, (eventType, filename) => {
+      expect(["rename", "change"]).toContain(eventType);
+      expect(filename).toEqual("test.txt");
+      watcher.close();
+    });
+    watcher.on("close", () => {
+      defaultFsImport.rmSync(tmpDir, { recursive: true });
+      done();
+    });
+    defaultFsImport.writeFileSync(path.join(tmpDir, "test.txt"), "hello");
+  });
+
+  it("should pass the file names as buffers", (done) => {
+    const tmpDir = defaultFsImport.mkdtempSync(path.join(os.tmpdir(), "test-"));
+
+    const watcher = defaultFsImport.watch(tmpDir, "buffer", (_, filename) => {
+      expect(Buffer.isBuffer(filename)).toBe(true);
+      expect(filename!.toString()).toEqual("test.txt");
+      watcher.close();
+      defaultFsImport.rmSync(tmpDir, { recursive: true });
+      done();
+    });
+    defaultFsImport.writeFileSync(path.join(tmpDir, "test.txt"), "hello");
+  });
+
+  it("should report changes to unreferenced watchers too", (done) => {
+    const tmpDir = defaultFsImport.mkdtempSync(path.join(os.tmpdir(), "test-"));
+
+    const unreferenced = defaultFsImport.watch(tmpDir, { persistent: false });
+    const watcher = defaultFsImport.watch(tmpDir);
+    const filenames: string[] = [];
+    const listener = (name: string) => (_: string, filename: any) => {
+      filenames.push(`${name}:${filename}`);
+      if (filenames.length < 2) return;
+      unreferenced.close();
+      watcher.close();
+      expect(filenames.sort()).toEqual(["a:test.txt", "b:test.txt"]);
+      defaultFsImport.rmSync(tmpDir, { recursive: true });
+      done();
+    };
+    unreferenced.once("change", listener("a"));
+    watcher.once("change", listener("b"));
+    defaultFsImport.writeFileSync(path.join(tmpDir, "test.txt"), "hello");
+  });
+
+  it("should close when the signal is aborted", (done) => {
+    const tmpDir = defaultFsImport.mkdtempSync(path.join(os.tmpdir(), "test-"));
+    const controller = new AbortController();
+
+    const watcher = defaultFsImport.watch(tmpDir, {
+      signal: controller.signal,
+    });
+    watcher.on("close", () => {
+      defaultFsImport.rmSync(tmpDir, { recursive: true });
+      done();
+    });
+    controller.abort();
+  });
+
+  it("should throw when the path does not exist", () => {
+    expect(() => defaultFsImport.watch("fixtures/nothing")).toThrow(
+      /fixtures\/nothing/
+    );
+  });
+
+  it("should iterate over the changes", async () => {
+    const tmpDir = await fs.mkdtemp(path.join(os.tmpdir(), "test-"));
+
+    const iterator = fs.watch(tmpDir);
+    await fs.writeFile(path.join(tmpDir, "test.txt"), "hello");
+    const { value, done } = await iterator.next();
+    expect(done).toBe(false);
+    expect(value.filename).toEqual("test.txt");
+    expect(await iterator.return!()).toEqual({ value: undefined, done: true });
+    expect((await iterator.next()).done).toBe(true);
+
+    await fs.rm(tmpDir, { recursive: true });
+  });
+
+  it("should reject with an AbortError when aborted", async () => {
+    const tmpDir = await fs.mkdtemp(path.join(os.tmpdir(), "test-"));
+    const controller = new AbortController();
+
+    const iterator = fs.watch(tmpDir, { signal: controller.signal });
+    const next = iterator.next();
+    controller.abort();
+    let error: any;
+    try {
+      await next;
+    } catch (err) {
+      error = err;
+    }
+    expect(error.name).toEqual("AbortError");
+
+    await fs.rm(tmpDir, { recursive: true });
+  });
+});
+
+describe("watchFile", () => {
+  it("should pass the current and the previous stats", (done) => {
+    const tmpDir = defaultFsImport.mkdtempSync(path.join(os.tmpdir(), "test-"));
+    const filePath = path.join(tmpDir, "test.txt");
+    defaultFsImport.writeFileSync(filePath, "hello");
+
+    const listener = (
+      curr: namedFsImport.Stats,
+      prev: namedFsImport.Stats
+    ) => {
+      expect(curr.size).toEqual(11);
+      expect(prev.size).toEqual(5);
+      defaultFsImport.unwatchFile(filePath, listener);
+    };
+    const watcher = defaultFsImport.watchFile(
+      filePath,
+      { interval: 10 },
+      listener
+    );
+    watcher.on("stop", () => {
+      defaultFsImport.rmSync(tmpDir, { recursive: true });
+      done();
+    });
+    defaultFsImport.writeFileSync(filePath, "hello world");
+  });
+
+  it("should report a missing file with zeroed stats", (done) => {
+    const tmpDir = defaultFsImport.mkdtempSync(path.join(os.tmpdir(), "test-"));
+    const filePath = path.join(tmpDir, "test.txt");
+
+    defaultFsImport.watchFile(filePath, { interval: 10 }, (curr, prev) => {
+      expect(curr.isFile()).toBe(true);
+      expect(prev.isFile()).toBe(false);
+      expect(prev.size).toEqual(0);
+      defaultFsImport.unwatchFile(filePath);
+      defaultFsImport.rmSync(tmpDir, { recursive: true });
+      done();
+    });
+    defaultFsImport.writeFileSync(filePath, "hello");
+  });
+});
+
+describe("streams", () => {
+  it("should read a file in chunks", (done) => {
+    const tmpDir = defaultFsImport.mkdtempSync(path.join(os.tmpdir(), "test-"));
+    const filePath = path.join(tmpDir, "test");
+    defaultFsImport.writeFileSync(filePath, Buffer.alloc(10000, "a"));
+
+    const stream = defaultFsImport.createReadStream(filePath, {
+      highWaterMark: 1000,
+    });
+    const chunks: Buffer[] = [];
+    stream.on("data", (chunk) => chunks.push(chunk));
+    stream.on("end", () => {
+      expect(chunks.length).toEqual(10);
+      expect(Buffer.concat(chunks).length).toEqual(10000);
+      expect(stream.bytesRead).toEqual(10000);
+    });
+    stream.on("close", () => {
+      defaultFsImport.rmSync(tmpDir, { recursive: true });
+      done();
+    });
+  });
+
+  it("should read a range of a file as strings", (done) => {
+    const tmpDir = defaultFsImport.mkdtempSync(path.join(os.tmpdir(), "test-"));
+    const filePath = path.join(tmpDir, "test");
+    defaultFsImport.writeFileSync(filePath, "héllo wörld");
+
+    const stream = defaultFsImport.createReadStream(filePath, {
+      start: 7,
+      end: 12,
+      encoding: "utf8",
+      highWaterMark: 1,
+    });
+    let data = "";
+    stream.on("data", (chunk) => (data += chunk));
+    stream.on("end", () => {
+      expect(data).toEqual("wörld");
+      defaultFsImport.rmSync(tmpDir, { recursive: true });
+      done();
+    });
+  });
+
+  it("should emit an error when the file can not be opened", (done) => {
+    const stream = defaultFsImport.createReadStream("fixtures/nothing");
+    stream.on("error", (err: any) => {
+      expect(err.message).toMatch(/fixtures\/nothing/);
+      expect(err.code).toEqual("ENOENT");
+      expect(err.syscall).toEqual("open");
+      done();
+    });
+  });
+
+  it("should emit open and ready once the file is open", (done) => {
+    const tmpDir = defaultFsImport.mkdtempSync(path.join(os.tmpdir(), "test-"));
+    const filePath = path.join(tmpDir, "test");
+
+    const stream = defaultFsImport.createWriteStream(filePath);
+    const events: string[] = [];
+    stream.on("open", () => events.push("open"));
+    stream.on("ready", () => events.push("ready"));
+    stream.write("hello");
+    stream.end(() => {
+      expect(events).toEqual(["open", "ready"]);
+      expect(defaultFsImport.readFileSync(filePath).toString()).toEqual(
+        "hello"
+      );
+      defaultFsImport.rmSync(tmpDir, { recursive: true });
+      done();
+    });
+  });
+
+  it("should write to a file", (done) => {
+    const tmpDir = defaultFsImport.mkdtempSync(path.join(os.tmpdir(), "test-"));
+    const filePath = path.join(tmpDir, "test");
+
+    const stream = defaultFsImport.createWriteStream(filePath);
+    expect(stream.write("hello ")).toBe(true);
+    stream.write(Buffer.from("world"));
+    stream.end(() => {
+      expect(defaultFsImport.readFileSync(filePath).toString()).toEqual(
+        "hello world"
+      );
+      expect(stream.bytesWritten).toEqual(11);
+      defaultFsImport.rmSync(tmpDir, { recursive: true });
+      done();
+    });
+  });
+
+  it("should write strings in the given encoding", (done) => {
+    const tmpDir = defaultFsImport.mkdtempSync(path.join(os.tmpdir(), "test-"));
+    const filePath = path.join(tmpDir, "test");
+
+    const stream = defaultFsImport.createWriteStream(filePath, {
+      highWaterMark: 4,
+    });
+    expect(stream.write("6869", "hex")).toBe(true);
+    expect(stream.write("aGk=", "base64")).toBe(false);
+    stream.write("!", () => {
+      stream.end(() => {
+        expect(defaultFsImport.readFileSync(filePath).toString()).toEqual(
+          "hihi!"
+        );
+        expect(stream.bytesWritten).toEqual(5);
+        defaultFsImport.rmSync(tmpDir, { recursive: true });
+        done();
+      });
+    });
+  });
+
+  it("should pipe a file with backpressure", (done) => {
+    const tmpDir = defaultFsImport.mkdtempSync(path.join(os.tmpdir(), "test-"));
+    const source = path.join(tmpDir, "source");
+    const destination = path.join(tmpDir, "destination");
+    const data = Buffer.alloc(1024 * 1024);
+    for (let i = 0; i < data.length; i++) {
+      data[i] = i % 251;
+    }
+    defaultFsImport.writeFileSync(source, data);
+
+    const readStream = defaultFsImport.createReadStream(source);
+    const writeStream = defaultFsImport.createWriteStream(destination, {
+      highWaterMark: 1024,
+    });
+    let drains = 0;
+    writeStream.on("drain", () => drains++);
+    writeStream.on("finish", () => {
+      expect(drains).toBeGreaterThan(0);
+      expect(
+        defaultFsImport.readFileSync(destination).toString("base64")
+      ).toEqual(data.toString("base64"));
+      defaultFsImport.rmSync(tmpDir, { recursive: true });
+      done();
+    });
+    expect(readStream.pipe(writeStream)).toBe(writeStream);
+  });
+
+  it("should pause and resume", (done) => {
+    const tmpDir = defaultFsImport.mkdtempSync(path.join(os.tmpdir(), "test-"));
+    const filePath = path.join(tmpDir, "test");
+    defaultFsImport.writeFileSync(filePath, Buffer.alloc(10));
+
+    const stream = defaultFsImport.createReadStream(filePath, {
+      highWaterMark: 2,
+    });
+    let chunks = 0;
+    stream.on("data", () => {
+      chunks++;
+      if (chunks === 2) {
+        stream.pause();
+        expect(stream.isPaused()).toBe(true);
+        setTimeout(() => {
+          expect(chunks).toEqual(2);
+          stream.resume();
+        }, 10);
+      }
+    });
+    stream.on("end", () => {
+      expect(chunks).toEqual(5);
+      defaultFsImport.rmSync(tmpDir, { recursive: true });
+      done();
+    });
+  });
+});
@@ -31,6 +31,32 @@ describe("import", () => {
     expect(mod.default.private).toEqual(true);
   });
 
+  it("should import a text file with a type attribute", async () => {
+    const mod = await import(`${CWD}/fixtures/import-attributes.mjs`);
+
+    expect(mod.text).toEqual("hello world!");
+    expect((await mod.importText()).default).toEqual("hello world!");
+  });
+
+  it("should import a bytes file with a type attribute", async () => {
+    const mod = await import(`${CWD}/fixtures/import-attributes.mjs`);
+
+    expect(mod.bytes).toBeInstanceOf(Uint8Array);
+    expect(new TextDecoder().decode(mod.bytes)).toEqual("hello world!");
+  });
+
+  it("should import a json file with a type attribute", async () => {
+    const mod = await import(`${CWD}/fixtures/import-attributes.mjs`);
+
+    expect(mod.pkg.private).toEqual(true);
+  });
+
+  it("should reject an unknown type attribute", async () => {
+    await expect(
+      import(`${CWD}/fixtures/import-attributes-unknown.mjs`)
+    ).rejects.toThrow("Unsupported import type 'css'");
+  });
+
   it("should have import.meta.url", async () => {
     const url = import.meta.url;
     expect(url).toEqual(
@@ -1,5 +1,8 @@
 import defaultImport from "process";
 import * as namedImport from "process";
+import fs from "fs";
+import os from "os";
+import path from "path";
 
 describe("process", () => {
   it("should have a process env", () => {
@@ -22,6 +25,12 @@ describe("process", () => {
     expect(namedImport.argv).toEqual(process.argv);
   });
 
+  it("should have a process execArgv", () => {
+    expect(Array.isArray(process.execArgv)).toBeTruthy();
+    expect(defaultImport.execArgv).toEqual(process.execArgv);
+    expect(namedImport.execArgv).toEqual(process.execArgv);
+  });
+
   it("should have a process platform", () => {
     expect(defaultImport.platform).toEqual(process.platform);
     expect(namedImport.platform).toEqual(process.platform);
@@ -56,4 +65,21 @@ describe("process", () => {
     expect(defaultImport.exit).toEqual(process.exit);
     expect(namedImport.exit).toEqual(process.exit);
   });
+
+  it("should load an env file without overriding variables", () => {
+    const dir = fs.mkdtempSync(path.join(os.tmpdir(), "llrt-test-env-"));
+    const file = path.join(dir, ".env");
+    process.env.LLRT_TEST_ENV_SET = "kept";
+    fs.writeFileSync(
+      file,
+      'LLRT_TEST_ENV_NEW="a ${LLRT_TEST_ENV_SET}"\nLLRT_TEST_ENV_SET=replaced\n'
+    );
+
+    process.loadEnvFile(file);
+
+    expect(process.env.LLRT_TEST_ENV_NEW).toEqual("a kept");
+    expect(process.env.LLRT_TEST_ENV_SET).toEqual("kept");
+    expect(() => process.loadEnvFile(path.join(dir, "missing.env"))).toThrow();
+    fs.rmSync(dir, { recursive: true });
+  });
 });
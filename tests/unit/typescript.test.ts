@@ -0,0 +1,24 @@
+const CWD = process.cwd();
+
+describe("typescript", () => {
+  it("should import a ts module", async () => {
+    const mod = await import(`${CWD}/fixtures/typescript/greet.ts`);
+
+    expect(mod.greet({ name: "llrt" })).toEqual("HELLO LLRT!");
+    expect(mod.greet({ name: "llrt", age: 2 }, "hi")).toEqual("HI LLRT!");
+    expect(mod.add(1, 2)).toEqual(3);
+    expect(mod.greetings()).toEqual(2);
+  });
+
+  it("should erase type only imports and exports", async () => {
+    const mod = await import(`${CWD}/fixtures/typescript/types.ts`);
+
+    expect(Object.keys(mod)).toEqual([]);
+  });
+
+  it("should reject a ts module with an enum", async () => {
+    await expect(
+      import(`${CWD}/fixtures/typescript/enum.ts`)
+    ).rejects.toThrow("enums are not supported");
+  });
+});
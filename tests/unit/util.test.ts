@@ -15,3 +15,25 @@ describe("Util.inherits", () => {
     expect(MyStream.super_).toEqual(EventEmitter);
   });
 });
+
+describe("Util.parseEnv", () => {
+  it("should parse env file contents", () => {
+    const env = util.parseEnv(
+      [
+        "# comment",
+        "export A=1 # trailing",
+        "B='${A} literal'",
+        'C="line\\n${A}"',
+        "D=`multi",
+        "line`",
+      ].join("\n")
+    );
+
+    expect(env).toEqual({
+      A: "1",
+      B: "${A} literal",
+      C: "line\n1",
+      D: "multi\nline",
+    });
+  });
+});
@@ -19,6 +19,8 @@
 declare module "fs" {
   import * as promises from "fs/promises";
   import { Buffer, BufferEncoding } from "buffer";
+  import { EventEmitter } from "events";
+  import { DefaultReadableStream, DefaultWritableStream } from "stream";
   export { promises };
 
   /**
@@ -26,6 +28,8 @@ declare module "fs" {
    */
   export type PathLike = string;
   export type Mode = number;
+  export type TimeLike = string | number | Date;
+  export type SymlinkType = "dir" | "file" | "junction";
 
   export interface StatsBase<T> {
     isFile(): boolean;
@@ -127,11 +131,85 @@ declare module "fs" {
      */
     name: string;
     /**
-     * The base path that this `fs.Dirent` object refers to.
+     * The path to the parent directory of the file this `fs.Dirent` object
+     * refers to.
      */
     parentPath: string;
   }
 
+  export interface OpenDirOptions {
+    /**
+     * Also read the entries of the subdirectories, as they are reached.
+     * @default false
+     */
+    recursive?: boolean | undefined;
+    /**
+     * The number of entries read from the file system at a time.
+     * @default 32
+     */
+    bufferSize?: number | undefined;
+  }
+
+  /**
+   * A class representing a directory stream, created by {@link opendir},
+   * {@link opendirSync}, or `fsPromises.opendir()`.
+   *
+   * Entries are read from the file system `bufferSize` at a time, so large
+   * or recursive directories are not held in memory.
+   *
+   * ```js
+   * import { opendir } from 'fs/promises';
+   *
+   * try {
+   *   const dir = await opendir('./');
+   *   for await (const dirent of dir)
+   *     console.log(dirent.name);
+   * } catch (err) {
+   *   console.error(err);
+   * }
+   * ```
+   *
+   * When using the async iterator, the `fs.Dir` object will be automatically
+   * closed after the iterator exits.
+   */
+  export class Dir implements AsyncIterable<Dirent> {
+    /**
+     * The read-only path of this directory as was provided to {@link opendir},
+     * {@link opendirSync}, or `fsPromises.opendir()`.
+     */
+    readonly path: string;
+    /**
+     * Asynchronously iterates over the directory until all entries have been
+     * read, then closes it.
+     */
+    [Symbol.asyncIterator](): AsyncIterableIterator<Dirent>;
+    /**
+     * Asynchronously close the directory's underlying resource handle.
+     * Subsequent reads will result in errors.
+     */
+    close(): Promise<void>;
+    /**
+     * Synchronously close the directory's underlying resource handle.
+     * Subsequent reads will result in errors.
+     */
+    closeSync(): void;
+    /**
+     * Asynchronously read the next directory entry as an `fs.Dirent`.
+     *
+     * Resolves with `null` when there are no more entries to read.
+     *
+     * Directory entries are not returned in any particular order.
+     */
+    read(): Promise<Dirent | null>;
+    /**
+     * Synchronously read the next directory entry as an `fs.Dirent`. See the
+     * POSIX [`readdir(3)`](http://man7.org/linux/man-pages/man3/readdir.3.html) documentation for more detail.
+     *
+     * If there are no more directory entries to read, `null` will be returned.
+     */
+    readSync(): Dirent | null;
+  }
+
   export interface StatSyncFn extends Function {
     (path: PathLike): Stats;
   }
@@ -241,6 +319,14 @@ declare module "fs" {
     }
   ): Dirent[];
 
+  /**
+   * Synchronously open a directory. See [`opendir(3)`](http://man7.org/linux/man-pages/man3/opendir.3.html).
+   *
+   * Creates an `fs.Dir`, which contains all further functions for reading from
+   * and cleaning up the directory.
+   */
+  export function opendirSync(path: PathLike, options?: OpenDirOptions): Dir;
+
   /**
    * Returns the contents of the `path`.
    *
@@ -300,6 +386,19 @@ declare module "fs" {
     const W_OK: number;
     /** Constant for fs.access(). File can be executed by the calling process. */
     const X_OK: number;
+    // File Copy Constants
+    /** Constant for fs.copyFile. Flag indicating the destination file should not be overwritten if it already exists. */
+    const COPYFILE_EXCL: number;
+    /**
+     * Constant for fs.copyFile. Copy operation will attempt to create a copy-on-write reflink.
+     * If the underlying platform does not support copy-on-write, then a fallback copy mechanism is used.
+     */
+    const COPYFILE_FICLONE: number;
+    /**
+     * Constant for fs.copyFile. Copy operation will attempt to create a copy-on-write reflink.
+     * If the underlying platform does not support copy-on-write, then a fallback copy mechanism is used.
+     */
+    const COPYFILE_FICLONE_FORCE: number;
   }
 
   /**
@@ -333,4 +432,801 @@ declare module "fs" {
    * See the POSIX [`chmod(2)`](http://man7.org/linux/man-pages/man2/chmod.2.html) documentation for more detail.
    */
   export function chmodSync(path: PathLike, mode: Mode): void;
+
+  /**
+   * Synchronously changes the ownership of a file. Does nothing on Windows.
+   */
+  export function chownSync(path: PathLike, uid: number, gid: number): void;
+
+  /**
+   * Synchronously append data to a file, creating the file if it does not yet
+   * exist.
+   */
+  export function appendFileSync(
+    path: PathLike,
+    data:
+      | string
+      | Buffer
+      | QuickJS.ArrayBufferView
+      | ArrayBuffer
+      | SharedArrayBuffer
+  ): void;
+
+  /**
+   * Synchronously copies `src` to `dest`. By default, `dest` is overwritten if it
+   * already exists. Returns `undefined`.
+   * @param mode modifiers for copy operation.
+   */
+  export function copyFileSync(
+    src: PathLike,
+    dest: PathLike,
+    mode?: number
+  ): void;
+
+  export interface CopyOptions {
+    /**
+     * Dereference symlinks
+     * @default false
+     */
+    dereference?: boolean;
+    /**
+     * When `force` is `false`, and the destination
+     * exists, throw an error.
+     * @default false
+     */
+    errorOnExist?: boolean;
+    /**
+     * Function to filter copied files/directories. Return
+     * `true` to copy the item, `false` to ignore it.
+     * The filter of {@link cpSync} can't return a Promise.
+     */
+    filter?(source: string, destination: string): boolean | Promise<boolean>;
+    /**
+     * Overwrite existing file or directory. The copy
+     * operation will ignore errors if you set this to false and the destination
+     * exists. Use the `errorOnExist` option to change this behavior.
+     * @default true
+     */
+    force?: boolean;
+    /**
+     * When `true` timestamps from `src` will
+     * be preserved.
+     * @default false
+     */
+    preserveTimestamps?: boolean;
+    /**
+     * Copy directories recursively.
+     * @default false
+     */
+    recursive?: boolean;
+  }
+
+  /**
+   * Synchronously copies the entire directory structure from `src` to `dest`,
+   * including subdirectories and files.
+   * @param src source path to copy.
+   * @param dest destination path to copy to.
+   */
+  export function cpSync(
+    src: PathLike,
+    dest: PathLike,
+    options?: CopyOptions
+  ): void;
+
+  /**
+   * Synchronously renames the file at `oldPath` to the pathname provided as
+   * `newPath`. Returns `undefined`.
+   */
+  export function renameSync(oldPath: PathLike, newPath: PathLike): void;
+
+  /**
+   * Synchronous lstat - Get file status. Does not dereference symbolic links.
+   * @param path A path to a file.
+   */
+  export const lstatSync: StatSyncFn;
+
+  /**
+   * Creates a new link from the `existingPath` to the `newPath`. Returns
+   * `undefined`.
+   */
+  export function linkSync(existingPath: PathLike, newPath: PathLike): void;
+
+  /**
+   * Creates the link called `path` pointing to `target`. Returns `undefined`.
+   */
+  export function symlinkSync(
+    target: PathLike,
+    path: PathLike,
+    type?: SymlinkType | null
+  ): void;
+
+  /**
+   * Returns the symbolic link's string value.
+   */
+  export function readlinkSync(path: PathLike): string;
+
+  /**
+   * Returns the resolved pathname.
+   */
+  export function realpathSync(path: PathLike): string;
+
+  /**
+   * Synchronous [`unlink(2)`](http://man7.org/linux/man-pages/man2/unlink.2.html). Returns `undefined`.
+   */
+  export function unlinkSync(path: PathLike): void;
+
+  /**
+   * Returns `undefined`.
+   *
+   * For detailed information, see the documentation of the asynchronous version of
+   * this API: {@link promises.utimes}.
+   */
+  export function utimesSync(
+    path: PathLike,
+    atime: TimeLike,
+    mtime: TimeLike
+  ): void;
+
+  /**
+   * Truncates the file. Returns `undefined`.
+   * @param [len=0]
+   */
+  export function truncateSync(path: PathLike, len?: number): void;
+
+  export type NoParamCallback = (err: Error | null) => void;
+
+  type Data =
+    | string
+    | Buffer
+    | QuickJS.ArrayBufferView
+    | ArrayBuffer
+    | SharedArrayBuffer;
+
+  // Callback API
+  //
+  // The functions below call `callback` with the error first, like Node.js.
+  // See the `fs/promises` functions of the same name for details.
+
+  export function access(path: PathLike, callback: NoParamCallback): void;
+  export function access(
+    path: PathLike,
+    mode: Mode | undefined,
+    callback: NoParamCallback
+  ): void;
+
+  export function appendFile(
+    path: PathLike,
+    data: Data,
+    callback: NoParamCallback
+  ): void;
+
+  export function chmod(
+    path: PathLike,
+    mode: Mode,
+    callback: NoParamCallback
+  ): void;
+
+  export function chown(
+    path: PathLike,
+    uid: number,
+    gid: number,
+    callback: NoParamCallback
+  ): void;
+
+  export function copyFile(
+    src: PathLike,
+    dest: PathLike,
+    callback: NoParamCallback
+  ): void;
+  export function copyFile(
+    src: PathLike,
+    dest: PathLike,
+    mode: number,
+    callback: NoParamCallback
+  ): void;
+
+  export function cp(
+    src: PathLike,
+    dest: PathLike,
+    callback: NoParamCallback
+  ): void;
+  export function cp(
+    src: PathLike,
+    dest: PathLike,
+    options: CopyOptions,
+    callback: NoParamCallback
+  ): void;
+
+  /**
+   * Tests whether or not the given path exists. The callback only gets
+   * `true` or `false`, there is no error argument.
+   * @deprecated Use {@link stat} or {@link access} instead.
+   */
+  export function exists(
+    path: PathLike,
+    callback: (exists: boolean) => void
+  ): void;
+
+  /**
+   * Returns `true` if the path exists, `false` otherwise.
+   */
+  export function existsSync(path: PathLike): boolean;
+
+  export function link(
+    existingPath: PathLike,
+    newPath: PathLike,
+    callback: NoParamCallback
+  ): void;
+
+  export function lstat(
+    path: PathLike,
+    callback: (err: Error | null, stats: Stats) => void
+  ): void;
+
+  export function mkdir(path: PathLike, callback: NoParamCallback): void;
+  export function mkdir(
+    path: PathLike,
+    options: MakeDirectoryOptions | undefined,
+    callback: (err: Error | null, path?: string) => void
+  ): void;
+
+  export function mkdtemp(
+    prefix: string,
+    callback: (err: Error | null, folder: string) => void
+  ): void;
+
+  export function opendir(
+    path: PathLike,
+    callback: (err: Error | null, dir: Dir) => void
+  ): void;
+  export function opendir(
+    path: PathLike,
+    options: OpenDirOptions,
+    callback: (err: Error | null, dir: Dir) => void
+  ): void;
+
+  export function readdir(
+    path: PathLike,
+    callback: (err: Error | null, files: string[]) => void
+  ): void;
+  export function readdir(
+    path: PathLike,
+    options: {
+      withFileTypes?: false | undefined;
+      recursive?: boolean | undefined;
+    },
+    callback: (err: Error | null, files: string[]) => void
+  ): void;
+  export function readdir(
+    path: PathLike,
+    options: {
+      withFileTypes: true;
+      recursive?: boolean | undefined;
+    },
+    callback: (err: Error | null, files: Dirent[]) => void
+  ): void;
+
+  export function readFile(
+    path: PathLike,
+    callback: (err: Error | null, data: Buffer) => void
+  ): void;
+  export function readFile(
+    path: PathLike,
+    options: { encoding: BufferEncoding } | BufferEncoding,
+    callback: (err: Error | null, data: string) => void
+  ): void;
+
+  export function readlink(
+    path: PathLike,
+    callback: (err: Error | null, linkString: string) => void
+  ): void;
+
+  export function realpath(
+    path: PathLike,
+    callback: (err: Error | null, resolvedPath: string) => void
+  ): void;
+
+  export function rename(
+    oldPath: PathLike,
+    newPath: PathLike,
+    callback: NoParamCallback
+  ): void;
+
+  export function rm(path: PathLike, callback: NoParamCallback): void;
+  export function rm(
+    path: PathLike,
+    options: RmOptions,
+    callback: NoParamCallback
+  ): void;
+
+  export function rmdir(path: PathLike, callback: NoParamCallback): void;
+  export function rmdir(
+    path: PathLike,
+    options: RmDirOptions,
+    callback: NoParamCallback
+  ): void;
+
+  export function stat(
+    path: PathLike,
+    callback: (err: Error | null, stats: Stats) => void
+  ): void;
+
+  export function symlink(
+    target: PathLike,
+    path: PathLike,
+    callback: NoParamCallback
+  ): void;
+  export function symlink(
+    target: PathLike,
+    path: PathLike,
+    type: SymlinkType | undefined | null,
+    callback: NoParamCallback
+  ): void;
+
+  export function truncate(path: PathLike, callback: NoParamCallback): void;
+  export function truncate(
+    path: PathLike,
+    len: number | undefined,
+    callback: NoParamCallback
+  ): void;
+
+  export function unlink(path: PathLike, callback: NoParamCallback): void;
+
+  export function utimes(
+    path: PathLike,
+    atime: TimeLike,
+    mtime: TimeLike,
+    callback: NoParamCallback
+  ): void;
+
+  export function writeFile(
+    file: PathLike,
+    data: Data,
+    callback: NoParamCallback
+  ): void;
+
+  // File descriptors
+  //
+  // The descriptors of `open` and `openSync` belong to the runtime, they are
+  // not those of the operating system. 0, 1 and 2 are stdin, stdout and
+  // stderr.
+
+  export interface ReadSyncOptions {
+    /**
+     * @default 0
+     */
+    offset?: number | undefined;
+    /**
+     * @default `length of buffer`
+     */
+    length?: number | undefined;
+    /**
+     * @default null
+     */
+    position?: number | null | undefined;
+  }
+
+  /**
+   * Asynchronous file open. See the POSIX [`open(2)`](http://man7.org/linux/man-pages/man2/open.2.html) documentation for more details.
+   * @param [flags='r'] See `support of file system `flags``.
+   * @param [mode=0o666]
+   */
+  export function open(
+    path: PathLike,
+    callback: (err: Error | null, fd: number) => void
+  ): void;
+  export function open(
+    path: PathLike,
+    flags: promises.FileSystemFlags | undefined,
+    callback: (err: Error | null, fd: number) => void
+  ): void;
+  export function open(
+    path: PathLike,
+    flags: promises.FileSystemFlags | undefined,
+    mode: Mode | undefined,
+    callback: (err: Error | null, fd: number) => void
+  ): void;
+
+  /**
+   * Returns a number representing the file descriptor.
+   * @param [flags='r']
+   * @param [mode=0o666]
+   */
+  export function openSync(
+    path: PathLike,
+    flags?: promises.FileSystemFlags,
+    mode?: Mode
+  ): number;
+
+  /**
+   * Closes the file descriptor.
+   */
+  export function close(fd: number, callback: NoParamCallback): void;
+
+  /**
+   * Closes the file descriptor. Returns `undefined`.
+   */
+  export function closeSync(fd: number): void;
+
+  /**
+   * Read data from the file specified by `fd`.
+   *
+   * The callback is given the three arguments, `(err, bytesRead, buffer)`.
+   * @param buffer The buffer that the data will be written to.
+   * @param offset The position in `buffer` to write the data to.
+   * @param length The number of bytes to read.
+   * @param position Specifies where to begin reading from in the file. If
+   * `position` is `null` or `-1 `, data will be read from the current file
+   * position, and the file position will be updated.
+   */
+  export function read<TBuffer extends QuickJS.ArrayBufferView>(
+    fd: number,
+    buffer: TBuffer,
+    offset: number,
+    length: number,
+    position: number | null,
+    callback: (err: Error | null, bytesRead: number, buffer: TBuffer) => void
+  ): void;
+  export function read<TBuffer extends QuickJS.ArrayBufferView>(
+    fd: number,
+    buffer: TBuffer,
+    options: ReadSyncOptions,
+    callback: (err: Error | null, bytesRead: number, buffer: TBuffer) => void
+  ): void;
+
+  /**
+   * Returns the number of `bytesRead`.
+   *
+   * For detailed information, see the documentation of the asynchronous version of
+   * this API: {@link read}.
+   */
+  export function readSync(
+    fd: number,
+    buffer: QuickJS.ArrayBufferView,
+    offset?: number,
+    length?: number,
+    position?: number | null
+  ): number;
+  export function readSync(
+    fd: number,
+    buffer: QuickJS.ArrayBufferView,
+    opts?: ReadSyncOptions
+  ): number;
+
+  /**
+   * Write `buffer` to the file specified by `fd`.
+   *
+   * The callback will be given three arguments `(err, bytesWritten, buffer)`
+   * where `bytesWritten` specifies how many _bytes_ were written from `buffer`.
+   * @param [offset=0]
+   * @param [length=buffer.byteLength - offset]
+   * @param [position='null']
+   */
+  export function write<TBuffer extends QuickJS.ArrayBufferView>(
+    fd: number,
+    buffer: TBuffer,
+    offset: number | undefined | null,
+    length: number | undefined | null,
+    position: number | undefined | null,
+    callback: (
+      err: Error | null,
+      bytesWritten: number,
+      buffer: TBuffer
+    ) => void
+  ): void;
+  export function write<TBuffer extends QuickJS.ArrayBufferView>(
+    fd: number,
+    buffer: TBuffer,
+    callback: (
+      err: Error | null,
+      bytesWritten: number,
+      buffer: TBuffer
+    ) => void
+  ): void;
+  export function write(
+    fd: number,
+    string: string,
+    position: number | undefined | null,
+    encoding: BufferEncoding | undefined | null,
+    callback: (err: Error | null, written: number, str: string) => void
+  ): void;
+  export function write(
+    fd: number,
+    string: string,
+    callback: (err: Error | null, written: number, str: string) => void
+  ): void;
+
+  /**
+   * Returns the number of bytes written.
+   *
+   * For detailed information, see the documentation of the asynchronous version of
+   * this API: {@link write}.
+   */
+  export function writeSync(
+    fd: number,
+    buffer: QuickJS.ArrayBufferView,
+    offset?: number | null,
+    length?: number | null,
+    position?: number | null
+  ): number;
+  export function writeSync(
+    fd: number,
+    string: string,
+    position?: number | null,
+    encoding?: BufferEncoding | null
+  ): number;
+
+  /**
+   * Invokes the callback with the `fs.Stats` for the file descriptor.
+   */
+  export function fstat(
+    fd: number,
+    callback: (err: Error | null, stats: Stats) => void
+  ): void;
+
+  /**
+   * Retrieves the `fs.Stats` for the file descriptor.
+   */
+  export function fstatSync(fd: number): Stats;
+
+  /**
+   * Request that all data for the open file descriptor is flushed to the
+   * storage device.
+   */
+  export function fsync(fd: number, callback: NoParamCallback): void;
+
+  /**
+   * Request that all data for the open file descriptor is flushed to the
+   * storage device. Returns `undefined`.
+   */
+  export function fsyncSync(fd: number): void;
+
+  // Watching
+
+  export type WatchEventType = "rename" | "change";
+
+  export interface WatchOptions {
+    /**
+     * Whether the process should keep running while files are watched.
+     * @default true
+     */
+    persistent?: boolean | undefined;
+    /**
+     * Whether all subdirectories should be watched too.
+     * @default false
+     */
+    recursive?: boolean | undefined;
+    /**
+     * The encoding of the file names, `"buffer"` passes them as `Buffer`.
+     * @default 'utf8'
+     */
+    encoding?: BufferEncoding | "buffer" | undefined;
+    /**
+     * Closes the watcher when aborted.
+     */
+    signal?: AbortSignal | undefined;
+  }
+
+  export type WatchListener<T> = (
+    eventType: WatchEventType,
+    filename: T | null
+  ) => void;
+
+  /**
+   * Returned by {@link watch}. Emits `"change"` with the type of the event
+   * and the name of the file relative to the watched directory.
+   */
+  export class FSWatcher extends EventEmitter<{
+    change: [eventType: WatchEventType, filename: string | Buffer];
+    close: [];
+    error: [error: Error];
+  }> {
+    /**
+     * Stops watching and emits `"close"`.
+     */
+    close(): void;
+
+    /**
+     * Makes the watcher keep the process running again after `unref()`.
+     */
+    ref(): this;
+
+    /**
+     * Lets the process exit while the watcher is still open. Changes are
+     * still reported while the process keeps running.
+     */
+    unref(): this;
+  }
+
+  /**
+   * Watches `filename`, which is either a file or a directory, for changes.
+   * Uses inotify on Linux and compares modification times at an interval
+   * elsewhere.
+   */
+  export function watch(
+    filename: PathLike,
+    options: (WatchOptions & { encoding: "buffer" }) | "buffer",
+    listener?: WatchListener<Buffer>
+  ): FSWatcher;
+  export function watch(
+    filename: PathLike,
+    options?: WatchOptions | BufferEncoding | null,
+    listener?: WatchListener<string>
+  ): FSWatcher;
+  export function watch(
+    filename: PathLike,
+    listener?: WatchListener<string>
+  ): FSWatcher;
+
+  export interface WatchFileOptions {
+    /**
+     * Whether the process should keep running while the file is watched.
+     * @default true
+     */
+    persistent?: boolean | undefined;
+    /**
+     * How often the file is checked, in milliseconds.
+     * @default 5007
+     */
+    interval?: number | undefined;
+  }
+
+  export type StatsListener = (curr: Stats, prev: Stats) => void;
+
+  /**
+   * Returned by {@link watchFile}. Emits `"change"` with the current and the
+   * previous `Stats`, and `"stop"` once the file is unwatched.
+   */
+  export class StatWatcher extends EventEmitter<{
+    change: [curr: Stats, prev: Stats];
+    stop: [];
+  }> {
+    /**
+     * Makes the watcher keep the process running again after `unref()`.
+     */
+    ref(): this;
+
+    /**
+     * Lets the process exit while the file is still watched. The file is not
+     * checked until the watcher is referenced again.
+     */
+    unref(): this;
+  }
+
+  /**
+   * Watches `filename` by checking its `Stats` every `interval`. `listener`
+   * is called when they changed. The `Stats` of a file that does not exist
+   * have all fields set to zero.
+   */
+  export function watchFile(
+    filename: PathLike,
+    options: WatchFileOptions | undefined,
+    listener: StatsListener
+  ): StatWatcher;
+  export function watchFile(
+    filename: PathLike,
+    listener: StatsListener
+  ): StatWatcher;
+
+  /**
+   * Stops calling `listener` for changes of `filename`, or all listeners
+   * when none is given. The file is no longer checked once it has no
+   * listeners.
+   */
+  export function unwatchFile(
+    filename: PathLike,
+    listener?: StatsListener
+  ): void;
+
+  // Streams
+
+  export interface StreamOptions {
+    /**
+     * @default 'r' for read streams, 'w' for write streams
+     */
+    flags?: string | undefined;
+    /**
+     * The encoding of the strings passed by a read stream, or of the strings
+     * written to a write stream.
+     */
+    encoding?: BufferEncoding | undefined;
+    /**
+     * The mode of a file that is created.
+     * @default 0o666
+     */
+    mode?: number | undefined;
+    /**
+     * The position in the file to start reading or writing at.
+     * @default 0
+     */
+    start?: number | undefined;
+    /**
+     * For read streams, how many bytes are read at once. For write streams,
+     * how many bytes can be buffered before `write()` returns `false`.
+     * @default 65536 for read streams, 16384 for write streams
+     */
+    highWaterMark?: number | undefined;
+  }
+
+  export interface ReadStreamOptions extends StreamOptions {
+    /**
+     * The position in the file to stop reading at, inclusive.
+     * @default Infinity
+     */
+    end?: number | undefined;
+  }
+
+  /**
+   * Returned by {@link createReadStream}.
+   */
+  export class ReadStream extends DefaultReadableStream {
+    /**
+     * The path of the file the stream reads from.
+     */
+    readonly path: string;
+
+    /**
+     * The number of bytes read from the file so far.
+     */
+    readonly bytesRead: number;
+
+    /**
+     * Stops reading and closes the file. `callback` is called once the
+     * stream emitted `'close'`.
+     */
+    close(callback?: () => void): void;
+  }
+
+  /**
+   * Returned by {@link createWriteStream}.
+   */
+  export class WriteStream extends DefaultWritableStream {
+    /**
+     * The path of the file the stream writes to.
+     */
+    readonly path: string;
+
+    /**
+     * The number of bytes written to the file so far.
+     */
+    readonly bytesWritten: number;
+
+    /**
+     * Writes `chunk`, if given, and ends the stream. `callback` is called
+     * once the stream emitted `'finish'`.
+     */
+    end(callback?: () => void): this;
+    end(
+      chunk: string | Buffer | QuickJS.ArrayBufferView,
+      callback?: () => void
+    ): this;
+
+    /**
+     * Ends the stream and closes the file. `callback` is called once the
+     * stream emitted `'close'`.
+     */
+    close(callback?: () => void): void;
+  }
+
+  /**
+   * Returns a stream reading `path` in chunks of `highWaterMark` bytes,
+   * without loading the whole file in memory. The file is opened in the
+   * background: the stream emits `'open'` and `'ready'` once it is open, or
+   * `'error'` if it can't be opened.
+   */
+  export function createReadStream(
+    path: PathLike,
+    options?: BufferEncoding | ReadStreamOptions
+  ): ReadStream;
+
+  /**
+   * Returns a stream writing to `path`, which is truncated unless `flags`
+   * says otherwise. The file is opened in the background: the stream emits
+   * `'open'` and `'ready'` once it is open, or `'error'` if it can't be
+   * opened. Writes made before that are queued.
+   */
+  export function createWriteStream(
+    path: PathLike,
+    options?: BufferEncoding | StreamOptions
+  ): WriteStream;
 }
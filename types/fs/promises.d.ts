@@ -6,13 +6,20 @@ declare module "fs/promises" {
   import { Buffer, BufferEncoding } from "buffer";
   import {
     constants as fsConstants,
+    CopyOptions,
+    Dir,
     Dirent,
     MakeDirectoryOptions,
     Mode,
+    OpenDirOptions,
     PathLike,
     RmDirOptions,
     RmOptions,
     Stats,
+    SymlinkType,
+    TimeLike,
+    WatchEventType,
+    WatchOptions,
   } from "fs";
 
   export type FileSystemFlags =
@@ -392,6 +399,32 @@ declare module "fs/promises" {
     }
   ): Promise<Dirent[]>;
 
+  /**
+   * Asynchronously open a directory for iterative scanning. See the POSIX [`opendir(3)`](http://man7.org/linux/man-pages/man3/opendir.3.html) documentation for more detail.
+   *
+   * Creates an `fs.Dir`, which contains all further functions for reading from
+   * and cleaning up the directory.
+   *
+   * Example using async iteration:
+   *
+   * ```js
+   * import { opendir } from 'fs/promises';
+   *
+   * try {
+   *   const dir = await opendir('./');
+   *   for await (const dirent of dir)
+   *     console.log(dirent.name);
+   * } catch (err) {
+   *   console.error(err);
+   * }
+   * ```
+   *
+   * When using the async iterator, the `fs.Dir` object will be automatically
+   * closed after the iterator exits.
+   * @return Fulfills with an {fs.Dir}.
+   */
+  function opendir(path: PathLike, options?: OpenDirOptions): Promise<Dir>;
+
   /**
    * Asynchronous stat - Get file status.
    * @param path A path to a file.
@@ -508,4 +541,153 @@ declare module "fs/promises" {
    * @return Fulfills with `undefined` upon success.
    */
   function chmod(path: PathLike, mode: Mode): Promise<void>;
+
+  /**
+   * Asynchronously appends data to a file, creating the file if it does not
+   * yet exist.
+   * @return Fulfills with `undefined` upon success.
+   */
+  function appendFile(
+    path: PathLike,
+    data:
+      | string
+      | Buffer
+      | QuickJS.ArrayBufferView
+      | ArrayBuffer
+      | SharedArrayBuffer
+  ): Promise<void>;
+
+  /**
+   * Asynchronously copies `src` to `dest`. By default, `dest` is overwritten if it
+   * already exists.
+   * @param mode Optional modifiers. `fs.constants.COPYFILE_EXCL` makes the copy
+   * fail if `dest` already exists.
+   * @return Fulfills with `undefined` upon success.
+   */
+  function copyFile(
+    src: PathLike,
+    dest: PathLike,
+    mode?: number
+  ): Promise<void>;
+
+  /**
+   * Asynchronously copies the entire directory structure from `src` to `dest`,
+   * including subdirectories and files.
+   *
+   * When copying a directory to another directory, globs are not supported and
+   * behavior is similar to `cp dir1/ dir2/`.
+   * @param src source path to copy.
+   * @param dest destination path to copy to.
+   * @return Fulfills with `undefined` upon success.
+   */
+  function cp(
+    src: PathLike,
+    dest: PathLike,
+    options?: CopyOptions
+  ): Promise<void>;
+
+  /**
+   * Renames `oldPath` to `newPath`.
+   * @return Fulfills with `undefined` upon success.
+   */
+  function rename(oldPath: PathLike, newPath: PathLike): Promise<void>;
+
+  /**
+   * Equivalent to {@link stat} unless `path` refers to a symbolic link, in
+   * which case the link itself is stat-ed, not the file that it refers to.
+   * @return Fulfills with the {fs.Stats} object for the given symbolic link
+   * `path`.
+   */
+  function lstat(path: PathLike): Promise<Stats>;
+
+  /**
+   * Creates a new link from the `existingPath` to the `newPath`.
+   * @return Fulfills with `undefined` upon success.
+   */
+  function link(existingPath: PathLike, newPath: PathLike): Promise<void>;
+
+  /**
+   * Creates a symbolic link.
+   *
+   * The `type` argument is only used on Windows platforms and can be one of
+   * `'dir'`, `'file'`, or `'junction'`. If `type` is not set, the type of the
+   * `target` is used.
+   * @return Fulfills with `undefined` upon success.
+   */
+  function symlink(
+    target: PathLike,
+    path: PathLike,
+    type?: SymlinkType | null
+  ): Promise<void>;
+
+  /**
+   * Reads the contents of the symbolic link referred to by `path`.
+   * @return Fulfills with the `linkString` upon success.
+   */
+  function readlink(path: PathLike): Promise<string>;
+
+  /**
+   * Determines the actual location of `path`, resolving `.`, `..` and
+   * symbolic links.
+   * @return Fulfills with the resolved path upon success.
+   */
+  function realpath(path: PathLike): Promise<string>;
+
+  /**
+   * If `path` refers to a symbolic link, then the link is removed without
+   * affecting the file or directory to which that link refers. If the `path`
+   * refers to a file path that is not a symbolic link, the file is deleted.
+   * @return Fulfills with `undefined` upon success.
+   */
+  function unlink(path: PathLike): Promise<void>;
+
+  /**
+   * Changes the file system timestamps of the object referenced by `path`.
+   *
+   * The `atime` and `mtime` arguments follow these rules:
+   *
+   * * Values can be either numbers representing Unix epoch time, `Date`s, or
+   * a numeric string like `'123456789.0'`.
+   * * If the value can not be converted to a number, or is `NaN`, `Infinity`,
+   * or `-Infinity`, an `Error` will be thrown.
+   * @return Fulfills with `undefined` upon success.
+   */
+  function utimes(
+    path: PathLike,
+    atime: TimeLike,
+    mtime: TimeLike
+  ): Promise<void>;
+
+  /**
+   * Changes the ownership of a file. Does nothing on Windows.
+   * @return Fulfills with `undefined` upon success.
+   */
+  function chown(path: PathLike, uid: number, gid: number): Promise<void>;
+
+  /**
+   * Truncates (shortens or extends the length) of the content at `path` to
+   * `len` bytes.
+   * @param [len=0]
+   * @return Fulfills with `undefined` upon success.
+   */
+  function truncate(path: PathLike, len?: number): Promise<void>;
+
+  interface FileChangeInfo<T extends string | Buffer> {
+    eventType: WatchEventType;
+    filename: T | null;
+  }
+
+  /**
+   * Watches `filename`, which is either a file or a directory, and yields
+   * every change. Watching stops when the iterator returns or `signal` is
+   * aborted, in which case `next()` rejects with an `AbortError`.
+   */
+  function watch(
+    filename: PathLike,
+    options: (WatchOptions & { encoding: "buffer" }) | "buffer"
+  ): AsyncIterableIterator<FileChangeInfo<Buffer>>;
+  function watch(
+    filename: PathLike,
+    options?: WatchOptions | BufferEncoding
+  ): AsyncIterableIterator<FileChangeInfo<string>>;
 }
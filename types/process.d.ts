@@ -89,6 +89,29 @@ declare module "process" {
      * ```
      */
     argv: string[];
+    /**
+     * The runtime flags passed when the llrt process was launched, such as
+     * `--gc-threshold=64`. They come before the script and do not appear in
+     * `process.argv`. Built executables list the flags given with their flag
+     * prefix.
+     *
+     * ```console
+     * $ llrt --stack-size=1024 script.js --version
+     * ```
+     *
+     * Results in `process.execArgv`:
+     *
+     * ```js
+     * ['--stack-size=1024']
+     * ```
+     *
+     * And `process.argv`:
+     *
+     * ```js
+     * ['llrt', 'script.js', '--version']
+     * ```
+     */
+    execArgv: string[];
     /**
      * The operating system CPU architecture for which the llrt binary was compiled.
      * Possible values are: `'arm64'` and `'x64'`.
@@ -184,6 +207,19 @@ declare module "process" {
      */
     exit(code?: number | string | null | undefined): never;
 
+    /**
+     * Loads the `.env` file at `path` into `process.env`. Variables that are
+     * set already keep their value.
+     *
+     * ```js
+     * import { loadEnvFile } from 'process';
+     *
+     * loadEnvFile('./development.env');
+     * ```
+     * @param path The file to load. **Default:** `'./.env'`.
+     */
+    loadEnvFile(path?: string): void;
+
     /**
      * The `process.getgid()` method returns the numerical group identity of the
      * process. (See [`getgid(2)`](http://man7.org/linux/man-pages/man2/getgid.2.html).)
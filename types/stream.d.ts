@@ -1,6 +1,6 @@
 declare module "stream" {
   import { EventEmitter } from "events";
-  import { Buffer } from "buffer";
+  import { Buffer, BufferEncoding } from "buffer";
 
   class ReadableStreamInner
     extends EventEmitter
@@ -96,6 +96,40 @@ declare module "stream" {
      */
     destroy(error?: Error): this;
 
+    /**
+     * Stops emitting `'data'` events until {@link resume} is called. The data
+     * that is not read yet stays in the underlying resource.
+     */
+    pause(): this;
+
+    /**
+     * Resumes emitting `'data'` events after {@link pause}.
+     */
+    resume(): this;
+
+    /**
+     * Returns whether the stream was paused with {@link pause}.
+     */
+    isPaused(): boolean;
+
+    /**
+     * Makes the stream emit strings in `encoding` instead of `Buffer`s. A
+     * character split across chunks is emitted with the chunk it ends in.
+     * @param [encoding='utf8']
+     */
+    setEncoding(encoding?: BufferEncoding): this;
+
+    /**
+     * Writes all data of this stream to `destination`. Reading is paused
+     * while `destination.write()` returns `false`, until it emits `'drain'`.
+     * `destination` is ended at the end of this stream unless `end` is false.
+     * @return `destination`, to allow chaining pipes.
+     */
+    pipe<T extends QuickJS.WritableStream>(
+      destination: T,
+      options?: { end?: boolean | undefined }
+    ): T;
+
     /**
      * Event emitter
      * The defined events on documents including:
@@ -198,7 +232,7 @@ declare module "stream" {
         | ArrayBuffer
         | SharedArrayBuffer,
       callback?: (error?: Error | null) => void
-    ): void;
+    ): boolean;
 
     /**
      * Calling the `writable.end()` method signals that no more data will be written
@@ -208,31 +242,44 @@ declare module "stream" {
      */
     end(): this;
 
+    /**
+     * Destroys the stream without writing the data that is still buffered.
+     * Optionally emits an `'error'` event, and emits a `'close'` event.
+     * @param error Error which will be passed as payload in `'error'` event
+     */
+    destroy(error?: Error): this;
+
     /**
      * Event emitter
      * The defined events on documents including:
      * 1. close
-     * 2. error
-     * 3. finish
+     * 2. drain
+     * 3. error
+     * 4. finish
      */
     addListener(event: EventKey, listener: (...args: any[]) => void): this;
     addListener(event: "close", listener: () => void): this;
+    addListener(event: "drain", listener: () => void): this;
     addListener(event: "error", listener: (err: Error) => void): this;
     addListener(event: "finish", listener: () => void): this;
     emit(event: EventKey, ...args: any[]): boolean;
     emit(event: "close"): boolean;
+    emit(event: "drain"): boolean;
     emit(event: "error", err: Error): boolean;
     emit(event: "finish"): boolean;
     on(event: EventKey, listener: (...args: any[]) => void): this;
     on(event: "close", listener: () => void): this;
+    on(event: "drain", listener: () => void): this;
     on(event: "error", listener: (err: Error) => void): this;
     on(event: "finish", listener: () => void): this;
     once(event: EventKey, listener: (...args: any[]) => void): this;
     once(event: "close", listener: () => void): this;
+    once(event: "drain", listener: () => void): this;
     once(event: "error", listener: (err: Error) => void): this;
     once(event: "finish", listener: () => void): this;
     prependListener(event: EventKey, listener: (...args: any[]) => void): this;
     prependListener(event: "close", listener: () => void): this;
+    prependListener(event: "drain", listener: () => void): this;
     prependListener(event: "error", listener: (err: Error) => void): this;
     prependListener(event: "finish", listener: () => void): this;
     prependOnceListener(
@@ -240,10 +287,12 @@ declare module "stream" {
       listener: (...args: any[]) => void
     ): this;
     prependOnceListener(event: "close", listener: () => void): this;
+    prependOnceListener(event: "drain", listener: () => void): this;
     prependOnceListener(event: "error", listener: (err: Error) => void): this;
     prependOnceListener(event: "finish", listener: () => void): this;
     removeListener(event: EventKey, listener: (...args: any[]) => void): this;
     removeListener(event: "close", listener: () => void): this;
+    removeListener(event: "drain", listener: () => void): this;
     removeListener(event: "error", listener: (err: Error) => void): this;
     removeListener(event: "finish", listener: () => void): this;
   }
@@ -114,6 +114,21 @@ declare module "util" {
     constructor: unknown,
     superConstructor: unknown
   ): void;
+  /**
+   * Parses the contents of a `.env` file into an object of its keys and values,
+   * with the same syntax as `--env-file`: comments, `export` prefixes, quoted
+   * values that span lines, escapes in double quoted values and `${VAR}`
+   * expansion.
+   *
+   * ```js
+   * import util from 'util';
+   *
+   * util.parseEnv('HELLO=world\nGREETING="hello ${HELLO}"');
+   * // Returns: { HELLO: 'world', GREETING: 'hello world' }
+   * ```
+   * @param content The contents of a `.env` file.
+   */
+  export function parseEnv(content: string): Record<string, string>;
   /**
    * An implementation of the [WHATWG Encoding Standard](https://encoding.spec.whatwg.org/) `TextDecoder` API.
    *